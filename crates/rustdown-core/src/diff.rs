@@ -0,0 +1,138 @@
+use imara_diff::{Algorithm, Diff, InternedInput};
+
+/// A replacement of base lines `base_start..base_end` with `replacement`.
+#[derive(Clone, Debug)]
+pub(crate) struct Edit<'a> {
+    pub(crate) base_start: usize,
+    pub(crate) base_end: usize,
+    pub(crate) replacement: Vec<&'a str>,
+}
+
+pub(crate) fn diff_edits<'a>(base: &'a str, other: &'a str) -> Vec<Edit<'a>> {
+    let mut input = InternedInput::default();
+    input.update_before(imara_diff::sources::lines(base));
+    let other_lines: Vec<&'a str> = imara_diff::sources::lines(other).collect();
+    edits_against(&mut input, &other_lines)
+}
+
+/// Edits turning the base lines already interned in `input.before` into
+/// `other_lines`, so one base can be diffed against several texts without
+/// being split and hashed again.
+pub(crate) fn edits_against<'a>(
+    input: &mut InternedInput<&'a str>,
+    other_lines: &[&'a str],
+) -> Vec<Edit<'a>> {
+    input.update_after(other_lines.iter().copied());
+    let mut diff = Diff::compute(Algorithm::Histogram, input);
+    diff.postprocess_lines(input);
+
+    let other_len = other_lines.len();
+
+    diff.hunks()
+        .map(|hunk| {
+            let after_start = (hunk.after.start as usize).min(other_len);
+            let after_end = (hunk.after.end as usize).min(other_len);
+            Edit {
+                base_start: hunk.before.start as usize,
+                base_end: hunk.before.end as usize,
+                replacement: other_lines[after_start..after_end].to_vec(),
+            }
+        })
+        .collect()
+}
+
+/// One changed region between two texts, for display.
+#[derive(Debug, PartialEq, Eq)]
+pub struct DiffHunk<'a> {
+    /// 1-based line in the left text where the hunk starts.
+    pub line: usize,
+    /// Lines only in the left text (with their line terminators).
+    pub removed: Vec<&'a str>,
+    /// Lines only in the right text (with their line terminators).
+    pub added: Vec<&'a str>,
+}
+
+/// Line-level hunks turning `left` into `right`.
+#[must_use]
+pub fn diff_hunks<'a>(left: &'a str, right: &'a str) -> Vec<DiffHunk<'a>> {
+    let left_lines: Vec<&'a str> = imara_diff::sources::lines(left).collect();
+    diff_edits(left, right)
+        .into_iter()
+        .map(|edit| {
+            let start = edit.base_start.min(left_lines.len());
+            let end = edit.base_end.min(left_lines.len());
+            DiffHunk {
+                line: start + 1,
+                removed: left_lines[start..end].to_vec(),
+                added: edit.replacement,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diff_edits_detects_change_kinds() {
+        for (base, current, base_start, base_end, replacement) in [
+            ("a\nb\nc\n", "a\nX\nc\n", 1, 2, &["X\n"][..]),
+            ("a\nc\n", "a\nb\nc\n", 1, 1, &["b\n"][..]),
+            ("a\nb\nc\n", "a\nc\n", 1, 2, &[][..]),
+        ] {
+            let edits = diff_edits(base, current);
+            assert_eq!(edits.len(), 1);
+            assert_eq!(edits[0].base_start, base_start);
+            assert_eq!(edits[0].base_end, base_end);
+            assert_eq!(edits[0].replacement, replacement.to_vec());
+        }
+    }
+
+    #[test]
+    fn diff_edits_empty_inputs() {
+        assert!(diff_edits("", "").is_empty());
+        assert!(!diff_edits("", "hello\nworld\n").is_empty());
+        assert!(!diff_edits("hello\nworld\n", "").is_empty());
+    }
+
+    #[test]
+    fn edits_against_a_shared_base_match_separate_diffs() {
+        let base = "a\nb\nc\nd\n";
+        let mut input = InternedInput::default();
+        input.update_before(imara_diff::sources::lines(base));
+        for other in ["a\nX\nc\nd\n", "b\nc\nd\ne\nf\n", "", "a\nb\nc\nd\n"] {
+            let lines: Vec<&str> = imara_diff::sources::lines(other).collect();
+            let shared = edits_against(&mut input, &lines);
+            let fresh = diff_edits(base, other);
+            assert_eq!(shared.len(), fresh.len(), "{other:?}");
+            for (s, f) in shared.iter().zip(&fresh) {
+                assert_eq!(
+                    (s.base_start, s.base_end, &s.replacement),
+                    (f.base_start, f.base_end, &f.replacement)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn diff_hunks_pair_removed_and_added_lines() {
+        let hunks = diff_hunks("a\nb\nc\nd\n", "a\nB\nc\nd\ne\n");
+        assert_eq!(
+            hunks,
+            vec![
+                DiffHunk {
+                    line: 2,
+                    removed: vec!["b\n"],
+                    added: vec!["B\n"],
+                },
+                DiffHunk {
+                    line: 5,
+                    removed: vec![],
+                    added: vec!["e\n"],
+                },
+            ]
+        );
+        assert!(diff_hunks("same\n", "same\n").is_empty());
+    }
+}
@@ -0,0 +1,11 @@
+#![forbid(unsafe_code)]
+//! `rustdown-core` — UI-independent text logic shared by the rustdown app and CLI.
+//!
+//! Provides line diffs (via `imara-diff`'s histogram algorithm) and the
+//! three-way merge used to reconcile external file changes with edits.
+
+mod diff;
+mod merge;
+
+pub use diff::{DiffHunk, diff_hunks};
+pub use merge::{Merge3Outcome, conflict_marker_starts, merge_three_way};
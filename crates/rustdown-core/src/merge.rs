@@ -1,5 +1,8 @@
-use imara_diff::{Algorithm, Diff, InternedInput};
+use imara_diff::InternedInput;
 
+use crate::diff::{Edit, edits_against};
+
+/// Result of a three-way line merge.
 #[derive(Debug, PartialEq, Eq)]
 pub enum Merge3Outcome {
     Clean(String),
@@ -9,13 +12,6 @@ pub enum Merge3Outcome {
     },
 }
 
-#[derive(Clone, Debug)]
-struct Edit<'a> {
-    base_start: usize,
-    base_end: usize,
-    replacement: Vec<&'a str>,
-}
-
 /// Push the same tokens to two output buffers (used for non-conflicting regions).
 fn push_both(a: &mut String, b: &mut String, tokens: &[&str]) {
     for tok in tokens {
@@ -24,7 +20,12 @@ fn push_both(a: &mut String, b: &mut String, tokens: &[&str]) {
     }
 }
 
-#[allow(clippy::too_many_lines)] // merge logic — linear flow with multiple phases
+/// Merge `ours` and `theirs` against their common `base`, line by line.
+///
+/// Line endings don't count as edits: when the texts mix LF and CRLF they
+/// are compared as LF, and the result uses ours' ending unless only theirs
+/// changed it.
+#[must_use]
 pub fn merge_three_way(base: &str, ours: &str, theirs: &str) -> Merge3Outcome {
     if ours == theirs {
         return Merge3Outcome::Clean(ours.to_owned());
@@ -36,13 +37,65 @@ pub fn merge_three_way(base: &str, ours: &str, theirs: &str) -> Merge3Outcome {
         return Merge3Outcome::Clean(ours.to_owned());
     }
 
+    // `None` for a text without line breaks, which has no style to keep.
+    let [base_crlf, ours_crlf, theirs_crlf] = [base, ours, theirs].map(uses_crlf);
+    let mut known = [base_crlf, ours_crlf, theirs_crlf].into_iter().flatten();
+    let first = known.next();
+    if known.all(|crlf| Some(crlf) == first) {
+        return merge_lines(base, ours, theirs);
+    }
+    // Line endings differ: diff with LF everywhere so only real edits
+    // count, then write the result with ours' ending — or theirs' when ours
+    // kept the base's.
+    let crlf = if ours_crlf.is_some() && ours_crlf != base_crlf {
+        ours_crlf
+    } else {
+        theirs_crlf.or(ours_crlf)
+    }
+    .unwrap_or_default();
+    let outcome = merge_three_way(&to_lf(base), &to_lf(ours), &to_lf(theirs));
+    if !crlf {
+        return outcome;
+    }
+    match outcome {
+        Merge3Outcome::Clean(text) => Merge3Outcome::Clean(to_crlf(&text)),
+        Merge3Outcome::Conflicted {
+            conflict_marked,
+            ours_wins,
+        } => Merge3Outcome::Conflicted {
+            conflict_marked: to_crlf(&conflict_marked),
+            ours_wins: to_crlf(&ours_wins),
+        },
+    }
+}
+
+fn uses_crlf(text: &str) -> Option<bool> {
+    text.contains('\n').then(|| text.contains("\r\n"))
+}
+
+fn to_lf(text: &str) -> String {
+    text.replace("\r\n", "\n")
+}
+
+/// `text` (with LF endings) with every line ending written as CRLF.
+fn to_crlf(text: &str) -> String {
+    text.replace('\n', "\r\n")
+}
+
+/// Line merge of texts that differ and share one line-ending style.
+#[allow(clippy::too_many_lines)] // merge logic — linear flow with multiple phases
+fn merge_lines(base: &str, ours: &str, theirs: &str) -> Merge3Outcome {
+    // Each text is split into lines once; the line cap and both diffs reuse
+    // these, and the base is interned once for both diffs.
     let base_lines: Vec<&str> = imara_diff::sources::lines(base).collect();
+    let ours_lines: Vec<&str> = imara_diff::sources::lines(ours).collect();
+    let theirs_lines: Vec<&str> = imara_diff::sources::lines(theirs).collect();
 
     const MAX_MERGE_LINES: usize = 20_000;
     let max_lines = base_lines
         .len()
-        .max(imara_diff::sources::lines(ours).count())
-        .max(imara_diff::sources::lines(theirs).count());
+        .max(ours_lines.len())
+        .max(theirs_lines.len());
     if max_lines > MAX_MERGE_LINES {
         let cap = ours.len() + theirs.len() + 80;
         let mut conflict_marked = String::with_capacity(cap);
@@ -60,8 +113,10 @@ pub fn merge_three_way(base: &str, ours: &str, theirs: &str) -> Merge3Outcome {
         };
     }
 
-    let ours_edits = diff_edits(base, ours);
-    let theirs_edits = diff_edits(base, theirs);
+    let mut input = InternedInput::default();
+    input.update_before(base_lines.iter().copied());
+    let ours_edits = edits_against(&mut input, &ours_lines);
+    let theirs_edits = edits_against(&mut input, &theirs_lines);
 
     let base_len = base_lines.len();
     let mut pos = 0usize;
@@ -211,27 +266,6 @@ pub fn merge_three_way(base: &str, ours: &str, theirs: &str) -> Merge3Outcome {
     }
 }
 
-fn diff_edits<'a>(base: &'a str, other: &'a str) -> Vec<Edit<'a>> {
-    let input = InternedInput::new(base, other);
-    let mut diff = Diff::compute(Algorithm::Histogram, &input);
-    diff.postprocess_lines(&input);
-
-    let other_lines: Vec<&'a str> = imara_diff::sources::lines(other).collect();
-    let other_len = other_lines.len();
-
-    diff.hunks()
-        .map(|hunk| {
-            let after_start = (hunk.after.start as usize).min(other_len);
-            let after_end = (hunk.after.end as usize).min(other_len);
-            Edit {
-                base_start: hunk.before.start as usize,
-                base_end: hunk.before.end as usize,
-                replacement: other_lines[after_start..after_end].to_vec(),
-            }
-        })
-        .collect()
-}
-
 #[allow(clippy::suspicious_operation_groupings)]
 const fn edits_overlap(left: &Edit<'_>, right: &Edit<'_>) -> bool {
     if left.base_start == left.base_end && right.base_start == right.base_end {
@@ -278,6 +312,32 @@ fn render_range_with_edits(base: &[&str], start: usize, end: usize, edits: &[Edi
     out
 }
 
+/// Byte offsets of the `<<<<<<<` lines opening each complete conflict block
+/// (`<<<<<<<` … `=======` … `>>>>>>>`) left in `text`.
+#[must_use]
+pub fn conflict_marker_starts(text: &str) -> Vec<usize> {
+    let mut starts = Vec::new();
+    let mut open: Option<(usize, bool)> = None;
+    let mut offset = 0;
+    for line in text.split_inclusive('\n') {
+        let start = offset;
+        offset += line.len();
+        let line = line.trim_end_matches(['\n', '\r']);
+        if line == "<<<<<<<" || line.starts_with("<<<<<<< ") {
+            open = Some((start, false));
+        } else if line == "=======" {
+            if let Some((_, separated)) = open.as_mut() {
+                *separated = true;
+            }
+        } else if (line == ">>>>>>>" || line.starts_with(">>>>>>> "))
+            && let Some((block_start, true)) = open.take()
+        {
+            starts.push(block_start);
+        }
+    }
+    starts
+}
+
 fn ensure_newline(buf: &mut String) {
     if !buf.is_empty() && !buf.ends_with('\n') {
         buf.push('\n');
@@ -385,6 +445,60 @@ mod tests {
         }
     }
 
+    #[test]
+    fn merge_three_way_ignores_line_ending_differences() {
+        for (label, base, ours, theirs, expected) in [
+            (
+                "disk switched to CRLF",
+                "a\nb\nc\n",
+                "A\nb\nc\n",
+                "a\r\nb\r\nC\r\n",
+                "A\r\nb\r\nC\r\n",
+            ),
+            (
+                "buffer switched to LF",
+                "a\r\nb\r\nc\r\n",
+                "A\nb\nc\n",
+                "a\r\nb\r\nC\r\n",
+                "A\nb\nC\n",
+            ),
+            (
+                "buffer kept CRLF, disk went LF",
+                "a\r\nb\r\nc\r\n",
+                "A\r\nb\r\nc\r\n",
+                "a\nb\nC\n",
+                "A\nb\nC\n",
+            ),
+            (
+                "ending change only",
+                "a\nb\n",
+                "a\nb\nc\n",
+                "a\r\nb\r\n",
+                "a\r\nb\r\nc\r\n",
+            ),
+        ] {
+            match merge_three_way(base, ours, theirs) {
+                Merge3Outcome::Clean(merged) => assert_eq!(merged, expected, "{label}"),
+                Merge3Outcome::Conflicted { .. } => panic!("{label}: unexpected conflict"),
+            }
+        }
+
+        // A real conflict keeps the chosen ending on its markers too.
+        let Merge3Outcome::Conflicted {
+            conflict_marked,
+            ours_wins,
+        } = merge_three_way("a\nb\n", "a\nX\n", "a\r\nY\r\n")
+        else {
+            panic!("expected a conflict");
+        };
+        assert_eq!(
+            conflict_marked,
+            "a\r\n<<<<<<< ours\r\nX\r\n=======\r\nY\r\n>>>>>>> theirs\r\n"
+        );
+        assert_eq!(ours_wins, "a\r\nX\r\n");
+        assert_eq!(conflict_marker_starts(&conflict_marked), [3]);
+    }
+
     #[test]
     fn merge_three_way_conflict_cases() {
         // Basic conflict.
@@ -425,21 +539,6 @@ mod tests {
         assert!(c2.contains("<<<<<<< ours"));
     }
 
-    #[test]
-    fn diff_edits_detects_change_kinds() {
-        for (base, current, base_start, base_end, replacement) in [
-            ("a\nb\nc\n", "a\nX\nc\n", 1, 2, &["X\n"][..]),
-            ("a\nc\n", "a\nb\nc\n", 1, 1, &["b\n"][..]),
-            ("a\nb\nc\n", "a\nc\n", 1, 2, &[][..]),
-        ] {
-            let edits = diff_edits(base, current);
-            assert_eq!(edits.len(), 1);
-            assert_eq!(edits[0].base_start, base_start);
-            assert_eq!(edits[0].base_end, base_end);
-            assert_eq!(edits[0].replacement, replacement.to_vec());
-        }
-    }
-
     #[test]
     fn edits_overlap_parameterized() {
         for (label, a, b, expected) in [
@@ -673,6 +772,26 @@ mod tests {
 
     // ── Chaos tests ──────────────────────────────────────────────────
 
+    #[test]
+    fn conflict_marker_starts_finds_complete_blocks() {
+        let (marked, _) = assert_conflict("a\nb\nc\n", "A\nb\nC\n", "X\nb\nY\n");
+        let starts = conflict_marker_starts(&marked);
+        assert_eq!(starts.len(), 2);
+        for start in starts {
+            assert!(marked[start..].starts_with("<<<<<<< ours\n"));
+        }
+
+        assert!(conflict_marker_starts("").is_empty());
+        // Setext underlines and incomplete blocks are not conflicts.
+        assert!(conflict_marker_starts("Title\n==========\n").is_empty());
+        assert!(conflict_marker_starts("<<<<<<< ours\nA\n>>>>>>> theirs\n").is_empty());
+        assert!(conflict_marker_starts("<<<<<<< ours\nA\n=======\nB\n").is_empty());
+        assert_eq!(
+            conflict_marker_starts("x\r\n<<<<<<<\r\nA\r\n=======\r\n>>>>>>>\r\n"),
+            vec![3]
+        );
+    }
+
     #[test]
     fn render_range_with_edits_edge_cases() {
         // Empty base.
@@ -690,11 +809,4 @@ mod tests {
         }];
         assert_eq!(render_range_with_edits(&["a\n"], 0, 1, &edits), "a\nx\n");
     }
-
-    #[test]
-    fn diff_edits_empty_inputs() {
-        assert!(diff_edits("", "").is_empty());
-        assert!(!diff_edits("", "hello\nworld\n").is_empty());
-        assert!(!diff_edits("hello\nworld\n", "").is_empty());
-    }
 }
@@ -1,6 +1,7 @@
 use std::{
     borrow::Cow,
-    io,
+    fs, io,
+    ops::Range,
     path::{Path, PathBuf},
     sync::Arc,
 };
@@ -8,18 +9,33 @@ use std::{
 use eframe::egui;
 
 use super::{
-    BundledDoc, ConflictChoice, Mode, PendingAction, RustdownApp, STATS_RECALC_DEBOUNCE,
-    default_image_uri_scheme, markdown_file_dialog, zoom_with_factor, zoom_with_step,
+    ASYNC_OPEN_MIN_BYTES, BundledDoc, ConflictChoice, Mode, PendingAction, RustdownApp,
+    SelectionPreview, default_image_uri_scheme, markdown_file_dialog, markdown_link_target,
+    title_stats_suffix, wiki_note_path, window_level, zoom_with_factor, zoom_with_step,
 };
 use crate::{
-    cli::{LaunchOptions, app_version},
+    changes::{self, LineChanges},
+    cli::{LaunchOptions, app_version, reveal_in_file_manager},
+    completion,
     disk::io::{
-        DiskRevision, atomic_write_utf8, disk_revision, next_merge_sidecar_path, read_stable_utf8,
+        DiskRevision, MAX_FILE_BYTES, MergeSidecarOptions, atomic_write_utf8, disk_revision,
+        file_too_large, next_merge_sidecar_path, read_stable_utf8,
     },
     disk::sync::ReloadKind,
-    document::{Document, DocumentStats},
-    format, nav, preferences,
+    document::{
+        ASYNC_STATS_MIN_BYTES, Document, DocumentStats, bytecount_newlines,
+        conflict_starts_outside_fences,
+    },
+    editor::{self, SelectionEdit},
+    fold::Folding,
+    format,
+    mergetool::{MergeInputs, MergeToolSession},
+    nav,
+    pasted_image::{self, PastedImage},
+    preferences::{self, ExportTheme, TitleStats},
     search::replace_all_occurrences,
+    session::SessionState,
+    ui_style,
 };
 
 impl RustdownApp {
@@ -34,24 +50,147 @@ impl RustdownApp {
             options.mode
         };
 
+        let mut app = Self::from_preferences(prefs);
+        app.mode = mode;
+        app.pending_line = options.line.filter(|_| options.path.is_some());
+        let mut session = SessionState::load();
+        app.scroll_positions = std::mem::take(&mut session.scroll_positions);
+        if let Some(path) = options.path {
+            app.open_path(path);
+        } else if options.merge.is_none() {
+            app.restore_session(session);
+        }
+        // Auto-show nav in preview modes if heading count exceeds threshold.
+        app.maybe_auto_show_nav();
+        app
+    }
+
+    /// An app with no document configured from `prefs`.
+    pub(crate) fn from_preferences(prefs: preferences::UserPreferences) -> Self {
         let mut app = Self {
-            mode,
             heading_color_mode: prefs.heading_color_mode,
             side_by_side_scroll_sync: prefs.side_by_side_scroll_sync,
             persisted_zoom: prefs.zoom_factor,
+            preview_code_wrap: prefs.preview_code_wrap,
+            preview_preserve_line_breaks: prefs.preview_preserve_line_breaks,
+            preview_render_html: prefs.preview_render_html,
+            editor_indent_with_tabs: prefs.editor_indent_with_tabs,
+            heading_scales: prefs.heading_scales.sanitized(),
+            reading_width: prefs.reading_width,
+            reading_width_chars: prefs.reading_width_chars,
+            merge_sidecar: MergeSidecarOptions {
+                dir: prefs.merge_sidecar_dir,
+                template: prefs.merge_sidecar_template,
+            },
+            disk_sync_mode: prefs.disk_sync_mode,
+            confirm_disk_merges: prefs.confirm_disk_merges,
+            confirm_save_with_conflicts: prefs.confirm_save_with_conflicts,
+            fonts: prefs.fonts,
+            font_size: ui_style::sanitized_font_size(prefs.font_size),
+            title_stats: prefs.title_stats,
+            follow_markdown_links: prefs.follow_markdown_links,
+            export_theme: prefs.export_theme,
+            export_css: prefs.export_css,
+            smart_punctuation: prefs.smart_punctuation,
+            autosave: prefs.autosave,
+            format_on_save: prefs.format_on_save,
+            open_any_file: prefs.open_any_file,
+            code_fence_language: prefs.code_fence_language,
+            minimap: prefs.minimap,
+            image_max_width: prefs.image_max_width,
+            export_front_matter: prefs.export_front_matter,
+            window: prefs.window,
+            notes_root: prefs.notes_root,
+            wiki_links: prefs.wiki_links,
+            code_colors: prefs.code_colors,
+            timings: prefs.timings,
             ..Self::default()
         };
         app.nav.visible = prefs.nav_visible;
         app.nav.heading_color_mode = prefs.heading_color_mode;
-        if let Some(path) = options.path {
-            app.open_path(path);
-        }
-        // Auto-show nav in preview modes if heading count exceeds threshold.
-        app.maybe_auto_show_nav();
         app
     }
 
-    pub(crate) fn set_mode(&mut self, mode: Mode, ctx: &egui::Context) {
+    /// Snapshot the active document for restoring on next launch.
+    pub(crate) fn session_state(&self) -> SessionState {
+        let dirty = self.doc.dirty;
+        SessionState {
+            path: self.doc.path.clone(),
+            text: dirty.then(|| self.doc.text.to_string()),
+            base_text: (dirty && self.doc.path.is_some()).then(|| self.doc.base_text.to_string()),
+            scroll_positions: self.scroll_positions.clone(),
+        }
+    }
+
+    /// Reopen the document from a saved session.
+    ///
+    /// Unsaved edits to a file are replayed on top of the edits' original
+    /// base, then reconciled with the current disk contents through the
+    /// normal reload/merge path, so external changes made while rustdown
+    /// was closed are merged (or raised as a conflict) rather than lost.
+    pub(crate) fn restore_session(&mut self, session: SessionState) {
+        let SessionState {
+            path,
+            text,
+            base_text,
+            ..
+        } = session;
+        if let Some(path) = path {
+            // Read inline, however large: the edits replay onto the loaded file.
+            self.open_path_reading(path, text.is_none());
+            let Some(text) = text else {
+                return;
+            };
+            if self.doc.path.is_none() {
+                // The file could not be read; keep the edits as an untitled buffer.
+                self.restore_unsaved_text(text);
+                return;
+            }
+            let disk_text = self.doc.base_text.to_string();
+            if let Some(base_text) = base_text {
+                self.doc.base_text = Arc::new(base_text);
+            }
+            self.restore_unsaved_text(text);
+            match self.doc.disk_rev {
+                Some(disk_rev) => self.incorporate_disk_text(disk_text, disk_rev),
+                // The file no longer exists on disk: the edits are all that's left.
+                None => self.doc.base_text = Arc::new(String::new()),
+            }
+        } else if let Some(text) = text {
+            self.restore_unsaved_text(text);
+        }
+    }
+
+    /// Open a mergetool run's merge as an unsaved edit of its output file,
+    /// with the cursor on the first conflict.
+    pub(crate) fn start_merge_tool(&mut self, inputs: MergeInputs) {
+        let MergeInputs {
+            output,
+            output_text,
+            output_rev,
+            merged,
+        } = inputs;
+        self.load_document(output.clone(), output_text, output_rev);
+        self.reset_disk_sync_state();
+        if merged != self.doc.text.as_str() {
+            self.restore_unsaved_text(merged);
+        }
+        self.pending_line = conflict_starts_outside_fences(&self.doc.text)
+            .first()
+            .map(|&start| bytecount_newlines(&self.doc.text[..start]) + 1);
+        self.merge_tool = Some(MergeToolSession::new(output));
+    }
+
+    fn restore_unsaved_text(&mut self, text: String) {
+        self.doc.text = Arc::new(text);
+        self.doc.mark_text_changed();
+        self.doc.bump_edit_seq();
+        self.doc.refresh_stats_if_dirty();
+        self.nav.invalidate_outline();
+    }
+
+    /// Switch panes, keeping the view at the same place in the document.
+    pub fn set_mode(&mut self, mode: Mode, ctx: &egui::Context) {
         if self.mode == mode {
             return;
         }
@@ -121,10 +260,55 @@ impl RustdownApp {
             side_by_side_scroll_sync: self.side_by_side_scroll_sync,
             zoom_factor: zoom,
             mode: self.mode.as_str().to_owned(),
+            preview_code_wrap: self.preview_code_wrap,
+            preview_preserve_line_breaks: self.preview_preserve_line_breaks,
+            preview_render_html: self.preview_render_html,
+            editor_indent_with_tabs: self.editor_indent_with_tabs,
+            heading_scales: self.heading_scales,
+            reading_width: self.reading_width,
+            reading_width_chars: self.reading_width_chars,
+            merge_sidecar_dir: self.merge_sidecar.dir.clone(),
+            merge_sidecar_template: self.merge_sidecar.template.clone(),
+            disk_sync_mode: self.disk_sync_mode,
+            confirm_disk_merges: self.confirm_disk_merges,
+            timings: self.timings,
+            confirm_save_with_conflicts: self.confirm_save_with_conflicts,
+            fonts: self.fonts.clone(),
+            font_size: self.font_size,
+            title_stats: self.title_stats,
+            follow_markdown_links: self.follow_markdown_links,
+            export_theme: self.export_theme,
+            export_css: self.export_css.clone(),
+            smart_punctuation: self.smart_punctuation,
+            autosave: self.autosave,
+            format_on_save: self.format_on_save,
+            open_any_file: self.open_any_file,
+            code_fence_language: self.code_fence_language.clone(),
+            minimap: self.minimap,
+            image_max_width: self.image_max_width,
+            export_front_matter: self.export_front_matter,
+            window: self.window,
+            notes_root: self.notes_root.clone(),
+            wiki_links: self.wiki_links,
+            code_colors: self.code_colors.clone(),
         };
         prefs.save();
     }
 
+    /// Change the base text size, re-laying out the editor and preview.
+    pub(crate) fn set_font_size(&mut self, ctx: &egui::Context, size: f32) {
+        let size = ui_style::sanitized_font_size(size);
+        if (size - self.font_size).abs() < f32::EPSILON {
+            return;
+        }
+        self.font_size = size;
+        ui_style::apply_font_size(ctx, size);
+        // The editor galley and preview heights embed font sizes.
+        self.doc.editor_galley_cache = None;
+        self.doc.preview_cache.clear();
+        self.save_preferences();
+    }
+
     #[allow(clippy::unused_self)]
     pub(crate) fn adjust_zoom(&self, ctx: &egui::Context, delta: f32) {
         let z = zoom_with_step(ctx.zoom_factor(), delta);
@@ -139,18 +323,52 @@ impl RustdownApp {
         self.save_preferences_with_zoom(z);
     }
 
+    /// Keep the window above other windows, or let it stack normally.
+    pub(crate) fn set_always_on_top(&mut self, ctx: &egui::Context, always_on_top: bool) {
+        self.window.always_on_top = always_on_top;
+        ctx.send_viewport_cmd(egui::ViewportCommand::WindowLevel(window_level(
+            always_on_top,
+        )));
+        self.save_preferences_with_zoom(ctx.zoom_factor());
+    }
+
+    /// Record when the window is maximized or made fullscreen (by the user
+    /// or the window manager), so the next launch opens it the same way.
+    pub(crate) fn remember_window_state(&mut self, ctx: &egui::Context) {
+        let (Some(maximized), Some(fullscreen)) =
+            ctx.input(|i| (i.viewport().maximized, i.viewport().fullscreen))
+        else {
+            return;
+        };
+        if (maximized, fullscreen) != (self.window.maximized, self.window.fullscreen) {
+            self.window.maximized = maximized;
+            self.window.fullscreen = fullscreen;
+            self.save_preferences_with_zoom(ctx.zoom_factor());
+        }
+    }
+
     pub(crate) fn update_viewport_title(&mut self, ctx: &egui::Context) {
         // Avoid format! allocation when nothing changed.
         use std::fmt::Write;
         let ver = app_version();
-        let file_title = self.doc.title();
+        // Like the stats, the title follows the debounced text.
+        if !self.doc.stats_dirty {
+            self.doc.refresh_detected_title();
+        }
+        let file_title = self.doc.display_title();
         let dirty_mark = if self.doc.dirty { "*" } else { "" };
+        // Read from the debounced stats so typing doesn't retitle every frame.
+        let stats = title_stats_suffix(self.title_stats, &self.doc.stats());
 
-        // Quick length pre-check: "rustdown v" + ver + " - " + title + dirty
-        let expected_len = 13 + ver.len() + file_title.len() + dirty_mark.len();
+        // Quick length pre-check: "rustdown v" + ver + " - " + title + dirty + stats
+        let expected_len = 13 + ver.len() + file_title.len() + dirty_mark.len() + stats.len();
         if self.last_viewport_title.len() == expected_len
-            && self.last_viewport_title.ends_with(dirty_mark)
-            && self.last_viewport_title.contains(file_title.as_ref())
+            && self
+                .last_viewport_title
+                .strip_suffix(stats.as_str())
+                .is_some_and(|head| {
+                    head.ends_with(dirty_mark) && head.contains(file_title.as_ref())
+                })
         {
             return;
         }
@@ -158,13 +376,20 @@ impl RustdownApp {
         self.last_viewport_title.clear();
         let _ = write!(
             self.last_viewport_title,
-            "rustdown v{ver} - {file_title}{dirty_mark}"
+            "rustdown v{ver} - {file_title}{dirty_mark}{stats}"
         );
         ctx.send_viewport_cmd(egui::ViewportCommand::Title(
             self.last_viewport_title.clone(),
         ));
     }
 
+    pub(crate) fn set_title_stats(&mut self, title_stats: TitleStats) {
+        if self.title_stats != title_stats {
+            self.title_stats = title_stats;
+            self.save_preferences();
+        }
+    }
+
     pub(crate) const fn bump_edit_seq(&mut self) {
         self.doc.bump_edit_seq();
     }
@@ -175,15 +400,21 @@ impl RustdownApp {
         self.doc.refresh_stats_if_dirty();
     }
 
+    /// Recompute stats once the debounce has passed: inline for small
+    /// buffers, on a worker thread for ones over `ASYNC_STATS_MIN_BYTES`.
     pub(crate) fn refresh_stats_if_due(&mut self, ctx: &egui::Context) {
-        if !self.doc.stats_dirty {
+        if !self.doc.stats_dirty || self.doc.poll_stats_job() {
             return;
         }
-        if let Some(remaining) = self.doc.debounce_remaining(STATS_RECALC_DEBOUNCE) {
+        if let Some(remaining) = self.doc.debounce_remaining(self.timings.stats_debounce()) {
             ctx.request_repaint_after(remaining);
             return;
         }
-        self.refresh_stats_now();
+        if self.doc.text.len() >= ASYNC_STATS_MIN_BYTES {
+            self.doc.spawn_stats_job(ctx);
+        } else {
+            self.refresh_stats_now();
+        }
     }
 
     pub(crate) fn note_text_changed(&mut self, defer_stats_recalc: bool) {
@@ -207,6 +438,24 @@ impl RustdownApp {
         self.focus_search = false;
     }
 
+    /// Close the find bar and hand focus back to the editor, if it's shown.
+    /// The query is kept for the next time the bar opens.
+    pub(crate) fn close_search_to_editor(&mut self, ctx: &egui::Context) {
+        self.close_search();
+        if self.mode != Mode::Preview {
+            ctx.memory_mut(|mem| mem.request_focus(egui::Id::new("editor")));
+        }
+    }
+
+    /// Open the find bar, or close it if it's already open.
+    pub(crate) fn toggle_search(&mut self, ctx: &egui::Context) {
+        if self.search.visible {
+            self.close_search_to_editor(ctx);
+        } else {
+            self.open_search(false);
+        }
+    }
+
     pub(crate) fn replace_all_matches(&mut self) -> usize {
         let (text, replaced) = replace_all_occurrences(
             self.doc.text.as_str(),
@@ -221,18 +470,388 @@ impl RustdownApp {
         replaced
     }
 
+    /// Apply an editor command to the text and the editor's selection, then
+    /// move the cursor to the selection the command returns.
+    pub(crate) fn edit_editor_selection(
+        &mut self,
+        ctx: &egui::Context,
+        command: impl FnOnce(&str, Range<usize>) -> SelectionEdit,
+    ) {
+        let id = egui::Id::new("editor");
+        let selection = egui::TextEdit::load_state(ctx, id)
+            .and_then(|state| state.cursor.char_range())
+            .map_or(0..0, |range| range.as_sorted_char_range());
+        let edit = command(self.doc.text.as_str(), selection);
+        if edit.text != self.doc.text.as_str() {
+            add_editor_undo_point(ctx, &self.doc.text);
+            self.doc.text = Arc::new(edit.text);
+            self.bump_edit_seq();
+            self.note_text_changed(true);
+        }
+        let mut state = egui::TextEdit::load_state(ctx, id).unwrap_or_default();
+        state
+            .cursor
+            .set_char_range(Some(egui::text::CCursorRange::two(
+                egui::text::CCursor::new(edit.selection.start),
+                egui::text::CCursor::new(edit.selection.end),
+            )));
+        state.store(ctx, id);
+    }
+
+    /// Indent (or, with `dedent`, outdent) the editor selection using the
+    /// configured Tab behaviour and the document's `.editorconfig` width.
+    pub(crate) fn indent_editor_selection(&mut self, ctx: &egui::Context, dedent: bool) {
+        let width = self.doc.indent_size;
+        if dedent {
+            self.edit_editor_selection(ctx, |text, selection| {
+                editor::dedent_selection(text, selection, width)
+            });
+        } else {
+            let indent = if self.editor_indent_with_tabs {
+                "\t".to_owned()
+            } else {
+                " ".repeat(width)
+            };
+            self.edit_editor_selection(ctx, |text, selection| {
+                editor::indent_selection(text, selection, &indent)
+            });
+        }
+    }
+
     pub(crate) fn format_document(&mut self) {
-        let options = format::options_for_path(self.doc.path.as_deref());
-        let formatted = format::format_markdown(self.doc.text.as_str(), options);
+        self.format_text(format::options_for_path(self.doc.path.as_deref()));
+    }
+
+    fn format_text(&mut self, options: format::FormatOptions) {
+        self.apply_formatted(format::format_markdown(self.doc.text.as_str(), options));
+    }
+
+    /// Align the pipes and columns of every table in the document.
+    pub(crate) fn reformat_tables(&mut self) {
+        self.apply_formatted(format::reformat_tables(self.doc.text.as_str()));
+    }
+
+    /// Replace the buffer with a formatted version of itself, keeping the
+    /// editor cursor on its line.
+    fn apply_formatted(&mut self, formatted: String) {
         if formatted == self.doc.text.as_str() {
             return;
         }
 
-        self.doc.text = Arc::new(formatted);
+        let before = std::mem::replace(&mut self.doc.text, Arc::new(formatted));
+        self.format_cursor_base.get_or_insert(before);
         self.bump_edit_seq();
         self.note_text_changed(false);
     }
 
+    /// The selected Markdown in the editor, if anything is selected.
+    pub(crate) fn editor_selection_text<'a>(ctx: &egui::Context, text: &'a str) -> Option<&'a str> {
+        let range = egui::TextEdit::load_state(ctx, egui::Id::new("editor"))?
+            .cursor
+            .char_range()?
+            .as_sorted_char_range();
+        let start = editor::char_index_to_byte(text, range.start);
+        let end = editor::char_index_to_byte(text, range.end);
+        (start < end).then(|| &text[start..end])
+    }
+
+    /// Show the editor selection rendered on its own, or close the one
+    /// shown.
+    pub(crate) fn toggle_selection_preview(&mut self, ctx: &egui::Context) {
+        if self.selection_preview.take().is_some() {
+            return;
+        }
+        match Self::editor_selection_text(ctx, &self.doc.text) {
+            Some(selected) => {
+                self.selection_preview = Some(SelectionPreview {
+                    text: selected.to_owned(),
+                    cache: rustdown_md::MarkdownCache::default(),
+                });
+            }
+            None => self.error = Some("Select some Markdown to preview".to_owned()),
+        }
+    }
+
+    /// Fence the selected lines, or insert an empty code block, tagged with
+    /// `language`, which is remembered for the next block.
+    pub(crate) fn insert_code_block(&mut self, ctx: &egui::Context, language: &str) {
+        let language = language.trim();
+        self.edit_editor_selection(ctx, |text, selection| {
+            editor::insert_code_fence(text, selection, language)
+        });
+        language.clone_into(&mut self.code_fence_language);
+    }
+
+    /// Show the document in the system file manager, selected where the
+    /// platform allows, or with `select` unset just open its folder.
+    pub(crate) fn reveal_document(&mut self, select: bool) {
+        let Some(path) = self.doc.path.clone() else {
+            self.error = Some("Save the document first to show it in its folder".to_owned());
+            return;
+        };
+        if let Err(err) = reveal_in_file_manager(&path, select) {
+            self.error = Some(format!("Couldn't open the file manager: {err}"));
+        }
+    }
+
+    /// Copy the document, rendered as in the preview but without markup, to
+    /// the clipboard.
+    pub(crate) fn copy_plain_text(&self, ctx: &egui::Context) {
+        ctx.copy_text(rustdown_md::plain_text(
+            self.doc.text.as_str(),
+            self.plain_text_parse_options(),
+        ));
+    }
+
+    /// The document as a standalone HTML document with the export
+    /// stylesheet: the custom CSS file when one is set, else the preset
+    /// sized by the heading scales.  `None` (with the error shown) when the
+    /// CSS file can't be read.
+    pub(crate) fn html_document(&mut self) -> Option<String> {
+        let stylesheet = match &self.export_css {
+            Some(path) => match fs::read_to_string(path) {
+                Ok(css) => css,
+                Err(err) => {
+                    self.error = Some(format!(
+                        "Couldn't read stylesheet {}: {err}",
+                        path.display()
+                    ));
+                    return None;
+                }
+            },
+            None => self
+                .export_theme
+                .html_theme()
+                .stylesheet(self.heading_scales.0),
+        };
+        Some(rustdown_md::markdown_to_html_document(
+            self.doc.text.as_str(),
+            &self.doc.title(),
+            &stylesheet,
+            self.html_parse_options(),
+        ))
+    }
+
+    /// Ask where to export the document as HTML, next to it by default.
+    pub(crate) fn export_html(&mut self) {
+        let stem = self
+            .doc
+            .path
+            .as_deref()
+            .and_then(Path::file_stem)
+            .map_or(Cow::Borrowed("Untitled"), |stem| stem.to_string_lossy());
+        let mut dialog = rfd::FileDialog::new()
+            .add_filter("HTML", &["html", "htm"])
+            .set_file_name(format!("{stem}.html"));
+        if let Some(dir) = self.doc.path.as_deref().and_then(Path::parent) {
+            dialog = dialog.set_directory(dir);
+        }
+        if let Some(path) = dialog.save_file() {
+            self.write_html_export(&path);
+        }
+    }
+
+    pub(crate) fn write_html_export(&mut self, path: &Path) -> bool {
+        let Some(html) = self.html_document() else {
+            return false;
+        };
+        match atomic_write_utf8(path, &html) {
+            Ok(()) => {
+                self.error = None;
+                true
+            }
+            Err(err) => {
+                self.error = Some(format!("Export failed: {err}"));
+                false
+            }
+        }
+    }
+
+    /// Use the `theme` preset for HTML, dropping any custom stylesheet.
+    pub(crate) fn set_export_theme(&mut self, theme: ExportTheme) {
+        if self.export_theme != theme || self.export_css.is_some() {
+            self.export_theme = theme;
+            self.export_css = None;
+            self.save_preferences();
+        }
+    }
+
+    /// Ask for a CSS file to embed in HTML instead of the preset.
+    pub(crate) fn pick_export_css(&mut self) {
+        if let Some(path) = rfd::FileDialog::new()
+            .add_filter("CSS", &["css"])
+            .pick_file()
+        {
+            self.export_css = Some(path);
+            self.save_preferences();
+        }
+    }
+
+    /// Copy the document as a standalone HTML document to the clipboard,
+    /// with a plain-text flavour for targets that can't paste rich text.
+    pub(crate) fn copy_html(&mut self, ctx: &egui::Context) {
+        let Some(html) = self.html_document() else {
+            return;
+        };
+        let plain =
+            rustdown_md::plain_text(self.doc.text.as_str(), self.plain_text_parse_options());
+        let result = self
+            .system_clipboard()
+            .and_then(|clipboard| clipboard.set_html(html.as_str(), Some(plain.as_str())));
+        if let Err(err) = result {
+            ctx.copy_text(html);
+            self.error = Some(format!(
+                "Rich clipboard unavailable ({err}); copied the HTML source as text"
+            ));
+        }
+    }
+
+    /// The system clipboard, opened on first use.
+    fn system_clipboard(&mut self) -> Result<&mut arboard::Clipboard, arboard::Error> {
+        match &mut self.clipboard {
+            Some(clipboard) => Ok(clipboard),
+            clipboard @ None => arboard::Clipboard::new().map(|opened| clipboard.insert(opened)),
+        }
+    }
+
+    /// Paste the clipboard's image, if it holds one.  Called only when the
+    /// paste keys pasted no text.
+    pub(crate) fn paste_clipboard_image(&mut self, ctx: &egui::Context) {
+        let Ok(clipboard) = self.system_clipboard() else {
+            return;
+        };
+        if let Ok(image) = clipboard.get_image() {
+            self.paste_image(ctx, image.into());
+        }
+    }
+
+    /// Save `image` beside the document and link it at the cursor.  A
+    /// document without a file is asked to be saved first.
+    pub(crate) fn paste_image(&mut self, ctx: &egui::Context, image: PastedImage) {
+        let Some(doc_path) = self.doc.path.clone() else {
+            self.pending_image_paste = Some(image);
+            return;
+        };
+        match pasted_image::save_beside(&doc_path, &image) {
+            Ok(markdown) => self.edit_editor_selection(ctx, |text, selection| {
+                editor::replace_selection(text, selection, &markdown)
+            }),
+            Err(err) => self.error = Some(format!("Paste image failed: {err}")),
+        }
+    }
+
+    /// Save the document under a new name, then paste the image waiting on
+    /// it.  Cancelling the save keeps the prompt up.
+    pub(crate) fn save_then_paste_image(&mut self, ctx: &egui::Context) {
+        if self.save_doc(true)
+            && let Some(image) = self.pending_image_paste.take()
+        {
+            self.paste_image(ctx, image);
+        }
+    }
+
+    /// Carry on after "Save anyway" with what the save was for: the action
+    /// behind the unsaved-changes prompt, or the image waiting to be pasted.
+    pub(crate) fn finish_confirmed_save(&mut self, ctx: &egui::Context) {
+        if self.pending_action.is_some() {
+            self.apply_pending_action_and_close_dialog();
+        } else if let Some(image) = self.pending_image_paste.take() {
+            self.paste_image(ctx, image);
+        }
+    }
+
+    /// Take a preview link click that points at a local Markdown file out of
+    /// this frame's output and open the file here instead of in the browser,
+    /// behind the unsaved-changes prompt.  Wiki links are always taken, as
+    /// nothing else could open them.
+    pub(crate) fn follow_markdown_links(&mut self, ctx: &egui::Context) {
+        let follow_files = self.follow_markdown_links;
+        let doc_dir = self.doc.path.as_deref().and_then(Path::parent);
+        let (target, note) = ctx.output_mut(|output| {
+            let mut target = None;
+            let mut note = None;
+            output.commands.retain(|command| match command {
+                egui::OutputCommand::OpenUrl(open) if target.is_none() && note.is_none() => {
+                    if let Some(name) = rustdown_md::wiki_link_target(&open.url) {
+                        note = Some(name.to_owned());
+                        return false;
+                    }
+                    if follow_files {
+                        target = markdown_link_target(&open.url, doc_dir);
+                    }
+                    target.is_none()
+                }
+                _ => true,
+            });
+            (target, note)
+        });
+        if let Some(path) = target {
+            self.request_action(PendingAction::Open(path));
+        }
+        if let Some(name) = note {
+            self.follow_wiki_link(&name);
+        }
+    }
+
+    /// Open the note a wiki link names: `Name.md` beside the document, else
+    /// a file of that name in the quick-open folder.  When there is none,
+    /// offer to create it beside the document.
+    pub(crate) fn follow_wiki_link(&mut self, target: &str) {
+        let name = target.split('#').next().unwrap_or(target).trim();
+        if name.is_empty() {
+            return;
+        }
+        let root = self.quick_open_root();
+        let doc_dir = self
+            .doc
+            .path
+            .as_deref()
+            .and_then(Path::parent)
+            .map(Path::to_path_buf);
+        let Some(dir) = doc_dir.or_else(|| root.clone()) else {
+            self.error = Some("Save the document first to follow wiki links".to_owned());
+            return;
+        };
+        let Some(beside) = wiki_note_path(&dir, name) else {
+            self.error = Some(format!(
+                "Wiki link [[{name}]] points outside the notes folder"
+            ));
+            return;
+        };
+        let found = if beside.is_file() {
+            Some(beside.clone())
+        } else {
+            root.and_then(|root| self.quick_open.find_note(&root, name))
+        };
+        match found {
+            Some(path) => self.request_action(PendingAction::Open(path)),
+            None => self.pending_new_note = Some(beside),
+        }
+    }
+
+    /// Create the note a wiki link named, titled by its name, and open it.
+    pub(crate) fn create_pending_note(&mut self) {
+        let Some(path) = self.pending_new_note.take() else {
+            return;
+        };
+        if !path.exists() {
+            let title = path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            let written = path
+                .parent()
+                .map_or(Ok(()), fs::create_dir_all)
+                .and_then(|()| atomic_write_utf8(&path, &format!("# {title}\n")));
+            if let Err(err) = written {
+                self.error = Some(format!("Couldn't create {}: {err}", path.display()));
+                return;
+            }
+            self.quick_open.mark_stale();
+        }
+        self.request_action(PendingAction::Open(path));
+    }
+
     pub(crate) fn request_action(&mut self, action: PendingAction) {
         if self.doc.dirty {
             self.pending_action = Some(action);
@@ -254,6 +873,16 @@ impl RustdownApp {
             .as_deref()
             .map_or_else(String::new, |p| default_image_uri_scheme(Some(p)));
         let next_seq = self.doc.edit_seq.wrapping_add(1);
+        let options = path
+            .as_deref()
+            .map(|path| format::options_for_path(Some(path)));
+        let wrap_column = options.and_then(|options| options.max_line_length);
+        let indent_size = options
+            .and_then(|options| options.indent_size)
+            .unwrap_or(editor::DEFAULT_INDENT_SIZE);
+        let tab_width = options
+            .and_then(|options| options.tab_width)
+            .unwrap_or(editor::DEFAULT_INDENT_SIZE);
         self.doc = Document {
             path,
             image_uri_scheme,
@@ -268,8 +897,17 @@ impl RustdownApp {
             last_edit_at: None,
             edit_seq: next_seq,
             editor_galley_cache: None,
+            wrap_column,
+            folding: Folding::default(),
+            line_changes: LineChanges::default(),
+            indent_size,
+            tab_width,
+            stats_job: None,
+            detected_title: None,
+            unresolved_conflicts: None,
         };
         self.disk.merge_sidecar_path = None;
+        self.format_cursor_base = None;
         self.nav.invalidate_outline();
         self.clear_side_by_side_scroll_state();
     }
@@ -315,24 +953,166 @@ impl RustdownApp {
     }
 
     pub(crate) fn open_file(&mut self) {
-        let Some(path) = markdown_file_dialog().pick_file() else {
+        let Some(path) = markdown_file_dialog(self.open_any_file).pick_file() else {
             return;
         };
         self.request_action(PendingAction::Open(path));
     }
 
+    /// Folder quick open searches: the configured notes folder, else the
+    /// open document's folder.
+    pub(crate) fn quick_open_root(&self) -> Option<PathBuf> {
+        self.notes_root.clone().or_else(|| {
+            self.doc
+                .path
+                .as_deref()
+                .and_then(Path::parent)
+                .filter(|dir| !dir.as_os_str().is_empty())
+                .map(Path::to_path_buf)
+        })
+    }
+
+    /// Show or hide quick open, listing the folder's files if needed.
+    pub(crate) fn toggle_quick_open(&mut self, ctx: &egui::Context) {
+        if self.quick_open.visible {
+            self.quick_open.visible = false;
+            return;
+        }
+        let Some(root) = self.quick_open_root() else {
+            self.error = Some(
+                "Set a notes folder (notes_root) or open a file to quick-open from its folder"
+                    .to_owned(),
+            );
+            return;
+        };
+        self.quick_open.ensure_index(ctx, &root);
+        self.quick_open.visible = true;
+        self.quick_open.focus = true;
+        self.quick_open.selected = 0;
+    }
+
+    /// Open a quick-open match (a path relative to `root`) through the
+    /// usual unsaved-changes prompt.
+    pub(crate) fn open_quick_open_match(&mut self, root: &Path, relative: &str) {
+        self.quick_open.visible = false;
+        self.request_action(PendingAction::Open(root.join(relative)));
+    }
+
+    /// Pick the notes folder quick open searches, and list it.
+    pub(crate) fn pick_notes_root(&mut self, ctx: &egui::Context) {
+        if let Some(dir) = rfd::FileDialog::new().pick_folder() {
+            self.quick_open.ensure_index(ctx, &dir);
+            self.notes_root = Some(dir);
+            self.save_preferences_with_zoom(ctx.zoom_factor());
+        }
+    }
+
+    /// Follow the wiki link or link path being typed at the editor cursor,
+    /// listing the notes folder for its completions if needed.
+    pub(crate) fn refresh_completion(&mut self, ctx: &egui::Context) {
+        let id = egui::Id::new("editor");
+        let focused = ctx.memory(|m| m.has_focus(id));
+        let text = self.doc.text.as_str();
+        let token = egui::TextEdit::load_state(ctx, id)
+            .and_then(|state| state.cursor.char_range())
+            .filter(|range| focused && range.primary.index == range.secondary.index)
+            .and_then(|range| {
+                completion::token_at(text, editor::char_index_to_byte(text, range.primary.index))
+            });
+        let root = token.as_ref().and_then(|_| self.quick_open_root());
+        if let Some(root) = &root {
+            self.quick_open.ensure_index(ctx, root);
+        }
+        let doc_dir = self.doc.path.as_deref().and_then(Path::parent);
+        // Until the folder is listed there is nothing to offer.
+        match root
+            .as_deref()
+            .and_then(|root| Some((root, self.quick_open.files(root)?)))
+        {
+            Some((root, files)) => self.completion.refresh(text, token, files, root, doc_dir),
+            None => self
+                .completion
+                .refresh(text, None, &[], Path::new(""), None),
+        }
+    }
+
+    /// Put completion `index` in place of the token being typed.
+    pub(crate) fn accept_completion(&mut self, ctx: &egui::Context, index: usize) {
+        let mut completion = std::mem::take(&mut self.completion);
+        self.edit_editor_selection(ctx, |text, selection| {
+            completion
+                .accept(text, index)
+                .unwrap_or_else(|| SelectionEdit {
+                    text: text.to_owned(),
+                    selection,
+                })
+        });
+        self.completion = completion;
+    }
+
+    /// Open `path`, replacing the current document.  Files of at least
+    /// `ASYNC_OPEN_MIN_BYTES` are read on the disk-read worker and applied
+    /// when the read completes; smaller ones are read inline, and ones over
+    /// `MAX_FILE_BYTES` are refused straight away.
     pub(crate) fn open_path(&mut self, path: PathBuf) {
-        match read_stable_utf8(&path) {
+        self.open_path_reading(path, true);
+    }
+
+    /// [`Self::open_path`], reading `path` on the worker only with
+    /// `allow_async` and a large enough file.
+    fn open_path_reading(&mut self, path: PathBuf, allow_async: bool) {
+        self.disk.open_nonce = self.disk.open_nonce.wrapping_add(1);
+        self.disk.opening = None;
+        match fs::metadata(&path).map(|meta| meta.len()) {
+            Ok(len) if len > MAX_FILE_BYTES => self.finish_open(path, Err(file_too_large(len))),
+            Ok(len) if allow_async && len >= ASYNC_OPEN_MIN_BYTES => self.start_open_read(path),
+            _ => {
+                let result = read_stable_utf8(&path);
+                self.finish_open(path, result);
+            }
+        }
+    }
+
+    /// Load the result of reading `path` for opening, or report why it
+    /// can't be opened.  A missing file opens as a new, empty document.
+    pub(crate) fn finish_open(
+        &mut self,
+        path: PathBuf,
+        result: io::Result<(String, DiskRevision)>,
+    ) {
+        let name = path.file_name().map_or_else(
+            || path.display().to_string(),
+            |name| name.to_string_lossy().into_owned(),
+        );
+        match result {
+            Ok((text, _)) if memchr::memchr(0, text.as_bytes()).is_some() => {
+                self.error
+                    .get_or_insert_with(|| format!("Open failed: {name} isn't a text file"));
+            }
             Ok((text, disk_rev)) => {
+                let scroll_byte = self.scroll_positions.get(&path);
                 self.load_document(path, text, Some(disk_rev));
                 self.error = None;
                 self.reset_disk_sync_state();
+                if let Some(byte_offset) = scroll_byte {
+                    self.nav.pending_scroll = Some(nav::panel::NavScrollTarget::ByteOffset(
+                        byte_offset.min(self.doc.text.len()),
+                    ));
+                }
             }
             Err(err) if err.kind() == io::ErrorKind::NotFound => {
                 self.load_document(path, String::new(), None);
                 self.error = None;
                 self.reset_disk_sync_state();
             }
+            Err(err) if err.kind() == io::ErrorKind::InvalidData => {
+                self.error
+                    .get_or_insert_with(|| format!("Open failed: {name} isn't UTF-8 text"));
+            }
+            Err(err) if err.kind() == io::ErrorKind::FileTooLarge => {
+                self.error
+                    .get_or_insert_with(|| format!("Open failed: {name} is {err}"));
+            }
             Err(err) => {
                 self.error
                     .get_or_insert_with(|| format!("Open failed: {err}"));
@@ -344,10 +1124,154 @@ impl RustdownApp {
         if !save_as && let Some(path) = self.doc.path.clone() {
             return Some((path, false));
         }
-        markdown_file_dialog().save_file().map(|path| (path, true))
+        markdown_file_dialog(false)
+            .save_file()
+            .map(|path| (path, true))
     }
 
+    /// Save a dirty, file-backed document once edits have paused for the
+    /// autosave delay.  Open conflicts, pending prompts and conflict
+    /// markers hold it off, as does a mergetool run, which a save ends; a
+    /// failed save waits for the next edit.
+    pub(crate) fn autosave_if_due(&mut self, ctx: &egui::Context) {
+        if !self.autosave
+            || !self.doc.dirty
+            || self.doc.path.is_none()
+            || self.disk.conflict.is_some()
+            || self.disk.deleted
+            || self.pending_conflict_save.is_some()
+            || self.merge_tool.is_some()
+            || self.autosave_failed_seq == Some(self.doc.edit_seq)
+        {
+            return;
+        }
+        if let Some(remaining) = self.doc.debounce_remaining(self.timings.autosave_delay()) {
+            ctx.request_repaint_after(remaining);
+            return;
+        }
+        if self.doc.has_unresolved_conflicts() {
+            return;
+        }
+        // Formatting under the cursor mid-session would fight the typing.
+        if !self.write_doc(false, false) {
+            self.autosave_failed_seq = Some(self.doc.edit_seq);
+        }
+    }
+
+    /// Move the editor's cursor and selection from where they were in the
+    /// text before the last format to the same line and column after it.
+    pub(crate) fn remap_cursor_after_format(&mut self, ctx: &egui::Context) {
+        let Some(before) = self.format_cursor_base.take() else {
+            return;
+        };
+        let id = egui::Id::new("editor");
+        let Some(mut state) = egui::TextEdit::load_state(ctx, id) else {
+            return;
+        };
+        let Some(range) = state.cursor.char_range() else {
+            return;
+        };
+        let remap = |cursor: egui::text::CCursor| egui::text::CCursor {
+            index: format::remap_char_index(&before, &self.doc.text, cursor.index),
+            ..cursor
+        };
+        state.cursor.set_char_range(Some(egui::text::CCursorRange {
+            primary: remap(range.primary),
+            secondary: remap(range.secondary),
+            ..range
+        }));
+        state.store(ctx, id);
+    }
+
+    /// Make the last disk-driven replacement undoable: the text it replaced
+    /// becomes an undo point of the editor, so Ctrl+Z brings back what was
+    /// on screen and Ctrl+Y returns to the disk version.
+    pub(crate) fn add_reload_undo_point(&mut self, ctx: &egui::Context) {
+        if let Some(previous) = self.disk.replaced_text.take() {
+            add_editor_undo_point(ctx, &previous);
+        }
+    }
+
+    /// Put the saved lines of the gutter marker at `index` back into the
+    /// buffer, as an edit of its own that Ctrl+Z takes back.
+    pub(crate) fn revert_line_change(&mut self, ctx: &egui::Context, index: usize) {
+        let Some(change) = self.doc.line_changes.markers.get(index).cloned() else {
+            return;
+        };
+        let base = Arc::clone(&self.doc.base_text);
+        self.edit_editor_selection(ctx, |text, _| changes::revert_change(&base, text, &change));
+    }
+
+    /// Put the editor cursor on the next conflict block after it (wrapping
+    /// to the first) and scroll there, switching out of Preview if needed.
+    pub(crate) fn go_to_next_conflict(&mut self, ctx: &egui::Context) {
+        let starts = conflict_starts_outside_fences(&self.doc.text);
+        let cursor_byte = egui::TextEdit::load_state(ctx, egui::Id::new("editor"))
+            .and_then(|state| state.cursor.char_range())
+            .map_or(0, |range| {
+                editor::char_index_to_byte(&self.doc.text, range.primary.index)
+            });
+        let Some(&target) = starts
+            .iter()
+            .find(|&&start| start > cursor_byte)
+            .or_else(|| starts.first())
+        else {
+            return;
+        };
+
+        if self.mode == Mode::Preview {
+            self.set_mode(Mode::Edit, ctx);
+        }
+        self.put_editor_cursor(ctx, target);
+    }
+
+    /// Put the cursor on the line given on the command line, once the
+    /// document it was given for has been read.
+    pub(crate) fn go_to_pending_line(&mut self, ctx: &egui::Context) {
+        if self.disk.opening.is_some() {
+            return;
+        }
+        if let Some(line) = self.pending_line.take() {
+            self.put_editor_cursor(ctx, editor::line_start(&self.doc.text, line - 1));
+        }
+    }
+
+    /// Focus the editor with the cursor at `byte` and scroll there.
+    fn put_editor_cursor(&mut self, ctx: &egui::Context, byte: usize) {
+        let id = egui::Id::new("editor");
+        let mut state = egui::TextEdit::load_state(ctx, id).unwrap_or_default();
+        let char_index = self.doc.text[..byte].chars().count();
+        state
+            .cursor
+            .set_char_range(Some(egui::text::CCursorRange::one(
+                egui::text::CCursor::new(char_index),
+            )));
+        state.store(ctx, id);
+        ctx.memory_mut(|mem| mem.request_focus(id));
+        self.nav.pending_scroll = Some(nav::panel::NavScrollTarget::ByteOffset(byte));
+    }
+
+    /// Save the document, to a new path with `save_as`.  While conflict
+    /// blocks remain and `confirm_save_with_conflicts` is on, nothing is
+    /// saved yet: the confirmation is asked for and `false` returned, and
+    /// "Save anyway" saves and carries on with the prompt that asked.
     pub(crate) fn save_doc(&mut self, save_as: bool) -> bool {
+        if self.confirm_save_with_conflicts && self.doc.has_unresolved_conflicts() {
+            self.pending_conflict_save = Some(save_as);
+            return false;
+        }
+        self.write_doc(save_as, self.formats_on_save())
+    }
+
+    /// Whether a save formats first: with `format_on_save`, except in a
+    /// mergetool run, whose OUTPUT git should get as it was edited.
+    pub(crate) const fn formats_on_save(&self) -> bool {
+        self.format_on_save && self.merge_tool.is_none()
+    }
+
+    /// Save the document whatever it contains, after the pre-save reload,
+    /// formatting it first with `format`.
+    pub(crate) fn write_doc(&mut self, save_as: bool, format: bool) -> bool {
         let Some((path, update_doc_path)) = self.save_path_choice(save_as) else {
             return false;
         };
@@ -369,18 +1293,41 @@ impl RustdownApp {
             return false;
         }
 
+        // Formatting follows the pre-save reload so it covers merged-in
+        // disk changes, and uses the target path's editorconfig.
+        if format {
+            self.format_text(format::options_for_path(Some(path.as_path())));
+        }
+
         match atomic_write_utf8(&path, self.doc.text.as_str()) {
             Ok(()) => {
                 if update_doc_path {
+                    self.quick_open.mark_stale();
                     self.doc.path = Some(path.clone());
                     self.doc.image_uri_scheme = default_image_uri_scheme(Some(path.as_path()));
+                    let options = format::options_for_path(Some(path.as_path()));
+                    self.doc.wrap_column = options.max_line_length;
+                    self.doc.indent_size =
+                        options.indent_size.unwrap_or(editor::DEFAULT_INDENT_SIZE);
+                    self.doc.tab_width = options.tab_width.unwrap_or(editor::DEFAULT_INDENT_SIZE);
                 }
                 self.doc.dirty = false;
                 self.doc.base_text = self.doc.text.clone();
                 self.doc.disk_rev = disk_revision(&path).ok();
-
                 self.error = None;
-                self.reset_disk_sync_state();
+                if let Some(tool) = self.merge_tool.as_mut()
+                    && tool.output == path
+                {
+                    tool.note_saved(&self.doc.text);
+                    if !tool.saved {
+                        self.error = Some(
+                            "Saved with conflicts left; resolve them and save to finish the merge"
+                                .to_owned(),
+                        );
+                    }
+                }
+
+                self.note_disk_saved();
                 true
             }
             Err(err) => {
@@ -390,24 +1337,40 @@ impl RustdownApp {
         }
     }
 
-    pub(crate) fn write_merge_sidecar(&mut self, doc_path: &Path, conflict_marked: &str) {
-        let sidecar_path = match next_merge_sidecar_path(doc_path) {
+    /// Write `contents` to the next free merge sidecar beside `doc_path` and
+    /// surface its path in the status bar.  Returns `false` (with the error
+    /// reported) if nothing was written.
+    pub(crate) fn write_merge_sidecar(&mut self, doc_path: &Path, contents: &str) -> bool {
+        let sidecar_path = match next_merge_sidecar_path(doc_path, &self.merge_sidecar) {
             Ok(path) => path,
             Err(err) => {
                 self.error
                     .get_or_insert_with(|| format!("Merge file path failed: {err}"));
-                return;
+                return false;
             }
         };
-        match atomic_write_utf8(&sidecar_path, conflict_marked) {
-            Ok(()) => self.disk.merge_sidecar_path = Some(sidecar_path),
+        match atomic_write_utf8(&sidecar_path, contents) {
+            Ok(()) => {
+                self.disk.merge_sidecar_path = Some(sidecar_path);
+                true
+            }
             Err(err) => {
                 self.error
                     .get_or_insert_with(|| format!("Merge file write failed: {err}"));
+                false
             }
         }
     }
 
+    /// Keep editing a document whose file was deleted: its text becomes
+    /// unsaved new content for the same path, which saving recreates.
+    pub(crate) fn keep_deleted_document(&mut self) {
+        self.disk.deleted = false;
+        self.doc.disk_rev = None;
+        self.doc.base_text = Arc::new(String::new());
+        self.doc.dirty = true;
+    }
+
     pub(crate) fn apply_conflict_choice(&mut self, choice: ConflictChoice) {
         let Some(conflict) = self.disk.conflict.take() else {
             return;
@@ -434,6 +1397,25 @@ impl RustdownApp {
                     self.write_merge_sidecar(doc_path.as_path(), conflict_marked.as_str());
                 }
             }
+            ConflictChoice::KeepBoth => {
+                // Only drop our edits from the buffer once they are safely on disk.
+                let Some(doc_path) = self.doc.path.clone() else {
+                    self.disk.conflict = Some(conflict);
+                    return;
+                };
+                let ours = self.doc.text.clone();
+                if !self.write_merge_sidecar(doc_path.as_path(), ours.as_str()) {
+                    self.disk.conflict = Some(conflict);
+                    return;
+                }
+                let disk_text = Arc::new(conflict.disk_text);
+                self.apply_disk_text_state(
+                    disk_text.clone(),
+                    disk_text,
+                    conflict.disk_rev,
+                    ReloadKind::Clean,
+                );
+            }
             ConflictChoice::SaveAs => {
                 // Save-as switches the active path, so the conflict prompt is no longer relevant.
                 if !self.save_doc(true) {
@@ -476,3 +1458,15 @@ impl RustdownApp {
         self.reset_disk_sync_state();
     }
 }
+
+/// Make `previous` an undo point of the editor at its current cursor, so
+/// Ctrl+Z brings it back after the buffer is replaced outside the editor.
+fn add_editor_undo_point(ctx: &egui::Context, previous: &str) {
+    let id = egui::Id::new("editor");
+    let mut state = egui::TextEdit::load_state(ctx, id).unwrap_or_default();
+    let cursor = state.cursor.char_range().unwrap_or_default();
+    let mut undoer = state.undoer();
+    undoer.add_undo(&(cursor, previous.to_owned()));
+    state.set_undoer(undoer);
+    state.store(ctx, id);
+}
@@ -1,16 +1,26 @@
-use std::cell::Cell;
+use std::{
+    borrow::Cow,
+    cell::{Cell, RefCell},
+    time::{Duration, SystemTime},
+};
 
 use eframe::egui;
-use rustdown_md::{MarkdownStyle, MarkdownViewer};
+use rustdown_md::{MarkdownStyle, MarkdownViewer, markdown_layout_job_with_code_colors};
 
 use super::{
-    BundledDoc, ConflictChoice, DEBOUNCE, Mode, PANEL_EDGE_PADDING, PendingAction, RustdownApp,
-    SCROLL_WHEEL_MULTIPLIER, SaveTrigger, ZOOM_STEP, first_markdown_path,
+    BundledDoc, ConflictChoice, Mode, PANEL_EDGE_PADDING, PendingAction, RustdownApp,
+    SCROLL_WHEEL_MULTIPLIER, SaveTrigger, ZOOM_STEP, disk_rev_label, first_dropped_path,
     save_trigger_from_shortcut,
 };
 use crate::{
-    document::{Document, EditorGalleyCache, TrackedTextBuffer},
-    editor, highlight,
+    changes::{self, LineChange, LineChangeKind},
+    disk::sync::DiskSyncMode,
+    document::{CursorPosition, Document, EditorGalleyCache, SelectionStats, TrackedTextBuffer},
+    editor,
+    fold::{self, Folding},
+    minimap,
+    nav::outline,
+    preferences::{ExportTheme, FrontMatterExport, TitleStats},
 };
 
 impl RustdownApp {
@@ -18,14 +28,17 @@ impl RustdownApp {
     /// zoom, search, format, mode-cycle, etc.).  Skipped when a dialog is open
     /// so that shortcuts do not fire behind modal windows.
     pub(crate) fn handle_keyboard_shortcuts(&mut self, ctx: &egui::Context) {
-        let dialog_open = self.pending_action.is_some() || self.disk.conflict.is_some();
+        let dialog_open = self.pending_action.is_some()
+            || self.pending_conflict_save.is_some()
+            || self.disk.conflict.is_some();
 
         let dropped_path = ctx.input(|i| {
-            first_markdown_path(
+            first_dropped_path(
                 i.raw
                     .dropped_files
                     .iter()
                     .filter_map(|file| file.path.as_deref()),
+                self.open_any_file,
             )
         });
         let (
@@ -33,6 +46,7 @@ impl RustdownApp {
             save_trigger,
             new_doc,
             cycle_mode,
+            jump_mode,
             search,
             replace_all,
             format_doc,
@@ -43,6 +57,12 @@ impl RustdownApp {
             toggle_nav,
             open_demo,
             open_verification,
+            bold,
+            italic,
+            copy_html,
+            code_block,
+            quick_open,
+            selection_preview,
         ) = ctx.input(|i| {
             let cmd = i.modifiers.command;
             (
@@ -50,6 +70,11 @@ impl RustdownApp {
                 save_trigger_from_shortcut(cmd, i.modifiers.shift, i.key_pressed(egui::Key::S)),
                 cmd && i.key_pressed(egui::Key::N),
                 cmd && i.key_pressed(egui::Key::Enter),
+                Mode::ALL.into_iter().find(|mode| {
+                    cmd && !i.modifiers.shift
+                        && !i.modifiers.alt
+                        && i.key_pressed(mode.shortcut_key())
+                }),
                 cmd && !i.modifiers.shift && !i.modifiers.alt && i.key_pressed(egui::Key::F),
                 cmd && i.modifiers.shift && !i.modifiers.alt && i.key_pressed(egui::Key::F),
                 cmd && i.modifiers.alt && !i.modifiers.shift && i.key_pressed(egui::Key::F),
@@ -60,6 +85,12 @@ impl RustdownApp {
                 cmd && i.modifiers.shift && i.key_pressed(egui::Key::T),
                 cmd && i.modifiers.shift && i.key_pressed(egui::Key::F11),
                 cmd && i.modifiers.shift && i.key_pressed(egui::Key::F12),
+                cmd && !i.modifiers.shift && !i.modifiers.alt && i.key_pressed(egui::Key::B),
+                cmd && !i.modifiers.shift && !i.modifiers.alt && i.key_pressed(egui::Key::I),
+                cmd && i.modifiers.shift && !i.modifiers.alt && i.key_pressed(egui::Key::H),
+                cmd && i.modifiers.shift && !i.modifiers.alt && i.key_pressed(egui::Key::K),
+                cmd && !i.modifiers.shift && !i.modifiers.alt && i.key_pressed(egui::Key::P),
+                cmd && i.modifiers.shift && !i.modifiers.alt && i.key_pressed(egui::Key::P),
             )
         });
 
@@ -81,8 +112,11 @@ impl RustdownApp {
         if cycle_mode {
             self.set_mode(self.mode.cycle(), ctx);
         }
+        if let Some(mode) = jump_mode {
+            self.set_mode(mode, ctx);
+        }
         if search {
-            self.open_search(false);
+            self.toggle_search(ctx);
         }
         if replace_all {
             self.open_search(true);
@@ -99,8 +133,21 @@ impl RustdownApp {
         if (zoom_delta - 1.0).abs() > f32::EPSILON {
             self.adjust_zoom_factor(ctx, zoom_delta);
         }
-        if escape && self.search.visible {
-            self.close_search();
+        if quick_open {
+            self.toggle_quick_open(ctx);
+        }
+        if selection_preview && self.uses_editor() {
+            self.toggle_selection_preview(ctx);
+        }
+        // With quick open, completions or a selection preview showing,
+        // Escape is theirs.
+        if escape
+            && self.search.visible
+            && !self.quick_open.visible
+            && !self.completion.is_showing()
+            && self.selection_preview.is_none()
+        {
+            self.close_search_to_editor(ctx);
         }
         if toggle_nav {
             self.nav.visible = !self.nav.visible;
@@ -112,6 +159,105 @@ impl RustdownApp {
         if open_verification {
             self.request_action(PendingAction::OpenBundled(BundledDoc::Verification));
         }
+        if copy_html {
+            self.copy_html(ctx);
+        }
+        if (bold || italic) && self.uses_editor() && !self.search_has_focus(ctx) {
+            let marker = if bold { "**" } else { "*" };
+            self.edit_editor_selection(ctx, |text, selection| {
+                editor::toggle_emphasis(text, selection, marker)
+            });
+        }
+        if code_block && self.uses_editor() && !self.search_has_focus(ctx) {
+            let language = self.code_fence_language.clone();
+            self.insert_code_block(ctx, &language);
+        }
+        // The integration only pastes text: a paste of a clipboard holding
+        // just an image sends no event but the key's release, so only a
+        // release with no text pasted since reads the clipboard.
+        let (text_pasted, paste_released) = ctx.input(|i| {
+            let text = i
+                .events
+                .iter()
+                .any(|event| matches!(event, egui::Event::Paste(_) | egui::Event::Text(_)));
+            let released = i.events.iter().any(|event| {
+                matches!(event, egui::Event::Key { key, pressed: false, modifiers, .. }
+                    if (*key == egui::Key::V && modifiers.command) || *key == egui::Key::Paste)
+            });
+            (text, released)
+        });
+        self.text_pasted |= text_pasted;
+        if paste_released
+            && !std::mem::take(&mut self.text_pasted)
+            && self.uses_editor()
+            && ctx.memory(|m| m.has_focus(egui::Id::new("editor")))
+        {
+            self.paste_clipboard_image(ctx);
+        }
+    }
+
+    /// Toolbar button that inserts a code block in the remembered language;
+    /// its context menu picks another language, which is then remembered.
+    fn show_code_block_button(&mut self, ui: &mut egui::Ui, label: egui::RichText) {
+        let hint = if self.code_fence_language.is_empty() {
+            "no language".to_owned()
+        } else {
+            self.code_fence_language.clone()
+        };
+        let shortcut = ui.ctx().format_shortcut(&egui::KeyboardShortcut::new(
+            egui::Modifiers::COMMAND | egui::Modifiers::SHIFT,
+            egui::Key::K,
+        ));
+        let button = ui.button(label).on_hover_text(format!(
+            "Insert code block, {hint} ({shortcut}). Right-click to pick the language."
+        ));
+        if button.clicked() {
+            let language = self.code_fence_language.clone();
+            self.insert_code_block(ui.ctx(), &language);
+        }
+        button.context_menu(|ui| {
+            let mut picked = None;
+            for language in CODE_FENCE_LANGUAGES {
+                let label = if language.is_empty() {
+                    "None"
+                } else {
+                    language
+                };
+                if ui
+                    .selectable_label(self.code_fence_language == language, label)
+                    .clicked()
+                {
+                    picked = Some(language.to_owned());
+                }
+            }
+            ui.separator();
+            let mut custom = self.code_fence_language.clone();
+            let field = ui.add(
+                egui::TextEdit::singleline(&mut custom)
+                    .hint_text("Other language")
+                    .desired_width(120.0),
+            );
+            if field.changed() {
+                self.code_fence_language = custom;
+            }
+            if field.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                picked = Some(self.code_fence_language.clone());
+            }
+            if let Some(language) = picked {
+                self.insert_code_block(ui.ctx(), &language);
+                self.save_preferences();
+                ui.close();
+            }
+        });
+    }
+
+    /// Returns `true` while one of the search bar's text fields has focus.
+    fn search_has_focus(&self, ctx: &egui::Context) -> bool {
+        self.search.visible
+            && ctx.memory(|m| {
+                m.has_focus(egui::Id::new("search-query"))
+                    || m.has_focus(egui::Id::new("search-replacement"))
+            })
     }
 
     /// Render the toolbar panel with mode buttons, heading-colour toggle,
@@ -123,10 +269,16 @@ impl RustdownApp {
             let tb = |text: &str| egui::RichText::new(text).font(toolbar_font.clone());
 
             ui.horizontal(|ui| {
-                for mode in [Mode::Edit, Mode::Preview, Mode::SideBySide] {
+                for mode in Mode::ALL {
+                    let shortcut =
+                        egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, mode.shortcut_key());
                     if ui
                         .selectable_label(self.mode == mode, tb(mode.icon()))
-                        .on_hover_text(mode.tooltip())
+                        .on_hover_text(format!(
+                            "{} ({})",
+                            mode.tooltip(),
+                            ui.ctx().format_shortcut(&shortcut)
+                        ))
                         .clicked()
                     {
                         self.set_mode(mode, ui.ctx());
@@ -160,14 +312,194 @@ impl RustdownApp {
                     self.doc.preview_cache.clear();
                     self.save_preferences();
                 }
-                ui.separator();
+                for (label, step, hint) in
+                    [("A−", -1.0, "Smaller text"), ("A+", 1.0, "Larger text")]
+                {
+                    if ui
+                        .button(tb(label))
+                        .on_hover_text(format!("{hint} ({} pt)", self.font_size))
+                        .clicked()
+                    {
+                        self.set_font_size(ui.ctx(), self.font_size + step);
+                    }
+                }
+                let chars = self.effective_reading_width_chars();
                 if ui
-                    .button(tb("Fmt"))
-                    .on_hover_text("Format document")
-                    .clicked()
+                    .toggle_value(&mut self.reading_width, tb(&format!("{chars}ch")))
+                    .on_hover_text(format!("Limit the text column to {chars} characters"))
+                    .changed()
+                {
+                    self.save_preferences();
+                }
+                if self.mode != Mode::Edit
+                    && ui
+                        .toggle_value(&mut self.preview_code_wrap, tb("Wrap"))
+                        .on_hover_text("Wrap code blocks")
+                        .changed()
+                {
+                    self.doc.preview_cache.clear();
+                    self.save_preferences();
+                }
+                if self.mode != Mode::Edit
+                    && ui
+                        .toggle_value(&mut self.preview_preserve_line_breaks, tb("¶"))
+                        .on_hover_text("Keep single line breaks in paragraphs")
+                        .changed()
+                {
+                    self.save_preferences();
+                }
+                if self.mode != Mode::Edit
+                    && ui
+                        .toggle_value(&mut self.preview_render_html, tb("</>"))
+                        .on_hover_text("Render inline HTML such as <br>, <sub> and <kbd>")
+                        .changed()
+                {
+                    self.save_preferences();
+                }
+                if self.mode != Mode::Edit
+                    && ui
+                        .toggle_value(&mut self.smart_punctuation.preview, tb("“”"))
+                        .on_hover_text("Typographic quotes, dashes and ellipses")
+                        .changed()
                 {
+                    self.save_preferences();
+                }
+                ui.separator();
+                let fmt = ui
+                    .button(tb("Fmt"))
+                    .on_hover_text("Format document. Right-click for tables and save options.");
+                if fmt.clicked() {
                     self.format_document();
                 }
+                fmt.context_menu(|ui| {
+                    if ui.button("Reformat tables").clicked() {
+                        self.reformat_tables();
+                        ui.close();
+                    }
+                    ui.separator();
+                    let format_on_save = ui
+                        .checkbox(&mut self.format_on_save, "Format on save")
+                        .changed();
+                    let autosave = ui
+                        .checkbox(&mut self.autosave, "Autosave")
+                        .on_hover_text("Save the file when you pause typing")
+                        .changed();
+                    if format_on_save || autosave {
+                        self.save_preferences();
+                    }
+                });
+                if self.uses_editor() {
+                    self.show_code_block_button(ui, tb("```"));
+                    let has_selection =
+                        Self::editor_selection_text(ui.ctx(), &self.doc.text).is_some();
+                    if ui
+                        .add_enabled(has_selection, egui::Button::new(tb("👁")))
+                        .on_hover_text("Preview the selection (Ctrl+Shift+P)")
+                        .on_disabled_hover_text("Select some Markdown to preview it")
+                        .clicked()
+                    {
+                        self.toggle_selection_preview(ui.ctx());
+                    }
+                    if ui
+                        .toggle_value(&mut self.minimap, tb("Map"))
+                        .on_hover_text("Minimap of headings, search matches and conflicts")
+                        .changed()
+                    {
+                        self.save_preferences();
+                    }
+                }
+                let reveal = ui
+                    .button(tb("📂"))
+                    .on_hover_text("Reveal in file manager. Right-click for more.");
+                if reveal.clicked() {
+                    self.reveal_document(true);
+                }
+                reveal.context_menu(|ui| {
+                    if ui.button("Open containing folder").clicked() {
+                        self.reveal_document(false);
+                        ui.close();
+                    }
+                    ui.separator();
+                    if ui
+                        .checkbox(&mut self.open_any_file, "Open any file type")
+                        .on_hover_text(
+                            "Show every file in the Open dialog and open any dropped file",
+                        )
+                        .changed()
+                    {
+                        self.save_preferences();
+                    }
+                });
+                let mut always_on_top = self.window.always_on_top;
+                if ui
+                    .toggle_value(&mut always_on_top, tb("📌"))
+                    .on_hover_text("Keep the window on top of other windows")
+                    .changed()
+                {
+                    self.set_always_on_top(ui.ctx(), always_on_top);
+                }
+                let copy = ui
+                    .button(tb("Copy"))
+                    .on_hover_text("Copy as plain text. Right-click for options.");
+                if copy.clicked() {
+                    self.copy_plain_text(ui.ctx());
+                }
+                copy.context_menu(|ui| {
+                    if ui
+                        .checkbox(&mut self.smart_punctuation.plain_text, "Smart punctuation")
+                        .changed()
+                    {
+                        self.save_preferences();
+                    }
+                });
+                let html = ui
+                    .button(tb("HTML"))
+                    .on_hover_text("Copy as HTML. Right-click to export or pick a style.");
+                if html.clicked() {
+                    self.copy_html(ui.ctx());
+                }
+                html.context_menu(|ui| {
+                    if ui.button("Export HTML…").clicked() {
+                        self.export_html();
+                        ui.close();
+                    }
+                    if ui
+                        .checkbox(&mut self.smart_punctuation.html, "Smart punctuation")
+                        .changed()
+                    {
+                        self.save_preferences();
+                    }
+                    ui.separator();
+                    ui.label("Front matter");
+                    for option in FrontMatterExport::ALL {
+                        if ui
+                            .radio_value(&mut self.export_front_matter, option, option.label())
+                            .changed()
+                        {
+                            self.save_preferences();
+                        }
+                    }
+                    ui.separator();
+                    ui.label("Style");
+                    for theme in [ExportTheme::Light, ExportTheme::Dark, ExportTheme::Github] {
+                        let selected = self.export_css.is_none() && self.export_theme == theme;
+                        if ui.radio(selected, theme.label()).clicked() {
+                            self.set_export_theme(theme);
+                            ui.close();
+                        }
+                    }
+                    let custom = self
+                        .export_css
+                        .as_deref()
+                        .and_then(std::path::Path::file_name)
+                        .map_or(Cow::Borrowed("Custom CSS…"), |name| {
+                            Cow::Owned(format!("Custom CSS: {}", name.to_string_lossy()))
+                        });
+                    if ui.radio(self.export_css.is_some(), custom).clicked() {
+                        self.pick_export_css();
+                        ui.close();
+                    }
+                });
                 if ui
                     .toggle_value(&mut self.nav.visible, tb("Nav"))
                     .on_hover_text("Navigation")
@@ -212,7 +544,8 @@ impl RustdownApp {
                     let replace_response = ui.add(
                         egui::TextEdit::singleline(&mut self.search.replacement)
                             .hint_text("Replace with")
-                            .desired_width(180.0),
+                            .desired_width(180.0)
+                            .id(egui::Id::new("search-replacement")),
                     );
                     if replace_response.changed() {
                         self.search.last_replace_count = None;
@@ -232,7 +565,7 @@ impl RustdownApp {
                 }
 
                 if ui.button("Close").clicked() {
-                    self.close_search();
+                    self.close_search_to_editor(ui.ctx());
                 }
             });
         });
@@ -242,9 +575,90 @@ impl RustdownApp {
         }
     }
 
-    /// Render the status bar: file path, line count, dirty marker, error
-    /// messages, and merge-sidecar controls.
+    /// Refresh the selection stats from the editor's cursor state.  Stats are
+    /// recomputed only when the selected range or the text changes.
+    pub(crate) fn refresh_selection_stats(&mut self, ctx: &egui::Context) {
+        let char_range = if self.uses_editor() {
+            egui::TextEdit::load_state(ctx, egui::Id::new("editor"))
+                .and_then(|state| state.cursor.char_range())
+                .map(|range| range.as_sorted_char_range())
+                .filter(|range| !range.is_empty())
+        } else {
+            None
+        };
+        let Some(char_range) = char_range else {
+            self.selection_stats = None;
+            return;
+        };
+        let edit_seq = self.doc.edit_seq;
+        if self
+            .selection_stats
+            .as_ref()
+            .is_some_and(|stats| stats.is_current(&char_range, edit_seq))
+        {
+            return;
+        }
+        self.selection_stats = Some(SelectionStats::from_selection(
+            self.doc.text.as_str(),
+            char_range,
+            edit_seq,
+        ));
+    }
+
+    /// Refresh the cursor's line and column from the editor's cursor state,
+    /// recomputed only when the cursor, the text or the tab width changes.
+    pub(crate) fn refresh_cursor_position(&mut self, ctx: &egui::Context) {
+        let cursor = if self.uses_editor() {
+            egui::TextEdit::load_state(ctx, egui::Id::new("editor"))
+                .and_then(|state| state.cursor.char_range())
+                .map(|range| range.primary.index)
+        } else {
+            None
+        };
+        let Some(cursor) = cursor else {
+            self.cursor_position = None;
+            return;
+        };
+        let (edit_seq, tab_width) = (self.doc.edit_seq, self.doc.tab_width);
+        if self
+            .cursor_position
+            .is_some_and(|position| position.is_current(cursor, edit_seq, tab_width))
+        {
+            return;
+        }
+        self.cursor_position = Some(CursorPosition::from_cursor(
+            self.doc.text.as_str(),
+            cursor,
+            edit_seq,
+            tab_width,
+        ));
+    }
+
+    /// Heading path of the section under the editor cursor.  The outline is
+    /// refreshed only once stats are settled, so typing doesn't re-extract
+    /// headings on every keystroke.
+    pub(crate) fn heading_breadcrumb(&mut self, ctx: &egui::Context) -> String {
+        if !self.uses_editor() {
+            return String::new();
+        }
+        let Some(cursor) = egui::TextEdit::load_state(ctx, egui::Id::new("editor"))
+            .and_then(|state| state.cursor.char_range())
+            .map(|range| range.primary.index)
+        else {
+            return String::new();
+        };
+        if !self.doc.stats_dirty {
+            self.nav.refresh_outline(&self.doc.text, self.doc.edit_seq);
+        }
+        self.nav.breadcrumb(cursor).to_owned()
+    }
+
+    /// Render the status bar: file path, line count, selection stats, dirty
+    /// marker, error messages, and merge-sidecar controls.
     pub(crate) fn show_status_bar(&mut self, ctx: &egui::Context) {
+        self.refresh_selection_stats(ctx);
+        self.refresh_cursor_position(ctx);
+        let breadcrumb = self.heading_breadcrumb(ctx);
         egui::TopBottomPanel::bottom("status").show(ctx, |ui| {
             let mut clear_error = false;
 
@@ -254,20 +668,158 @@ impl RustdownApp {
 
             ui.horizontal(|ui| {
                 ui.label(tb(&self.doc.path_label()));
+                if let Some(opening) = &self.disk.opening {
+                    // The spinner keeps frames coming, so the read is
+                    // drained as soon as it lands.
+                    ui.separator();
+                    ui.spinner();
+                    let name = opening.file_name().unwrap_or(opening.as_os_str());
+                    ui.label(tb(&format!("Loading {}…", name.to_string_lossy())));
+                }
+                if let Some(rev) = &self.doc.disk_rev {
+                    // Re-rendered only when the age shown would change.
+                    let (label, next) = disk_rev_label(rev, SystemTime::now());
+                    ui.separator();
+                    ui.label(tb(&label))
+                        .on_hover_text("Last written to disk, and its size");
+                    ctx.request_repaint_after(next);
+                }
+                if self.merge_tool.is_some() {
+                    let conflicts = self.doc.stats.conflict_blocks;
+                    ui.separator();
+                    ui.label(tb(&if conflicts == 0 {
+                        "Merging: save to finish".to_owned()
+                    } else {
+                        format!(
+                            "Merging: {conflicts} conflict{} left",
+                            if conflicts == 1 { "" } else { "s" }
+                        )
+                    }))
+                    .on_hover_text("Saving writes the merge result for git and closes the window");
+                }
+                if let Some((notice, at)) = self.disk.reload_notice {
+                    match RELOAD_NOTICE_DURATION.checked_sub(at.elapsed()) {
+                        Some(left) => {
+                            ui.separator();
+                            ui.label(tb(notice).color(ui.visuals().weak_text_color()))
+                                .on_hover_text(
+                                    "Undo (Ctrl+Z) in the editor restores the previous text",
+                                );
+                            ctx.request_repaint_after(left);
+                        }
+                        None => self.disk.reload_notice = None,
+                    }
+                }
                 let stats = self.doc.stats();
 
                 ui.separator();
+                let mut title_stats = self.title_stats;
                 ui.label(
                     egui::RichText::new(format!("{} lines · {} words", stats.lines, stats.words))
                         .font(toolbar_font.clone()),
-                );
+                )
+                .on_hover_text("Right-click to show stats in the window title")
+                .context_menu(|ui| {
+                    ui.label("Show in window title");
+                    for option in [TitleStats::Off, TitleStats::Words, TitleStats::ReadingTime] {
+                        if ui
+                            .radio_value(&mut title_stats, option, option.label())
+                            .clicked()
+                        {
+                            ui.close();
+                        }
+                    }
+                });
+                self.set_title_stats(title_stats);
+
+                if let Some(position) = self.cursor_position {
+                    ui.separator();
+                    ui.label(tb(&format!(
+                        "Ln {}, Col {}",
+                        position.line, position.column
+                    )))
+                    .on_hover_text(format!(
+                        "Tabs count to the next multiple of {} columns",
+                        position.tab_width
+                    ));
+                }
+
+                if !breadcrumb.is_empty() {
+                    ui.separator();
+                    ui.label(tb(&breadcrumb))
+                        .on_hover_text("Headings enclosing the cursor");
+                }
+
+                if let Some(selection) = &self.selection_stats {
+                    ui.separator();
+                    ui.label(tb(&format!(
+                        "{} words · {} chars selected",
+                        selection.words, selection.chars
+                    )));
+                }
 
                 if self.doc.dirty {
                     ui.separator();
                     ui.colored_label(ui.visuals().warn_fg_color, tb("Modified"));
                 }
 
+                if let Some(path) = self.doc.path.clone() {
+                    let mode = self.disk.effective_mode(&path, self.disk_sync_mode);
+                    let hint = match mode {
+                        DiskSyncMode::Watch => "External changes are detected as they happen",
+                        DiskSyncMode::Poll => "External changes are detected by polling the file",
+                        DiskSyncMode::Off => "External changes are not detected until you save",
+                    };
+                    ui.separator();
+                    if ui
+                        .add(egui::Button::new(tb(mode.label())).frame(false))
+                        .on_hover_text(format!("{hint}. Click to change for this document."))
+                        .clicked()
+                    {
+                        self.disk.mode_overrides.insert(path, mode.next());
+                    }
+                }
+
+                if let Some(line) = stats.unclosed_fence_line {
+                    ui.separator();
+                    ui.colored_label(
+                        ui.visuals().warn_fg_color,
+                        tb(&format!("Unclosed code fence at line {line}")),
+                    )
+                    .on_hover_text("Everything after this fence is treated as code");
+                }
+
+                if stats.conflict_blocks > 0 {
+                    let count = stats.conflict_blocks;
+                    ui.separator();
+                    ui.colored_label(
+                        ui.visuals().warn_fg_color,
+                        tb(&format!(
+                            "{count} unresolved conflict{}",
+                            if count == 1 { "" } else { "s" }
+                        )),
+                    );
+                    if ui
+                        .add(egui::Button::new(tb("Next conflict")).frame(false))
+                        .on_hover_text("Move the cursor to the next conflict marker block")
+                        .clicked()
+                    {
+                        self.go_to_next_conflict(ctx);
+                    }
+                }
+
                 ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    let shortcut = ctx.format_shortcut(&egui::KeyboardShortcut::new(
+                        egui::Modifiers::COMMAND,
+                        egui::Key::F,
+                    ));
+                    if ui
+                        .selectable_label(self.search.visible, tb("Find"))
+                        .on_hover_text(format!("Show or hide the find bar ({shortcut})"))
+                        .clicked()
+                    {
+                        self.toggle_search(ctx);
+                    }
                     let mut clear_merge_sidecar = false;
                     let mut open_merge_path: Option<std::path::PathBuf> = None;
                     if let Some(path) = &self.disk.merge_sidecar_path {
@@ -351,16 +903,117 @@ impl RustdownApp {
         }
     }
 
-    pub(crate) fn show_editor(&mut self, ui: &mut egui::Ui) {
+    /// Parser options for the preview.
+    pub(crate) const fn preview_parse_options(&self) -> rustdown_md::ParseOptions {
+        rustdown_md::ParseOptions {
+            preserve_line_breaks: self.preview_preserve_line_breaks,
+            smart_punctuation: self.smart_punctuation.preview,
+            render_html: self.preview_render_html,
+            front_matter: rustdown_md::FrontMatter::Keep,
+            wiki_links: self.wiki_links,
+        }
+    }
+
+    /// Parser options for HTML: the preview's, with its own smart
+    /// punctuation and front matter settings.
+    pub(crate) const fn html_parse_options(&self) -> rustdown_md::ParseOptions {
+        rustdown_md::ParseOptions {
+            smart_punctuation: self.smart_punctuation.html,
+            front_matter: self.export_front_matter.front_matter(),
+            ..self.preview_parse_options()
+        }
+    }
+
+    /// Parser options for plain-text copies: the preview's, with their own
+    /// smart punctuation and front matter settings.
+    pub(crate) const fn plain_text_parse_options(&self) -> rustdown_md::ParseOptions {
+        rustdown_md::ParseOptions {
+            smart_punctuation: self.smart_punctuation.plain_text,
+            front_matter: self.export_front_matter.front_matter(),
+            ..self.preview_parse_options()
+        }
+    }
+
+    /// Clamp the configured reading width to a usable range.
+    pub(crate) fn effective_reading_width_chars(&self) -> u16 {
+        self.reading_width_chars.clamp(20, 400)
+    }
+
+    /// Width in points of the reading column, when the reading width is
+    /// enabled, from the body font's average glyph advance.
+    pub(crate) fn reading_column_width(&self, ui: &egui::Ui) -> Option<f32> {
+        if !self.reading_width {
+            return None;
+        }
+        Some(body_char_advance(ui) * f32::from(self.effective_reading_width_chars()))
+    }
+
+    /// Draw the highlighted source editor into `ui`.
+    pub fn show_editor(&mut self, ui: &mut egui::Ui) {
+        self.add_reload_undo_point(ui.ctx());
+        self.remap_cursor_after_format(ui.ctx());
+        // Tab / Shift+Tab indent and dedent while the editor has focus.  The
+        // editor locks focus so Tab never moves focus out of it; elsewhere
+        // Tab keeps its normal focus-traversal role.
+        if ui.memory(|m| m.has_focus(egui::Id::new("editor"))) {
+            if self.completion.is_showing() {
+                self.handle_completion_keys(ui.ctx());
+            }
+            let (dedent, indent) = ui.input_mut(|i| {
+                let dedent = i.consume_key(egui::Modifiers::SHIFT, egui::Key::Tab);
+                (dedent, i.consume_key(egui::Modifiers::NONE, egui::Key::Tab))
+            });
+            if dedent || indent {
+                self.indent_editor_selection(ui.ctx(), dedent);
+            }
+        }
+
         let heading_color_mode = self.heading_color_mode;
+        let heading_scales = self.heading_scales.0;
+        let code_colors = self.code_colors.resolve();
+        let wiki_links = self.wiki_links;
         let nav_visible = self.nav.visible;
-        let (changed, next_seq) = {
+        let reading_column = self.reading_column_width(ui);
+        let ruler_offset = self
+            .doc
+            .wrap_column
+            .map(|column| body_char_advance(ui) * column as f32);
+        let gutter_width = body_char_advance(ui) * 2.0;
+        // Fold sections follow the (debounced) outline; with folds in place
+        // the layouter extracts the headings of an edit itself so hidden
+        // ranges never lag behind the text, and hands them to the outline.
+        if !self.doc.stats_dirty {
+            self.nav.refresh_outline(&self.doc.text, self.doc.edit_seq);
+            self.doc
+                .line_changes
+                .refresh(&self.doc.base_text, &self.doc.text, self.doc.edit_seq);
+        }
+        if self.nav.outline_seq() == self.doc.edit_seq {
+            self.doc
+                .folding
+                .refresh(&self.doc.text, &self.nav.outline, self.doc.edit_seq);
+        }
+        let full_rect = ui.available_rect_before_wrap();
+        let minimap_rect = self.minimap.then(|| {
+            egui::Rect::from_x_y_ranges(
+                full_rect.right() - minimap::MINIMAP_WIDTH..=full_rect.right(),
+                full_rect.y_range(),
+            )
+        });
+        let editor_rect = minimap_rect.map_or(full_rect, |map| full_rect.with_max_x(map.left()));
+        let mut fresh_outline = None;
+        let (changed, next_seq, content_height, viewport, revert) = {
             let seq = Cell::new(self.doc.edit_seq);
             let Document {
                 text,
                 editor_galley_cache,
+                folding,
+                line_changes,
+                base_text,
                 ..
             } = &mut self.doc;
+            let folding = RefCell::new(folding);
+            let laid_out = Cell::new(None);
 
             let mut buffer = TrackedTextBuffer { text, seq: &seq };
 
@@ -368,6 +1021,8 @@ impl RustdownApp {
                 .desired_width(f32::INFINITY)
                 .font(egui::TextStyle::Body)
                 .frame(false)
+                .margin(EDITOR_MARGIN)
+                .lock_focus(true)
                 .id(egui::Id::new("editor"));
 
             let mut layouter = |ui: &egui::Ui, text_buf: &dyn egui::TextBuffer, wrap_width: f32| {
@@ -375,14 +1030,24 @@ impl RustdownApp {
                 let seq = seq.get();
                 let wrap_width_bits = wrap_width.to_bits();
                 let zoom_factor_bits = ui.ctx().zoom_factor().to_bits();
+                let mut folding = folding.borrow_mut();
+                if !folding.folded.is_empty() && !folding.is_current(seq) {
+                    let headings = outline::extract_headings(string);
+                    folding.refresh(string, &headings, seq);
+                    fresh_outline = Some((seq, headings));
+                }
+                let hidden = folding.hidden();
 
-                // Full cache hit: text, color, zoom, and wrap width all match.
+                // Full cache hit: text, color, zoom, wrap width, and folds
+                // all match.
                 if let Some(cache) = editor_galley_cache.as_ref()
                     && cache.content_seq == seq
                     && cache.content_color_mode == heading_color_mode
                     && cache.wrap_width_bits == wrap_width_bits
                     && cache.zoom_factor_bits == zoom_factor_bits
+                    && cache.hidden == hidden
                 {
+                    laid_out.set(Some(cache.galley.clone()));
                     return cache.galley.clone();
                 }
 
@@ -406,17 +1071,22 @@ impl RustdownApp {
                     // Reuse existing sections allocation by not cloning again
                     (job, None)
                 } else {
-                    let mut job = highlight::markdown_layout_job(
+                    let mut job = markdown_layout_job_with_code_colors(
                         ui.style(),
                         ui.visuals(),
                         string,
                         heading_color_mode,
+                        &heading_scales,
+                        code_colors,
+                        wiki_links,
                     );
                     job.wrap.max_width = wrap_width;
                     let sections = job.sections.clone();
                     (job, Some(sections))
                 };
 
+                let mut job = job;
+                fold::hide_ranges(&mut job.sections, hidden);
                 let galley = ui.fonts_mut(|fonts| fonts.layout_job(job));
                 let row_byte_offsets = if nav_visible {
                     editor::build_row_byte_offsets(&galley, string)
@@ -435,17 +1105,31 @@ impl RustdownApp {
                         layout_sections: sections,
                         galley: galley.clone(),
                         row_byte_offsets,
+                        hidden: hidden.to_vec(),
                     });
                 } else if let Some(cache) = editor_galley_cache.as_mut() {
                     cache.wrap_width_bits = wrap_width_bits;
                     cache.zoom_factor_bits = zoom_factor_bits;
                     cache.galley = galley.clone();
                     cache.row_byte_offsets = row_byte_offsets;
+                    cache.hidden = hidden.to_vec();
                 }
+                laid_out.set(Some(galley.clone()));
                 galley
             };
 
-            let editor_size = ui.available_size();
+            let mut editor_size = editor_rect.size();
+            editor_size.x = (editor_size.x - gutter_width).max(0.0);
+            // A capped reading column narrows the editor (and so its wrap
+            // width) and centers it in the pane.
+            let column_pad = match reading_column {
+                Some(column) if column < editor_size.x => {
+                    let pad = (editor_size.x - column) / 2.0;
+                    editor_size.x = column;
+                    pad
+                }
+                _ => 0.0,
+            };
             let scroll_to = self.nav.pending_editor_scroll_y.take();
             let mut scroll_area = egui::ScrollArea::both()
                 .id_salt("editor_scroll")
@@ -454,18 +1138,174 @@ impl RustdownApp {
             if let Some(y) = scroll_to {
                 scroll_area = scroll_area.vertical_scroll_offset(y);
             }
-            let response = scroll_area
-                .show(ui, |ui| {
-                    ui.add_sized(editor_size, editor.layouter(&mut layouter))
+            let output = ui
+                .scope_builder(egui::UiBuilder::new().max_rect(editor_rect), |ui| {
+                    scroll_area.show(ui, |ui| {
+                        let response = ui
+                            .horizontal_top(|ui| {
+                                ui.add_space(column_pad + gutter_width);
+                                ui.add_sized(editor_size, editor.layouter(&mut layouter))
+                            })
+                            .inner;
+                        if let Some(offset) = ruler_offset {
+                            paint_wrap_ruler(ui, response.rect, offset);
+                        }
+                        let revert = laid_out.take().and_then(|galley| {
+                            let mut folding = folding.borrow_mut();
+                            if folding.is_current(seq.get()) {
+                                show_fold_gutter(
+                                    ui,
+                                    &mut folding,
+                                    &galley,
+                                    response.rect,
+                                    gutter_width,
+                                );
+                            }
+                            // Shown after the fold toggles so the marker
+                            // strip takes clicks where the two overlap.
+                            show_change_gutter(
+                                ui,
+                                &line_changes.markers,
+                                base_text,
+                                line_changes.is_current(base_text, seq.get()),
+                                &galley,
+                                response.rect,
+                                gutter_width,
+                            )
+                        });
+                        (response, revert)
+                    })
                 })
                 .inner;
-            (response.changed(), seq.get())
+            let viewport = egui::Rangef::new(
+                output.state.offset.y,
+                output.state.offset.y + output.inner_rect.height(),
+            );
+            let (response, revert) = output.inner;
+            (
+                response.changed(),
+                seq.get(),
+                output.content_size.y,
+                viewport,
+                revert,
+            )
         };
 
         self.doc.edit_seq = next_seq;
         if changed {
             self.note_text_changed(true);
         }
+        if let Some((seq, headings)) = fresh_outline
+            && seq == next_seq
+        {
+            self.nav.set_outline(&self.doc.text, seq, headings);
+        }
+        if let Some(index) = revert {
+            self.revert_line_change(ui.ctx(), index);
+        }
+        if let Some(rect) = minimap_rect {
+            self.show_minimap(ui, rect, content_height, viewport);
+        }
+        self.refresh_completion(ui.ctx());
+        self.show_completion(ui.ctx());
+        if !self.doc.folding.folded.is_empty()
+            && let Some(cursor) = egui::TextEdit::load_state(ui.ctx(), egui::Id::new("editor"))
+                .and_then(|state| state.cursor.char_range())
+            && self.doc.folding.unfold_at(cursor.primary.index)
+        {
+            ui.ctx().request_repaint();
+        }
+    }
+
+    /// While completions show, Up and Down move the highlight, Enter or Tab
+    /// takes it and Escape hides the popup, instead of editing the text.
+    fn handle_completion_keys(&mut self, ctx: &egui::Context) {
+        let (down, up, enter, tab, escape) = ctx.input_mut(|i| {
+            (
+                i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowDown),
+                i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowUp),
+                i.consume_key(egui::Modifiers::NONE, egui::Key::Enter),
+                i.consume_key(egui::Modifiers::NONE, egui::Key::Tab),
+                i.consume_key(egui::Modifiers::NONE, egui::Key::Escape),
+            )
+        });
+        if down || up {
+            self.completion.move_selection(if down { 1 } else { -1 });
+        }
+        if enter || tab {
+            self.accept_completion(ctx, self.completion.selected);
+        } else if escape {
+            self.completion.dismiss();
+        }
+    }
+
+    /// The completion popup, just below the editor cursor.
+    fn show_completion(&mut self, ctx: &egui::Context) {
+        if !self.completion.is_showing() {
+            return;
+        }
+        let Some(cursor) = ctx.output(|o| o.ime.map(|ime| ime.cursor_rect)) else {
+            return;
+        };
+        let mut chosen = None;
+        egui::Area::new(egui::Id::new("completion"))
+            .order(egui::Order::Foreground)
+            .fixed_pos(cursor.left_bottom())
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    for (row, candidate) in self.completion.candidates.iter().enumerate() {
+                        let label = ui
+                            .selectable_label(row == self.completion.selected, &candidate.insert)
+                            .on_hover_text(&candidate.label);
+                        if label.clicked() {
+                            chosen = Some(row);
+                        }
+                    }
+                });
+            });
+        if let Some(row) = chosen {
+            self.accept_completion(ctx, row);
+            ctx.memory_mut(|m| m.request_focus(egui::Id::new("editor")));
+        }
+    }
+
+    /// Draw the minimap strip into `rect` and scroll the editor to where it
+    /// is clicked.
+    fn show_minimap(
+        &mut self,
+        ui: &egui::Ui,
+        rect: egui::Rect,
+        content_height: f32,
+        viewport: egui::Rangef,
+    ) {
+        let query = if self.search.visible {
+            self.search.query.as_str()
+        } else {
+            ""
+        };
+        self.minimap_marks.refresh(
+            &self.doc.text,
+            self.nav.outline_seq(),
+            &self.nav.outline,
+            query,
+            self.doc.stats.conflict_blocks > 0,
+        );
+        self.ensure_row_byte_offsets();
+        let Some(cache) = self.doc.editor_galley_cache.as_ref() else {
+            return;
+        };
+        let target = minimap::show(
+            ui,
+            rect,
+            &self.minimap_marks.marks,
+            |byte_offset| editor::row_byte_offset_to_y(&cache.row_byte_offsets, byte_offset),
+            content_height,
+            viewport,
+        );
+        if let Some(y) = target {
+            self.nav.pending_editor_scroll_y = Some(y);
+            ui.ctx().request_repaint();
+        }
     }
 
     /// Rebuild the cached `MarkdownStyle` when the theme, colour mode, or
@@ -473,11 +1313,19 @@ impl RustdownApp {
     pub(crate) fn ensure_preview_style(&mut self, visuals: &egui::Visuals) {
         let dark = visuals.dark_mode;
         let colored = self.heading_color_mode;
+        let code_wrap = self.preview_code_wrap;
         let uri = &self.doc.image_uri_scheme;
+        let image_max_width = self.image_max_width.preview();
         let c = &self.preview_style_cache;
 
         let needs_rebuild = match &c.style {
-            Some(_) => c.dark_mode != dark || c.colored != colored || c.image_uri != *uri,
+            Some(_) => {
+                c.dark_mode != dark
+                    || c.colored != colored
+                    || c.code_wrap != code_wrap
+                    || c.image_uri != *uri
+                    || c.image_max_width != image_max_width
+            }
             None => true,
         };
 
@@ -488,41 +1336,81 @@ impl RustdownApp {
                 MarkdownStyle::from_visuals(visuals)
             };
             style.image_base_uri.clone_from(uri);
+            style.code_wrap = code_wrap;
+            style.image_max_width = image_max_width;
+            style.set_heading_scales(self.heading_scales.0);
+            style.set_code_colors(self.code_colors.resolve());
             let c = &mut self.preview_style_cache;
             c.dark_mode = dark;
             c.colored = colored;
+            c.code_wrap = code_wrap;
             c.image_uri.clone_from(uri);
+            c.image_max_width = image_max_width;
             c.style = Some(style);
         }
     }
 
-    pub(crate) fn show_preview(&mut self, ui: &mut egui::Ui) {
+    /// Draw the rendered preview into `ui`.
+    pub fn show_preview(&mut self, ui: &mut egui::Ui) {
         if self.mode == Mode::SideBySide
-            && let Some(remaining) = self.doc.debounce_remaining(DEBOUNCE)
+            && let Some(remaining) = self.doc.debounce_remaining(self.timings.preview_debounce())
         {
             ui.ctx().request_repaint_after(remaining);
+            // Keep the last rendered preview up, marked as catching up.
+            self.render_preview_text(ui, None, None);
+            Self::paint_preview_pending(ui);
             return;
         }
 
         self.doc.consume_preview_dirty();
 
-        self.ensure_preview_style(ui.visuals());
-
         // Consume any pending nav-scroll target and pass it directly to the
         // ScrollArea, avoiding the ID-mismatch problem with external state lookup.
         let scroll_y = self.nav.pending_preview_scroll_y.take();
+        let text = self.doc.text.clone();
+        self.render_preview_text(ui, Some(text.as_str()), scroll_y);
+    }
+
+    /// Render `text`, or with `None` what the preview cache last parsed.
+    fn render_preview_text(
+        &mut self,
+        ui: &mut egui::Ui,
+        text: Option<&str>,
+        scroll_y: Option<f32>,
+    ) {
+        self.doc
+            .preview_cache
+            .set_parse_options(self.preview_parse_options());
+        self.ensure_preview_style(ui.visuals());
+        let reading_column = self.reading_column_width(ui);
+        if let Some(style) = self.preview_style_cache.style.as_mut() {
+            style.max_content_width = reading_column;
+        }
 
         if let Some(ref style) = self.preview_style_cache.style {
-            MarkdownViewer::new("preview_markdown").show_scrollable(
-                ui,
-                &mut self.doc.preview_cache,
-                style,
-                self.doc.text.as_str(),
-                scroll_y,
-            );
+            let viewer = MarkdownViewer::new("preview_markdown");
+            let cache = &mut self.doc.preview_cache;
+            match text {
+                Some(text) => viewer.show_scrollable(ui, cache, style, text, scroll_y),
+                None => viewer.show_scrollable_cached(ui, cache, style, scroll_y),
+            }
         }
     }
 
+    /// Faint "updating" note in the preview's top-right corner while a
+    /// debounced re-render is pending.
+    fn paint_preview_pending(ui: &egui::Ui) {
+        let rect = ui.clip_rect();
+        let font = egui::FontId::proportional(ui.text_style_height(&egui::TextStyle::Small));
+        ui.painter().text(
+            rect.right_top() + egui::vec2(-PANEL_EDGE_PADDING, PANEL_EDGE_PADDING),
+            egui::Align2::RIGHT_TOP,
+            "Updating preview…",
+            font,
+            ui.visuals().weak_text_color(),
+        );
+    }
+
     pub(crate) fn show_dialogs(&mut self, ctx: &egui::Context) {
         if self.pending_action.is_none() {
             return;
@@ -558,6 +1446,52 @@ impl RustdownApp {
             });
     }
 
+    /// Confirm a save while the document still contains conflict markers.
+    pub(crate) fn show_conflict_save_dialog(&mut self, ctx: &egui::Context) {
+        let Some(save_as) = self.pending_conflict_save else {
+            return;
+        };
+        if ctx.input(|i| i.key_pressed(egui::Key::Escape)) {
+            self.pending_conflict_save = None;
+            return;
+        }
+
+        egui::Window::new("Unresolved conflicts")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.label(format!(
+                    "\"{}\" still contains conflict markers.",
+                    self.doc.title()
+                ));
+                ui.add_space(8.0);
+
+                ui.horizontal(|ui| {
+                    if ui.button("Save anyway").clicked() {
+                        self.pending_conflict_save = None;
+                        if self.write_doc(save_as, self.formats_on_save()) {
+                            self.finish_confirmed_save(ctx);
+                        }
+                    }
+                    if ui.button("Go to conflict").clicked() {
+                        self.pending_conflict_save = None;
+                        self.go_to_next_conflict(ctx);
+                    }
+                    if ui.button("Cancel").clicked() {
+                        self.pending_conflict_save = None;
+                    }
+                });
+
+                ui.add_space(8.0);
+                let mut dont_ask = !self.confirm_save_with_conflicts;
+                if ui.checkbox(&mut dont_ask, "Don't ask again").changed() {
+                    self.confirm_save_with_conflicts = !dont_ask;
+                    self.save_preferences();
+                }
+            });
+    }
+
     pub(crate) fn show_disk_conflict_dialog(&mut self, ctx: &egui::Context) {
         if self.disk.conflict.is_none() {
             return;
@@ -578,6 +1512,7 @@ impl RustdownApp {
                     for (label, choice) in [
                         ("Open conflict merge", ConflictChoice::OpenConflictMerge),
                         ("Keep mine (+ merge file)", ConflictChoice::KeepMineWriteSidecar),
+                        ("Keep both", ConflictChoice::KeepBoth),
                     ] {
                         if ui.button(label).clicked() {
                             self.apply_conflict_choice(choice);
@@ -598,10 +1533,589 @@ impl RustdownApp {
                     }
                 });
 
+                ui.add_space(8.0);
+                self.show_disk_conflict_diff(ui);
+
+                ui.add_space(8.0);
+                let mut auto_merge = !self.confirm_disk_merges;
+                if ui
+                    .checkbox(&mut auto_merge, "Merge changes that don't conflict without asking")
+                    .changed()
+                {
+                    self.confirm_disk_merges = !auto_merge;
+                    self.save_preferences();
+                }
+
                 ui.add_space(8.0);
                 ui.small(
-                    "Tip: “Keep mine” applies non-conflicting disk edits and writes a merge file so no changes are lost.",
+                    "Tip: “Keep mine” applies non-conflicting disk edits and writes a merge file so no changes are lost. “Keep both” saves your edits to a merge file and loads the disk version.",
+                );
+            });
+    }
+
+    pub(crate) fn show_deleted_file_dialog(&mut self, ctx: &egui::Context) {
+        // Closing a dirty document asks about its unsaved changes first.
+        if !self.disk.deleted || self.disk.conflict.is_some() || self.pending_action.is_some() {
+            return;
+        }
+
+        egui::Window::new("File deleted on disk")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.label(format!(
+                    "\"{}\" was deleted or moved away on disk.",
+                    self.doc.title()
+                ));
+                ui.add_space(8.0);
+
+                ui.horizontal(|ui| {
+                    if ui.button("Keep editing").clicked() {
+                        self.keep_deleted_document();
+                    }
+                    if ui.button("Close").clicked() {
+                        self.request_action(PendingAction::NewBlank);
+                    }
+                });
+
+                ui.add_space(8.0);
+                ui.small("Tip: “Keep editing” holds on to the text as unsaved; saving writes the file again.");
+            });
+    }
+
+    pub(crate) fn show_new_note_dialog(&mut self, ctx: &egui::Context) {
+        let Some(path) = self.pending_new_note.clone() else {
+            return;
+        };
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+
+        egui::Window::new("Create note?")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.label(format!("\"{name}\" doesn't exist yet."));
+                ui.small(path.display().to_string());
+                ui.add_space(8.0);
+
+                ui.horizontal(|ui| {
+                    if ui.button("Create").clicked() {
+                        self.create_pending_note();
+                    }
+                    if ui.button("Cancel").clicked() {
+                        self.pending_new_note = None;
+                    }
+                });
+            });
+    }
+
+    pub(crate) fn show_image_paste_dialog(&mut self, ctx: &egui::Context) {
+        if self.pending_image_paste.is_none() {
+            return;
+        }
+        egui::Window::new("Save before pasting?")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.label("Pasted images are saved beside the document,");
+                ui.label("so it needs a file first.");
+                ui.add_space(8.0);
+
+                ui.horizontal(|ui| {
+                    if ui.button("Save…").clicked() {
+                        self.save_then_paste_image(ctx);
+                    }
+                    if ui.button("Cancel").clicked() {
+                        self.pending_image_paste = None;
+                    }
+                });
+            });
+    }
+
+    /// The selection preview: the snapshot taken of the editor selection,
+    /// rendered with the preview's style and options.  Escape closes it.
+    pub(crate) fn show_selection_preview(&mut self, ctx: &egui::Context) {
+        let Some(mut preview) = self.selection_preview.take() else {
+            return;
+        };
+        if ctx.input_mut(|i| i.consume_key(egui::Modifiers::NONE, egui::Key::Escape)) {
+            return;
+        }
+        self.ensure_preview_style(&ctx.style().visuals);
+        preview
+            .cache
+            .set_parse_options(self.preview_parse_options());
+        let mut open = true;
+        egui::Window::new("Selection preview")
+            .open(&mut open)
+            .collapsible(false)
+            .default_size(egui::vec2(480.0, 320.0))
+            .show(ctx, |ui| {
+                if let Some(style) = &self.preview_style_cache.style {
+                    MarkdownViewer::new("selection_preview").show_scrollable(
+                        ui,
+                        &mut preview.cache,
+                        style,
+                        &preview.text,
+                        None,
+                    );
+                }
+            });
+        if open {
+            self.selection_preview = Some(preview);
+        }
+    }
+
+    /// The quick-open finder: a query field over the best-matching files
+    /// of the notes folder.  Up and Down move the highlight, Enter opens it
+    /// and Escape closes the finder.
+    pub(crate) fn show_quick_open(&mut self, ctx: &egui::Context) {
+        if !self.quick_open.visible {
+            return;
+        }
+        let Some(root) = self.quick_open_root() else {
+            self.quick_open.visible = false;
+            return;
+        };
+        self.quick_open.ensure_index(ctx, &root);
+
+        let (down, up, enter, escape) = ctx.input_mut(|i| {
+            (
+                i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowDown),
+                i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowUp),
+                i.consume_key(egui::Modifiers::NONE, egui::Key::Enter),
+                i.consume_key(egui::Modifiers::NONE, egui::Key::Escape),
+            )
+        });
+        if escape {
+            self.quick_open.visible = false;
+            return;
+        }
+
+        let mut open = None;
+        let mut rescan = false;
+        let mut pick_root = false;
+        egui::Window::new("Quick open")
+            .title_bar(false)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_TOP, egui::vec2(0.0, 48.0))
+            .show(ctx, |ui| {
+                let folder = root.file_name().map_or_else(
+                    || root.display().to_string(),
+                    |name| name.to_string_lossy().into_owned(),
+                );
+                let field = ui.add(
+                    egui::TextEdit::singleline(&mut self.quick_open.query)
+                        .hint_text(format!("Find a file in {folder}"))
+                        .desired_width(420.0)
+                        .id(egui::Id::new("quick-open-query")),
                 );
+                if std::mem::take(&mut self.quick_open.focus) {
+                    field.request_focus();
+                }
+                if field.changed() {
+                    self.quick_open.selected = 0;
+                }
+
+                let matches = self.quick_open.matches(&root);
+                if down || up {
+                    self.quick_open
+                        .move_selection(if down { 1 } else { -1 }, matches.len());
+                }
+                if enter && let Some(file) = matches.get(self.quick_open.selected) {
+                    open = Some(file.clone());
+                }
+
+                egui::ScrollArea::vertical()
+                    .max_height(320.0)
+                    .auto_shrink([false, true])
+                    .show(ui, |ui| {
+                        for (row, file) in matches.iter().enumerate() {
+                            let selected = row == self.quick_open.selected;
+                            let label = ui.selectable_label(selected, file.as_str());
+                            if selected && (down || up) {
+                                label.scroll_to_me(None);
+                            }
+                            if label.clicked() {
+                                open = Some(file.clone());
+                            }
+                        }
+                    });
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    if self.quick_open.is_scanning() {
+                        ui.spinner();
+                        ui.weak("Listing files…");
+                    } else {
+                        let count = self.quick_open.file_count(&root);
+                        ui.weak(format!("{count} Markdown files"))
+                            .on_hover_text(root.display().to_string());
+                    }
+                    rescan = ui.small_button("Rescan").clicked();
+                    pick_root = ui
+                        .small_button("Folder…")
+                        .on_hover_text("Choose the notes folder")
+                        .clicked();
+                });
             });
+
+        if let Some(file) = open {
+            self.open_quick_open_match(&root, &file);
+        } else if rescan {
+            self.quick_open.mark_stale();
+            self.quick_open.ensure_index(ctx, &root);
+        } else if pick_root {
+            self.pick_notes_root(ctx);
+        }
+    }
+
+    /// Collapsible, scrollable diff of the buffer (removed) against the
+    /// on-disk text (added), shown inside the disk conflict dialog.
+    fn show_disk_conflict_diff(&self, ui: &mut egui::Ui) {
+        let Some(conflict) = self.disk.conflict.as_ref() else {
+            return;
+        };
+        egui::CollapsingHeader::new("Show differences")
+            .id_salt("disk-conflict-diff")
+            .show(ui, |ui| {
+                let hunks = &conflict.diff;
+                let removed = ui.visuals().error_fg_color;
+                let added = added_line_color(ui.visuals());
+                let context = ui.visuals().weak_text_color();
+                egui::ScrollArea::both()
+                    .max_height(240.0)
+                    .max_width(520.0)
+                    .auto_shrink([false, true])
+                    .show(ui, |ui| {
+                        if hunks.is_empty() {
+                            ui.weak("No line differences.");
+                        }
+                        for hunk in hunks {
+                            ui.colored_label(
+                                context,
+                                egui::RichText::new(format!("@@ line {}", hunk.line)).monospace(),
+                            );
+                            for (prefix, color, lines) in
+                                [("-", removed, &hunk.removed), ("+", added, &hunk.added)]
+                            {
+                                for line in lines {
+                                    ui.add(
+                                        egui::Label::new(
+                                            egui::RichText::new(format!("{prefix} {line}"))
+                                                .monospace()
+                                                .color(color),
+                                        )
+                                        .extend(),
+                                    );
+                                }
+                            }
+                        }
+                    });
+            });
+    }
+}
+
+/// Languages offered by the code block button's picker; empty means none.
+const CODE_FENCE_LANGUAGES: [&str; 13] = [
+    "",
+    "rust",
+    "python",
+    "javascript",
+    "typescript",
+    "bash",
+    "json",
+    "toml",
+    "yaml",
+    "html",
+    "css",
+    "sql",
+    "text",
+];
+
+/// How long the status bar shows that the buffer was replaced from disk.
+const RELOAD_NOTICE_DURATION: Duration = Duration::from_secs(4);
+
+/// Editor text margin, fixed so the wrap ruler can be placed from the
+/// editor's outer rect.
+const EDITOR_MARGIN: egui::Margin = egui::Margin::symmetric(4, 2);
+
+/// Average glyph advance of the body font, used to turn character columns
+/// into points.
+fn body_char_advance(ui: &egui::Ui) -> f32 {
+    const SAMPLE: &str = "etaoin shrdlu cmfwyp vbgkqj xz";
+    let font = egui::TextStyle::Body.resolve(ui.style());
+    ui.fonts_mut(|fonts| {
+        SAMPLE
+            .chars()
+            .map(|c| fonts.glyph_width(&font, c))
+            .sum::<f32>()
+    }) / SAMPLE.len() as f32
+}
+
+/// Colour of added lines in the change gutter and the disk conflict diff;
+/// removed lines use the theme's error colour.
+const fn added_line_color(visuals: &egui::Visuals) -> egui::Color32 {
+    if visuals.dark_mode {
+        egui::Color32::from_rgb(0x50, 0xFA, 0x7B)
+    } else {
+        egui::Color32::from_rgb(0x1A, 0x7F, 0x37)
+    }
+}
+
+/// Paint a bar beside the added and modified lines of `galley` at the left
+/// edge of the `width`-point gutter left of the editor at `rect`, and a
+/// notch where lines were removed.  With `revertible`, hovering a marker
+/// shows the saved lines it replaced, and the index of a clicked one is
+/// returned to be reverted.
+fn show_change_gutter(
+    ui: &egui::Ui,
+    markers: &[LineChange],
+    base: &str,
+    revertible: bool,
+    galley: &egui::Galley,
+    rect: egui::Rect,
+    width: f32,
+) -> Option<usize> {
+    const MODIFIED: egui::Color32 = egui::Color32::from_rgb(0x61, 0xAF, 0xEF);
+    /// Most saved lines shown when hovering a marker.
+    const PREVIEW_LINES: usize = 8;
+
+    if markers.is_empty() {
+        return None;
+    }
+    let origin = rect.min + EDITOR_MARGIN.left_top();
+    let bar_width = (width * 0.15).max(2.0);
+    let notch = bar_width * 1.5;
+    let left = rect.left() - width;
+    let clip = ui.clip_rect().y_range();
+
+    // The visible vertical extent of each marker, in marker order.
+    let mut spans: Vec<(usize, egui::Rangef)> = Vec::new();
+    let mut line = 0;
+    let mut starts_line = true;
+    let mut next = 0;
+    for row in &galley.rows {
+        let row_y = row.rect().y_range();
+        let y = egui::Rangef::new(row_y.min + origin.y, row_y.max + origin.y);
+        if y.min > clip.max {
+            break;
+        }
+        // Skip markers wholly above this line; a removal marker stays
+        // until the line it sits before.
+        while markers
+            .get(next)
+            .is_some_and(|m| m.lines.end.max(m.lines.start + 1) <= line)
+        {
+            next += 1;
+        }
+        if y.max >= clip.min {
+            for (index, marker) in markers
+                .iter()
+                .enumerate()
+                .skip(next)
+                .take_while(|(_, m)| m.lines.start <= line)
+            {
+                let y = match marker.kind {
+                    LineChangeKind::Removed if starts_line => {
+                        egui::Rangef::new(y.min - notch, y.min + notch)
+                    }
+                    LineChangeKind::Removed => continue,
+                    _ => y,
+                };
+                match spans.last_mut() {
+                    Some((last, span)) if *last == index => span.max = y.max,
+                    _ => spans.push((index, y)),
+                }
+            }
+        }
+        starts_line = row.ends_with_newline;
+        if row.ends_with_newline {
+            line += 1;
+        }
+    }
+
+    let mut clicked = None;
+    for (index, y) in spans {
+        let marker = &markers[index];
+        let hovered = revertible && {
+            let hit = egui::Rect::from_x_y_ranges(left..=width.mul_add(0.35, left), y);
+            let response = ui
+                .interact(
+                    hit,
+                    ui.id().with(("line-change", index)),
+                    egui::Sense::click(),
+                )
+                .on_hover_ui(|ui| {
+                    let saved = changes::saved_lines(base, marker);
+                    if saved.is_empty() {
+                        ui.label("Click to remove the added lines");
+                        return;
+                    }
+                    ui.label("Click to revert to the saved lines:");
+                    let mut preview: String = saved
+                        .lines()
+                        .take(PREVIEW_LINES)
+                        .collect::<Vec<_>>()
+                        .join("\n");
+                    if marker.base_lines.len() > PREVIEW_LINES {
+                        preview.push_str("\n…");
+                    }
+                    ui.monospace(preview);
+                });
+            if response.clicked() {
+                clicked = Some(index);
+            }
+            response.hovered()
+        };
+        let bar = if hovered { bar_width * 2.0 } else { bar_width };
+        let painter = ui.painter();
+        match marker.kind {
+            LineChangeKind::Removed => {
+                let size = if hovered { notch * 1.5 } else { notch };
+                let mid = y.center();
+                painter.add(egui::Shape::convex_polygon(
+                    vec![
+                        egui::pos2(left, mid - size),
+                        egui::pos2(left + size, mid),
+                        egui::pos2(left, mid + size),
+                    ],
+                    ui.visuals().error_fg_color,
+                    egui::Stroke::NONE,
+                ));
+            }
+            kind => {
+                let color = if kind == LineChangeKind::Added {
+                    added_line_color(ui.visuals())
+                } else {
+                    MODIFIED
+                };
+                painter.rect_filled(
+                    egui::Rect::from_x_y_ranges(left..=left + bar, y),
+                    0.0,
+                    color,
+                );
+            }
+        }
+    }
+    clicked
+}
+
+/// Paint fold toggles in the `width`-point gutter left of the editor at
+/// `rect` beside each foldable heading of `galley`, and a `…` marker after
+/// folded ones.  Unfolded toggles only show while the pointer is over the
+/// gutter, so they don't flicker in and out as the outline catches up with
+/// typing.
+fn show_fold_gutter(
+    ui: &egui::Ui,
+    folding: &mut Folding,
+    galley: &egui::Galley,
+    rect: egui::Rect,
+    width: f32,
+) {
+    let origin = rect.min + EDITOR_MARGIN.left_top();
+    let gutter = egui::Rect::from_x_y_ranges(rect.left() - width..=rect.left(), rect.y_range());
+    let show_open = ui.rect_contains_pointer(gutter);
+    let font = egui::TextStyle::Body.resolve(ui.style());
+    let mut toggle = None;
+    for (index, section) in folding.sections.iter().enumerate() {
+        let folded = folding.is_folded(section);
+        if !folded && !show_open {
+            continue;
+        }
+        let row = galley
+            .pos_from_cursor(egui::text::CCursor::new(section.heading_char))
+            .translate(origin.to_vec2());
+        if !ui.is_rect_visible(row) {
+            continue;
+        }
+        let hit = egui::Rect::from_x_y_ranges(gutter.x_range(), row.y_range());
+        let response = ui
+            .interact(hit, ui.id().with(("fold", index)), egui::Sense::click())
+            .on_hover_text(if folded {
+                "Unfold section"
+            } else {
+                "Fold section"
+            });
+        let color = if folded || response.hovered() {
+            ui.visuals().text_color()
+        } else {
+            ui.visuals().weak_text_color()
+        };
+        paint_fold_toggle(ui.painter(), hit.center(), width * 0.25, folded, color);
+        let mut clicked = response.clicked();
+        if folded {
+            let end = galley
+                .pos_from_cursor(egui::text::CCursor::new(section.heading_end_char))
+                .translate(origin.to_vec2());
+            let marker = ui.painter().text(
+                egui::pos2(width.mul_add(0.25, end.right()), end.center().y),
+                egui::Align2::LEFT_CENTER,
+                "…",
+                font.clone(),
+                ui.visuals().weak_text_color(),
+            );
+            clicked |= ui
+                .interact(
+                    marker,
+                    ui.id().with(("fold-marker", index)),
+                    egui::Sense::click(),
+                )
+                .on_hover_text("Unfold section")
+                .clicked();
+        }
+        if clicked {
+            toggle = Some(index);
+        }
+    }
+    if let Some(index) = toggle {
+        folding.toggle(index);
+        ui.ctx().request_repaint();
+    }
+}
+
+/// Paint a small triangle at `center`: pointing right when `folded`,
+/// down otherwise.
+fn paint_fold_toggle(
+    painter: &egui::Painter,
+    center: egui::Pos2,
+    radius: f32,
+    folded: bool,
+    color: egui::Color32,
+) {
+    let points = if folded {
+        vec![
+            center + egui::vec2(-radius * 0.6, -radius),
+            center + egui::vec2(radius * 0.8, 0.0),
+            center + egui::vec2(-radius * 0.6, radius),
+        ]
+    } else {
+        vec![
+            center + egui::vec2(-radius, -radius * 0.6),
+            center + egui::vec2(radius, -radius * 0.6),
+            center + egui::vec2(0.0, radius * 0.8),
+        ]
+    };
+    painter.add(egui::Shape::convex_polygon(
+        points,
+        color,
+        egui::Stroke::NONE,
+    ));
+}
+
+/// Paint a faint vertical line `offset` points into the text of the editor
+/// occupying `rect`, unless that falls outside it.
+fn paint_wrap_ruler(ui: &egui::Ui, rect: egui::Rect, offset: f32) {
+    let x = rect.left() + f32::from(EDITOR_MARGIN.left) + offset;
+    if x < rect.right() {
+        let color = ui.visuals().weak_text_color().gamma_multiply(0.35);
+        ui.painter()
+            .vline(x, rect.y_range(), egui::Stroke::new(1.0, color));
     }
 }
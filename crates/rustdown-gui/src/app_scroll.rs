@@ -25,6 +25,35 @@ impl RustdownApp {
         )
     }
 
+    /// Remember where the current file is scrolled to, so reopening it later
+    /// (or after a restart) restores the position.  The byte offset is only
+    /// recomputed when the view has moved, and not while a scroll target is
+    /// still pending (the view hasn't reached it yet).
+    pub(crate) fn remember_scroll_position(&mut self, ctx: &egui::Context) {
+        if self.doc.path.is_none()
+            || self.nav.pending_scroll.is_some()
+            || self.nav.pending_editor_scroll_y.is_some()
+            || self.nav.pending_preview_scroll_y.is_some()
+        {
+            return;
+        }
+        let y = if self.uses_editor() {
+            egui::scroll_area::State::load(ctx, scroll_math::editor_scroll_id())
+                .map(|state| state.offset.y)
+        } else {
+            Some(self.doc.preview_cache.last_scroll_y)
+        };
+        if y.is_none() || y == self.remembered_scroll_y {
+            return;
+        }
+        self.remembered_scroll_y = y;
+        if let Some(byte_offset) = self.current_scroll_byte_offset(ctx)
+            && let Some(path) = self.doc.path.as_deref()
+        {
+            self.scroll_positions.remember(path, byte_offset);
+        }
+    }
+
     /// Determine the current scroll position as a byte offset in the source
     /// text.  Works in both editor and preview modes.
     pub(crate) fn current_scroll_byte_offset(&mut self, ctx: &egui::Context) -> Option<usize> {
@@ -1,8 +1,13 @@
 use super::*;
-use crate::cli::{LaunchOptions, parse_launch_options};
-use crate::disk::io::{DiskRevision, atomic_write_utf8};
-use crate::disk::sync::{DiskConflict, ReloadKind};
-use crate::document::{EditorGalleyCache, TrackedTextBuffer, bytecount_newlines};
+use crate::cli::{
+    Command, LaunchOptions, file_manager_command, format_line_diff, format_outline,
+    parse_launch_options,
+};
+use crate::disk::io::{DiskRevision, MAX_FILE_BYTES, atomic_write_utf8};
+use crate::disk::sync::{DiskConflict, DiskSyncMode, ReloadKind};
+use crate::document::{
+    ASYNC_STATS_MIN_BYTES, EditorGalleyCache, TrackedTextBuffer, bytecount_newlines,
+};
 use crate::scroll_math;
 use crate::search::replace_all_occurrences;
 use std::{
@@ -76,6 +81,7 @@ fn dummy_editor_cache(ctx: &egui::Context, row_byte_offsets: Vec<(f32, u32)>) ->
         layout_sections: Vec::new(),
         galley,
         row_byte_offsets,
+        hidden: Vec::new(),
     }
 }
 
@@ -130,8 +136,22 @@ fn parse_launch_options_covers_modes_paths_and_diagnostics() {
             DIAGNOSTICS_DEFAULT_ITERATIONS
         );
         assert_eq!(options.diagnostics_runs, DIAGNOSTICS_DEFAULT_RUNS);
+        assert_eq!(options.diagnostics_format, DiagnosticsFormat::Text);
     }
 
+    let options = parse(&["-s", "README.md", "--", "OTHER.md", "-x.md"]);
+    assert_eq!(options.mode, Mode::SideBySide);
+    assert_eq!(
+        options.path.as_deref(),
+        Some(PathBuf::from("README.md")).as_deref()
+    );
+    assert_eq!(
+        options.extra_paths,
+        [PathBuf::from("OTHER.md"), PathBuf::from("-x.md")]
+    );
+    assert!(parse(&["README.md"]).extra_paths.is_empty());
+    assert_eq!(parse(&["README.md"]).line, None);
+
     let options = parse(&["--diagnostics-open", "README.md"]);
     assert_eq!(options.diagnostics, DiagnosticsMode::OpenPipeline);
     assert_eq!(
@@ -145,6 +165,13 @@ fn parse_launch_options_covers_modes_paths_and_diagnostics() {
     );
     assert_eq!(options.diagnostics_runs, DIAGNOSTICS_DEFAULT_RUNS);
 
+    let options = parse(&["--diag-save", "README.md"]);
+    assert_eq!(options.diagnostics, DiagnosticsMode::SavePipeline);
+    assert_eq!(
+        parse(&["--diagnostics-save"]).diagnostics,
+        DiagnosticsMode::SavePipeline
+    );
+
     let options = parse(&["-v"]);
     assert!(options.print_version);
     assert_eq!(options.mode, Mode::Edit);
@@ -187,6 +214,21 @@ fn parse_launch_options_covers_modes_paths_and_diagnostics() {
         assert_eq!(options.diagnostics_runs, expected);
     }
 
+    let format_cases = [
+        ("--diag-format=json", DiagnosticsFormat::Json),
+        ("--diagnostics-format=json", DiagnosticsFormat::Json),
+        ("--diag-format=text", DiagnosticsFormat::Text),
+        ("--diag-format=yaml", DiagnosticsFormat::Text),
+    ];
+    for (flag, expected) in format_cases {
+        let options = parse(&["--diag-open", flag, "README.md"]);
+        assert_eq!(options.diagnostics_format, expected, "{flag}");
+        assert_eq!(
+            options.path.as_deref(),
+            Some(PathBuf::from("README.md")).as_deref()
+        );
+    }
+
     #[cfg(debug_assertions)]
     {
         let options = parse(&["--diagnostics-nav", "README.md"]);
@@ -227,9 +269,17 @@ fn document_stats_and_path_helpers() {
         Path::new("chapter.markdown"),
         Path::new("later.md"),
     ];
+    for any_file in [false, true] {
+        assert_eq!(
+            first_dropped_path(files, any_file),
+            Some(PathBuf::from("chapter.markdown"))
+        );
+    }
+    let plain = [Path::new("notes.txt"), Path::new("todo.text")];
+    assert_eq!(first_dropped_path(plain, false), None);
     assert_eq!(
-        first_markdown_path(files),
-        Some(PathBuf::from("chapter.markdown"))
+        first_dropped_path(plain, true),
+        Some(PathBuf::from("notes.txt"))
     );
 }
 
@@ -312,7 +362,7 @@ fn edit_seq_dirty_flags_stats_and_replace_all() {
     app.bump_edit_seq();
     app.note_text_changed(true);
     assert!(app.doc.stats_dirty);
-    app.doc.last_edit_at = Instant::now().checked_sub(STATS_RECALC_DEBOUNCE);
+    app.doc.last_edit_at = Instant::now().checked_sub(app.timings.stats_debounce());
     let ctx = egui::Context::default();
     app.refresh_stats_if_due(&ctx);
     assert!(!app.doc.stats_dirty);
@@ -356,6 +406,121 @@ fn open_path_missing_file_treats_path_as_new_document() {
     let _ = fs::remove_dir_all(&dir);
 }
 
+#[test]
+fn session_restore_round_trips_and_merges_disk_changes() {
+    let dir = make_temp_dir("rustdown-session-restore-test");
+    let path = dir.join("notes.md");
+    assert!(fs::write(&path, "a\nb\n").is_ok());
+
+    // Clean file: only the path is stored, and restore simply reopens it.
+    let mut app = RustdownApp::default();
+    app.open_path(path.clone());
+    let session = app.session_state();
+    assert_eq!(session.path.as_deref(), Some(path.as_path()));
+    assert!(session.text.is_none() && session.base_text.is_none());
+    let mut restored = RustdownApp::default();
+    restored.restore_session(session);
+    assert_eq!(restored.doc.text.as_str(), "a\nb\n");
+    assert!(!restored.doc.dirty);
+
+    // Dirty file, unchanged on disk: edits come back as-is.
+    app.doc.text = Arc::new("a\nB\n".to_owned());
+    app.doc.mark_text_changed();
+    let session = app.session_state();
+    assert_eq!(session.text.as_deref(), Some("a\nB\n"));
+    assert_eq!(session.base_text.as_deref(), Some("a\nb\n"));
+    let mut restored = RustdownApp::default();
+    restored.restore_session(session.clone());
+    assert_eq!(restored.doc.text.as_str(), "a\nB\n");
+    assert_eq!(restored.doc.base_text.as_str(), "a\nb\n");
+    assert!(restored.doc.dirty);
+
+    // Dirty file changed on disk meanwhile: the edits merge with disk.
+    assert!(fs::write(&path, "A\nb\n").is_ok());
+    let mut restored = RustdownApp::default();
+    restored.restore_session(session.clone());
+    assert_eq!(restored.doc.text.as_str(), "A\nB\n");
+    assert_eq!(restored.doc.base_text.as_str(), "A\nb\n");
+    assert!(restored.doc.dirty);
+    assert!(restored.disk.conflict.is_none());
+
+    // Conflicting disk change: the conflict prompt is raised.
+    assert!(fs::write(&path, "a\nX\n").is_ok());
+    let mut restored = RustdownApp::default();
+    restored.restore_session(session);
+    assert_eq!(restored.doc.text.as_str(), "a\nB\n");
+    assert!(restored.disk.conflict.is_some());
+
+    // A file large enough to open on the worker still takes its edits.
+    let line = "A line of a large document.\n";
+    let large = line.repeat(ASYNC_OPEN_MIN_BYTES as usize / line.len() + 1);
+    assert!(fs::write(&path, &large).is_ok());
+    let mut restored = RustdownApp::default();
+    restored.restore_session(crate::session::SessionState {
+        path: Some(path.clone()),
+        text: Some(format!("{large}edit\n")),
+        base_text: Some(large.clone()),
+        ..Default::default()
+    });
+    assert!(restored.disk.opening.is_none());
+    assert_eq!(restored.doc.path.as_deref(), Some(path.as_path()));
+    assert_eq!(restored.doc.text.as_str(), format!("{large}edit\n"));
+    assert_eq!(restored.doc.base_text.as_str(), large);
+    assert!(restored.doc.dirty && restored.disk.conflict.is_none());
+
+    // Untitled buffer.
+    let mut restored = RustdownApp::default();
+    restored.restore_session(crate::session::SessionState {
+        text: Some("draft".to_owned()),
+        ..Default::default()
+    });
+    assert!(restored.doc.path.is_none());
+    assert_eq!(restored.doc.text.as_str(), "draft");
+    assert!(restored.doc.dirty);
+    assert_eq!(restored.session_state().text.as_deref(), Some("draft"));
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn scroll_positions_restore_on_open_and_follow_disk_reloads() {
+    // Offsets before the edit stay, after it shift, inside it snap to start.
+    let old = "# A\n\nintro\n\n# B\n";
+    let new = "# A\n\nlonger intro text\n\n# B\n";
+    assert_eq!(scroll_math::map_byte_through_edit(old, new, 2), 2);
+    assert_eq!(scroll_math::map_byte_through_edit(old, new, 13), 25);
+    assert_eq!(scroll_math::map_byte_through_edit(old, new, 8), 5);
+    assert_eq!(scroll_math::map_byte_through_edit("é", "", 1), 0);
+
+    let dir = make_temp_dir("rustdown-scroll-positions-test");
+    let path = dir.join("notes.md");
+    assert!(fs::write(&path, old).is_ok());
+
+    let mut app = RustdownApp::default();
+    app.open_path(path.clone());
+    assert!(app.nav.pending_scroll.is_none(), "nothing remembered yet");
+
+    app.scroll_positions.remember(&path, 13);
+    app.open_path(path.clone());
+    assert_eq!(
+        app.nav.pending_scroll,
+        Some(nav::panel::NavScrollTarget::ByteOffset(13))
+    );
+
+    // A clean reload keeps the view on the same content.
+    app.nav.pending_scroll = None;
+    app.incorporate_disk_text(new.to_owned(), test_rev(9, 9));
+    assert_eq!(app.doc.text.as_str(), new);
+    assert_eq!(app.scroll_positions.get(&path), Some(25));
+    assert_eq!(
+        app.nav.pending_scroll,
+        Some(nav::panel::NavScrollTarget::ByteOffset(25))
+    );
+    assert_eq!(app.session_state().scroll_positions.get(&path), Some(25));
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
 #[test]
 #[allow(clippy::type_complexity)]
 fn incorporate_disk_text_handles_clean_merge_and_conflict_outcomes() {
@@ -437,6 +602,65 @@ fn incorporate_disk_text_handles_clean_merge_and_conflict_outcomes() {
     }
 }
 
+#[test]
+fn confirm_disk_merges_asks_before_applying_a_clean_merge() {
+    let mut app = merge_app("a\nb\n", "a\nB\n", 1, 4, true);
+    app.confirm_disk_merges = true;
+    app.incorporate_disk_text("A\nb\n".to_owned(), test_rev(2, 4));
+    assert_eq!(app.doc.text.as_str(), "a\nB\n", "buffer untouched");
+    let conflict = disk_conflict(&app);
+    assert_eq!(conflict.disk_text, "A\nb\n");
+    assert_eq!(conflict.conflict_marked, "A\nB\n");
+    assert_eq!(conflict.ours_wins, "A\nB\n");
+
+    // A clean document still just reloads.
+    let mut app = merge_app("old", "old", 1, 3, false);
+    app.confirm_disk_merges = true;
+    app.incorporate_disk_text("new".to_owned(), test_rev(2, 3));
+    assert_eq!(app.doc.text.as_str(), "new");
+    assert!(app.disk.conflict.is_none());
+}
+
+#[test]
+fn identical_disk_reload_keeps_stats_and_caches() {
+    let mut app = merge_app("a\nb\n", "a\nb\n", 1, 4, false);
+    app.refresh_stats_now();
+    let edit_seq = app.doc.edit_seq;
+    let text = app.doc.text.clone();
+    let lines = app.doc.stats.lines;
+
+    // Same content, new revision (e.g. the file was touched or rewritten).
+    app.incorporate_disk_text("a\nb\n".to_owned(), test_rev(2, 4));
+    assert_eq!(app.doc.disk_rev, Some(test_rev(2, 4)));
+    assert_eq!(app.doc.edit_seq, edit_seq, "no reparse or relayout");
+    assert!(Arc::ptr_eq(&app.doc.text, &text));
+    assert!(Arc::ptr_eq(&app.doc.text, &app.doc.base_text));
+    assert!(!app.doc.stats_dirty);
+    assert_eq!(app.doc.stats.lines, lines);
+
+    // A real change still refreshes everything.
+    app.incorporate_disk_text("a\nb\nc\n".to_owned(), test_rev(3, 6));
+    assert_ne!(app.doc.edit_seq, edit_seq);
+    assert_eq!(app.doc.stats.lines, lines + 1);
+}
+
+#[test]
+fn merge_matching_disk_shares_base_text_allocation() {
+    // Ours already matches the new disk text, so the merge result is clean
+    // and should not hold a second copy of it.
+    let mut app = merge_app("a\nb\n", "a\nT\n", 1, 4, true);
+    app.incorporate_disk_text("a\nT\n".to_owned(), test_rev(2, 4));
+    assert!(app.disk.conflict.is_none());
+    assert_eq!(app.doc.text.as_str(), "a\nT\n");
+    assert!(Arc::ptr_eq(&app.doc.text, &app.doc.base_text));
+
+    // Differing contents keep separate allocations.
+    let mut app = merge_app("a\nb\nc\n", "A\nb\nc\n", 1, 6, true);
+    app.incorporate_disk_text("a\nb\nC\n".to_owned(), test_rev(2, 6));
+    assert_eq!(app.doc.text.as_str(), "A\nb\nC\n");
+    assert!(!Arc::ptr_eq(&app.doc.text, &app.doc.base_text));
+}
+
 #[test]
 fn conflict_resolution_open_merge_and_keep_mine() {
     // OpenConflictMerge: replaces buffer with conflict markers.
@@ -474,6 +698,117 @@ fn conflict_resolution_open_merge_and_keep_mine() {
     let _ = fs::remove_dir_all(&dir);
 }
 
+#[test]
+fn conflict_resolution_keep_both_writes_ours_and_reloads_disk() {
+    let dir = make_temp_dir("rustdown-merge-keep-both-test");
+    let original = dir.join("note.md");
+    let _ = atomic_write_utf8(&original, "a\nb\n");
+    let mut app = merge_app("a\nb\n", "a\nO\n", 1, 4, true);
+    app.doc.path = Some(original);
+    app.incorporate_disk_text("a\nT\n".to_owned(), test_rev(2, 4));
+    assert!(app.disk.conflict.is_some());
+
+    app.apply_conflict_choice(ConflictChoice::KeepBoth);
+    assert_eq!(app.doc.text.as_str(), "a\nT\n");
+    assert_eq!(app.doc.base_text.as_str(), "a\nT\n");
+    assert_eq!(app.doc.disk_rev, Some(test_rev(2, 4)));
+    assert!(!app.doc.dirty);
+    assert!(app.disk.conflict.is_none());
+    let sidecar_path = app
+        .disk
+        .merge_sidecar_path
+        .clone()
+        .unwrap_or_else(|| unreachable!());
+    assert_eq!(read_file(&sidecar_path), "a\nO\n");
+
+    // If the document's directory can't be written, the sidecar lands in
+    // the temp dir and that is the path reported.
+    let mut app = merge_app("a\nb\n", "a\nO\n", 1, 4, true);
+    app.doc.path = Some(dir.join("missing").join("note.md"));
+    app.incorporate_disk_text("a\nT\n".to_owned(), test_rev(2, 4));
+    app.apply_conflict_choice(ConflictChoice::KeepBoth);
+    assert!(app.disk.conflict.is_none());
+    let sidecar_path = app
+        .disk
+        .merge_sidecar_path
+        .clone()
+        .unwrap_or_else(|| unreachable!());
+    assert_eq!(sidecar_path.parent(), Some(std::env::temp_dir().as_path()));
+    assert_eq!(read_file(&sidecar_path), "a\nO\n");
+    let _ = fs::remove_file(&sidecar_path);
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn conflict_markers_gate_save_and_drive_next_conflict() {
+    let marked = "intro\n<<<<<<< ours\nA\n=======\nB\n>>>>>>> theirs\nmid\n<<<<<<< ours\nC\n=======\nD\n>>>>>>> theirs\n";
+    let starts = rustdown_core::conflict_marker_starts(marked);
+    assert_eq!(starts.len(), 2);
+    assert_eq!(DocumentStats::from_text(marked).conflict_blocks, 2);
+
+    let mut app = RustdownApp {
+        mode: Mode::Preview,
+        confirm_save_with_conflicts: true,
+        ..RustdownApp::default()
+    };
+    app.doc.text = Arc::new(marked.to_owned());
+    app.doc.dirty = true;
+    assert!(!app.save_doc(false));
+    assert_eq!(app.pending_conflict_save, Some(false));
+    assert!(app.doc.dirty);
+
+    // Markers shown in a fenced code block are content, not conflicts.
+    let mut fenced = Document {
+        text: Arc::new(format!("Resolve these:\n\n```text\n{marked}```\n")),
+        ..Document::default()
+    };
+    assert!(!fenced.has_unresolved_conflicts());
+    assert_eq!(DocumentStats::from_text(&fenced.text).conflict_blocks, 0);
+    let mut after_fence = Document {
+        text: Arc::new(format!("~~~\n<<<<<<<\n~~~\n{marked}")),
+        ..Document::default()
+    };
+    assert!(after_fence.has_unresolved_conflicts());
+    assert_eq!(
+        DocumentStats::from_text(&after_fence.text).conflict_blocks,
+        2
+    );
+
+    let ctx = warm_ctx();
+    let id = egui::Id::new("editor");
+    let cursor_byte = |ctx: &egui::Context| {
+        let state = egui::TextEdit::load_state(ctx, id).unwrap_or_default();
+        let index = state
+            .cursor
+            .char_range()
+            .map_or(0, |range| range.primary.index);
+        editor::char_index_to_byte(marked, index)
+    };
+    for expected in [starts[0], starts[1], starts[0]] {
+        app.go_to_next_conflict(&ctx);
+        assert_eq!(cursor_byte(&ctx), expected);
+        assert_eq!(
+            app.nav.pending_scroll,
+            Some(nav::panel::NavScrollTarget::ByteOffset(expected))
+        );
+    }
+    assert_eq!(app.mode, Mode::Edit);
+
+    // A fenced example is skipped on the way to the real conflict.
+    let example = "```\n<<<<<<<\nA\n=======\nB\n>>>>>>>\n```\n";
+    app.doc.text = Arc::new(format!("{example}{marked}"));
+    app.doc.mark_text_changed();
+    let mut state = egui::TextEdit::load_state(&ctx, id).unwrap_or_default();
+    state
+        .cursor
+        .set_char_range(Some(egui::text::CCursorRange::one(
+            egui::text::CCursor::new(0),
+        )));
+    state.store(&ctx, id);
+    app.go_to_next_conflict(&ctx);
+    assert_eq!(cursor_byte(&ctx), example.len() + starts[0]);
+}
+
 #[test]
 fn document_metadata_title_path_debounce_and_bytecount() {
     let default_doc = Document::default();
@@ -508,6 +843,10 @@ fn mode_transitions_icons_and_uses_editor() {
     assert_eq!(Mode::Edit.cycle(), Mode::Preview);
     assert_eq!(Mode::Preview.cycle(), Mode::SideBySide);
     assert_eq!(Mode::SideBySide.cycle(), Mode::Edit);
+    assert_eq!(
+        Mode::ALL.map(Mode::shortcut_key),
+        [egui::Key::Num1, egui::Key::Num2, egui::Key::Num3]
+    );
     for (mode, icon, tooltip) in [
         (Mode::Edit, "Ed", "Edit"),
         (Mode::Preview, "Pr", "Preview"),
@@ -723,6 +1062,18 @@ fn default_image_uri_scheme_covers_all_paths() {
         "Expected '{scheme}' to contain '{dir_name}'"
     );
     let _ = fs::remove_dir_all(&dir);
+
+    for (dir, expected) in [
+        ("/home/me/My Notes", "file:///home/me/My%20Notes/"),
+        ("/docs/#1 100%/", "file:///docs/%231%20100%25/"),
+        ("/café", "file:///caf%C3%A9/"),
+        (r"C:\Users\me\My Docs", "file:///C:/Users/me/My%20Docs/"),
+        (r"\\server\share\dir a", "file://server/share/dir%20a/"),
+        (r"\\?\C:\Users\me", "file:///C:/Users/me/"),
+        (r"\\?\UNC\server\share", "file://server/share/"),
+    ] {
+        assert_eq!(file_url_for_dir(dir), expected, "{dir}");
+    }
 }
 
 #[test]
@@ -861,6 +1212,230 @@ fn document_lifecycle_load_new_blank_and_sidecar_clearing() {
     let _ = fs::remove_dir_all(&dir);
 }
 
+#[test]
+fn parse_diff_subcommand_takes_two_operands() {
+    let options = parse(&["diff", "old.md", "-"]);
+    assert_eq!(
+        options.command,
+        Some(Command::Diff {
+            old: PathBuf::from("old.md"),
+            new: PathBuf::from("-"),
+        })
+    );
+    assert!(options.path.is_none());
+
+    let options = parse(&["diff", "--", "-a.md", "b.md"]);
+    assert_eq!(
+        options.command,
+        Some(Command::Diff {
+            old: PathBuf::from("-a.md"),
+            new: PathBuf::from("b.md"),
+        })
+    );
+
+    for args in [
+        &["diff"][..],
+        &["diff", "a.md"][..],
+        &["diff", "a.md", "b.md", "c.md"][..],
+        &["diff", "-", "-"][..],
+        &["diff", "--color", "a.md", "b.md"][..],
+    ] {
+        assert!(
+            matches!(parse(args).command, Some(Command::Invalid(_))),
+            "{args:?}"
+        );
+    }
+
+    assert!(parse(&["README.md", "diff"]).command.is_none());
+
+    // A file named like a subcommand opens after `--` or as a path.
+    for args in [&["--", "diff"][..], &["./diff"][..]] {
+        let options = parse(args);
+        assert!(options.command.is_none(), "{args:?}");
+        assert_eq!(
+            options.path.as_deref(),
+            Some(Path::new(args[args.len() - 1]))
+        );
+    }
+    assert!(matches!(
+        parse(&["diff"]).command,
+        Some(Command::Invalid(message)) if message.contains("rustdown -- diff")
+    ));
+}
+
+#[test]
+fn outline_subcommand_prints_headings_by_line() {
+    assert_eq!(
+        parse(&["outline", "--json", "-"]).command,
+        Some(Command::Outline {
+            path: PathBuf::from("-"),
+            json: true,
+        })
+    );
+    assert!(matches!(
+        parse(&["outline"]).command,
+        Some(Command::Invalid(message)) if message.contains("rustdown -- outline")
+    ));
+    assert_eq!(
+        parse(&["--", "outline"]).path.as_deref(),
+        Some(Path::new("outline"))
+    );
+    for args in [
+        &["outline"][..],
+        &["outline", "a.md", "b.md"][..],
+        &["outline", "--tree", "a.md"][..],
+    ] {
+        assert!(
+            matches!(parse(args).command, Some(Command::Invalid(_))),
+            "{args:?}"
+        );
+    }
+
+    let source = "# Guide\n\nIntro\n\n## Setup\n\n```\n# not a heading\n```\n\n### From \"source\"\n\n\n\n\n## Setup\n";
+    assert_eq!(
+        format_outline(source, false),
+        " 1  Guide\n 5    Setup\n11      From \"source\"\n16    Setup\n"
+    );
+    assert_eq!(
+        format_outline(source, true),
+        "[{\"level\":1,\"line\":1,\"text\":\"Guide\",\"slug\":\"guide\"},\
+         {\"level\":2,\"line\":5,\"text\":\"Setup\",\"slug\":\"setup\"},\
+         {\"level\":3,\"line\":11,\"text\":\"From \\\"source\\\"\",\"slug\":\"from-source\"},\
+         {\"level\":2,\"line\":16,\"text\":\"Setup\",\"slug\":\"setup-1\"}]\n"
+    );
+    assert_eq!(format_outline("no headings\n", false), "");
+    assert_eq!(format_outline("no headings\n", true), "[]\n");
+}
+
+#[test]
+fn format_line_diff_prints_unified_hunks() {
+    assert_eq!(format_line_diff("a", "same\n", "b", "same\n"), "");
+    assert_eq!(
+        format_line_diff("old.md", "a\nb\nc\n", "new.md", "a\nB\nc\nd"),
+        "--- old.md\n+++ new.md\n\
+         @@ -2,1 +2,1 @@\n-b\n+B\n\
+         @@ -3,0 +4,1 @@\n+d\n\\ No newline at end of file\n"
+    );
+    assert_eq!(
+        format_line_diff("old", "x\ny\nz\n", "new", "z\n"),
+        "--- old\n+++ new\n@@ -1,2 +0,0 @@\n-x\n-y\n"
+    );
+}
+
+#[test]
+fn launch_line_puts_the_editor_cursor_on_it() {
+    let dir = make_temp_dir("rustdown-launch-line");
+    let path = dir.join("notes.md");
+    fs::write(&path, "one\ntwo\nthree\n").unwrap_or_else(|_| unreachable!());
+    let arg = |suffix: &str| format!("{}{suffix}", path.display());
+
+    let options = parse(&[&arg(":3")]);
+    assert_eq!(options.path.as_deref(), Some(path.as_path()));
+    assert_eq!(options.line, Some(3));
+    assert_eq!(options.mode, Mode::Edit);
+    let options = parse(&["+2", &arg("")]);
+    assert_eq!(options.path.as_deref(), Some(path.as_path()));
+    assert_eq!(options.line, Some(2));
+    assert_eq!(parse(&["-p", "+2", &arg("")]).mode, Mode::Preview);
+    // Only a numeric suffix on an existing file is a line.
+    for suffix in [":0", ":x", ":3:4", ":"] {
+        let options = parse(&[&arg(suffix)]);
+        assert_eq!(options.path, Some(PathBuf::from(arg(suffix))), "{suffix}");
+        assert_eq!(options.line, None);
+    }
+    assert_eq!(parse(&["missing.md:3"]).line, None);
+
+    let mut app = RustdownApp::from_launch_options(parse(&[&arg(":3")]));
+    let ctx = warm_ctx();
+    let _ = ctx.run(egui::RawInput::default(), |ctx| {
+        app.go_to_pending_line(ctx);
+    });
+    let cursor = egui::TextEdit::load_state(&ctx, egui::Id::new("editor"))
+        .and_then(|state| state.cursor.char_range())
+        .map(|range| range.primary.index);
+    assert_eq!(cursor, Some("one\ntwo\n".len()));
+    assert!(ctx.memory(|mem| mem.has_focus(egui::Id::new("editor"))));
+    assert!(app.pending_line.is_none());
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn merge_tool_opens_the_merge_and_exits_by_whether_it_was_resolved() {
+    let options = parse(&["--merge", "b.md", "o.md", "t.md", "out.md"]);
+    assert_eq!(
+        options.merge,
+        Some(crate::cli::MergeToolPaths {
+            base: PathBuf::from("b.md"),
+            ours: PathBuf::from("o.md"),
+            theirs: PathBuf::from("t.md"),
+            output: PathBuf::from("out.md"),
+        })
+    );
+    assert_eq!(options.mode, Mode::Edit);
+    assert!(matches!(
+        parse(&["--merge", "b.md", "o.md"]).command,
+        Some(Command::Invalid(_))
+    ));
+
+    let dir = make_temp_dir("rustdown-mergetool");
+    let paths = crate::cli::MergeToolPaths {
+        base: dir.join("base.md"),
+        ours: dir.join("ours.md"),
+        theirs: dir.join("theirs.md"),
+        output: dir.join("out.md"),
+    };
+    for (path, text) in [
+        (&paths.base, "# T\n\nbase\n"),
+        (&paths.ours, "# T\n\nours\n"),
+        (&paths.theirs, "# T\n\ntheirs\n"),
+    ] {
+        fs::write(path, text).unwrap_or_else(|_| unreachable!());
+    }
+    let run = |resolve: bool| {
+        let inputs = crate::mergetool::MergeInputs::read(&paths).unwrap_or_else(|_| unreachable!());
+        let mut app = RustdownApp::default();
+        app.start_merge_tool(inputs);
+        // Unsaved unless git already wrote the same merge to OUTPUT.
+        assert_eq!(app.doc.dirty, !paths.output.exists());
+        assert!(app.doc.text.contains("<<<<<<<"));
+        assert_eq!(app.pending_line, Some(3));
+        assert!(app.merge_tool.as_ref().is_some_and(|tool| !tool.saved));
+        // Only a deliberate save writes OUTPUT, unformatted.
+        app.autosave = true;
+        app.format_on_save = true;
+        app.doc.last_edit_at = None;
+        let before = fs::read_to_string(&paths.output).ok();
+        app.autosave_if_due(&warm_ctx());
+        assert_eq!(fs::read_to_string(&paths.output).ok(), before);
+        if resolve {
+            // A documented marker in a code fence is not a conflict left.
+            app.doc.text =
+                Arc::new("# T\n\nboth  \n\n```\n<<<<<<<\nA\n=======\nB\n>>>>>>>\n```\n".to_owned());
+            app.bump_edit_seq();
+        }
+        assert!(app.save_doc(false));
+        let tool = app.merge_tool.unwrap_or_else(|| unreachable!());
+        assert_eq!(tool.saved, resolve, "the window stays open to finish");
+        crate::mergetool::exit_code(&tool.resolved)
+    };
+    assert_eq!(run(false), 1);
+    assert!(
+        fs::read_to_string(&paths.output)
+            .unwrap_or_default()
+            .contains(">>>>>>>")
+    );
+    assert_eq!(run(true), 0);
+    assert!(
+        fs::read_to_string(&paths.output)
+            .unwrap_or_default()
+            .starts_with("# T\n\nboth  \n\n```\n")
+    );
+
+    fs::remove_file(&paths.ours).unwrap_or_else(|_| unreachable!());
+    assert!(crate::mergetool::MergeInputs::read(&paths).is_err_and(|(path, _)| path == paths.ours));
+    let _ = fs::remove_dir_all(&dir);
+}
+
 #[test]
 fn app_operations_search_format_stats_action_reload() {
     // Launch options.
@@ -868,10 +1443,15 @@ fn app_operations_search_format_stats_action_reload() {
         mode: Mode::Preview,
         mode_explicit: true,
         path: None,
+        line: None,
+        extra_paths: Vec::new(),
         print_version: false,
         diagnostics: DiagnosticsMode::Off,
         diagnostics_iterations: 200,
         diagnostics_runs: 1,
+        diagnostics_format: DiagnosticsFormat::Text,
+        command: None,
+        merge: None,
     };
     assert_eq!(RustdownApp::from_launch_options(opts).mode, Mode::Preview);
 
@@ -1033,3 +1613,1356 @@ fn bundled_docs_are_parseable_and_loadable() {
     assert_eq!(app.doc.text.as_str(), app.doc.base_text.as_str());
     assert!(app.doc.edit_seq > seq_before);
 }
+
+#[test]
+fn debounced_side_by_side_preview_keeps_last_render() {
+    let ctx = warm_ctx();
+    let mut app = RustdownApp {
+        mode: Mode::SideBySide,
+        ..RustdownApp::default()
+    };
+    app.doc.text = Arc::new("# Before\n".to_owned());
+    let _ = ctx.run(egui::RawInput::default(), |ctx| {
+        egui::CentralPanel::default().show(ctx, |ui| app.show_preview(ui));
+    });
+    let blocks = |app: &RustdownApp| app.doc.preview_cache.blocks.len();
+    assert_eq!(blocks(&app), 1);
+
+    // Mid-typing: the old render stays up and nothing is reparsed yet, and
+    // the preview holds no reference that would make edits copy the text.
+    let before = Arc::as_ptr(&app.doc.text);
+    Arc::make_mut(&mut app.doc.text).push_str("\nmore\n");
+    assert_eq!(Arc::as_ptr(&app.doc.text), before, "edited in place");
+    app.bump_edit_seq();
+    app.note_text_changed(true);
+    let _ = ctx.run(egui::RawInput::default(), |ctx| {
+        egui::CentralPanel::default().show(ctx, |ui| app.show_preview(ui));
+    });
+    assert!(app.doc.preview_dirty);
+    assert_eq!(blocks(&app), 1);
+
+    // Once the debounce has passed the preview catches up.
+    app.doc.last_edit_at = None;
+    let _ = ctx.run(egui::RawInput::default(), |ctx| {
+        egui::CentralPanel::default().show(ctx, |ui| app.show_preview(ui));
+    });
+    assert!(!app.doc.preview_dirty);
+    assert_eq!(blocks(&app), 2);
+    assert_eq!(Arc::strong_count(&app.doc.text), 1);
+}
+
+#[test]
+fn title_stats_follow_debounced_stats() {
+    assert_eq!(group_thousands(0), "0");
+    assert_eq!(group_thousands(999), "999");
+    assert_eq!(group_thousands(1_204), "1,204");
+    assert_eq!(group_thousands(12_345_678), "12,345,678");
+
+    let ctx = warm_ctx();
+    let mut app = RustdownApp::default();
+    app.update_viewport_title(&ctx);
+    let plain = app.last_viewport_title.clone();
+    assert!(plain.ends_with(&*app.doc.title()));
+
+    app.title_stats = preferences::TitleStats::Words;
+    app.doc.text = Arc::new("one two three".to_owned());
+    app.note_text_changed(true);
+    app.update_viewport_title(&ctx);
+    assert!(app.last_viewport_title.ends_with("* - 0 words"));
+
+    app.refresh_stats_now();
+    app.update_viewport_title(&ctx);
+    assert!(app.last_viewport_title.ends_with("* - 3 words"));
+
+    app.title_stats = preferences::TitleStats::ReadingTime;
+    app.doc.stats.words = 401;
+    app.update_viewport_title(&ctx);
+    assert!(app.last_viewport_title.ends_with("* - 3 min read"));
+}
+
+#[test]
+fn window_title_prefers_the_detected_title() {
+    let ctx = warm_ctx();
+    let mut app = RustdownApp::default();
+    app.doc.path = Some(PathBuf::from("notes/scratch.md"));
+    let retitle = |app: &mut RustdownApp, text: &str, debounced: bool| {
+        app.doc.text = Arc::new(text.to_owned());
+        app.bump_edit_seq();
+        app.note_text_changed(!debounced);
+        app.update_viewport_title(&ctx);
+        app.last_viewport_title.clone()
+    };
+
+    assert!(retitle(&mut app, "no heading\n", true).ends_with(" - scratch.md*"));
+    assert!(retitle(&mut app, "## Sub\n\n# Plan for *Q3*\n", true).ends_with(" - Plan for Q3*"));
+    assert_eq!(app.doc.display_title(), "Plan for Q3");
+    assert!(retitle(&mut app, "---\ntitle: Roadmap\n---\n# Plan\n", true).ends_with(" - Roadmap*"));
+
+    // Mid-typing, before the stats debounce, the last title stays.
+    assert!(retitle(&mut app, "# Other\n", false).ends_with(" - Roadmap*"));
+    app.refresh_stats_now();
+    app.update_viewport_title(&ctx);
+    assert!(app.last_viewport_title.ends_with(" - Other*"));
+    assert_eq!(app.doc.title(), "scratch.md", "dialogs keep the file name");
+}
+
+#[test]
+fn copy_plain_text_uses_preview_parse_options() {
+    let ctx = warm_ctx();
+    let mut app = RustdownApp::default();
+    app.doc.text = Arc::new("**one**\n[two](https://x.io)\n".to_owned());
+    let copied = |app: &RustdownApp| {
+        let output = ctx.run(egui::RawInput::default(), |ctx| app.copy_plain_text(ctx));
+        output
+            .platform_output
+            .commands
+            .into_iter()
+            .find_map(|command| match command {
+                egui::OutputCommand::CopyText(text) => Some(text),
+                _ => None,
+            })
+    };
+    assert_eq!(copied(&app).as_deref(), Some("one two (https://x.io)\n"));
+    app.preview_preserve_line_breaks = true;
+    assert_eq!(copied(&app).as_deref(), Some("one\ntwo (https://x.io)\n"));
+}
+
+#[test]
+fn wrap_column_follows_editorconfig_max_line_length() {
+    let dir = make_temp_dir("rustdown-wrap-column-test");
+    let _ = fs::write(
+        dir.join(".editorconfig"),
+        "root = true\n[*.md]\nmax_line_length = 72\n",
+    );
+    let path = dir.join("note.md");
+    let _ = fs::write(&path, "text\n");
+
+    let mut app = RustdownApp::default();
+    assert_eq!(app.doc.wrap_column, None);
+    app.open_path(path);
+    assert_eq!(app.doc.wrap_column, Some(72));
+    app.load_bundled(BundledDoc::Demo);
+    assert_eq!(app.doc.wrap_column, None);
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn markdown_link_targets_resolve_local_markdown_only() {
+    let dir = Path::new("/docs/notes");
+    for (url, expected) in [
+        ("other.md", Some("/docs/notes/other.md")),
+        (
+            "../guide/Setup.MARKDOWN#linux",
+            Some("/docs/notes/../guide/Setup.MARKDOWN"),
+        ),
+        ("my%20notes.md?raw=1", Some("/docs/notes/my notes.md")),
+        ("https://example.com/readme.md", None),
+        ("mailto:someone@example.com", None),
+        ("image.png", None),
+        ("#section", None),
+        ("", None),
+    ] {
+        assert_eq!(
+            markdown_link_target(url, Some(dir)),
+            expected.map(PathBuf::from),
+            "{url}"
+        );
+    }
+    assert_eq!(markdown_link_target("other.md", None), None);
+    #[cfg(unix)]
+    {
+        assert_eq!(
+            markdown_link_target("file:///srv/a%20b.md", None),
+            Some(PathBuf::from("/srv/a b.md"))
+        );
+        assert_eq!(
+            markdown_link_target("/srv/abs.md", None),
+            Some(PathBuf::from("/srv/abs.md"))
+        );
+    }
+}
+
+#[test]
+fn follow_markdown_links_opens_in_app_behind_unsaved_guard() {
+    let ctx = warm_ctx();
+    let mut app = RustdownApp {
+        follow_markdown_links: true,
+        ..RustdownApp::default()
+    };
+    app.doc.path = Some(PathBuf::from("/docs/note.md"));
+    app.doc.dirty = true;
+    let output = ctx.run(egui::RawInput::default(), |ctx| {
+        ctx.open_url(egui::OpenUrl::same_tab("https://example.com"));
+        ctx.open_url(egui::OpenUrl::same_tab("other.md"));
+        app.follow_markdown_links(ctx);
+    });
+    assert!(matches!(
+        &app.pending_action,
+        Some(PendingAction::Open(path)) if path == Path::new("/docs/other.md")
+    ));
+    let urls: Vec<_> = output
+        .platform_output
+        .commands
+        .iter()
+        .filter_map(|command| match command {
+            egui::OutputCommand::OpenUrl(open) => Some(open.url.as_str()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(urls, ["https://example.com"]);
+
+    app.pending_action = None;
+    app.follow_markdown_links = false;
+    let output = ctx.run(egui::RawInput::default(), |ctx| {
+        ctx.open_url(egui::OpenUrl::same_tab("other.md"));
+        app.follow_markdown_links(ctx);
+    });
+    assert!(app.pending_action.is_none());
+    assert_eq!(output.platform_output.commands.len(), 1);
+}
+
+#[test]
+fn wiki_links_open_notes_by_name_or_offer_to_create_them() {
+    let dir = make_temp_dir("rustdown-wiki-links");
+    let _ = fs::create_dir_all(dir.join("topics"));
+    let _ = fs::write(dir.join("index.md"), "[[Deep Dive]] [[Missing Note]]\n");
+    let _ = fs::write(dir.join("topics/deep dive.md"), "# Deep\n");
+    let ctx = warm_ctx();
+    let mut app = RustdownApp::default();
+    app.open_path(dir.join("index.md"));
+
+    // Clicks on wiki links are taken even with local links left to the OS.
+    let output = ctx.run(egui::RawInput::default(), |ctx| {
+        ctx.open_url(egui::OpenUrl::same_tab("wiki:Deep Dive#Intro"));
+        app.follow_markdown_links(ctx);
+    });
+    assert!(output.platform_output.commands.is_empty());
+    assert_eq!(
+        app.doc.path.as_deref(),
+        Some(dir.join("topics/deep dive.md").as_path())
+    );
+
+    app.follow_wiki_link("Missing Note");
+    let new_note = dir.join("topics/Missing Note.md");
+    assert_eq!(app.pending_new_note.as_deref(), Some(new_note.as_path()));
+    app.create_pending_note();
+    assert!(app.pending_new_note.is_none());
+    assert_eq!(app.doc.path.as_deref(), Some(new_note.as_path()));
+    assert_eq!(app.doc.text.as_str(), "# Missing Note\n");
+
+    for outside in ["../escape", "/etc/passwd", "a/../../b"] {
+        app.error = None;
+        app.follow_wiki_link(outside);
+        assert!(app.pending_new_note.is_none(), "{outside}");
+        assert!(app.error.is_some(), "{outside}");
+    }
+    assert!(!dir.parent().unwrap_or(&dir).join("escape.md").exists());
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn heading_breadcrumb_follows_cursor_section() {
+    let md = "# Intro\n\n## Setup\n\n### Linux\n\napt install\n\n## Usage\n\nrun it\n";
+    let mut app = RustdownApp::default();
+    app.load_document(PathBuf::from("a.md"), md.to_owned(), None);
+    let ctx = warm_ctx();
+    let id = egui::Id::new("editor");
+    let place_cursor = |byte: usize| {
+        let mut state = egui::TextEdit::load_state(&ctx, id).unwrap_or_default();
+        let index = md[..byte].chars().count();
+        state
+            .cursor
+            .set_char_range(Some(egui::text::CCursorRange::one(
+                egui::text::CCursor::new(index),
+            )));
+        state.store(&ctx, id);
+    };
+
+    assert_eq!(app.heading_breadcrumb(&ctx), "");
+    place_cursor(md.find("apt").unwrap_or_default());
+    assert_eq!(app.heading_breadcrumb(&ctx), "Intro › Setup › Linux");
+    place_cursor(md.find("run").unwrap_or_default());
+    assert_eq!(app.heading_breadcrumb(&ctx), "Intro › Usage");
+
+    app.mode = Mode::Preview;
+    assert_eq!(app.heading_breadcrumb(&ctx), "");
+}
+
+#[test]
+fn change_markers_follow_edits_once_stats_settle_and_clear_on_save() {
+    let dir = make_temp_dir("rustdown-change-gutter");
+    let path = dir.join("a.md");
+    let mut app = RustdownApp::default();
+    app.load_document(path, "one\ntwo\n".to_owned(), None);
+    let ctx = warm_ctx();
+    let frame = |app: &mut RustdownApp| {
+        let _ = ctx.run(egui::RawInput::default(), |ctx| {
+            egui::CentralPanel::default().show(ctx, |ui| app.show_editor(ui));
+        });
+    };
+    frame(&mut app);
+    assert!(app.doc.line_changes.markers.is_empty());
+
+    app.doc.text = Arc::new("one\n2\nthree\n".to_owned());
+    app.bump_edit_seq();
+    app.note_text_changed(true);
+    frame(&mut app);
+    assert!(app.doc.line_changes.markers.is_empty(), "debounced");
+    app.refresh_stats_now();
+    frame(&mut app);
+    assert_eq!(
+        app.doc.line_changes.markers,
+        [crate::changes::LineChange {
+            lines: 1..3,
+            base_lines: 1..2,
+            kind: crate::changes::LineChangeKind::Modified,
+        }]
+    );
+
+    assert!(app.save_doc(false));
+    frame(&mut app);
+    assert!(app.doc.line_changes.markers.is_empty());
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn folded_sections_lay_out_hidden_and_unfold_under_cursor() {
+    let md = "# Intro\nintro text\n## Setup\nsetup text\n# End\n";
+    let mut app = RustdownApp::default();
+    app.load_document(PathBuf::from("a.md"), md.to_owned(), None);
+    let ctx = warm_ctx();
+    let frame = |app: &mut RustdownApp| {
+        let _ = ctx.run(egui::RawInput::default(), |ctx| {
+            egui::CentralPanel::default().show(ctx, |ui| app.show_editor(ui));
+        });
+    };
+    frame(&mut app);
+    assert_eq!(app.doc.folding.sections.len(), 2);
+    assert!(
+        app.doc
+            .editor_galley_cache
+            .as_ref()
+            .is_some_and(|cache| cache.hidden.is_empty())
+    );
+
+    app.doc.folding.toggle(0);
+    frame(&mut app);
+    let body = md.find("intro text").unwrap_or_default()..md.find("# End").unwrap_or_default();
+    assert_eq!(app.doc.folding.hidden(), std::slice::from_ref(&body));
+    assert!(
+        app.doc
+            .editor_galley_cache
+            .as_ref()
+            .is_some_and(|cache| cache.hidden == app.doc.folding.hidden())
+    );
+
+    // Typing above the fold keeps it, re-resolved against the new text.
+    let id = egui::Id::new("editor");
+    let mut text = (*app.doc.text).clone();
+    text.insert_str(0, "Preface\n\n");
+    app.doc.text = Arc::new(text);
+    app.bump_edit_seq();
+    app.note_text_changed(true);
+    frame(&mut app);
+    assert_eq!(app.doc.folding.folded.len(), 1);
+    assert_eq!(app.doc.folding.hidden()[0].start, body.start + 9);
+    // The headings the layouter found for the edit become the outline.
+    assert_eq!(app.nav.outline_seq(), app.doc.edit_seq);
+    assert_eq!(app.nav.outline.len(), 3);
+
+    // A caret moved into the hidden body unfolds it.
+    let mut state = egui::TextEdit::load_state(&ctx, id).unwrap_or_default();
+    state
+        .cursor
+        .set_char_range(Some(egui::text::CCursorRange::one(
+            egui::text::CCursor::new(body.start + 12),
+        )));
+    state.store(&ctx, id);
+    frame(&mut app);
+    assert!(app.doc.folding.folded.is_empty());
+}
+
+#[test]
+fn html_export_embeds_the_chosen_stylesheet() {
+    let dir = make_temp_dir("rustdown-export");
+    let out = dir.join("notes.html");
+    let mut app = RustdownApp {
+        export_theme: preferences::ExportTheme::Dark,
+        ..RustdownApp::default()
+    };
+    app.heading_scales = preferences::HeadingScales([3.0, 1.5, 1.25, 1.1, 1.05, 1.0]);
+    app.load_document(dir.join("notes.md"), "# Notes\n\nbody\n".to_owned(), None);
+
+    assert!(app.write_html_export(&out));
+    let html = read_file(&out);
+    assert!(html.contains("<title>notes.md</title>"));
+    assert!(html.contains("background: #1e1e1e"));
+    assert!(html.contains("h1 { font-size: 3em; }"));
+    assert!(html.contains("<h1 id=\"notes\">Notes</h1>"));
+
+    let css = dir.join("custom.css");
+    fs::write(&css, "body { color: teal; }\n").ok();
+    app.export_css = Some(css);
+    assert!(app.write_html_export(&out));
+    let html = read_file(&out);
+    assert!(html.contains("<style>\nbody { color: teal; }\n</style>"));
+    assert!(!html.contains("#1e1e1e"));
+
+    app.export_css = Some(dir.join("missing.css"));
+    assert!(!app.write_html_export(&out));
+    assert!(
+        app.error
+            .as_deref()
+            .is_some_and(|error| error.starts_with("Couldn't read stylesheet"))
+    );
+    assert!(
+        read_file(&out).contains("color: teal"),
+        "export left untouched"
+    );
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn exports_strip_front_matter_unless_asked_to_keep_it() {
+    let mut app = RustdownApp::default();
+    app.doc.text = Arc::new("---\ntitle: Notes\n---\n# Body\n".to_owned());
+    assert_eq!(
+        app.preview_parse_options().front_matter,
+        rustdown_md::FrontMatter::Keep
+    );
+    let html = app.html_document().unwrap_or_default();
+    assert!(!html.contains("title: Notes"));
+    assert!(html.contains("<h1 id=\"body\">Body</h1>"));
+
+    app.export_front_matter = preferences::FrontMatterExport::Metadata;
+    let html = app.html_document().unwrap_or_default();
+    assert!(html.contains("<title>Notes</title>"));
+    assert!(!html.contains("title: Notes"));
+
+    app.export_front_matter = preferences::FrontMatterExport::Keep;
+    assert!(
+        app.html_document()
+            .unwrap_or_default()
+            .contains("title: Notes")
+    );
+    assert_eq!(
+        app.plain_text_parse_options().front_matter,
+        rustdown_md::FrontMatter::Keep
+    );
+}
+
+#[test]
+fn smart_punctuation_is_set_per_output() {
+    let mut app = RustdownApp::default();
+    assert!(!app.preview_parse_options().smart_punctuation);
+    assert!(!app.html_parse_options().smart_punctuation);
+    assert!(!app.plain_text_parse_options().smart_punctuation);
+
+    app.preview_preserve_line_breaks = true;
+    app.smart_punctuation.html = true;
+    assert!(!app.preview_parse_options().smart_punctuation);
+    assert!(app.html_parse_options().smart_punctuation);
+    assert!(app.html_parse_options().preserve_line_breaks);
+    assert!(!app.plain_text_parse_options().smart_punctuation);
+
+    app.doc.text = Arc::new("\"a\" -- b\n".to_owned());
+    let html = app.html_document().unwrap_or_default();
+    assert!(html.contains("\u{201c}a\u{201d} \u{2013} b"));
+}
+
+#[test]
+fn disk_rev_label_shows_relative_age_and_size() {
+    let now = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000);
+    let label = |ago: Duration, len: u64| {
+        let rev = DiskRevision {
+            modified: now.checked_sub(ago).unwrap_or(now),
+            ..test_rev(0, len)
+        };
+        disk_rev_label(&rev, now)
+    };
+    assert_eq!(
+        label(Duration::from_secs(3), 512),
+        ("saved just now · 512 B".to_owned(), Duration::from_secs(7))
+    );
+    assert_eq!(
+        label(Duration::from_millis(42_500), 14 * 1024).0,
+        "saved 42s ago · 14 KB"
+    );
+    assert_eq!(
+        label(Duration::from_millis(42_500), 0).1,
+        Duration::from_millis(500)
+    );
+    assert_eq!(
+        label(Duration::from_secs(150), 1536),
+        ("saved 2m ago · 1.5 KB".to_owned(), Duration::from_secs(30))
+    );
+    assert_eq!(
+        label(Duration::from_hours(2), 3 * 1024 * 1024).0,
+        "saved 2h ago · 3.0 MB"
+    );
+    assert_eq!(label(Duration::from_hours(72), 0).0, "saved 3d ago · 0 B");
+
+    // A modification time ahead of the clock reads as just saved.
+    let future = DiskRevision {
+        modified: now + Duration::from_secs(30),
+        ..test_rev(0, 1)
+    };
+    assert_eq!(disk_rev_label(&future, now).0, "saved just now · 1 B");
+}
+
+#[test]
+fn disk_reload_can_be_undone_in_the_editor() {
+    let ctx = warm_ctx();
+    let id = egui::Id::new("editor");
+    let mut app = RustdownApp::default();
+    app.load_document(PathBuf::from("a.md"), "mine\n".to_owned(), None);
+    let frame = |app: &mut RustdownApp, events: Vec<egui::Event>| {
+        let input = egui::RawInput {
+            events,
+            ..Default::default()
+        };
+        let _ = ctx.run(input, |ctx| {
+            egui::CentralPanel::default().show(ctx, |ui| app.show_editor(ui));
+        });
+    };
+    ctx.memory_mut(|mem| mem.request_focus(id));
+    frame(&mut app, Vec::new());
+
+    app.incorporate_disk_text("theirs\n".to_owned(), test_rev(5, 7));
+    assert_eq!(app.doc.text.as_str(), "theirs\n");
+    assert!(app.disk.replaced_text.is_some());
+    assert_eq!(
+        app.disk.reload_notice.map(|(notice, _)| notice),
+        Some("Reloaded from disk")
+    );
+
+    frame(&mut app, Vec::new());
+    assert!(app.disk.replaced_text.is_none());
+    let undo = egui::Event::Key {
+        key: egui::Key::Z,
+        physical_key: None,
+        pressed: true,
+        repeat: false,
+        modifiers: egui::Modifiers::COMMAND,
+    };
+    frame(&mut app, vec![undo]);
+    assert_eq!(app.doc.text.as_str(), "mine\n");
+}
+
+#[test]
+fn reverting_a_change_marker_is_undoable() {
+    let ctx = warm_ctx();
+    let mut app = RustdownApp::default();
+    app.load_document(PathBuf::from("a.md"), "a\nb\nc\n".to_owned(), None);
+    let frame = |app: &mut RustdownApp, events: Vec<egui::Event>| {
+        let input = egui::RawInput {
+            events,
+            ..Default::default()
+        };
+        let _ = ctx.run(input, |ctx| {
+            egui::CentralPanel::default().show(ctx, |ui| app.show_editor(ui));
+        });
+    };
+    ctx.memory_mut(|mem| mem.request_focus(egui::Id::new("editor")));
+    app.doc.text = Arc::new("A\nb\nc\nd\n".to_owned());
+    app.bump_edit_seq();
+    app.note_text_changed(false);
+    frame(&mut app, Vec::new());
+    assert_eq!(app.doc.line_changes.markers.len(), 2);
+
+    app.revert_line_change(&ctx, 1);
+    assert_eq!(app.doc.text.as_str(), "A\nb\nc\n");
+    let undo = egui::Event::Key {
+        key: egui::Key::Z,
+        physical_key: None,
+        pressed: true,
+        repeat: false,
+        modifiers: egui::Modifiers::COMMAND,
+    };
+    frame(&mut app, vec![undo]);
+    assert_eq!(app.doc.text.as_str(), "A\nb\nc\nd\n");
+}
+
+#[test]
+fn tab_indent_uses_the_loaded_width_and_is_undoable() {
+    let ctx = warm_ctx();
+    let mut app = RustdownApp::default();
+    app.load_document(PathBuf::from("a.md"), "a\n".to_owned(), None);
+    app.doc.indent_size = 3;
+    let frame = |app: &mut RustdownApp, events: Vec<egui::Event>| {
+        let input = egui::RawInput {
+            events,
+            ..Default::default()
+        };
+        let _ = ctx.run(input, |ctx| {
+            egui::CentralPanel::default().show(ctx, |ui| app.show_editor(ui));
+        });
+    };
+    ctx.memory_mut(|mem| mem.request_focus(egui::Id::new("editor")));
+    frame(&mut app, Vec::new());
+    let id = egui::Id::new("editor");
+    let mut state = egui::TextEdit::load_state(&ctx, id).unwrap_or_default();
+    state
+        .cursor
+        .set_char_range(Some(egui::text::CCursorRange::one(
+            egui::text::CCursor::new(0),
+        )));
+    state.store(&ctx, id);
+    frame(&mut app, vec![egui::Event::Text("b".to_owned())]);
+
+    app.indent_editor_selection(&ctx, false);
+    assert_eq!(app.doc.text.as_str(), "b   a\n");
+    let undo = egui::Event::Key {
+        key: egui::Key::Z,
+        physical_key: None,
+        pressed: true,
+        repeat: false,
+        modifiers: egui::Modifiers::COMMAND,
+    };
+    frame(&mut app, vec![undo]);
+    assert_eq!(app.doc.text.as_str(), "ba\n");
+}
+
+#[test]
+fn window_state_is_restored_and_tracked() {
+    let window = preferences::WindowSettings {
+        always_on_top: true,
+        maximized: true,
+        fullscreen: false,
+    };
+    let viewport = native_viewport(window);
+    assert_eq!(viewport.window_level, Some(egui::WindowLevel::AlwaysOnTop));
+    assert_eq!(viewport.maximized, Some(true));
+    assert_eq!(viewport.fullscreen, Some(false));
+
+    let ctx = warm_ctx();
+    let mut app = RustdownApp::default();
+    let mut input = egui::RawInput::default();
+    let info = input.viewports.entry(egui::ViewportId::ROOT).or_default();
+    info.maximized = Some(true);
+    info.fullscreen = Some(false);
+    let _ = ctx.run(input, |ctx| {
+        app.remember_window_state(ctx);
+    });
+    assert!(app.window.maximized);
+    assert!(!app.window.fullscreen);
+
+    // Without window info (as when embedded), nothing is recorded.
+    let _ = ctx.run(egui::RawInput::default(), |ctx| {
+        app.remember_window_state(ctx);
+    });
+    assert!(app.window.maximized);
+}
+
+#[test]
+fn embedded_app_shows_in_a_host_ui_and_reports_edits() {
+    let ctx = warm_ctx();
+    let mut app = RustdownApp::builder()
+        .text("# Notes\n")
+        .path("notes/a.md")
+        .mode(Mode::SideBySide)
+        .build();
+    assert_eq!(app.text(), "# Notes\n");
+    assert_eq!(app.path(), Some(Path::new("notes/a.md")));
+    assert!(!app.is_dirty());
+
+    let frame = |app: &mut RustdownApp, events: Vec<egui::Event>| {
+        let input = egui::RawInput {
+            events,
+            ..Default::default()
+        };
+        let _ = ctx.run(input, |ctx| {
+            egui::CentralPanel::default().show(ctx, |ui| app.show(ui));
+        });
+    };
+    ctx.memory_mut(|mem| mem.request_focus(egui::Id::new("editor")));
+    frame(&mut app, Vec::new());
+    frame(&mut app, vec![egui::Event::Text("x".to_owned())]);
+    assert!(app.text().contains('x'));
+    assert!(app.is_dirty());
+    assert_eq!(app.mode(), Mode::SideBySide);
+}
+
+#[test]
+fn format_on_save_and_autosave_write_the_formatted_buffer() {
+    let dir = make_temp_dir("rustdown-format-on-save");
+    let path = dir.join("notes.md");
+    let _ = fs::write(&path, "# Notes\n");
+    let mut app = RustdownApp::default();
+    app.load_document(
+        path.clone(),
+        "# Notes\n".to_owned(),
+        disk::io::disk_revision(&path).ok(),
+    );
+    let edited = "# Notes   \nbody";
+    let formatted = format::format_markdown(edited, format::options_for_path(Some(&path)));
+    assert_ne!(formatted, edited);
+    let edit = |app: &mut RustdownApp, text: &str| {
+        app.doc.text = Arc::new(text.to_owned());
+        app.bump_edit_seq();
+        app.note_text_changed(false);
+    };
+
+    edit(&mut app, edited);
+    assert!(app.save_doc(false));
+    assert_eq!(read_file(&path), edited, "formatting is opt-in");
+
+    app.format_on_save = true;
+    edit(&mut app, edited);
+    assert!(app.save_doc(false));
+    assert_eq!(read_file(&path), formatted);
+    assert_eq!(app.doc.text.as_str(), formatted);
+    assert_eq!(app.doc.base_text.as_str(), formatted);
+    assert!(!app.doc.dirty);
+
+    // Autosave waits for a pause in editing, then saves on its own,
+    // leaving formatting to explicit saves.
+    let ctx = warm_ctx();
+    app.autosave = true;
+    edit(&mut app, "# Notes   \nmore\n");
+    app.autosave_if_due(&ctx);
+    assert!(app.doc.dirty, "still typing");
+    app.doc.last_edit_at = Instant::now().checked_sub(app.timings.autosave_delay());
+    app.autosave_if_due(&ctx);
+    assert!(!app.doc.dirty);
+    assert_eq!(read_file(&path), "# Notes   \nmore\n");
+    app.format_on_save = false;
+    edit(&mut app, "# Notes\nmore\n");
+    app.doc.last_edit_at = None;
+    app.autosave_if_due(&ctx);
+    assert_eq!(read_file(&path), "# Notes\nmore\n");
+
+    // Unresolved conflict markers are never autosaved.
+    edit(&mut app, "<<<<<<< ours\na\n=======\nb\n>>>>>>> theirs\n");
+    app.doc.last_edit_at = None;
+    app.autosave_if_due(&ctx);
+    assert!(app.doc.dirty);
+    assert_eq!(read_file(&path), "# Notes\nmore\n");
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn number_shortcuts_jump_straight_to_a_mode() {
+    let ctx = warm_ctx();
+    let mut app = RustdownApp::default();
+    let press = |app: &mut RustdownApp, key, modifiers| {
+        let input = egui::RawInput {
+            modifiers,
+            events: vec![egui::Event::Key {
+                key,
+                physical_key: None,
+                pressed: true,
+                repeat: false,
+                modifiers,
+            }],
+            ..Default::default()
+        };
+        let _ = ctx.run(input, |ctx| app.handle_keyboard_shortcuts(ctx));
+    };
+    press(&mut app, egui::Key::Num3, egui::Modifiers::COMMAND);
+    assert_eq!(app.mode, Mode::SideBySide);
+    press(&mut app, egui::Key::Num2, egui::Modifiers::COMMAND);
+    assert_eq!(app.mode, Mode::Preview);
+    press(&mut app, egui::Key::Num2, egui::Modifiers::COMMAND);
+    assert_eq!(app.mode, Mode::Preview, "no cycling past the target");
+    press(&mut app, egui::Key::Num1, egui::Modifiers::NONE);
+    assert_eq!(app.mode, Mode::Preview, "needs the command modifier");
+    press(&mut app, egui::Key::Num1, egui::Modifiers::COMMAND);
+    assert_eq!(app.mode, Mode::Edit);
+}
+
+#[test]
+fn format_keeps_the_editor_cursor_on_its_line() {
+    let ctx = warm_ctx();
+    let id = egui::Id::new("editor");
+    let before = "# Title \n\n\n\n\npara one\nlast  \n";
+    let mut app = RustdownApp::default();
+    app.load_document(PathBuf::from("a.md"), before.to_owned(), None);
+    let cursor = before.find("one").unwrap_or_default();
+    let mut state = egui::text_edit::TextEditState::default();
+    state
+        .cursor
+        .set_char_range(Some(egui::text::CCursorRange::two(
+            egui::text::CCursor::new(cursor),
+            egui::text::CCursor::new(cursor + 3),
+        )));
+    state.store(&ctx, id);
+
+    app.format_document();
+    let after = app.doc.text.clone();
+    assert_ne!(after.as_str(), before);
+    let _ = ctx.run(egui::RawInput::default(), |ctx| {
+        egui::CentralPanel::default().show(ctx, |ui| app.show_editor(ui));
+    });
+    let range = egui::TextEdit::load_state(&ctx, id)
+        .and_then(|state| state.cursor.char_range())
+        .map(|range| range.as_sorted_char_range());
+    let start = after.find("one").unwrap_or_default();
+    assert_eq!(range, Some(start..start + 3));
+    assert!(app.format_cursor_base.is_none());
+}
+
+#[test]
+fn open_path_accepts_text_files_and_refuses_binary_or_huge_ones() {
+    let dir = make_temp_dir("rustdown-open-text");
+    let notes = dir.join("notes.txt");
+    let _ = fs::write(&notes, "plain notes\n");
+    let mut app = RustdownApp::default();
+    app.open_path(notes.clone());
+    assert_eq!(app.doc.text.as_str(), "plain notes\n");
+    assert_eq!(app.doc.path.as_deref(), Some(notes.as_path()));
+    assert!(app.error.is_none());
+
+    for (name, bytes, message) in [
+        (
+            "image.png",
+            &b"\x89PNG\r\n\x1a\n\xff\xfe"[..],
+            "isn't UTF-8 text",
+        ),
+        ("data.bin", &b"abc\0def"[..], "isn't a text file"),
+    ] {
+        let path = dir.join(name);
+        let _ = fs::write(&path, bytes);
+        app.error = None;
+        app.open_path(path);
+        assert_eq!(app.doc.path.as_deref(), Some(notes.as_path()), "{name}");
+        let error = app.error.clone().unwrap_or_default();
+        assert!(error.contains(message), "{name}: {error}");
+    }
+
+    let huge = dir.join("huge.txt");
+    if let Ok(file) = fs::File::create(&huge) {
+        let _ = file.set_len(MAX_FILE_BYTES + 1);
+    }
+    app.error = None;
+    app.open_path(huge);
+    assert_eq!(app.doc.path.as_deref(), Some(notes.as_path()));
+    assert!(
+        app.error
+            .unwrap_or_default()
+            .contains("over the 64 MB limit")
+    );
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn code_block_shortcut_uses_the_remembered_language() {
+    let ctx = warm_ctx();
+    let mut app = RustdownApp::default();
+    app.doc.text = Arc::new("ab".to_owned());
+    app.code_fence_language = "rust".to_owned();
+    let mut state = egui::text_edit::TextEditState::default();
+    state
+        .cursor
+        .set_char_range(Some(egui::text::CCursorRange::one(
+            egui::text::CCursor::new(2),
+        )));
+    state.store(&ctx, egui::Id::new("editor"));
+
+    let modifiers = egui::Modifiers::COMMAND | egui::Modifiers::SHIFT;
+    let input = egui::RawInput {
+        modifiers,
+        events: vec![egui::Event::Key {
+            key: egui::Key::K,
+            physical_key: None,
+            pressed: true,
+            repeat: false,
+            modifiers,
+        }],
+        ..Default::default()
+    };
+    let _ = ctx.run(input, |ctx| app.handle_keyboard_shortcuts(ctx));
+    assert_eq!(app.doc.text.as_str(), "ab\n```rust\n\n```\n");
+    assert!(app.doc.dirty);
+
+    app.doc.text = Arc::new(String::new());
+    app.insert_code_block(&ctx, " python ");
+    assert_eq!(app.doc.text.as_str(), "```python\n\n```\n");
+    assert_eq!(app.code_fence_language, "python");
+}
+
+#[test]
+fn reformat_tables_rewrites_the_buffer_and_marks_it_dirty() {
+    let mut app = RustdownApp::default();
+    app.doc.text = Arc::new("|a|bb|\n|-|--:|\n|ccc|d|\n".to_owned());
+    app.reformat_tables();
+    assert_eq!(
+        app.doc.text.as_str(),
+        "| a   |  bb |\n| --- | --: |\n| ccc |   d |\n"
+    );
+    assert!(app.doc.dirty);
+    assert!(app.format_cursor_base.is_some());
+}
+
+#[test]
+fn minimap_click_scrolls_the_editor() {
+    let ctx = warm_ctx();
+    let mut app = RustdownApp {
+        minimap: true,
+        ..RustdownApp::default()
+    };
+    app.doc.text = Arc::new("line\n".repeat(400) + "# End\n");
+    let screen = egui::Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(800.0, 600.0));
+    // Near the bottom of the strip, which sits against the panel's right edge.
+    let pos = egui::pos2(785.0, 590.0);
+    let button = |pressed| egui::Event::PointerButton {
+        pos,
+        button: egui::PointerButton::Primary,
+        pressed,
+        modifiers: egui::Modifiers::NONE,
+    };
+    for events in [
+        Vec::new(),
+        vec![egui::Event::PointerMoved(pos), button(true)],
+        vec![button(false)],
+    ] {
+        let input = egui::RawInput {
+            screen_rect: Some(screen),
+            events,
+            ..Default::default()
+        };
+        let _ = ctx.run(input, |ctx| {
+            egui::CentralPanel::default().show(ctx, |ui| app.show_editor(ui));
+        });
+    }
+    let content_height = app
+        .doc
+        .editor_galley_cache
+        .as_ref()
+        .map_or(0.0, |cache| cache.galley.size().y);
+    let y = app.nav.pending_editor_scroll_y.unwrap_or_default();
+    assert!(
+        y > content_height * 0.8,
+        "scrolled to {y} of {content_height}"
+    );
+}
+
+#[test]
+fn large_buffer_stats_are_computed_off_thread_and_dropped_when_stale() {
+    let ctx = warm_ctx();
+    let mut app = RustdownApp::default();
+    let line = "word ".repeat(15) + "\n";
+    let text = line.repeat(ASYNC_STATS_MIN_BYTES / line.len() + 1);
+    let lines = text.matches('\n').count() + 1;
+    app.doc.text = Arc::new(text);
+    app.note_text_changed(true);
+    app.doc.last_edit_at = None;
+
+    app.refresh_stats_if_due(&ctx);
+    assert!(app.doc.stats_dirty, "a large buffer is not counted inline");
+    assert!(app.doc.stats_job.is_some());
+
+    // An edit before the result arrives makes it stale.
+    app.bump_edit_seq();
+    std::thread::sleep(Duration::from_millis(200));
+    assert!(!app.doc.poll_stats_job());
+    assert!(app.doc.stats_job.is_none());
+
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while app.doc.stats_dirty && Instant::now() < deadline {
+        app.refresh_stats_if_due(&ctx);
+        std::thread::sleep(Duration::from_millis(5));
+    }
+    assert!(!app.doc.stats_dirty);
+    assert_eq!(app.doc.stats.lines, lines);
+    assert_eq!(app.doc.stats.words, (lines - 1) * 15);
+}
+
+#[test]
+fn cursor_column_uses_the_editorconfig_tab_width() {
+    let dir = make_temp_dir("rustdown-tab-width-test");
+    let _ = fs::write(
+        dir.join(".editorconfig"),
+        "root = true\n[*.md]\ntab_width = 8\n",
+    );
+    let path = dir.join("note.md");
+    let _ = fs::write(&path, "# T\n\tcode\n");
+
+    let ctx = warm_ctx();
+    let mut app = RustdownApp::default();
+    assert_eq!(app.doc.tab_width, editor::DEFAULT_INDENT_SIZE);
+    app.open_path(path);
+    assert_eq!(app.doc.tab_width, 8);
+
+    let mut state = egui::text_edit::TextEditState::default();
+    state
+        .cursor
+        .set_char_range(Some(egui::text::CCursorRange::one(
+            egui::text::CCursor::new(6),
+        )));
+    state.store(&ctx, egui::Id::new("editor"));
+    app.refresh_cursor_position(&ctx);
+    let position = app.cursor_position.map(|p| (p.line, p.column));
+    assert_eq!(position, Some((2, 10)));
+
+    app.load_bundled(BundledDoc::Demo);
+    assert_eq!(app.doc.tab_width, editor::DEFAULT_INDENT_SIZE);
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn run_diff_refuses_inputs_over_the_size_cap() {
+    let dir = make_temp_dir("rustdown-diff-cap");
+    let small = dir.join("small.md");
+    let huge = dir.join("huge.md");
+    let _ = fs::write(&small, "text\n");
+    if let Ok(file) = fs::File::create(&huge) {
+        let _ = file.set_len(MAX_FILE_BYTES + 1);
+    }
+    assert_eq!(crate::cli::run_diff(&small, &small), 0);
+    assert_eq!(crate::cli::run_diff(&small, &huge), 2);
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn large_files_open_on_the_worker_and_later_opens_win() {
+    let dir = make_temp_dir("rustdown-async-open");
+    let large = dir.join("large.md");
+    let small = dir.join("small.md");
+    let line = "A line of a large document.\n";
+    let text = line.repeat(ASYNC_OPEN_MIN_BYTES as usize / line.len() + 1);
+    let _ = fs::write(&large, &text);
+    let _ = fs::write(&small, "small\n");
+    let ctx = warm_ctx();
+    let mut app = RustdownApp::default();
+    let settle = |app: &mut RustdownApp| {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while app.disk.opening.is_some() && Instant::now() < deadline {
+            app.tick_disk_sync(&ctx);
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        // A superseded read may still be in flight; let it land.
+        std::thread::sleep(Duration::from_millis(100));
+        app.tick_disk_sync(&ctx);
+    };
+
+    app.open_path(large.clone());
+    assert_eq!(app.disk.opening.as_deref(), Some(large.as_path()));
+    assert!(app.doc.path.is_none(), "the document loads later");
+    settle(&mut app);
+    assert_eq!(app.doc.path.as_deref(), Some(large.as_path()));
+    assert_eq!(app.doc.text.as_str(), text);
+    assert!(app.doc.disk_rev.is_some());
+
+    // Opening something else before the read lands discards it.
+    app.open_path(large.clone());
+    app.open_path(small.clone());
+    assert!(app.disk.opening.is_none());
+    settle(&mut app);
+    assert_eq!(app.doc.path.as_deref(), Some(small.as_path()));
+    assert_eq!(app.doc.text.as_str(), "small\n");
+
+    // Edits made while the read is in flight aren't thrown away unasked.
+    app.open_path(large.clone());
+    Arc::make_mut(&mut app.doc.text).push_str("typed\n");
+    app.doc.dirty = true;
+    app.doc.bump_edit_seq();
+    settle(&mut app);
+    assert_eq!(app.doc.text.as_str(), "small\ntyped\n");
+    assert!(matches!(
+        app.pending_action.as_ref(),
+        Some(PendingAction::Open(path)) if path == &large
+    ));
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn deleted_file_prompts_to_keep_editing_or_close() {
+    let dir = make_temp_dir("rustdown-deleted");
+    let path = dir.join("gone.md");
+    let _ = fs::write(&path, "kept\n");
+    let ctx = warm_ctx();
+    let mut app = RustdownApp {
+        disk_sync_mode: DiskSyncMode::Poll,
+        ..RustdownApp::default()
+    };
+    app.open_path(path.clone());
+    app.tick_disk_sync(&ctx);
+    assert!(!app.disk.deleted);
+
+    let _ = fs::remove_file(&path);
+    app.disk.poll_at = None;
+    app.tick_disk_sync(&ctx);
+    assert!(app.disk.deleted);
+    assert!(app.error.is_none(), "a deletion is not a disk error");
+
+    // Keeping the text makes it unsaved content that saving recreates.
+    app.keep_deleted_document();
+    assert!(app.doc.dirty && app.doc.disk_rev.is_none());
+    app.disk.poll_at = None;
+    app.tick_disk_sync(&ctx);
+    assert!(!app.disk.deleted, "a document never on disk isn't prompted");
+    assert!(app.save_doc(false));
+    assert_eq!(fs::read_to_string(&path).ok().as_deref(), Some("kept\n"));
+
+    let _ = fs::remove_file(&path);
+    app.disk.poll_at = None;
+    app.tick_disk_sync(&ctx);
+    assert!(app.disk.deleted);
+    // Closing with unsaved edits asks before dropping them.
+    app.doc.dirty = true;
+    app.request_action(PendingAction::NewBlank);
+    assert!(app.pending_action.is_some());
+    assert!(app.disk.deleted && app.doc.path.as_deref() == Some(path.as_path()));
+    app.apply_pending_action_and_close_dialog();
+    assert!(!app.disk.deleted && app.doc.path.is_none());
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn saving_keeps_the_watcher_and_ignores_its_own_write() {
+    let dir = make_temp_dir("rustdown-save-watch");
+    let path = dir.join("doc.md");
+    let _ = fs::write(&path, "one\n");
+    let ctx = warm_ctx();
+    let mut app = RustdownApp::default();
+    app.open_path(path.clone());
+    app.tick_disk_sync(&ctx);
+    let watching = app.disk.watcher.is_some();
+
+    app.doc.text = Arc::new("two\n".to_owned());
+    app.doc.dirty = true;
+    assert!(app.save_doc(false));
+    assert_eq!(app.disk.watcher.is_some(), watching);
+
+    // The save's own events settle without scheduling a reload.
+    std::thread::sleep(Duration::from_millis(200));
+    app.tick_disk_sync(&ctx);
+    assert!(app.disk.pending_reload_at.is_none() && !app.disk.reload_in_flight);
+    assert!(app.disk.conflict.is_none());
+
+    // Another writer straight after the save is still noticed.
+    let _ = fs::write(&path, "three, from elsewhere\n");
+    app.disk.poll_at = None;
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while app.disk.pending_reload_at.is_none() && Instant::now() < deadline {
+        std::thread::sleep(Duration::from_millis(20));
+        app.tick_disk_sync(&ctx);
+    }
+    assert!(app.disk.pending_reload_at.is_some());
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn reveal_needs_a_saved_document_and_opens_its_folder() {
+    let mut app = RustdownApp::default();
+    app.reveal_document(true);
+    assert!(
+        app.error
+            .as_deref()
+            .is_some_and(|e| e.starts_with("Save the document")),
+        "{:?}",
+        app.error
+    );
+
+    let command = file_manager_command(Path::new("/docs/notes.md"), false);
+    let args: Vec<_> = command.get_args().collect();
+    assert_eq!(args.last().copied(), Some(std::ffi::OsStr::new("/docs")));
+    let bare = file_manager_command(Path::new("notes.md"), false);
+    assert_eq!(bare.get_args().last(), Some(std::ffi::OsStr::new(".")));
+}
+
+#[test]
+fn quick_open_lists_the_notes_folder_and_opens_a_match() {
+    let ctx = warm_ctx();
+    let mut app = RustdownApp::default();
+    app.toggle_quick_open(&ctx);
+    assert!(!app.quick_open.visible, "no folder to search yet");
+    assert!(app.error.is_some());
+
+    let dir = make_temp_dir("rustdown-quick-open-app");
+    let _ = fs::create_dir_all(dir.join("journal"));
+    let _ = fs::write(dir.join("journal/week-plan.md"), "# Plan\n");
+    let _ = fs::write(dir.join("ideas.md"), "# Ideas\n");
+    app.notes_root = Some(dir.clone());
+    app.toggle_quick_open(&ctx);
+    assert!(app.quick_open.visible);
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while app.quick_open.is_scanning() && Instant::now() < deadline {
+        std::thread::sleep(Duration::from_millis(5));
+        app.quick_open.drain_scan();
+    }
+    assert_eq!(app.quick_open.file_count(&dir), 2);
+
+    app.quick_open.query = "wkpl".to_owned();
+    assert_eq!(app.quick_open.matches(&dir), ["journal/week-plan.md"]);
+    app.open_quick_open_match(&dir, "journal/week-plan.md");
+    assert!(!app.quick_open.visible);
+    assert_eq!(
+        app.doc.path.as_deref(),
+        Some(dir.join("journal/week-plan.md").as_path())
+    );
+
+    // Once marked stale, the next showing lists the folder again.
+    app.quick_open.mark_stale();
+    let _ = fs::write(dir.join("later.md"), "");
+    app.quick_open.ensure_index(&ctx, &dir);
+    assert!(app.quick_open.is_scanning());
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn find_shortcut_toggles_the_bar_and_keeps_the_query() {
+    let ctx = warm_ctx();
+    let mut app = RustdownApp::default();
+    let press_find = |app: &mut RustdownApp, modifiers: egui::Modifiers| {
+        let key = |pressed| egui::Event::Key {
+            key: egui::Key::F,
+            physical_key: None,
+            pressed,
+            repeat: false,
+            modifiers,
+        };
+        let input = egui::RawInput {
+            modifiers,
+            events: vec![key(true), key(false)],
+            ..Default::default()
+        };
+        let _ = ctx.run(input, |ctx| app.handle_keyboard_shortcuts(ctx));
+    };
+
+    press_find(&mut app, egui::Modifiers::COMMAND);
+    assert!(app.search.visible && app.focus_search);
+    app.search.query = "needle".to_owned();
+
+    press_find(&mut app, egui::Modifiers::COMMAND);
+    assert!(!app.search.visible);
+    assert_eq!(app.search.query, "needle", "closing keeps the query");
+    assert!(ctx.memory(|mem| mem.has_focus(egui::Id::new("editor"))));
+
+    press_find(&mut app, egui::Modifiers::COMMAND);
+    assert!(app.search.visible && !app.search.replace_mode);
+    assert_eq!(app.search.query, "needle");
+
+    // Cmd+Shift+F still opens replace mode, and Cmd+F then closes the bar.
+    app.close_search();
+    press_find(&mut app, egui::Modifiers::COMMAND | egui::Modifiers::SHIFT);
+    assert!(app.search.visible && app.search.replace_mode);
+    press_find(&mut app, egui::Modifiers::COMMAND);
+    assert!(!app.search.visible);
+}
+
+#[test]
+fn completion_offers_notes_at_the_cursor_and_fills_in_the_link() {
+    let dir = make_temp_dir("rustdown-completion-app");
+    let _ = fs::create_dir_all(dir.join("journal"));
+    let _ = fs::write(dir.join("journal/week-plan.md"), "# Plan\n");
+    let _ = fs::write(dir.join("index.md"), "See [[wee");
+    let ctx = warm_ctx();
+    let mut app = RustdownApp::default();
+    app.open_path(dir.join("index.md"));
+    let id = egui::Id::new("editor");
+    let mut state = egui::TextEdit::load_state(&ctx, id).unwrap_or_default();
+    state
+        .cursor
+        .set_char_range(Some(egui::text::CCursorRange::one(
+            egui::text::CCursor::new(9),
+        )));
+    state.store(&ctx, id);
+
+    // Nothing is offered while the editor is unfocused.
+    app.refresh_completion(&ctx);
+    assert!(!app.completion.is_showing());
+
+    ctx.memory_mut(|m| m.request_focus(id));
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while !app.completion.is_showing() && Instant::now() < deadline {
+        std::thread::sleep(Duration::from_millis(5));
+        app.refresh_completion(&ctx);
+    }
+    assert_eq!(app.completion.candidates[0].insert, "week-plan");
+    app.accept_completion(&ctx, 0);
+    assert_eq!(app.doc.text.as_str(), "See [[week-plan]]");
+    app.refresh_completion(&ctx);
+    assert!(!app.completion.is_showing());
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn pasted_images_are_saved_beside_the_document_and_linked() {
+    let dir = make_temp_dir("rustdown-paste-image-app");
+    let _ = fs::write(dir.join("notes.md"), "Before  after");
+    let ctx = warm_ctx();
+    let mut app = RustdownApp::default();
+    let image = || crate::pasted_image::PastedImage {
+        width: 1,
+        height: 1,
+        rgba: vec![0, 0, 0, 255],
+    };
+
+    // Without a file there is nowhere to put it yet.
+    app.paste_image(&ctx, image());
+    assert!(app.pending_image_paste.is_some());
+    assert!(app.doc.text.is_empty());
+    app.pending_image_paste = None;
+
+    app.open_path(dir.join("notes.md"));
+    let id = egui::Id::new("editor");
+    let mut state = egui::TextEdit::load_state(&ctx, id).unwrap_or_default();
+    state
+        .cursor
+        .set_char_range(Some(egui::text::CCursorRange::one(
+            egui::text::CCursor::new(7),
+        )));
+    state.store(&ctx, id);
+    app.paste_image(&ctx, image());
+    assert_eq!(app.doc.text.as_str(), "Before ![](notes-image-1.png) after");
+    assert!(dir.join("notes-image-1.png").is_file());
+    assert!(app.doc.dirty);
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn selection_preview_renders_the_selected_markdown_until_escape() {
+    let md = "Intro\n\n| a | b |\n|---|---|\n| 1 | 2 |\n\nOutro\n";
+    let mut app = RustdownApp::default();
+    app.load_document(PathBuf::from("a.md"), md.to_owned(), None);
+    let ctx = warm_ctx();
+
+    app.toggle_selection_preview(&ctx);
+    assert!(app.selection_preview.is_none(), "nothing selected");
+    assert!(app.error.is_some());
+
+    let id = egui::Id::new("editor");
+    let mut state = egui::TextEdit::load_state(&ctx, id).unwrap_or_default();
+    let table = md.find('|').unwrap_or_default()..md.find("\n\nOutro").unwrap_or_default();
+    state
+        .cursor
+        .set_char_range(Some(egui::text::CCursorRange::two(
+            egui::text::CCursor::new(table.end),
+            egui::text::CCursor::new(table.start),
+        )));
+    state.store(&ctx, id);
+    app.toggle_selection_preview(&ctx);
+    assert_eq!(
+        app.selection_preview.as_ref().map(|p| p.text.as_str()),
+        Some(&md[table])
+    );
+
+    let _ = ctx.run(egui::RawInput::default(), |ctx| {
+        app.show_selection_preview(ctx);
+    });
+    assert!(app.selection_preview.is_some());
+    let escape = egui::RawInput {
+        events: vec![egui::Event::Key {
+            key: egui::Key::Escape,
+            physical_key: None,
+            pressed: true,
+            repeat: false,
+            modifiers: egui::Modifiers::NONE,
+        }],
+        ..Default::default()
+    };
+    let _ = ctx.run(escape, |ctx| app.show_selection_preview(ctx));
+    assert!(app.selection_preview.is_none());
+}
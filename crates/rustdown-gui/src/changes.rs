@@ -0,0 +1,190 @@
+//! Lines changed since the document was opened or last saved, shown as
+//! markers in the editor gutter.
+//!
+//! The markers come from a line diff of the buffer against its base text.
+//! They are recomputed once edits settle (alongside the stats) and are kept
+//! as line numbers, so between recomputes they stay on their lines while
+//! the buffer is typed into.  Each marker also records the lines it
+//! replaced in the base, so it can be reverted on its own.
+
+use std::{
+    ops::Range,
+    sync::{Arc, Weak},
+};
+
+use crate::document::bytecount_newlines;
+use crate::editor::{SelectionEdit, line_start};
+
+/// Above this many lines in either text, no markers are computed.
+const MAX_CHANGE_LINES: usize = 20_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineChangeKind {
+    Added,
+    Modified,
+    /// Lines were deleted before the marker's (empty) line range.
+    Removed,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineChange {
+    /// Zero-based lines of the buffer; empty for [`LineChangeKind::Removed`].
+    pub lines: Range<usize>,
+    /// Zero-based lines of the base these replaced; empty for
+    /// [`LineChangeKind::Added`].
+    pub base_lines: Range<usize>,
+    pub kind: LineChangeKind,
+}
+
+/// The change markers of one document and what they were computed from.
+#[derive(Debug, Default)]
+pub struct LineChanges {
+    pub markers: Vec<LineChange>,
+    /// `edit_seq` and base text the markers were computed for.
+    seq: Option<u64>,
+    base: Weak<String>,
+}
+
+impl LineChanges {
+    /// Recompute the markers unless they already match `edit_seq` and
+    /// `base`.
+    pub fn refresh(&mut self, base: &Arc<String>, text: &str, edit_seq: u64) {
+        if self.is_current(base, edit_seq) {
+            return;
+        }
+        self.seq = Some(edit_seq);
+        self.base = Arc::downgrade(base);
+        self.markers = line_changes(base, text);
+    }
+
+    /// `true` when the markers were computed for `edit_seq` and `base`, so
+    /// their line numbers can be trusted to edit the buffer.
+    pub fn is_current(&self, base: &Arc<String>, edit_seq: u64) -> bool {
+        self.seq == Some(edit_seq) && std::ptr::eq(self.base.as_ptr(), Arc::as_ptr(base))
+    }
+}
+
+/// Markers for the lines of `text` that differ from `base`, in line order.
+pub fn line_changes(base: &str, text: &str) -> Vec<LineChange> {
+    if base == text
+        || bytecount_newlines(base) > MAX_CHANGE_LINES
+        || bytecount_newlines(text) > MAX_CHANGE_LINES
+    {
+        return Vec::new();
+    }
+    // Hunk lines count in `base`; `shift` moves them into `text`.
+    let mut shift = 0isize;
+    rustdown_core::diff_hunks(base, text)
+        .into_iter()
+        .map(|hunk| {
+            let base_start = hunk.line - 1;
+            let start = base_start.saturating_add_signed(shift);
+            shift += hunk.added.len().cast_signed() - hunk.removed.len().cast_signed();
+            let kind = match (hunk.removed.is_empty(), hunk.added.is_empty()) {
+                (true, _) => LineChangeKind::Added,
+                (false, true) => LineChangeKind::Removed,
+                (false, false) => LineChangeKind::Modified,
+            };
+            LineChange {
+                lines: start..start + hunk.added.len(),
+                base_lines: base_start..base_start + hunk.removed.len(),
+                kind,
+            }
+        })
+        .collect()
+}
+
+/// The lines of `base` that `change` replaced.
+pub fn saved_lines<'a>(base: &'a str, change: &LineChange) -> &'a str {
+    &base[line_start(base, change.base_lines.start)..line_start(base, change.base_lines.end)]
+}
+
+/// `text` with the lines of `change` put back as they are in `base`, and
+/// the cursor at the start of them.
+pub fn revert_change(base: &str, text: &str, change: &LineChange) -> SelectionEdit {
+    let start = line_start(text, change.lines.start);
+    let end = line_start(text, change.lines.end);
+    let saved = saved_lines(base, change);
+    let mut reverted = String::with_capacity(text.len() - (end - start) + saved.len());
+    reverted.push_str(&text[..start]);
+    reverted.push_str(saved);
+    reverted.push_str(&text[end..]);
+    let cursor = text[..start].chars().count();
+    SelectionEdit {
+        text: reverted,
+        selection: cursor..cursor,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn change(lines: Range<usize>, base_lines: Range<usize>, kind: LineChangeKind) -> LineChange {
+        LineChange {
+            lines,
+            base_lines,
+            kind,
+        }
+    }
+
+    #[test]
+    fn line_changes_place_markers_on_buffer_lines() {
+        let base = "a\nb\nc\nd\ne\n";
+        let text = "new\na\nB\nc\ne\nend\n";
+        assert_eq!(
+            line_changes(base, text),
+            [
+                change(0..1, 0..0, LineChangeKind::Added),
+                change(2..3, 1..2, LineChangeKind::Modified),
+                change(4..4, 3..4, LineChangeKind::Removed),
+                change(5..6, 5..5, LineChangeKind::Added),
+            ]
+        );
+        assert!(line_changes(base, base).is_empty());
+    }
+
+    #[test]
+    fn refresh_recomputes_on_new_edits_or_base() {
+        let base = Arc::new("a\n".to_owned());
+        let mut changes = LineChanges::default();
+        changes.refresh(&base, "b\n", 1);
+        assert_eq!(
+            changes.markers,
+            [change(0..1, 0..1, LineChangeKind::Modified)]
+        );
+        assert!(changes.is_current(&base, 1));
+
+        // Same seq and base: the cached markers stand.
+        changes.refresh(&base, "a\n", 1);
+        assert_eq!(changes.markers.len(), 1);
+
+        // A save makes the buffer the new base.
+        let saved = Arc::new("b\n".to_owned());
+        changes.refresh(&saved, "b\n", 1);
+        assert!(changes.markers.is_empty());
+        assert!(!changes.is_current(&base, 1));
+    }
+
+    #[test]
+    fn revert_change_restores_one_hunk_and_leaves_the_others() {
+        let base = "a\nb\nc\nd\ne\n";
+        let text = "new\na\nB\nc\ne\nend";
+        let markers = line_changes(base, text);
+        let reverted: Vec<String> = markers
+            .iter()
+            .map(|change| revert_change(base, text, change).text)
+            .collect();
+        assert_eq!(
+            reverted,
+            [
+                "a\nB\nc\ne\nend",
+                "new\na\nb\nc\ne\nend",
+                "new\na\nB\nc\nd\ne\nend",
+                "new\na\nB\nc\ne\n",
+            ]
+        );
+        assert_eq!(saved_lines(base, &markers[1]), "b\n");
+        assert_eq!(revert_change(base, text, &markers[1]).selection, 6..6);
+    }
+}
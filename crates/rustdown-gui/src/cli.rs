@@ -1,8 +1,19 @@
 //! Command-line argument parsing, version output, and platform workarounds.
 
-use std::{ffi::OsString, path::PathBuf};
+use std::{
+    ffi::OsString,
+    fmt::Write as _,
+    io::{self, Read as _},
+    path::{Path, PathBuf},
+};
+
+use rustdown_md::HeadingSlugger;
 
 use super::{DIAGNOSTICS_DEFAULT_ITERATIONS, DIAGNOSTICS_DEFAULT_RUNS, Mode};
+use crate::diagnostics::push_json_string;
+use crate::disk::io::{MAX_FILE_BYTES, file_too_large};
+use crate::document::bytecount_newlines;
+use crate::nav::outline::extract_headings;
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct LaunchOptions {
@@ -10,21 +21,78 @@ pub struct LaunchOptions {
     /// `true` when the user explicitly chose a mode via CLI flag (`-p`, `-s`).
     pub mode_explicit: bool,
     pub path: Option<PathBuf>,
+    /// 1-based line to put the cursor on in `path`, from `+N` or `path:N`.
+    pub line: Option<usize>,
+    /// Further paths after the first; each opens in its own window.
+    pub extra_paths: Vec<PathBuf>,
     pub print_version: bool,
     pub diagnostics: DiagnosticsMode,
     pub diagnostics_iterations: usize,
     pub diagnostics_runs: usize,
+    pub diagnostics_format: DiagnosticsFormat,
+    /// A non-GUI subcommand to run instead of opening the window.
+    pub command: Option<Command>,
+    /// Files of a `--merge` run as a git mergetool.
+    pub merge: Option<MergeToolPaths>,
+}
+
+/// The files git hands a mergetool: `--merge BASE OURS THEIRS OUTPUT`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MergeToolPaths {
+    pub base: PathBuf,
+    pub ours: PathBuf,
+    pub theirs: PathBuf,
+    /// Where the resolved document is saved.
+    pub output: PathBuf,
+}
+
+/// Subcommands that run in the terminal and exit.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Command {
+    /// `rustdown diff <old> <new>`; either side may be `-` for stdin.
+    Diff { old: PathBuf, new: PathBuf },
+    /// `rustdown outline [--json] <file>`; the file may be `-` for stdin.
+    Outline { path: PathBuf, json: bool },
+    /// A subcommand with unusable arguments; holds the message to print.
+    Invalid(String),
 }
 
+const DIFF_USAGE: &str = "usage: rustdown diff <old> <new>  (use - for stdin)\n\
+                          to open a file named diff instead: rustdown -- diff";
+const OUTLINE_USAGE: &str = "usage: rustdown outline [--json] <file>  (use - for stdin)\n\
+                             to open a file named outline instead: rustdown -- outline";
+const MERGE_USAGE: &str = "usage: rustdown --merge <base> <ours> <theirs> <output>";
+
 #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
 pub enum DiagnosticsMode {
     #[default]
     Off,
     OpenPipeline,
+    SavePipeline,
     #[cfg(debug_assertions)]
     NavPipeline,
 }
 
+/// How diagnostics metrics are printed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DiagnosticsFormat {
+    /// One `key=value` line per metric.
+    #[default]
+    Text,
+    /// One JSON object per run, on a single line.
+    Json,
+}
+
+impl DiagnosticsFormat {
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "text" => Some(Self::Text),
+            "json" => Some(Self::Json),
+            _ => None,
+        }
+    }
+}
+
 /// Parse a `--long-name=VALUE` or `--short-name=VALUE` positive integer argument.
 fn parse_kv_usize(arg: &OsString, long: &str, short: &str) -> Option<usize> {
     arg.to_str()
@@ -41,14 +109,30 @@ where
 {
     let mut mode = None;
     let mut path = None;
+    let mut line = None;
+    let mut extra_paths = Vec::new();
     let mut print_version = false;
     let mut diagnostics = DiagnosticsMode::Off;
     let mut diagnostics_iterations = DIAGNOSTICS_DEFAULT_ITERATIONS;
     let mut diagnostics_runs = DIAGNOSTICS_DEFAULT_RUNS;
+    let mut diagnostics_format = DiagnosticsFormat::Text;
+    let mut merge = None;
     let mut parse_flags = true;
 
-    for arg in args {
-        let arg = arg.into();
+    // Only a first argument spelled exactly `diff` or `outline` is a
+    // subcommand; `rustdown -- diff` or `rustdown ./diff` opens the file.
+    let mut args = args.into_iter().map(Into::into).peekable();
+    let mut command = if args.peek().is_some_and(|arg| arg == "diff") {
+        args.next();
+        Some(parse_diff_command(args.by_ref()))
+    } else if args.peek().is_some_and(|arg| arg == "outline") {
+        args.next();
+        Some(parse_outline_command(args.by_ref()))
+    } else {
+        None
+    };
+
+    while let Some(arg) = args.next() {
         if arg == "-v" || arg == "--version" {
             print_version = true;
             continue;
@@ -66,10 +150,37 @@ where
                 mode = Some(Mode::SideBySide);
                 continue;
             }
+            if let Some(n) = arg
+                .to_str()
+                .and_then(|s| s.strip_prefix('+'))
+                .and_then(parse_line_number)
+            {
+                line = Some(n);
+                continue;
+            }
+            if arg == "--merge" {
+                let paths: Vec<PathBuf> = args.by_ref().take(4).map(PathBuf::from).collect();
+                match <[PathBuf; 4]>::try_from(paths) {
+                    Ok([base, ours, theirs, output]) => {
+                        merge = Some(MergeToolPaths {
+                            base,
+                            ours,
+                            theirs,
+                            output,
+                        });
+                    }
+                    Err(_) => command = Some(Command::Invalid(MERGE_USAGE.to_owned())),
+                }
+                continue;
+            }
             if arg == "--diagnostics-open" || arg == "--diag-open" {
                 diagnostics = DiagnosticsMode::OpenPipeline;
                 continue;
             }
+            if arg == "--diagnostics-save" || arg == "--diag-save" {
+                diagnostics = DiagnosticsMode::SavePipeline;
+                continue;
+            }
             #[cfg(debug_assertions)]
             if arg == "--diagnostics-nav" || arg == "--diag-nav" {
                 diagnostics = DiagnosticsMode::NavPipeline;
@@ -84,19 +195,35 @@ where
                 diagnostics_runs = v;
                 continue;
             }
+            if let Some(format) = arg
+                .to_str()
+                .and_then(|s| {
+                    s.strip_prefix("--diag-format=")
+                        .or_else(|| s.strip_prefix("--diagnostics-format="))
+                })
+                .and_then(DiagnosticsFormat::parse)
+            {
+                diagnostics_format = format;
+                continue;
+            }
             if arg.to_str().is_some_and(|value| value.starts_with('-')) {
                 continue;
             }
         }
 
         if path.is_none() {
-            path = Some(PathBuf::from(arg));
+            let (file, file_line) = split_line_suffix(arg);
+            path = Some(file);
+            line = file_line.or(line);
+        } else {
+            // Spawned windows split off their own `:N` suffix.
+            extra_paths.push(PathBuf::from(arg));
         }
     }
 
     let mode_explicit = mode.is_some();
     let mode = mode.unwrap_or_else(|| {
-        if path.is_some() {
+        if path.is_some() && line.is_none() {
             Mode::Preview
         } else {
             Mode::Edit
@@ -107,11 +234,308 @@ where
         mode,
         mode_explicit,
         path,
+        line,
+        extra_paths,
         print_version,
         diagnostics,
         diagnostics_iterations,
         diagnostics_runs,
+        diagnostics_format,
+        command,
+        merge,
+    }
+}
+
+/// Split a `file:N` argument into the file and line `N`, when `N` is a
+/// line number and `file` exists but the whole argument doesn't.
+fn split_line_suffix(arg: OsString) -> (PathBuf, Option<usize>) {
+    let path = PathBuf::from(arg);
+    if !path.exists()
+        && let Some((file, n)) = path.to_str().and_then(|s| s.rsplit_once(':'))
+        && let Some(n) = parse_line_number(n)
+        && Path::new(file).exists()
+    {
+        return (PathBuf::from(file), Some(n));
+    }
+    (path, None)
+}
+
+/// A 1-based line number: digits only, not zero.
+fn parse_line_number(s: &str) -> Option<usize> {
+    if !s.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    s.parse().ok().filter(|&n| n > 0)
+}
+
+fn parse_diff_command(args: impl Iterator<Item = OsString>) -> Command {
+    let mut operands = Vec::with_capacity(2);
+    let mut parse_flags = true;
+    for arg in args {
+        if parse_flags && arg == "--" {
+            parse_flags = false;
+            continue;
+        }
+        if parse_flags && arg != "-" && arg.to_str().is_some_and(|value| value.starts_with('-')) {
+            return Command::Invalid(format!(
+                "rustdown diff: unknown option {}\n{DIFF_USAGE}",
+                arg.to_string_lossy()
+            ));
+        }
+        operands.push(PathBuf::from(arg));
+    }
+    match <[PathBuf; 2]>::try_from(operands) {
+        Ok([old, new]) if old == Path::new("-") && new == Path::new("-") => Command::Invalid(
+            format!("rustdown diff: only one side can be read from stdin\n{DIFF_USAGE}"),
+        ),
+        Ok([old, new]) => Command::Diff { old, new },
+        Err(_) => Command::Invalid(DIFF_USAGE.to_owned()),
+    }
+}
+
+fn parse_outline_command(args: impl Iterator<Item = OsString>) -> Command {
+    let mut operands = Vec::with_capacity(1);
+    let mut json = false;
+    let mut parse_flags = true;
+    for arg in args {
+        if parse_flags && arg == "--" {
+            parse_flags = false;
+            continue;
+        }
+        if parse_flags && arg == "--json" {
+            json = true;
+            continue;
+        }
+        if parse_flags && arg != "-" && arg.to_str().is_some_and(|value| value.starts_with('-')) {
+            return Command::Invalid(format!(
+                "rustdown outline: unknown option {}\n{OUTLINE_USAGE}",
+                arg.to_string_lossy()
+            ));
+        }
+        operands.push(PathBuf::from(arg));
     }
+    match <[PathBuf; 1]>::try_from(operands) {
+        Ok([path]) => Command::Outline { path, json },
+        Err(_) => Command::Invalid(OUTLINE_USAGE.to_owned()),
+    }
+}
+
+/// Read a subcommand's input file, or stdin for `-`, refusing input over
+/// [`MAX_FILE_BYTES`] as the GUI does.
+fn read_command_input(path: &Path) -> io::Result<String> {
+    if path == Path::new("-") {
+        let mut text = String::new();
+        io::stdin()
+            .take(MAX_FILE_BYTES + 1)
+            .read_to_string(&mut text)?;
+        if text.len() as u64 > MAX_FILE_BYTES {
+            return Err(file_too_large(text.len() as u64));
+        }
+        Ok(text)
+    } else {
+        let len = std::fs::metadata(path)?.len();
+        if len > MAX_FILE_BYTES {
+            return Err(file_too_large(len));
+        }
+        std::fs::read_to_string(path)
+    }
+}
+
+/// Run `rustdown diff`, printing the line diff to stdout.
+///
+/// Returns the process exit code, following `diff(1)`: 0 when the inputs
+/// match, 1 when they differ, 2 when an input cannot be read.
+#[must_use]
+pub fn run_diff(old: &Path, new: &Path) -> i32 {
+    let (old_text, new_text) = match (read_command_input(old), read_command_input(new)) {
+        (Ok(old_text), Ok(new_text)) => (old_text, new_text),
+        (Err(err), _) => {
+            eprintln!("rustdown diff: {}: {err}", old.display());
+            return 2;
+        }
+        (_, Err(err)) => {
+            eprintln!("rustdown diff: {}: {err}", new.display());
+            return 2;
+        }
+    };
+    let out = format_line_diff(
+        &old.to_string_lossy(),
+        &old_text,
+        &new.to_string_lossy(),
+        &new_text,
+    );
+    print!("{out}");
+    i32::from(!out.is_empty())
+}
+
+/// Run `rustdown outline`, printing the document's headings to stdout.
+///
+/// Returns the process exit code: 0 once printed, 2 when the input
+/// cannot be read.
+#[must_use]
+pub fn run_outline(path: &Path, json: bool) -> i32 {
+    match read_command_input(path) {
+        Ok(text) => {
+            print!("{}", format_outline(&text, json));
+            0
+        }
+        Err(err) => {
+            eprintln!("rustdown outline: {}: {err}", path.display());
+            2
+        }
+    }
+}
+
+/// Format the headings of `source` as the navigation panel finds them:
+/// one line each, indented by level after its line number, or with `json`
+/// an array of `{level, line, text, slug}` objects whose slugs are the
+/// HTML export's heading anchors.
+#[must_use]
+pub fn format_outline(source: &str, json: bool) -> String {
+    let headings = extract_headings(source);
+    let mut lines = Vec::with_capacity(headings.len());
+    let (mut line, mut counted) = (1, 0);
+    for heading in &headings {
+        line += bytecount_newlines(&source[counted..heading.byte_offset]);
+        counted = heading.byte_offset;
+        lines.push(line);
+    }
+
+    let mut out = String::new();
+    if json {
+        let mut slugger = HeadingSlugger::new();
+        out.push('[');
+        for (i, (heading, line)) in headings.iter().zip(lines).enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            let text = heading.label(source);
+            let _ = write!(
+                out,
+                "{{\"level\":{},\"line\":{line},\"text\":",
+                heading.level
+            );
+            push_json_string(&mut out, text);
+            out.push_str(",\"slug\":");
+            push_json_string(&mut out, &slugger.slug(text));
+            out.push('}');
+        }
+        out.push_str("]\n");
+    } else {
+        let width = lines.last().map_or(1, |last| last.to_string().len());
+        for (heading, line) in headings.iter().zip(lines) {
+            let indent = usize::from(heading.level.saturating_sub(1)) * 2;
+            let _ = writeln!(
+                out,
+                "{line:>width$}  {:indent$}{}",
+                "",
+                heading.label(source)
+            );
+        }
+    }
+    out
+}
+
+/// Format a unified-style line diff without context lines.
+///
+/// Returns an empty string when the texts are identical.
+#[must_use]
+pub fn format_line_diff(old_label: &str, old: &str, new_label: &str, new: &str) -> String {
+    let hunks = rustdown_core::diff_hunks(old, new);
+    if hunks.is_empty() {
+        return String::new();
+    }
+
+    // Unified diffs name the line before an empty range.
+    let range = |start: usize, len: usize| {
+        if len == 0 {
+            format!("{},0", start - 1)
+        } else {
+            format!("{start},{len}")
+        }
+    };
+
+    let mut out = format!("--- {old_label}\n+++ {new_label}\n");
+    let (mut added_before, mut removed_before) = (0usize, 0usize);
+    for hunk in hunks {
+        let new_start = hunk.line + added_before - removed_before;
+        let _ = writeln!(
+            out,
+            "@@ -{} +{} @@",
+            range(hunk.line, hunk.removed.len()),
+            range(new_start, hunk.added.len())
+        );
+        for (prefix, lines) in [('-', &hunk.removed), ('+', &hunk.added)] {
+            for line in lines {
+                out.push(prefix);
+                out.push_str(line);
+                if !line.ends_with('\n') {
+                    out.push_str("\n\\ No newline at end of file\n");
+                }
+            }
+        }
+        added_before += hunk.added.len();
+        removed_before += hunk.removed.len();
+    }
+    out
+}
+
+/// Open `path` in a new rustdown process, passing `mode` on when the user
+/// chose one explicitly.
+pub fn spawn_window(path: &Path, mode: Option<Mode>) -> io::Result<()> {
+    let mut command = std::process::Command::new(std::env::current_exe()?);
+    match mode {
+        Some(Mode::Preview) => {
+            command.arg("-p");
+        }
+        Some(Mode::SideBySide) => {
+            command.arg("-s");
+        }
+        Some(Mode::Edit) | None => {}
+    }
+    command.arg("--").arg(path).spawn().map(drop)
+}
+
+/// Show `path` in the system file manager: selected in Explorer or Finder
+/// when `select` is set, else (and always with `xdg-open`) its folder.
+pub fn reveal_in_file_manager(path: &Path, select: bool) -> io::Result<()> {
+    file_manager_command(path, select).spawn().map(drop)
+}
+
+pub(crate) fn file_manager_command(path: &Path, select: bool) -> std::process::Command {
+    let folder = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+        .unwrap_or_else(|| Path::new("."));
+    #[cfg(windows)]
+    let command = {
+        let mut command = std::process::Command::new("explorer");
+        if select {
+            command.arg("/select,").arg(path);
+        } else {
+            command.arg(folder);
+        }
+        command
+    };
+    #[cfg(target_os = "macos")]
+    let command = {
+        let mut command = std::process::Command::new("open");
+        if select {
+            command.arg("-R").arg(path);
+        } else {
+            command.arg(folder);
+        }
+        command
+    };
+    // xdg-open can't select a file, so it opens the folder either way.
+    #[cfg(not(any(windows, target_os = "macos")))]
+    let command = {
+        let _ = select;
+        let mut command = std::process::Command::new("xdg-open");
+        command.arg(folder);
+        command
+    };
+    command
 }
 
 #[must_use]
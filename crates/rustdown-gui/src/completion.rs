@@ -0,0 +1,310 @@
+//! Completing note names after `[[` and file paths in link destinations.
+//!
+//! While the editor cursor sits in a partial wiki link (`[[Oth`) or link
+//! destination (`](notes/pl`), the Markdown files of the quick-open listing
+//! that match it are offered in a popup at the cursor.  Choosing one
+//! replaces the partial token, and Escape dismisses the popup until the
+//! cursor leaves the token.
+
+use std::{
+    ops::Range,
+    path::{Component, Path},
+};
+
+use crate::editor::SelectionEdit;
+use crate::quick_open::fuzzy_score;
+
+/// Most completions offered at once.
+const MAX_COMPLETIONS: usize = 8;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TokenKind {
+    /// A note name after `[[`.
+    WikiLink,
+    /// A relative path after `](`.
+    LinkPath,
+}
+
+/// The partial name being typed: its kind, and its bytes up to the cursor.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub range: Range<usize>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Candidate {
+    /// Shown in the popup: the file's path under the notes folder.
+    pub label: String,
+    /// Put in place of the token.
+    pub insert: String,
+}
+
+#[derive(Debug, Default)]
+pub struct Completion {
+    token: Option<Token>,
+    pub candidates: Vec<Candidate>,
+    pub selected: usize,
+    /// Start of the token Escape dismissed the popup for.
+    dismissed: Option<usize>,
+}
+
+impl Completion {
+    /// `true` while the popup has something to offer.
+    pub const fn is_showing(&self) -> bool {
+        self.token.is_some() && !self.candidates.is_empty()
+    }
+
+    /// Follow the token under the cursor, offering the `files` (paths
+    /// relative to `root`) that match it.  Links are made relative to
+    /// `doc_dir`.
+    pub fn refresh(
+        &mut self,
+        text: &str,
+        token: Option<Token>,
+        files: &[String],
+        root: &Path,
+        doc_dir: Option<&Path>,
+    ) {
+        if token.as_ref().map(|t| t.range.start) != self.dismissed {
+            self.dismissed = None;
+        }
+        let token = token.filter(|t| self.dismissed != Some(t.range.start));
+        if token == self.token {
+            return;
+        }
+        self.candidates = token.as_ref().map_or_else(Vec::new, |token| {
+            candidates(token.kind, &text[token.range.clone()], files, root, doc_dir)
+        });
+        self.selected = 0;
+        self.token = token;
+    }
+
+    /// Hide the popup until the cursor leaves the current token.
+    pub fn dismiss(&mut self) {
+        self.dismissed = self.token.take().map(|token| token.range.start);
+        self.candidates.clear();
+    }
+
+    /// Move the highlight by `delta` rows, wrapping around.
+    pub const fn move_selection(&mut self, delta: isize) {
+        let len = self.candidates.len().cast_signed();
+        if len > 0 {
+            self.selected = (self.selected.cast_signed() + delta)
+                .rem_euclid(len)
+                .cast_unsigned();
+        }
+    }
+
+    /// The edit choosing candidate `index` makes to `text`.  The popup
+    /// stays hidden while the cursor is still in the completed token.
+    pub fn accept(&mut self, text: &str, index: usize) -> Option<SelectionEdit> {
+        let candidate = self.candidates.get(index)?;
+        let token = self.token.take()?;
+        let edit = apply_completion(text, &token, &candidate.insert);
+        self.dismissed = Some(token.range.start);
+        self.candidates.clear();
+        Some(edit)
+    }
+}
+
+/// The wiki link or link destination being typed before byte `cursor`,
+/// whichever opened last on its line.
+pub fn token_at(text: &str, cursor: usize) -> Option<Token> {
+    let line_start = text[..cursor].rfind('\n').map_or(0, |i| i + 1);
+    let before = &text[line_start..cursor];
+    let open_after = |opener: &str, stops: &[char]| {
+        let start = before.rfind(opener)? + opener.len();
+        (!before[start..].contains(stops)).then_some(line_start + start)
+    };
+    let wiki = open_after("[[", &['[', ']', '|', '#']);
+    let path = open_after("](", &[')', ' ', '<', '#', '?', ':']);
+    let (kind, start) = match (wiki, path) {
+        (Some(wiki), Some(path)) if path > wiki => (TokenKind::LinkPath, path),
+        (Some(wiki), _) => (TokenKind::WikiLink, wiki),
+        (None, Some(path)) => (TokenKind::LinkPath, path),
+        (None, None) => return None,
+    };
+    Some(Token {
+        kind,
+        range: start..cursor,
+    })
+}
+
+/// The files best matching `query`: note names for wiki links, paths
+/// relative to `doc_dir` (percent-encoded) for link destinations.
+fn candidates(
+    kind: TokenKind,
+    query: &str,
+    files: &[String],
+    root: &Path,
+    doc_dir: Option<&Path>,
+) -> Vec<Candidate> {
+    let mut scored: Vec<(i64, Candidate)> = files
+        .iter()
+        .filter_map(|file| {
+            let insert = match kind {
+                TokenKind::WikiLink => {
+                    let name = file.rsplit('/').next().unwrap_or(file);
+                    name.rsplit_once('.')
+                        .map_or(name, |(stem, _)| stem)
+                        .to_owned()
+                }
+                TokenKind::LinkPath => {
+                    rustdown_md::percent_encode_path(&relative_path(doc_dir?, &root.join(file))?)
+                }
+            };
+            let score = fuzzy_score(query, &insert)?;
+            Some((
+                score,
+                Candidate {
+                    label: file.clone(),
+                    insert,
+                },
+            ))
+        })
+        .collect();
+    scored.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.label.cmp(&b.1.label)));
+    let mut picked: Vec<Candidate> = Vec::new();
+    for (_, candidate) in scored {
+        if !picked.iter().any(|c| c.insert == candidate.insert) {
+            picked.push(candidate);
+        }
+        if picked.len() == MAX_COMPLETIONS {
+            break;
+        }
+    }
+    picked
+}
+
+/// `text` with `token` replaced by `insert`, a wiki link closed with `]]`
+/// unless it already is, and the cursor after it.
+pub fn apply_completion(text: &str, token: &Token, insert: &str) -> SelectionEdit {
+    let rest = &text[token.range.end..];
+    let close = token.kind == TokenKind::WikiLink && !rest.starts_with("]]");
+    let mut edited = String::with_capacity(text.len() + insert.len() + 2);
+    edited.push_str(&text[..token.range.start]);
+    edited.push_str(insert);
+    if close {
+        edited.push_str("]]");
+    }
+    let mut cursor = edited.chars().count();
+    if token.kind == TokenKind::WikiLink && !close {
+        cursor += 2;
+    }
+    edited.push_str(rest);
+    SelectionEdit {
+        text: edited,
+        selection: cursor..cursor,
+    }
+}
+
+/// `to` relative to the folder `from`, `/`-separated, or `None` when they
+/// share no root.
+fn relative_path(from: &Path, to: &Path) -> Option<String> {
+    let from: Vec<Component> = from.components().collect();
+    let to: Vec<Component> = to.components().collect();
+    let common = from.iter().zip(&to).take_while(|(a, b)| a == b).count();
+    if common == 0 {
+        return None;
+    }
+    let parts: Vec<String> = std::iter::repeat_n("..".to_owned(), from.len() - common)
+        .chain(
+            to[common..]
+                .iter()
+                .map(|part| part.as_os_str().to_string_lossy().into_owned()),
+        )
+        .collect();
+    Some(parts.join("/"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn token_at_finds_the_link_being_typed() {
+        let text = "See [[Oth and [x](notes/pl";
+        assert_eq!(
+            token_at(text, 9),
+            Some(Token {
+                kind: TokenKind::WikiLink,
+                range: 6..9,
+            })
+        );
+        assert_eq!(
+            token_at(text, text.len()),
+            Some(Token {
+                kind: TokenKind::LinkPath,
+                range: 18..text.len(),
+            })
+        );
+        assert_eq!(token_at("[[Done]] now", 12), None);
+        assert_eq!(token_at("[[Note|lab", 10), None);
+        assert_eq!(token_at("[x](https://a", 13), None);
+        assert_eq!(token_at("[[\nnext", 7), None);
+    }
+
+    #[test]
+    fn completions_rank_names_and_close_wiki_links() {
+        let files = [
+            "journal/week plan.md".to_owned(),
+            "plans.md".to_owned(),
+            "100%.md".to_owned(),
+        ];
+        let root = Path::new("/notes");
+        let mut completion = Completion::default();
+        let text = "Read [[pla";
+        completion.refresh(text, token_at(text, 10), &files, root, None);
+        let inserts: Vec<_> = completion
+            .candidates
+            .iter()
+            .map(|c| c.insert.as_str())
+            .collect();
+        assert_eq!(inserts, ["plans", "week plan"]);
+        completion.move_selection(-1);
+        assert_eq!(completion.selected, 1);
+        let edit = completion.accept(text, 0);
+        assert_eq!(
+            edit.map(|edit| (edit.text, edit.selection)),
+            Some(("Read [[plans]]".to_owned(), 14..14))
+        );
+        assert!(!completion.is_showing());
+
+        let text = "[x](pl";
+        completion.refresh(
+            text,
+            token_at(text, 6),
+            &files,
+            root,
+            Some(Path::new("/notes/journal")),
+        );
+        let inserts: Vec<_> = completion
+            .candidates
+            .iter()
+            .map(|c| c.insert.as_str())
+            .collect();
+        assert_eq!(inserts, ["../plans.md", "week%20plan.md"]);
+        let text = "[x](100";
+        completion.refresh(
+            text,
+            token_at(text, 7),
+            &files,
+            root,
+            Some(Path::new("/notes/journal")),
+        );
+        assert_eq!(completion.candidates[0].insert, "../100%25.md");
+        let text = "[x](pl";
+
+        completion.dismiss();
+        completion.refresh(text, token_at(text, 6), &files, root, None);
+        assert!(
+            !completion.is_showing(),
+            "dismissed until the token changes"
+        );
+        completion.refresh(text, None, &files, root, None);
+        let text = "[[x]] [[p";
+        completion.refresh(text, token_at(text, 9), &files, root, None);
+        assert!(completion.is_showing());
+    }
+}
@@ -1,4 +1,5 @@
 use std::{
+    fmt::{Display, Write as _},
     io,
     path::Path,
     sync::Arc,
@@ -6,11 +7,20 @@ use std::{
 };
 
 use eframe::egui;
-use rustdown_md::MarkdownCache;
+use rustdown_core::{Merge3Outcome, merge_three_way};
+use rustdown_md::{MarkdownCache, markdown_layout_job};
 
 use crate::{
-    Document, DocumentStats, Mode, RustdownApp, SearchState, default_image_uri_scheme,
-    disk::io::read_stable_utf8, find_match_count, highlight, ui_style,
+    DiagnosticsFormat, Document, DocumentStats, Mode, RustdownApp, SearchState,
+    changes::LineChanges,
+    default_image_uri_scheme,
+    disk::{
+        io::{atomic_write_utf8, disk_revision, read_stable_utf8},
+        sync::ReloadKind,
+    },
+    find_match_count,
+    fold::Folding,
+    format, ui_style,
 };
 
 #[allow(clippy::cast_precision_loss)] // iterations.max(1) is small
@@ -26,6 +36,98 @@ fn measure_iterations(iterations: usize, mut f: impl FnMut()) -> Duration {
     start.elapsed()
 }
 
+/// Metrics of one diagnostics run, printed as `key=value` lines or as a
+/// single-line JSON object.
+struct MetricsReport {
+    format: DiagnosticsFormat,
+    out: String,
+}
+
+impl MetricsReport {
+    /// Start a report for the pipeline named `kind`.
+    fn new(format: DiagnosticsFormat, kind: &str) -> Self {
+        let mut report = Self {
+            format,
+            out: String::new(),
+        };
+        report.text("rustdown_diagnostics", kind);
+        report
+    }
+
+    /// Add a metric whose value is a number or a bool.
+    fn value(&mut self, name: &str, value: impl Display) {
+        self.key(name);
+        let _ = write!(self.out, "{value}");
+    }
+
+    /// Add a metric whose value is text, quoted in JSON.
+    fn text(&mut self, name: &str, value: impl Display) {
+        self.key(name);
+        match self.format {
+            DiagnosticsFormat::Text => {
+                let _ = write!(self.out, "{value}");
+            }
+            DiagnosticsFormat::Json => push_json_string(&mut self.out, &value.to_string()),
+        }
+    }
+
+    fn key(&mut self, name: &str) {
+        match self.format {
+            DiagnosticsFormat::Text => {
+                if !self.out.is_empty() {
+                    self.out.push('\n');
+                }
+                let _ = write!(self.out, "{name}=");
+            }
+            DiagnosticsFormat::Json => {
+                self.out.push(if self.out.is_empty() { '{' } else { ',' });
+                push_json_string(&mut self.out, name);
+                self.out.push(':');
+            }
+        }
+    }
+
+    fn finish(mut self) -> String {
+        if self.format == DiagnosticsFormat::Json {
+            self.out.push('}');
+        }
+        self.out
+    }
+}
+
+pub(crate) fn push_json_string(out: &mut String, value: &str) {
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => {
+                let _ = write!(out, "\\u{:04x}", u32::from(c));
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+macro_rules! metric {
+    ($report:ident, $name:literal, $value:expr) => {
+        $report.value($name, $value);
+    };
+}
+
+macro_rules! avg_metric {
+    ($report:ident, $name:literal, $duration:expr, $iterations:expr) => {
+        $report.value(
+            $name,
+            format_args!("{:.2}", avg_duration_us($duration, $iterations)),
+        );
+    };
+}
+
 #[must_use]
 pub fn diagnostics_raw_input() -> egui::RawInput {
     egui::RawInput {
@@ -50,6 +152,7 @@ fn estimate_text_heap_bytes(text: &Arc<String>, base_text: &Arc<String>) -> usiz
 pub fn run_open_pipeline_diagnostics(
     path: Option<&Path>,
     diagnostics_iterations: usize,
+    format: DiagnosticsFormat,
 ) -> io::Result<()> {
     let Some(path) = path else {
         return Err(io::Error::new(
@@ -78,7 +181,11 @@ pub fn run_open_pipeline_diagnostics(
 
     let egui_start = Instant::now();
     let ctx = egui::Context::default();
-    ui_style::configure_fonts(&ctx).map_err(io::Error::other)?;
+    if let Some(notice) =
+        ui_style::configure_fonts(&ctx, &crate::preferences::FontSettings::default())
+    {
+        eprintln!("{notice}");
+    }
     ui_style::configure_style(&ctx);
     // egui only guarantees fonts are available after the first frame has run.
     let _ = ctx.run(egui::RawInput::default(), |_ctx| {});
@@ -86,11 +193,12 @@ pub fn run_open_pipeline_diagnostics(
 
     let style = ctx.style();
     let highlight_job_start = Instant::now();
-    let job = std::hint::black_box(highlight::markdown_layout_job(
+    let job = std::hint::black_box(markdown_layout_job(
         style.as_ref(),
         &style.visuals,
         std::hint::black_box(text.as_str()),
         false,
+        &rustdown_md::HEADING_FONT_SCALES,
     ));
     let highlight_job_ms = highlight_job_start.elapsed();
 
@@ -117,6 +225,14 @@ pub fn run_open_pipeline_diagnostics(
             last_edit_at: None,
             edit_seq,
             editor_galley_cache: None,
+            wrap_column: None,
+            folding: Folding::default(),
+            line_changes: LineChanges::default(),
+            indent_size: crate::editor::DEFAULT_INDENT_SIZE,
+            tab_width: crate::editor::DEFAULT_INDENT_SIZE,
+            stats_job: None,
+            detected_title: None,
+            unresolved_conflicts: None,
         }
     };
     let make_app = |mode: Mode,
@@ -190,19 +306,21 @@ pub fn run_open_pipeline_diagnostics(
         )));
     });
     let highlight_job_loop = measure_iterations(diagnostics_iterations, || {
-        std::hint::black_box(highlight::markdown_layout_job(
+        std::hint::black_box(markdown_layout_job(
             style.as_ref(),
             &style.visuals,
             std::hint::black_box(app.doc.text.as_str()),
             false,
+            &rustdown_md::HEADING_FONT_SCALES,
         ));
     });
     let highlight_layout_loop = measure_iterations(diagnostics_iterations, || {
-        let loop_job = highlight::markdown_layout_job(
+        let loop_job = markdown_layout_job(
             style.as_ref(),
             &style.visuals,
             std::hint::black_box(app.doc.text.as_str()),
             false,
+            &rustdown_md::HEADING_FONT_SCALES,
         );
         let loop_galley = ctx.fonts_mut(|fonts| fonts.layout_job(loop_job));
         std::hint::black_box(loop_galley.rows.len());
@@ -256,102 +374,259 @@ pub fn run_open_pipeline_diagnostics(
     let mut dirty_text = app.doc.text.clone();
     Arc::make_mut(&mut dirty_text).push('x');
     let dirty_text_heap_bytes = estimate_text_heap_bytes(&dirty_text, &app.doc.base_text);
+    // A merge that lands back on the disk text should share one allocation.
+    let mut merged_app = new_edit_bench_app();
+    let merged_text = Arc::new(merged_app.doc.text.as_str().to_owned());
+    let merged_base = merged_app.doc.base_text.clone();
+    merged_app.apply_disk_text_state(merged_text, merged_base, disk_rev, ReloadKind::Merged);
+    let merged_text_shared = Arc::ptr_eq(&merged_app.doc.text, &merged_app.doc.base_text);
 
     let total_ms = total_start.elapsed();
-    macro_rules! metric {
-        ($name:literal, $value:expr) => {
-            println!(concat!($name, "={}"), $value);
-        };
-    }
-    macro_rules! avg_metric {
-        ($name:literal, $duration:expr, $iterations:expr) => {
-            println!(
-                concat!($name, "={:.2}"),
-                avg_duration_us($duration, $iterations)
-            );
-        };
-    }
-
-    println!("rustdown_diagnostics=open_pipeline");
-    metric!("path", path.display());
-    metric!("disk_len", disk_rev.len);
-    metric!("text_bytes", app.doc.text.len());
-    metric!("base_text_bytes", app.doc.base_text.len());
-    metric!("stats_lines", stats.lines);
-    metric!("t_read_ms", read_ms.as_millis());
-    metric!("t_clone_base_ms", clone_ms.as_millis());
-    metric!("t_stats_ms", stats_ms.as_millis());
-    metric!("t_egui_setup_ms", egui_ms.as_millis());
-    metric!("t_highlight_job_ms", highlight_job_ms.as_millis());
-    metric!("t_highlight_layout_ms", highlight_layout_ms.as_millis());
-    metric!("galley_rows", galley.rows.len());
-    metric!("t_editor_frame1_ms", editor_frame1_ms.as_millis());
-    metric!("t_editor_frame2_ms", editor_frame2_ms.as_millis());
-    metric!("t_core_total_ms", core_total_ms.as_millis());
-    metric!("t_preview_frame1_ms", preview_frame1_ms.as_millis());
-    metric!("t_preview_frame2_ms", preview_frame2_ms.as_millis());
-    metric!("diag_iterations", diagnostics_iterations);
-    metric!("diag_edit_iterations", edit_iterations);
-    metric!("diag_frame_iterations", frame_iterations);
-    avg_metric!("t_stats_loop_avg_us", stats_loop, diagnostics_iterations);
+    let mut report = MetricsReport::new(format, "open_pipeline");
+
+    report.text("path", path.display());
+    metric!(report, "disk_len", disk_rev.len);
+    metric!(report, "text_bytes", app.doc.text.len());
+    metric!(report, "base_text_bytes", app.doc.base_text.len());
+    metric!(report, "stats_lines", stats.lines);
+    metric!(report, "t_read_ms", read_ms.as_millis());
+    metric!(report, "t_clone_base_ms", clone_ms.as_millis());
+    metric!(report, "t_stats_ms", stats_ms.as_millis());
+    metric!(report, "t_egui_setup_ms", egui_ms.as_millis());
+    metric!(report, "t_highlight_job_ms", highlight_job_ms.as_millis());
+    metric!(
+        report,
+        "t_highlight_layout_ms",
+        highlight_layout_ms.as_millis()
+    );
+    metric!(report, "galley_rows", galley.rows.len());
+    metric!(report, "t_editor_frame1_ms", editor_frame1_ms.as_millis());
+    metric!(report, "t_editor_frame2_ms", editor_frame2_ms.as_millis());
+    metric!(report, "t_core_total_ms", core_total_ms.as_millis());
+    metric!(report, "t_preview_frame1_ms", preview_frame1_ms.as_millis());
+    metric!(report, "t_preview_frame2_ms", preview_frame2_ms.as_millis());
+    metric!(report, "diag_iterations", diagnostics_iterations);
+    metric!(report, "diag_edit_iterations", edit_iterations);
+    metric!(report, "diag_frame_iterations", frame_iterations);
     avg_metric!(
+        report,
+        "t_stats_loop_avg_us",
+        stats_loop,
+        diagnostics_iterations
+    );
+    avg_metric!(
+        report,
         "t_highlight_job_loop_avg_us",
         highlight_job_loop,
         diagnostics_iterations
     );
     avg_metric!(
+        report,
         "t_highlight_layout_loop_avg_us",
         highlight_layout_loop,
         diagnostics_iterations
     );
     avg_metric!(
+        report,
         "t_search_count_loop_avg_us",
         search_count_loop,
         diagnostics_iterations
     );
     avg_metric!(
+        report,
         "t_search_cached_count_loop_avg_us",
         search_cached_loop,
         diagnostics_iterations
     );
     avg_metric!(
+        report,
         "t_image_uri_recompute_avg_us",
         image_uri_recompute,
         diagnostics_iterations
     );
     avg_metric!(
+        report,
         "t_image_uri_cached_lookup_avg_us",
         image_uri_cached,
         diagnostics_iterations
     );
     avg_metric!(
+        report,
         "t_edit_note_change_deferred_avg_us",
         edit_deferred_loop,
         edit_iterations
     );
     avg_metric!(
+        report,
         "t_edit_note_change_immediate_avg_us",
         edit_immediate_loop,
         edit_iterations
     );
     avg_metric!(
+        report,
         "t_editor_cached_frame_avg_us",
         editor_cached_loop,
         frame_iterations
     );
     avg_metric!(
+        report,
         "t_preview_cached_frame_avg_us",
         preview_cached_loop,
         frame_iterations
     );
-    metric!("text_heap_clean_bytes", clean_text_heap_bytes);
-    metric!("text_heap_dirty_bytes", dirty_text_heap_bytes);
-    metric!("t_total_ms", total_ms.as_millis());
+    metric!(report, "text_heap_clean_bytes", clean_text_heap_bytes);
+    metric!(report, "text_heap_dirty_bytes", dirty_text_heap_bytes);
+    metric!(
+        report,
+        "text_base_shared_clean",
+        Arc::ptr_eq(&app.doc.text, &app.doc.base_text)
+    );
+    metric!(report, "text_base_shared_after_merge", merged_text_shared);
+    metric!(report, "t_total_ms", total_ms.as_millis());
+    println!("{}", report.finish());
 
     std::hint::black_box(app);
     Ok(())
 }
 
+/// Copy of `text` with a line added after its first line and another at
+/// its end, standing in for a local edit and an edit made on disk that a
+/// three-way merge combines cleanly.
+fn simulated_edits(text: &str) -> (String, String) {
+    let first_line_end = text.find('\n').map_or(text.len(), |i| i + 1);
+    let mut ours = String::with_capacity(text.len() + 32);
+    ours.push_str(&text[..first_line_end]);
+    if !ours.is_empty() && !ours.ends_with('\n') {
+        ours.push('\n');
+    }
+    ours.push_str("Edited in rustdown.\n");
+    ours.push_str(&text[first_line_end..]);
+
+    let mut theirs = text.to_owned();
+    if !theirs.is_empty() && !theirs.ends_with('\n') {
+        theirs.push('\n');
+    }
+    theirs.push_str("Edited on disk.\n");
+    (ours, theirs)
+}
+
+/// Benchmark saving the Markdown file at `path`: formatting, the atomic
+/// write and revision check, and a three-way merge with a disk edit.
+///
+/// Writes go to a copy in the temp directory; the file itself is only read.
+pub fn run_save_pipeline_diagnostics(
+    path: Option<&Path>,
+    diagnostics_iterations: usize,
+    format: DiagnosticsFormat,
+) -> io::Result<()> {
+    let Some(path) = path else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "missing markdown path (usage: rustdown --diagnostics-save <file.md>)",
+        ));
+    };
+    let diagnostics_iterations = diagnostics_iterations.max(1);
+    let total_start = Instant::now();
+
+    let (text, disk_rev) = read_stable_utf8(path)?;
+
+    let options_start = Instant::now();
+    let options = format::options_for_path(Some(path));
+    let options_ms = options_start.elapsed();
+
+    let format_start = Instant::now();
+    let formatted = std::hint::black_box(format::format_markdown(&text, options));
+    let format_ms = format_start.elapsed();
+    let format_loop = measure_iterations(diagnostics_iterations, || {
+        std::hint::black_box(format::format_markdown(
+            std::hint::black_box(text.as_str()),
+            options,
+        ));
+    });
+
+    let file_name = path
+        .file_name()
+        .map_or_else(|| "document.md".into(), |name| name.to_string_lossy());
+    let copy = std::env::temp_dir().join(format!(
+        "rustdown-diag-save-{}-{file_name}",
+        std::process::id()
+    ));
+    // Disk writes are slow and wear the disk; cap them like frame loops.
+    let write_iterations = diagnostics_iterations.min(64);
+    let write_result = (|| {
+        let write_start = Instant::now();
+        atomic_write_utf8(&copy, &formatted)?;
+        let write_ms = write_start.elapsed();
+        let mut write_error = None;
+        let write_loop = measure_iterations(write_iterations, || {
+            if let Err(err) = atomic_write_utf8(&copy, std::hint::black_box(&formatted)) {
+                write_error.get_or_insert(err);
+            }
+        });
+        if let Some(err) = write_error {
+            return Err(err);
+        }
+        let revision_loop = measure_iterations(diagnostics_iterations, || {
+            std::hint::black_box(disk_revision(std::hint::black_box(&copy)).ok());
+        });
+        Ok((write_ms, write_loop, revision_loop))
+    })();
+    let _ = std::fs::remove_file(&copy);
+    let (write_ms, write_loop, revision_loop) = write_result?;
+
+    let (ours, theirs) = simulated_edits(&text);
+    let merge_start = Instant::now();
+    let merged = merge_three_way(&text, &ours, &theirs);
+    let merge_ms = merge_start.elapsed();
+    let merge_clean = matches!(merged, Merge3Outcome::Clean(_));
+    let merge_iterations = diagnostics_iterations.min(64);
+    let merge_loop = measure_iterations(merge_iterations, || {
+        std::hint::black_box(merge_three_way(
+            std::hint::black_box(text.as_str()),
+            &ours,
+            &theirs,
+        ));
+    });
+
+    let total_ms = total_start.elapsed();
+    let mut report = MetricsReport::new(format, "save_pipeline");
+    report.text("path", path.display());
+    metric!(report, "disk_len", disk_rev.len);
+    metric!(report, "text_bytes", text.len());
+    metric!(report, "formatted_bytes", formatted.len());
+    metric!(report, "format_changed", formatted != text);
+    metric!(report, "t_format_options_ms", options_ms.as_millis());
+    metric!(report, "t_format_ms", format_ms.as_millis());
+    metric!(report, "t_atomic_write_ms", write_ms.as_millis());
+    metric!(report, "t_merge_ms", merge_ms.as_millis());
+    metric!(report, "merge_clean", merge_clean);
+    metric!(report, "diag_iterations", diagnostics_iterations);
+    metric!(report, "diag_write_iterations", write_iterations);
+    metric!(report, "diag_merge_iterations", merge_iterations);
+    avg_metric!(
+        report,
+        "t_format_loop_avg_us",
+        format_loop,
+        diagnostics_iterations
+    );
+    avg_metric!(
+        report,
+        "t_atomic_write_loop_avg_us",
+        write_loop,
+        write_iterations
+    );
+    avg_metric!(
+        report,
+        "t_disk_revision_loop_avg_us",
+        revision_loop,
+        diagnostics_iterations
+    );
+    avg_metric!(report, "t_merge_loop_avg_us", merge_loop, merge_iterations);
+    metric!(report, "t_total_ms", total_ms.as_millis());
+    println!("{}", report.finish());
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -450,21 +725,76 @@ mod tests {
         assert_eq!(estimate_text_heap_bytes(&t4, &b4), t4.capacity());
     }
 
+    // ── MetricsReport ───────────────────────────────────────────────
+
+    #[test]
+    fn metrics_report_prints_lines_or_one_json_object() {
+        let fill = |format| {
+            let mut report = MetricsReport::new(format, "open_pipeline");
+            report.text("path", "C:\\docs\\\"a\".md");
+            report.value("disk_len", 42);
+            report.value("t_avg_us", format_args!("{:.2}", 1.5));
+            report.value("shared", true);
+            report.finish()
+        };
+        assert_eq!(
+            fill(DiagnosticsFormat::Text),
+            "rustdown_diagnostics=open_pipeline\npath=C:\\docs\\\"a\".md\n\
+             disk_len=42\nt_avg_us=1.50\nshared=true"
+        );
+        assert_eq!(
+            fill(DiagnosticsFormat::Json),
+            r#"{"rustdown_diagnostics":"open_pipeline","path":"C:\\docs\\\"a\".md","disk_len":42,"t_avg_us":1.50,"shared":true}"#
+        );
+    }
+
     // ── run_open_pipeline_diagnostics ───────────────────────────────
 
     #[test]
     fn run_diagnostics_rejects_none_path() {
-        let result = run_open_pipeline_diagnostics(None, 1);
+        let result = run_open_pipeline_diagnostics(None, 1, DiagnosticsFormat::Text);
         assert!(result.is_err());
         let err = result.err().unwrap_or_else(|| unreachable!());
         assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
     }
 
+    #[test]
+    fn save_diagnostics_merge_simulated_edits_without_touching_the_file() {
+        let base = "# Title\nintro\n\nbody";
+        let (ours, theirs) = simulated_edits(base);
+        assert_eq!(ours, "# Title\nEdited in rustdown.\nintro\n\nbody");
+        assert_eq!(theirs, "# Title\nintro\n\nbody\nEdited on disk.\n");
+        assert_eq!(
+            merge_three_way(&format!("{base}\n"), &format!("{ours}\n"), &theirs),
+            Merge3Outcome::Clean(
+                "# Title\nEdited in rustdown.\nintro\n\nbody\nEdited on disk.\n".to_owned()
+            )
+        );
+
+        let path =
+            std::env::temp_dir().join(format!("rustdown-diag-save-src-{}.md", std::process::id()));
+        std::fs::write(&path, "# Title  \n\n\n\nbody\n").unwrap_or_else(|_| unreachable!());
+        assert!(run_save_pipeline_diagnostics(Some(&path), 2, DiagnosticsFormat::Json).is_ok());
+        assert_eq!(
+            std::fs::read_to_string(&path).ok().as_deref(),
+            Some("# Title  \n\n\n\nbody\n"),
+            "only the temp copy is written"
+        );
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(
+            run_save_pipeline_diagnostics(None, 1, DiagnosticsFormat::Text)
+                .err()
+                .map(|err| err.kind()),
+            Some(io::ErrorKind::InvalidInput)
+        );
+    }
+
     #[test]
     fn run_diagnostics_rejects_nonexistent_file() {
         let result = run_open_pipeline_diagnostics(
             Some(Path::new("/tmp/rustdown_diag_nonexistent_987654.md")),
             1,
+            DiagnosticsFormat::Json,
         );
         assert!(result.is_err());
     }
@@ -0,0 +1,109 @@
+//! Reloading preview images edited on disk.
+//!
+//! egui's image loaders keep each decoded image keyed by its URI, so a
+//! redrawn image is never decoded twice.  This records the disk revision
+//! (mtime, length) each local image had when the preview first drew it, and
+//! reports the URIs whose file has changed so their cached copy can be
+//! forgotten and decoded afresh.
+
+use std::collections::{BTreeSet, HashMap};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use crate::disk::io::{DiskRevision, disk_revision};
+
+/// How often the revisions of drawn images are checked.
+pub const IMAGE_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Most images tracked at once; beyond this the oldest set is dropped.
+const MAX_TRACKED_IMAGES: usize = 512;
+
+#[derive(Debug, Default)]
+pub struct ImageRevisions {
+    revs: HashMap<String, Option<DiskRevision>>,
+    check_at: Option<Instant>,
+}
+
+impl ImageRevisions {
+    /// Start tracking the loader URIs in `shown`, and when a check is due
+    /// return the tracked URIs whose file changed or disappeared since it
+    /// was first seen.
+    pub fn changed(&mut self, shown: BTreeSet<String>, now: Instant) -> Vec<String> {
+        if self.revs.len() + shown.len() > MAX_TRACKED_IMAGES {
+            self.revs.clear();
+        }
+        for uri in shown {
+            self.revs
+                .entry(uri)
+                .or_insert_with_key(|uri| revision_of(uri));
+        }
+
+        if self.revs.is_empty() || self.check_at.is_some_and(|at| now < at) {
+            return Vec::new();
+        }
+        self.check_at = Some(now + IMAGE_CHECK_INTERVAL);
+
+        let mut changed = Vec::new();
+        for (uri, rev) in &mut self.revs {
+            let current = revision_of(uri);
+            if current != *rev {
+                *rev = current;
+                changed.push(uri.clone());
+            }
+        }
+        changed
+    }
+
+    /// `true` once any image is tracked, so the caller keeps checking.
+    pub fn is_tracking(&self) -> bool {
+        !self.revs.is_empty()
+    }
+}
+
+/// The revision of the file behind a `file://` loader URI, if readable.
+fn revision_of(uri: &str) -> Option<DiskRevision> {
+    let path = uri.strip_prefix("file://")?;
+    disk_revision(Path::new(path)).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn changed_reports_images_edited_after_first_draw() {
+        let dir = std::env::temp_dir().join(format!("rustdown-images-{}", std::process::id()));
+        let _ = std::fs::create_dir_all(&dir);
+        let path = dir.join("pic.png");
+        std::fs::write(&path, b"one").unwrap_or_else(|_| unreachable!());
+        let uri = format!("file://{}", path.display());
+
+        let mut revs = ImageRevisions::default();
+        let start = Instant::now();
+        assert!(
+            revs.changed(BTreeSet::from([uri.clone()]), start)
+                .is_empty()
+        );
+        assert!(revs.is_tracking());
+
+        std::fs::write(&path, b"longer").unwrap_or_else(|_| unreachable!());
+        // Not due yet: the next check waits for the interval.
+        assert!(revs.changed(BTreeSet::new(), start).is_empty());
+        let later = start + IMAGE_CHECK_INTERVAL;
+        assert_eq!(
+            revs.changed(BTreeSet::new(), later),
+            std::slice::from_ref(&uri)
+        );
+        assert!(
+            revs.changed(BTreeSet::new(), later + IMAGE_CHECK_INTERVAL)
+                .is_empty()
+        );
+
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(
+            revs.changed(BTreeSet::new(), later + IMAGE_CHECK_INTERVAL * 2),
+            [uri]
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -1,5 +1,5 @@
 use std::{
-    ffi::OsString,
+    ffi::{OsStr, OsString},
     fs,
     io::{self, Write as _},
     path::{Path, PathBuf},
@@ -15,6 +15,22 @@ const STABLE_READ_RETRY_SLEEP: Duration = Duration::from_millis(5);
 const ATOMIC_WRITE_MAX_ATTEMPTS: u64 = 10;
 /// Maximum merge sidecar files before giving up.
 const MERGE_SIDECAR_MAX_FILES: usize = 100;
+/// Largest file read into memory; anything bigger is refused rather than
+/// loaded, whether opened, reloaded or diffed.
+pub const MAX_FILE_BYTES: u64 = 64 * 1024 * 1024;
+
+/// The error for a `len`-byte file over [`MAX_FILE_BYTES`].
+#[must_use]
+pub fn file_too_large(len: u64) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::FileTooLarge,
+        format!(
+            "{}, over the {} limit",
+            crate::format_size(len),
+            crate::format_size(MAX_FILE_BYTES)
+        ),
+    )
+}
 
 /// Generate a hard-to-predict suffix for temporary files.
 ///
@@ -93,6 +109,9 @@ pub fn read_stable_utf8(path: &Path) -> io::Result<(String, DiskRevision)> {
     let mut last_err = None;
     for _ in 0..STABLE_READ_RETRIES {
         let before = disk_revision(path)?;
+        if before.len > MAX_FILE_BYTES {
+            return Err(file_too_large(before.len));
+        }
 
         let text = match fs::read_to_string(path) {
             Ok(text) => text,
@@ -123,6 +142,15 @@ pub fn read_stable_utf8(path: &Path) -> io::Result<(String, DiskRevision)> {
 }
 
 pub fn atomic_write_utf8(path: &Path, contents: &str) -> io::Result<()> {
+    // Write through symlinks instead of replacing the link with a regular
+    // file; a dangling or looping link surfaces as an error here.
+    let resolved;
+    let path = if fs::symlink_metadata(path).is_ok_and(|meta| meta.file_type().is_symlink()) {
+        resolved = fs::canonicalize(path)?;
+        resolved.as_path()
+    } else {
+        path
+    };
     let dir = match path.parent() {
         Some(parent) if !parent.as_os_str().is_empty() => parent,
         _ => Path::new("."),
@@ -192,34 +220,136 @@ pub fn atomic_write_utf8(path: &Path, contents: &str) -> io::Result<()> {
     ))
 }
 
-pub fn next_merge_sidecar_path(original: &Path) -> io::Result<PathBuf> {
-    let dir = match original.parent() {
-        Some(parent) if !parent.as_os_str().is_empty() => parent,
-        _ => Path::new("."),
+/// Default merge sidecar file-name template, e.g. `notes.rustdown-merge-2.md`.
+///
+/// `{stem}` is the original file stem, `{n}` is empty for the first sidecar
+/// and `-2`, `-3`, … afterwards, and `{ext}` is `.` plus the original
+/// extension (or empty).
+pub const DEFAULT_MERGE_SIDECAR_TEMPLATE: &str = "{stem}.rustdown-merge{n}{ext}";
+
+/// Where merge sidecars are written and how they are named.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MergeSidecarOptions {
+    /// Directory for sidecars; `None` writes next to the original file.
+    pub dir: Option<PathBuf>,
+    /// File-name template; `None` (or a template without `{n}` or with a
+    /// path separator) uses [`DEFAULT_MERGE_SIDECAR_TEMPLATE`].
+    pub template: Option<String>,
+}
+
+impl MergeSidecarOptions {
+    fn template(&self) -> &str {
+        self.template
+            .as_deref()
+            .filter(|t| t.contains("{n}") && !t.contains(['/', '\\']))
+            .unwrap_or(DEFAULT_MERGE_SIDECAR_TEMPLATE)
+    }
+}
+
+/// Strip a `.rustdown-merge[-N]` suffix so that a conflict in an open merge
+/// file yields another numbered sidecar of the original rather than a
+/// merge-of-a-merge (`notes.rustdown-merge.rustdown-merge.md`).
+fn merge_sidecar_origin_stem(stem: &OsStr) -> &OsStr {
+    const MARKER: &str = ".rustdown-merge";
+    let Some(text) = stem.to_str() else {
+        return stem;
     };
+    let Some(at) = text.rfind(MARKER) else {
+        return stem;
+    };
+    let suffix = &text[at + MARKER.len()..];
+    let numbered = suffix
+        .strip_prefix('-')
+        .is_some_and(|n| !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()));
+    if at > 0 && (suffix.is_empty() || numbered) {
+        OsStr::new(&text[..at])
+    } else {
+        stem
+    }
+}
+
+/// Expand a sidecar file-name template for the `n`-th candidate.
+#[allow(clippy::literal_string_with_formatting_args)] // template placeholders, not format args
+fn render_merge_sidecar_name(
+    template: &str,
+    stem: &OsStr,
+    ext: Option<&OsStr>,
+    n: usize,
+) -> OsString {
+    let mut name = OsString::new();
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        name.push(&rest[..open]);
+        let tail = &rest[open..];
+        if let Some(after) = tail.strip_prefix("{stem}") {
+            name.push(stem);
+            rest = after;
+        } else if let Some(after) = tail.strip_prefix("{n}") {
+            if n > 1 {
+                name.push(format!("-{n}"));
+            }
+            rest = after;
+        } else if let Some(after) = tail.strip_prefix("{ext}") {
+            if let Some(ext) = ext {
+                name.push(".");
+                name.push(ext);
+            }
+            rest = after;
+        } else {
+            name.push("{");
+            rest = &tail[1..];
+        }
+    }
+    name.push(rest);
+    name
+}
+
+/// Reserve the next free merge sidecar path for `original`.
+///
+/// Sidecars go in the configured directory (created if needed) or next to
+/// the original.  If that directory isn't writable, the system temp dir is
+/// used instead; the returned path is always the file actually created.
+pub fn next_merge_sidecar_path(
+    original: &Path,
+    options: &MergeSidecarOptions,
+) -> io::Result<PathBuf> {
     let stem = original
         .file_stem()
         .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "missing file stem"))?;
+    let stem = merge_sidecar_origin_stem(stem);
+    let dir = match (options.dir.as_deref(), original.parent()) {
+        (Some(dir), _) => {
+            let _ = fs::create_dir_all(dir);
+            dir
+        }
+        (None, Some(parent)) if !parent.as_os_str().is_empty() => parent,
+        (None, _) => Path::new("."),
+    };
 
+    let template = options.template();
     let ext = original.extension();
-    // Reusable buffer for the numeric suffix to avoid per-iteration format! allocations.
-    let mut suffix_buf = String::new();
-    for n in 1..=MERGE_SIDECAR_MAX_FILES {
-        let mut name = OsString::new();
-        name.push(stem);
-        name.push(".rustdown-merge");
-        if n > 1 {
-            use std::fmt::Write as _;
-            suffix_buf.clear();
-            let _ = write!(suffix_buf, "-{n}");
-            name.push(&suffix_buf);
-        }
-        if let Some(ext) = ext {
-            name.push(".");
-            name.push(ext);
+    match create_merge_sidecar_in(dir, template, stem, ext) {
+        // Only an exhausted sequence is final; anything else means the
+        // directory can't be written, so try the temp dir.
+        Err(err) if err.kind() != io::ErrorKind::AlreadyExists => {
+            let temp = std::env::temp_dir();
+            if temp == dir {
+                return Err(err);
+            }
+            create_merge_sidecar_in(&temp, template, stem, ext)
         }
+        result => result,
+    }
+}
 
-        let candidate = dir.join(&name);
+fn create_merge_sidecar_in(
+    dir: &Path,
+    template: &str,
+    stem: &OsStr,
+    ext: Option<&OsStr>,
+) -> io::Result<PathBuf> {
+    for n in 1..=MERGE_SIDECAR_MAX_FILES {
+        let candidate = dir.join(render_merge_sidecar_name(template, stem, ext, n));
         // Use create_new to atomically check-and-create, avoiding a
         // TOCTOU race between exists() and the caller's subsequent write.
         match fs::OpenOptions::new()
@@ -281,6 +411,21 @@ mod tests {
         assert_eq!(text, "content");
         assert_eq!(rev.len, 7);
 
+        // A file over the cap is refused before it is read.
+        let huge = dir.join("huge.md");
+        if let Ok(file) = fs::File::create(&huge) {
+            let _ = file.set_len(MAX_FILE_BYTES + 1);
+        }
+        let err = read_stable_utf8(&huge).err();
+        assert_eq!(
+            err.as_ref().map(io::Error::kind),
+            Some(io::ErrorKind::FileTooLarge)
+        );
+        assert!(
+            err.is_some_and(|err| err.to_string().ends_with("over the 64 MB limit")),
+            "error names the limit"
+        );
+
         // Missing file returns error.
         assert!(disk_revision(Path::new("/tmp/rustdown-nonexistent-12345.md")).is_err());
         assert!(read_stable_utf8(Path::new("/tmp/rustdown-nonexistent-stable-99999.md")).is_err());
@@ -342,17 +487,20 @@ mod tests {
     fn next_merge_sidecar_path_naming_and_edge_cases() {
         let dir = test_dir("rustdown-sidecar-test");
         let original = dir.join("notes.md");
-        let sidecar = next_merge_sidecar_path(&original).unwrap_or_else(|_| unreachable!());
+        let sidecar = next_merge_sidecar_path(&original, &MergeSidecarOptions::default())
+            .unwrap_or_else(|_| unreachable!());
         assert_eq!(
             sidecar.file_name().unwrap_or_default(),
             "notes.rustdown-merge.md"
         );
-        let sidecar = next_merge_sidecar_path(&original).unwrap_or_else(|_| unreachable!());
+        let sidecar = next_merge_sidecar_path(&original, &MergeSidecarOptions::default())
+            .unwrap_or_else(|_| unreachable!());
         assert_eq!(
             sidecar.file_name().unwrap_or_default(),
             "notes.rustdown-merge-2.md"
         );
-        let sidecar = next_merge_sidecar_path(&original).unwrap_or_else(|_| unreachable!());
+        let sidecar = next_merge_sidecar_path(&original, &MergeSidecarOptions::default())
+            .unwrap_or_else(|_| unreachable!());
         assert_eq!(
             sidecar.file_name().unwrap_or_default(),
             "notes.rustdown-merge-3.md"
@@ -360,7 +508,8 @@ mod tests {
 
         let dir2 = test_dir("rustdown-sidecar-noext-test");
         let sidecar =
-            next_merge_sidecar_path(&dir2.join("README")).unwrap_or_else(|_| unreachable!());
+            next_merge_sidecar_path(&dir2.join("README"), &MergeSidecarOptions::default())
+                .unwrap_or_else(|_| unreachable!());
         assert_eq!(
             sidecar.file_name().unwrap_or_default(),
             "README.rustdown-merge"
@@ -368,10 +517,117 @@ mod tests {
 
         // Bare filename defaults to current dir — but create_new may fail
         // if the file already exists from a prior test run. Just verify no panic.
-        let _ = next_merge_sidecar_path(Path::new("notes.md"));
+        let _ = next_merge_sidecar_path(Path::new("notes.md"), &MergeSidecarOptions::default());
 
         // Bare directory rejected.
-        assert!(next_merge_sidecar_path(Path::new("/")).is_err());
+        assert!(next_merge_sidecar_path(Path::new("/"), &MergeSidecarOptions::default()).is_err());
+    }
+
+    #[test]
+    fn next_merge_sidecar_path_honours_dir_and_template() {
+        let dir = test_dir("rustdown-sidecar-config-test");
+        let sidecars = dir.join("merges");
+        let options = MergeSidecarOptions {
+            dir: Some(sidecars.clone()),
+            template: Some("{stem}{n}.conflict{ext}".to_owned()),
+        };
+        let original = dir.join("notes.md");
+        for expected in ["notes.conflict.md", "notes-2.conflict.md"] {
+            let sidecar =
+                next_merge_sidecar_path(&original, &options).unwrap_or_else(|_| unreachable!());
+            assert_eq!(sidecar, sidecars.join(expected));
+        }
+
+        // Templates that can't number or would escape the directory fall back.
+        for template in ["{stem}.merge{ext}", "../{stem}{n}{ext}"] {
+            let options = MergeSidecarOptions {
+                dir: None,
+                template: Some(template.to_owned()),
+            };
+            let sidecar = next_merge_sidecar_path(&dir.join("other.md"), &options)
+                .unwrap_or_else(|_| unreachable!());
+            assert_eq!(sidecar.parent(), Some(dir.0.as_path()), "{template}");
+            let _ = fs::remove_file(&sidecar);
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn atomic_write_follows_symlinks_and_rejects_loops() {
+        let dir = test_dir("rustdown-atomic-symlink-test");
+        let target = dir.join("target.md");
+        let link = dir.join("link.md");
+        let _ = fs::write(&target, "old");
+        assert!(std::os::unix::fs::symlink(&target, &link).is_ok());
+        assert!(atomic_write_utf8(&link, "new").is_ok());
+        assert!(
+            fs::symlink_metadata(&link).is_ok_and(|meta| meta.file_type().is_symlink()),
+            "the link itself is preserved"
+        );
+        assert_eq!(fs::read_to_string(&target).unwrap_or_default(), "new");
+
+        let loop_a = dir.join("a.md");
+        let loop_b = dir.join("b.md");
+        assert!(std::os::unix::fs::symlink(&loop_b, &loop_a).is_ok());
+        assert!(std::os::unix::fs::symlink(&loop_a, &loop_b).is_ok());
+        assert!(atomic_write_utf8(&loop_a, "text").is_err());
+        assert!(fs::symlink_metadata(&loop_a).is_ok_and(|meta| meta.file_type().is_symlink()));
+    }
+
+    #[test]
+    fn next_merge_sidecar_path_does_not_nest_sidecars() {
+        let dir = test_dir("rustdown-sidecar-nesting-test");
+        let first = next_merge_sidecar_path(&dir.join("notes.md"), &MergeSidecarOptions::default())
+            .unwrap_or_else(|_| unreachable!());
+        let second = next_merge_sidecar_path(&first, &MergeSidecarOptions::default())
+            .unwrap_or_else(|_| unreachable!());
+        assert_eq!(
+            second.file_name().unwrap_or_default(),
+            "notes.rustdown-merge-2.md"
+        );
+        let third = next_merge_sidecar_path(&second, &MergeSidecarOptions::default())
+            .unwrap_or_else(|_| unreachable!());
+        assert_eq!(
+            third.file_name().unwrap_or_default(),
+            "notes.rustdown-merge-3.md"
+        );
+
+        for (stem, expected) in [
+            ("notes", "notes"),
+            ("notes.rustdown-merge", "notes"),
+            ("notes.rustdown-merge-12", "notes"),
+            ("notes.rustdown-merge-x", "notes.rustdown-merge-x"),
+            (".rustdown-merge", ".rustdown-merge"),
+        ] {
+            assert_eq!(
+                merge_sidecar_origin_stem(OsStr::new(stem)),
+                OsStr::new(expected),
+                "{stem}"
+            );
+        }
+    }
+
+    #[test]
+    fn next_merge_sidecar_path_falls_back_to_temp_dir() {
+        let dir = test_dir("rustdown-sidecar-fallback-test");
+        // A plain file can't be used as the sidecar directory.
+        let blocked = dir.join("not-a-dir");
+        let _ = fs::write(&blocked, "");
+        let options = MergeSidecarOptions {
+            dir: Some(blocked),
+            template: None,
+        };
+        let stem = dir
+            .0
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or_default()
+            .to_owned();
+        let sidecar = next_merge_sidecar_path(&dir.0.join(format!("{stem}.md")), &options)
+            .unwrap_or_else(|_| unreachable!());
+        assert_eq!(sidecar.parent(), Some(std::env::temp_dir().as_path()));
+        assert!(sidecar.exists());
+        let _ = fs::remove_file(&sidecar);
     }
 
     #[test]
@@ -381,11 +637,11 @@ mod tests {
 
         // Fill all 100 slots by calling the function (which now creates files).
         for _ in 1..=MERGE_SIDECAR_MAX_FILES {
-            let result = next_merge_sidecar_path(&original);
+            let result = next_merge_sidecar_path(&original, &MergeSidecarOptions::default());
             assert!(result.is_ok());
         }
 
-        let result = next_merge_sidecar_path(&original);
+        let result = next_merge_sidecar_path(&original, &MergeSidecarOptions::default());
         assert!(result.is_err());
     }
 
@@ -471,7 +727,8 @@ mod tests {
         // The new create_new–based sidecar function should create the file.
         let dir = test_dir("rustdown-sidecar-atomic-test");
         let original = dir.join("doc.md");
-        let sidecar = next_merge_sidecar_path(&original).unwrap_or_else(|_| unreachable!());
+        let sidecar = next_merge_sidecar_path(&original, &MergeSidecarOptions::default())
+            .unwrap_or_else(|_| unreachable!());
         // File should already exist (created by create_new).
         assert!(
             sidecar.exists(),
@@ -1,9 +1,55 @@
-use std::{ffi::OsString, io, path::PathBuf, sync::mpsc, time::Instant};
+use std::{
+    collections::HashMap,
+    ffi::OsString,
+    io,
+    path::{Path, PathBuf},
+    sync::{Arc, mpsc},
+    time::Instant,
+};
 
 use notify::RecommendedWatcher;
+use serde::{Deserialize, Serialize};
 
 use crate::disk::io::DiskRevision;
 
+/// Watch errors for one directory before falling back to polling it.
+const WATCH_ERROR_LIMIT: u32 = 3;
+
+/// How external changes to the open document are detected.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiskSyncMode {
+    /// File-system notifications, polling only while no watcher is running.
+    #[default]
+    Watch,
+    /// Poll the file's revision; for network drives and FUSE mounts where
+    /// notifications fail or flood.
+    Poll,
+    /// Never check the disk; external changes are only seen when saving.
+    Off,
+}
+
+impl DiskSyncMode {
+    #[must_use]
+    pub const fn label(self) -> &'static str {
+        match self {
+            Self::Watch => "Watching",
+            Self::Poll => "Polling",
+            Self::Off => "Sync off",
+        }
+    }
+
+    /// The next mode when cycling through them from the status bar.
+    #[must_use]
+    pub const fn next(self) -> Self {
+        match self {
+            Self::Watch => Self::Poll,
+            Self::Poll => Self::Off,
+            Self::Off => Self::Watch,
+        }
+    }
+}
+
 /// How the document should be flagged after applying disk text.
 #[derive(Clone, Copy, Debug)]
 pub enum ReloadKind {
@@ -39,6 +85,9 @@ pub struct DiskReadMessage {
     pub path: PathBuf,
     pub nonce: u64,
     pub edit_seq: u64,
+    /// Read to open `path` as a new document (always a `Replace`
+    /// outcome), checked against `open_nonce` instead of `reload_nonce`.
+    pub opening: bool,
     pub outcome: io::Result<DiskReloadOutcome>,
 }
 
@@ -48,6 +97,40 @@ pub struct DiskConflict {
     pub disk_rev: DiskRevision,
     pub conflict_marked: String,
     pub ours_wins: String,
+    /// The buffer against `disk_text`, computed once for the dialog.
+    pub diff: Vec<ConflictHunk>,
+}
+
+/// One hunk of a [`DiskConflict`]'s diff, with line terminators trimmed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConflictHunk {
+    /// 1-based line in the buffer where the hunk starts.
+    pub line: usize,
+    /// Buffer lines the disk text doesn't have.
+    pub removed: Vec<String>,
+    /// Disk lines the buffer doesn't have.
+    pub added: Vec<String>,
+}
+
+impl ConflictHunk {
+    /// The hunks turning `buffer` into `disk_text`.
+    #[must_use]
+    pub fn diff(buffer: &str, disk_text: &str) -> Vec<Self> {
+        let owned = |lines: Vec<&str>| {
+            lines
+                .into_iter()
+                .map(|line| line.trim_end_matches(['\r', '\n']).to_owned())
+                .collect()
+        };
+        rustdown_core::diff_hunks(buffer, disk_text)
+            .into_iter()
+            .map(|hunk| Self {
+                line: hunk.line,
+                removed: owned(hunk.removed),
+                added: owned(hunk.added),
+            })
+            .collect()
+    }
 }
 
 /// Persistent state for the disk-synchronisation subsystem.
@@ -63,8 +146,54 @@ pub struct DiskSyncState {
     pub reload_in_flight: bool,
     pub read_tx: Option<mpsc::Sender<DiskReadMessage>>,
     pub read_rx: Option<mpsc::Receiver<DiskReadMessage>>,
+    /// File being read on the worker to be opened, shown as loading.
+    pub opening: Option<PathBuf>,
+    /// Bumped by every open so only the latest one's read is applied.
+    pub open_nonce: u64,
     pub conflict: Option<DiskConflict>,
+    /// The open file went missing from disk (deleted or renamed away) and
+    /// the user hasn't yet chosen to keep editing or close it.
+    pub deleted: bool,
     pub merge_sidecar_path: Option<PathBuf>,
+    /// Per-document sync modes chosen from the status bar this session.
+    pub mode_overrides: HashMap<PathBuf, DiskSyncMode>,
+    /// Watch errors seen per watched directory this session.
+    pub watch_errors: HashMap<PathBuf, u32>,
+    /// Buffer text a disk reload replaced, made an editor undo point the
+    /// next time the editor is shown.
+    pub replaced_text: Option<Arc<String>>,
+    /// Status-bar notice for the last disk-driven replacement, and when it
+    /// happened.
+    pub reload_notice: Option<(&'static str, Instant)>,
+}
+
+impl DiskSyncState {
+    /// The sync mode in effect for `path`: a per-document override, else
+    /// `default`, downgraded to polling for directories whose watcher keeps
+    /// failing.
+    #[must_use]
+    pub fn effective_mode(&self, path: &Path, default: DiskSyncMode) -> DiskSyncMode {
+        if let Some(mode) = self.mode_overrides.get(path) {
+            return *mode;
+        }
+        let root = path.parent().unwrap_or_else(|| Path::new("."));
+        if default == DiskSyncMode::Watch && self.watch_failing(root) {
+            DiskSyncMode::Poll
+        } else {
+            default
+        }
+    }
+
+    /// Record a watcher failure for `root`.
+    pub fn note_watch_error(&mut self, root: &Path) {
+        *self.watch_errors.entry(root.to_path_buf()).or_default() += 1;
+    }
+
+    fn watch_failing(&self, root: &Path) -> bool {
+        self.watch_errors
+            .get(root)
+            .is_some_and(|count| *count >= WATCH_ERROR_LIMIT)
+    }
 }
 
 #[cfg(test)]
@@ -83,6 +212,42 @@ mod tests {
         }
     }
 
+    #[test]
+    fn effective_mode_prefers_overrides_then_falls_back_to_polling() {
+        let mut s = DiskSyncState::default();
+        let doc = Path::new("/mnt/share/notes.md");
+        let root = Path::new("/mnt/share");
+        assert_eq!(
+            s.effective_mode(doc, DiskSyncMode::Watch),
+            DiskSyncMode::Watch
+        );
+
+        for _ in 0..WATCH_ERROR_LIMIT {
+            s.note_watch_error(root);
+        }
+        assert_eq!(
+            s.effective_mode(doc, DiskSyncMode::Watch),
+            DiskSyncMode::Poll
+        );
+        assert_eq!(s.effective_mode(doc, DiskSyncMode::Off), DiskSyncMode::Off);
+        assert_eq!(
+            s.effective_mode(Path::new("/home/notes.md"), DiskSyncMode::Watch),
+            DiskSyncMode::Watch
+        );
+
+        s.mode_overrides
+            .insert(doc.to_path_buf(), DiskSyncMode::Off);
+        assert_eq!(
+            s.effective_mode(doc, DiskSyncMode::Watch),
+            DiskSyncMode::Off
+        );
+
+        assert_eq!(
+            DiskSyncMode::Watch.next().next().next(),
+            DiskSyncMode::Watch
+        );
+    }
+
     #[test]
     fn sync_state_mutation_and_defaults() {
         // All Option fields default to None, counters to zero.
@@ -115,6 +280,7 @@ mod tests {
             disk_rev: dummy_rev(),
             conflict_marked: "cm".into(),
             ours_wins: "ow".into(),
+            diff: Vec::new(),
         });
         assert!(s.conflict.is_some());
         s.conflict = None;
@@ -155,6 +321,7 @@ mod tests {
                 path: PathBuf::from("/tmp/test.md"),
                 nonce,
                 edit_seq: 0,
+                opening: false,
                 outcome,
             })
             .ok();
@@ -215,6 +382,7 @@ mod tests {
             disk_rev: dummy_rev(),
             conflict_marked: "cm".into(),
             ours_wins: "ow".into(),
+            diff: ConflictHunk::diff("a\r\nb\n", "a\r\nc\n"),
         };
         let c2 = c.clone();
         assert_eq!(
@@ -225,6 +393,14 @@ mod tests {
             ),
             ("d", "cm", "ow")
         );
+        assert_eq!(
+            c2.diff,
+            [ConflictHunk {
+                line: 2,
+                removed: vec!["b".to_owned()],
+                added: vec!["c".to_owned()],
+            }]
+        );
         assert!(format!("{c:?}").contains("DiskConflict"));
 
         // ReloadKind: debug, Copy.
@@ -8,14 +8,32 @@ use std::sync::mpsc;
 use std::time::Instant;
 
 use eframe::egui;
-use notify::{Event, RecursiveMode, Watcher};
+use notify::{Event, EventKind, RecursiveMode, Watcher, event::ModifyKind};
 
+use crate::disk::images::IMAGE_CHECK_INTERVAL;
 use crate::disk::io::{DiskRevision, disk_revision, read_stable_utf8};
-use crate::disk::sync::{DiskConflict, DiskReadMessage, DiskReloadOutcome, ReloadKind};
+use crate::disk::sync::{
+    ConflictHunk, DiskConflict, DiskReadMessage, DiskReloadOutcome, DiskSyncMode, ReloadKind,
+};
 use crate::document::DocumentStats;
-use crate::live_merge::{Merge3Outcome, merge_three_way};
-
-use crate::{DISK_POLL_INTERVAL, DISK_RELOAD_DEBOUNCE, RustdownApp};
+use crate::nav::panel::NavScrollTarget;
+use crate::scroll_math;
+use rustdown_core::{Merge3Outcome, merge_three_way};
+
+use crate::{DISK_RELOAD_DEBOUNCE, Mode, PendingAction, RustdownApp};
+
+/// Merge a disk change into a modified buffer.  With `confirm` even a
+/// clean merge comes back as a conflict, with the merged text offered as
+/// both the conflict merge and "keep mine", so the user always chooses.
+fn merge_disk_change(base: &str, ours: &str, disk: &str, confirm: bool) -> Merge3Outcome {
+    match merge_three_way(base, ours, disk) {
+        Merge3Outcome::Clean(merged) if confirm => Merge3Outcome::Conflicted {
+            conflict_marked: merged.clone(),
+            ours_wins: merged,
+        },
+        outcome => outcome,
+    }
+}
 
 impl RustdownApp {
     fn clear_disk_watcher(&mut self) {
@@ -43,19 +61,41 @@ impl RustdownApp {
         disk_rev: DiskRevision,
         kind: ReloadKind,
     ) {
-        self.doc.text = text;
+        // A reload that leaves the buffer text as-is (e.g. a disk write of
+        // what we already have) keeps stats, preview, and layout caches.
+        let text_changed = !Arc::ptr_eq(&self.doc.text, &text) && self.doc.text != text;
+        if text_changed {
+            // Keep the view on the same content: carry the remembered scroll
+            // position through the text change instead of jumping to the top.
+            if let Some(path) = self.doc.path.as_deref()
+                && let Some(byte_offset) = self.scroll_positions.get(path)
+            {
+                let mapped = scroll_math::map_byte_through_edit(&self.doc.text, &text, byte_offset);
+                self.scroll_positions.remember(path, mapped);
+                self.nav.pending_scroll = Some(NavScrollTarget::ByteOffset(mapped));
+            }
+            let previous = std::mem::replace(&mut self.doc.text, text);
+            // Keep the oldest unapplied text: it is what the editor last showed.
+            self.disk.replaced_text.get_or_insert(previous);
+            self.disk.reload_notice = match kind {
+                ReloadKind::Clean => Some(("Reloaded from disk", Instant::now())),
+                ReloadKind::Merged => Some(("Merged changes from disk", Instant::now())),
+                ReloadKind::ConflictResolved => None,
+            };
+            self.bump_edit_seq();
+            self.doc.stats = DocumentStats::from_text(self.doc.text.as_str());
+            self.doc.stats_dirty = false;
+            self.doc.preview_cache.clear();
+            self.doc.preview_dirty = false;
+            self.doc.editor_galley_cache = None;
+        }
         self.doc.base_text = base_text;
+        self.doc.share_base_text_if_equal();
         self.doc.disk_rev = Some(disk_rev);
-        self.bump_edit_seq();
-        self.doc.stats = DocumentStats::from_text(self.doc.text.as_str());
-        self.doc.stats_dirty = false;
-        self.doc.preview_cache.clear();
-        self.doc.preview_dirty = false;
         self.doc.dirty = !matches!(kind, ReloadKind::Clean);
         if matches!(kind, ReloadKind::Clean | ReloadKind::ConflictResolved) {
             self.doc.last_edit_at = None;
         }
-        self.doc.editor_galley_cache = None;
         self.error = None;
     }
 
@@ -67,6 +107,7 @@ impl RustdownApp {
         ours_wins: String,
     ) {
         self.disk.conflict = Some(DiskConflict {
+            diff: ConflictHunk::diff(&self.doc.text, &disk_text),
             disk_text,
             disk_rev,
             conflict_marked,
@@ -80,9 +121,33 @@ impl RustdownApp {
         self.disk.pending_reload_at = None;
         self.disk.reload_in_flight = false;
         self.disk.conflict = None;
+        self.disk.deleted = false;
         self.clear_disk_watcher();
     }
 
+    /// The document was just saved to disk: drop reloads pending or in
+    /// flight, as they read the file before the save.  The watcher stays,
+    /// so a change made right after the save is still seen; the save's own
+    /// events are ignored by [`Self::tick_disk_sync`] because the file is
+    /// then at the document's `disk_rev`.
+    pub(crate) fn note_disk_saved(&mut self) {
+        self.disk.reload_nonce = self.disk.reload_nonce.wrapping_add(1);
+        self.disk.poll_at = None;
+        self.disk.pending_reload_at = None;
+        self.disk.reload_in_flight = false;
+        self.disk.conflict = None;
+        self.disk.deleted = false;
+    }
+
+    /// The open file was not found on disk.  If it was there before, ask
+    /// what to do with the document; one never saved there is left alone.
+    const fn note_disk_file_missing(&mut self) {
+        self.disk.pending_reload_at = None;
+        if self.doc.disk_rev.is_some() {
+            self.disk.deleted = true;
+        }
+    }
+
     fn ensure_disk_read_channel(&mut self) {
         if self.disk.read_tx.is_some() {
             return;
@@ -118,6 +183,7 @@ impl RustdownApp {
         let mut watcher = match notify::recommended_watcher(handler) {
             Ok(watcher) => watcher,
             Err(err) => {
+                self.disk.note_watch_error(watch_root);
                 self.error
                     .get_or_insert_with(|| format!("Watch setup failed: {err}"));
                 return;
@@ -125,6 +191,7 @@ impl RustdownApp {
         };
 
         if let Err(err) = watcher.watch(watch_root, RecursiveMode::NonRecursive) {
+            self.disk.note_watch_error(watch_root);
             self.error
                 .get_or_insert_with(|| format!("Watch start failed: {err}"));
             return;
@@ -137,6 +204,10 @@ impl RustdownApp {
         self.disk.poll_at = None;
     }
 
+    /// `true` when a watch event touched the open file.  Removals and
+    /// renames away count too: the reload they schedule finds the file
+    /// missing and raises the deleted-file prompt, while an editor that
+    /// replaces the file by renaming over it just looks like a change.
     fn drain_disk_watch_events(&mut self) -> bool {
         let Some(rx) = self.disk.watch_rx.as_ref() else {
             return false;
@@ -150,6 +221,17 @@ impl RustdownApp {
         loop {
             match rx.try_recv() {
                 Ok(Ok(event)) => {
+                    // A Markdown file added, removed or renamed changes
+                    // what quick open should list.
+                    let renamed = matches!(
+                        event.kind,
+                        EventKind::Create(_)
+                            | EventKind::Remove(_)
+                            | EventKind::Modify(ModifyKind::Name(_))
+                    );
+                    if renamed && event.paths.iter().any(|path| crate::is_markdown_path(path)) {
+                        self.quick_open.mark_stale();
+                    }
                     if let Some(target) = target_name
                         && event
                             .paths
@@ -171,6 +253,9 @@ impl RustdownApp {
             }
         }
         if let Some(err) = watch_error {
+            if let Some(root) = self.disk.watch_root.clone() {
+                self.disk.note_watch_error(&root);
+            }
             self.error.get_or_insert(err);
             self.clear_disk_watcher();
             return false;
@@ -194,10 +279,22 @@ impl RustdownApp {
             return;
         };
 
-        self.ensure_disk_watcher(ctx, path.as_path());
+        match self.disk.effective_mode(&path, self.disk_sync_mode) {
+            DiskSyncMode::Watch => self.ensure_disk_watcher(ctx, path.as_path()),
+            DiskSyncMode::Poll => self.clear_disk_watcher(),
+            DiskSyncMode::Off => {
+                self.clear_disk_watcher();
+                self.disk.poll_at = None;
+                self.disk.pending_reload_at = None;
+                return;
+            }
+        }
 
         let now = Instant::now();
-        if self.drain_disk_watch_events() {
+        // Saving renames a temp file over the document, which the watcher
+        // reports like any other change; nothing to reload while the file
+        // is still at the revision the save recorded.
+        if self.drain_disk_watch_events() && disk_revision(&path).ok() != self.doc.disk_rev {
             self.schedule_disk_reload(now);
         }
 
@@ -205,11 +302,14 @@ impl RustdownApp {
             match self.disk.poll_at {
                 Some(next) if now < next => {}
                 _ => {
-                    self.disk.poll_at = Some(now + DISK_POLL_INTERVAL);
+                    self.disk.poll_at = Some(now + self.timings.disk_poll_interval());
 
                     match disk_revision(path.as_path()) {
                         Ok(rev) if Some(rev) != self.doc.disk_rev => self.schedule_disk_reload(now),
                         Ok(_) => {}
+                        Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                            self.note_disk_file_missing();
+                        }
                         Err(err) => {
                             self.error
                                 .get_or_insert_with(|| format!("Disk check failed: {err}"));
@@ -247,6 +347,25 @@ impl RustdownApp {
         }
     }
 
+    /// Forget the decoded copies of preview images whose file changed on
+    /// disk, so the preview loads them again.  Keeps checking while the
+    /// preview is showing tracked images.
+    pub(crate) fn reload_changed_images(&mut self, ctx: &egui::Context) {
+        let shown = rustdown_md::take_shown_images(ctx);
+        if self.mode == Mode::Edit {
+            return;
+        }
+        let changed = self.image_revisions.changed(shown, Instant::now());
+        for uri in &changed {
+            ctx.forget_image(uri);
+        }
+        if !changed.is_empty() {
+            ctx.request_repaint();
+        } else if self.image_revisions.is_tracking() {
+            ctx.request_repaint_after(IMAGE_CHECK_INTERVAL);
+        }
+    }
+
     fn start_disk_reload(&mut self, ctx: &egui::Context, path: PathBuf) {
         self.ensure_disk_read_channel();
         let Some(tx) = self.disk.read_tx.clone() else {
@@ -255,6 +374,7 @@ impl RustdownApp {
 
         let edit_seq = self.doc.edit_seq;
         let dirty = self.doc.dirty;
+        let confirm_merge = self.confirm_disk_merges;
         let base_text = dirty.then(|| self.doc.base_text.clone());
         let ours_text = dirty.then(|| self.doc.text.clone());
 
@@ -268,10 +388,11 @@ impl RustdownApp {
                 Ok((disk_text, disk_rev)) => {
                     if dirty {
                         match (base_text, ours_text) {
-                            (Some(base_text), Some(ours_text)) => match merge_three_way(
+                            (Some(base_text), Some(ours_text)) => match merge_disk_change(
                                 base_text.as_str(),
                                 ours_text.as_str(),
                                 disk_text.as_str(),
+                                confirm_merge,
                             ) {
                                 Merge3Outcome::Clean(merged_text) => {
                                     Ok(DiskReloadOutcome::MergeClean {
@@ -306,12 +427,60 @@ impl RustdownApp {
                 path,
                 nonce,
                 edit_seq,
+                opening: false,
                 outcome,
             });
             ctx.request_repaint();
         });
     }
 
+    /// Read `path` on the disk-read worker for [`Self::finish_open`]; the
+    /// document is replaced when the result is drained, unless it was
+    /// edited meanwhile, when the open asks first like any other.
+    pub(crate) fn start_open_read(&mut self, path: PathBuf) {
+        self.ensure_disk_read_channel();
+        let Some(tx) = self.disk.read_tx.clone() else {
+            return;
+        };
+        let nonce = self.disk.open_nonce;
+        let edit_seq = self.doc.edit_seq;
+        self.disk.opening = Some(path.clone());
+        std::thread::spawn(move || {
+            let outcome =
+                read_stable_utf8(&path).map(|(disk_text, disk_rev)| DiskReloadOutcome::Replace {
+                    disk_text,
+                    disk_rev,
+                });
+            let _ = tx.send(DiskReadMessage {
+                path,
+                nonce,
+                edit_seq,
+                opening: true,
+                outcome,
+            });
+        });
+    }
+
+    fn finish_open_read(&mut self, msg: DiskReadMessage) {
+        if msg.nonce != self.disk.open_nonce || self.disk.opening.as_ref() != Some(&msg.path) {
+            return;
+        }
+        self.disk.opening = None;
+        if self.doc.dirty && msg.edit_seq != self.doc.edit_seq {
+            self.request_action(PendingAction::Open(msg.path));
+            return;
+        }
+        let result = match msg.outcome {
+            Ok(DiskReloadOutcome::Replace {
+                disk_text,
+                disk_rev,
+            }) => Ok((disk_text, disk_rev)),
+            Ok(_) => Err(io::Error::other("unexpected read outcome")),
+            Err(err) => Err(err),
+        };
+        self.finish_open(msg.path, result);
+    }
+
     fn drain_disk_read_results(&mut self) {
         loop {
             let recv = match self.disk.read_rx.as_ref() {
@@ -319,6 +488,7 @@ impl RustdownApp {
                 None => return,
             };
             match recv {
+                Ok(msg) if msg.opening => self.finish_open_read(msg),
                 Ok(msg) => {
                     if msg.nonce != self.disk.reload_nonce {
                         continue;
@@ -332,6 +502,10 @@ impl RustdownApp {
                         self.schedule_disk_reload(Instant::now());
                         continue;
                     }
+                    // Readable again: the file is back, if it went away.
+                    if msg.outcome.is_ok() {
+                        self.disk.deleted = false;
+                    }
 
                     match msg.outcome {
                         Ok(DiskReloadOutcome::Replace {
@@ -366,6 +540,9 @@ impl RustdownApp {
                         }) => {
                             self.set_disk_conflict(disk_text, disk_rev, conflict_marked, ours_wins);
                         }
+                        Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                            self.note_disk_file_missing();
+                        }
                         Err(err) => {
                             self.error = Some(format!("Reload failed: {err}"));
                         }
@@ -393,10 +570,11 @@ impl RustdownApp {
             return;
         }
 
-        match merge_three_way(
+        match merge_disk_change(
             self.doc.base_text.as_str(),
             self.doc.text.as_str(),
             disk_text.as_str(),
+            self.confirm_disk_merges,
         ) {
             Merge3Outcome::Clean(merged) => {
                 self.apply_disk_text_state(
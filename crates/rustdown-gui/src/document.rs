@@ -1,15 +1,19 @@
 use std::{
     borrow::Cow,
     cell::Cell,
+    ops::Range,
     path::PathBuf,
-    sync::Arc,
+    sync::{Arc, mpsc},
     time::{Duration, Instant},
 };
 
 use eframe::egui;
-use rustdown_md::MarkdownCache;
+use rustdown_md::{MarkdownCache, consume_fence_delimiter, unclosed_fence_line};
 
+use crate::changes::LineChanges;
 use crate::disk::io::DiskRevision;
+use crate::editor::{char_index_to_byte, visual_column};
+use crate::fold::Folding;
 
 pub struct Document {
     pub path: Option<PathBuf>,
@@ -21,10 +25,45 @@ pub struct Document {
     pub stats_dirty: bool,
     pub preview_dirty: bool,
     pub dirty: bool,
+    /// The preview's parsed blocks.  While a re-render is debounced they
+    /// stay on screen, so the pane doesn't go blank mid-typing, without
+    /// holding on to the text they came from, which would make every
+    /// keystroke copy the document.
     pub preview_cache: MarkdownCache,
     pub last_edit_at: Option<Instant>,
     pub edit_seq: u64,
     pub editor_galley_cache: Option<EditorGalleyCache>,
+    /// Column of the editor ruler, from `.editorconfig` `max_line_length`;
+    /// read when the document is loaded or saved under a new path.
+    pub wrap_column: Option<usize>,
+    /// Folded heading sections in the editor.
+    pub folding: Folding,
+    /// Gutter markers for lines changed since the base text.
+    pub line_changes: LineChanges,
+    /// Spaces Tab indents by, from `.editorconfig` `indent_size`; read
+    /// with `wrap_column` so Tab doesn't search the filesystem.
+    pub indent_size: usize,
+    /// Columns a tab spans when reporting the cursor column, from
+    /// `.editorconfig` `tab_width` (or `indent_size`).
+    pub tab_width: usize,
+    /// Stats being computed off the UI thread for a large buffer.
+    pub stats_job: Option<StatsJob>,
+    /// Title found in the text (front matter `title`, else the first H1),
+    /// with the `edit_seq` it was detected at.
+    pub detected_title: Option<(u64, Option<String>)>,
+    /// Whether conflict blocks remain, with the `edit_seq` it was checked
+    /// at.  See [`Self::has_unresolved_conflicts`].
+    pub unresolved_conflicts: Option<(u64, bool)>,
+}
+
+/// Buffers at least this large have their stats computed on a worker
+/// thread once the debounce fires; smaller ones are quicker inline.
+pub const ASYNC_STATS_MIN_BYTES: usize = 2 * 1024 * 1024;
+
+/// Stats being computed on a worker thread for the text at `edit_seq`.
+pub struct StatsJob {
+    pub edit_seq: u64,
+    pub rx: mpsc::Receiver<DocumentStats>,
 }
 
 impl Default for Document {
@@ -44,6 +83,14 @@ impl Default for Document {
             last_edit_at: None,
             edit_seq: 0,
             editor_galley_cache: None,
+            wrap_column: None,
+            folding: Folding::default(),
+            line_changes: LineChanges::default(),
+            indent_size: crate::editor::DEFAULT_INDENT_SIZE,
+            tab_width: crate::editor::DEFAULT_INDENT_SIZE,
+            stats_job: None,
+            detected_title: None,
+            unresolved_conflicts: None,
         }
     }
 }
@@ -64,6 +111,28 @@ impl Document {
             .map_or_else(|| Cow::Borrowed("Untitled"), |name| name.to_string_lossy())
     }
 
+    /// The name to show for the document: the title detected in its text,
+    /// else its file name.  See [`Self::refresh_detected_title`].
+    #[must_use]
+    pub fn display_title(&self) -> Cow<'_, str> {
+        match &self.detected_title {
+            Some((_, Some(title))) => Cow::Borrowed(title),
+            _ => self.title(),
+        }
+    }
+
+    /// Detect the title again if the text changed since the last call.
+    pub fn refresh_detected_title(&mut self) {
+        if self
+            .detected_title
+            .as_ref()
+            .is_some_and(|(seq, _)| *seq == self.edit_seq)
+        {
+            return;
+        }
+        self.detected_title = Some((self.edit_seq, rustdown_md::document_title(&self.text)));
+    }
+
     #[must_use]
     pub fn path_label(&self) -> Cow<'_, str> {
         self.path
@@ -85,6 +154,28 @@ impl Document {
         self.last_edit_at = Some(Instant::now());
     }
 
+    /// Point `base_text` at the `text` allocation when their contents match,
+    /// so a document that ends up identical to disk keeps one copy.
+    pub fn share_base_text_if_equal(&mut self) {
+        if !Arc::ptr_eq(&self.text, &self.base_text) && self.text == self.base_text {
+            self.base_text = Arc::clone(&self.text);
+        }
+    }
+
+    /// Whether conflict blocks (`<<<<<<<` … `=======` … `>>>>>>>`) remain
+    /// outside fenced code blocks, where marker-like lines are content.
+    /// The answer is kept until the text changes.
+    pub fn has_unresolved_conflicts(&mut self) -> bool {
+        if let Some((seq, found)) = self.unresolved_conflicts
+            && seq == self.edit_seq
+        {
+            return found;
+        }
+        let found = !conflict_starts_outside_fences(&self.text).is_empty();
+        self.unresolved_conflicts = Some((self.edit_seq, found));
+        found
+    }
+
     /// Increment `edit_seq` monotonically (wraps at `u64::MAX`).
     pub const fn bump_edit_seq(&mut self) {
         self.edit_seq = self.edit_seq.wrapping_add(1);
@@ -95,6 +186,57 @@ impl Document {
         if self.stats_dirty {
             self.stats = DocumentStats::from_text(self.text.as_str());
             self.stats_dirty = false;
+            self.stats_job = None;
+        }
+    }
+
+    /// Start computing stats for the current text on a worker thread,
+    /// unless one is already running for this `edit_seq`.  `ctx` is
+    /// repainted when the result is ready.
+    pub fn spawn_stats_job(&mut self, ctx: &egui::Context) {
+        if self
+            .stats_job
+            .as_ref()
+            .is_some_and(|job| job.edit_seq == self.edit_seq)
+        {
+            return;
+        }
+        let (tx, rx) = mpsc::channel();
+        let text = Arc::clone(&self.text);
+        let ctx = ctx.clone();
+        std::thread::spawn(move || {
+            let _ = tx.send(DocumentStats::from_text(text.as_str()));
+            ctx.request_repaint();
+        });
+        self.stats_job = Some(StatsJob {
+            edit_seq: self.edit_seq,
+            rx,
+        });
+    }
+
+    /// Apply the worker's stats if they are ready and still describe the
+    /// current text; a job for an older `edit_seq` is dropped.  Returns
+    /// `true` when the stats were updated.
+    pub fn poll_stats_job(&mut self) -> bool {
+        let Some(job) = self.stats_job.as_ref() else {
+            return false;
+        };
+        if job.edit_seq != self.edit_seq {
+            self.stats_job = None;
+            return false;
+        }
+        match job.rx.try_recv() {
+            Ok(stats) => {
+                self.stats = stats;
+                self.stats_dirty = false;
+                self.stats_job = None;
+                true
+            }
+            Err(mpsc::TryRecvError::Empty) => false,
+            Err(mpsc::TryRecvError::Disconnected) => {
+                self.stats_job = None;
+                false
+            }
         }
     }
 
@@ -115,9 +257,23 @@ impl Document {
 pub struct DocumentStats {
     pub lines: usize,
     pub words: usize,
+    /// Line number of a code fence left open at the end of the document.
+    pub unclosed_fence_line: Option<usize>,
+    /// Number of unresolved `<<<<<<<` … `>>>>>>>` conflict blocks.
+    pub conflict_blocks: usize,
 }
 
+/// Average silent reading speed used for reading-time estimates.
+const WORDS_PER_MINUTE: usize = 200;
+
 impl DocumentStats {
+    /// Estimated reading time in whole minutes, rounded up; zero only for an
+    /// empty document.
+    #[must_use]
+    pub const fn reading_minutes(&self) -> usize {
+        self.words.div_ceil(WORDS_PER_MINUTE)
+    }
+
     #[must_use]
     pub fn from_text(text: &str) -> Self {
         let lines = if text.is_empty() {
@@ -126,13 +282,91 @@ impl DocumentStats {
             1 + bytecount_newlines(text)
         };
         let words = text.split_whitespace().count();
-        Self { lines, words }
+        Self {
+            lines,
+            words,
+            unclosed_fence_line: unclosed_fence_line(text),
+            conflict_blocks: conflict_starts_outside_fences(text).len(),
+        }
     }
 }
 
 impl Default for DocumentStats {
     fn default() -> Self {
-        Self { lines: 1, words: 0 }
+        Self {
+            lines: 1,
+            words: 0,
+            unclosed_fence_line: None,
+            conflict_blocks: 0,
+        }
+    }
+}
+
+/// Word and character counts for the editor selection, cached by the
+/// selected char range and the document's `edit_seq`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SelectionStats {
+    pub char_range: Range<usize>,
+    pub edit_seq: u64,
+    pub words: usize,
+    pub chars: usize,
+}
+
+impl SelectionStats {
+    /// Compute stats for `char_range` (in chars, as reported by the editor
+    /// cursor) within `text`.  The range is clamped to the text.
+    #[must_use]
+    pub fn from_selection(text: &str, char_range: Range<usize>, edit_seq: u64) -> Self {
+        let start = char_index_to_byte(text, char_range.start);
+        let end = char_index_to_byte(text, char_range.end).max(start);
+        let selected = &text[start..end];
+        Self {
+            char_range,
+            edit_seq,
+            words: DocumentStats::from_text(selected).words,
+            chars: selected.chars().count(),
+        }
+    }
+
+    /// Returns `true` if these stats are still valid for the given selection.
+    #[must_use]
+    pub fn is_current(&self, char_range: &Range<usize>, edit_seq: u64) -> bool {
+        self.edit_seq == edit_seq && self.char_range == *char_range
+    }
+}
+
+/// 1-based line and visual column of the editor cursor, cached by its
+/// char index, the document's `edit_seq` and the tab width.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CursorPosition {
+    pub char_index: usize,
+    pub edit_seq: u64,
+    pub tab_width: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl CursorPosition {
+    /// Locate char index `char_index` in `text`, counting tabs to the next
+    /// multiple of `tab_width`.
+    #[must_use]
+    pub fn from_cursor(text: &str, char_index: usize, edit_seq: u64, tab_width: usize) -> Self {
+        let byte = char_index_to_byte(text, char_index);
+        let prefix = &text[..byte];
+        let line_start = memchr::memrchr(b'\n', prefix.as_bytes()).map_or(0, |i| i + 1);
+        Self {
+            char_index,
+            edit_seq,
+            tab_width,
+            line: bytecount_newlines(prefix) + 1,
+            column: visual_column(&prefix[line_start..], tab_width) + 1,
+        }
+    }
+
+    /// Returns `true` if this position is still valid for the given cursor.
+    #[must_use]
+    pub const fn is_current(&self, char_index: usize, edit_seq: u64, tab_width: usize) -> bool {
+        self.char_index == char_index && self.edit_seq == edit_seq && self.tab_width == tab_width
     }
 }
 
@@ -152,6 +386,8 @@ pub struct EditorGalleyCache {
     pub layout_sections: Vec<egui::text::LayoutSection>,
     pub galley: Arc<egui::Galley>,
     pub row_byte_offsets: Vec<(f32, u32)>,
+    /// Folded byte ranges the galley was laid out with.
+    pub hidden: Vec<Range<usize>>,
 }
 
 pub struct TrackedTextBuffer<'a, 'b> {
@@ -188,6 +424,29 @@ impl egui::TextBuffer for TrackedTextBuffer<'_, '_> {
     }
 }
 
+/// Byte offsets of the conflict blocks opening outside fenced code blocks,
+/// where marker-like lines are content.  See
+/// [`rustdown_core::conflict_marker_starts`].
+#[must_use]
+pub fn conflict_starts_outside_fences(text: &str) -> Vec<usize> {
+    let starts = rustdown_core::conflict_marker_starts(text);
+    let Some(&last) = starts.last() else {
+        return starts;
+    };
+    let mut outside = Vec::with_capacity(starts.len());
+    let mut fence = None;
+    let mut starts = starts.iter().copied().peekable();
+    let mut offset = 0;
+    for line in text[..=last].split_inclusive('\n') {
+        if starts.next_if_eq(&offset).is_some() && fence.is_none() {
+            outside.push(offset);
+        }
+        consume_fence_delimiter(line.trim_end_matches(['\n', '\r']), &mut fence);
+        offset += line.len();
+    }
+    outside
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -220,6 +479,45 @@ mod tests {
         assert_eq!(DocumentStats::default(), DocumentStats::from_text(""));
     }
 
+    #[test]
+    fn stats_track_unclosed_fence() {
+        assert_eq!(DocumentStats::from_text("a\n").unclosed_fence_line, None);
+        assert_eq!(
+            DocumentStats::from_text("# T\n\n```sh\nls\n").unclosed_fence_line,
+            Some(3)
+        );
+    }
+
+    #[test]
+    fn selection_stats_slice_by_char_range() {
+        let text = "héllo wörld foo";
+        let sel = SelectionStats::from_selection(text, 0..11, 7);
+        assert_eq!((sel.words, sel.chars), (2, 11));
+        assert!(sel.is_current(&(0..11), 7));
+        assert!(!sel.is_current(&(0..11), 8));
+        assert!(!sel.is_current(&(0..5), 7));
+
+        let tail = SelectionStats::from_selection(text, 12..99, 0);
+        assert_eq!((tail.words, tail.chars), (1, 3));
+    }
+
+    #[test]
+    fn cursor_position_counts_tabs_to_tab_stops() {
+        let text = "first\nabcd\tx\n\tü";
+        let after_tab = CursorPosition::from_cursor(text, 11, 3, 4);
+        assert_eq!((after_tab.line, after_tab.column), (2, 9));
+        assert!(after_tab.is_current(11, 3, 4));
+        assert!(!after_tab.is_current(11, 3, 8));
+        assert!(!after_tab.is_current(11, 4, 4));
+
+        let wide = CursorPosition::from_cursor(text, 11, 3, 8);
+        assert_eq!(wide.column, 9);
+        let last = CursorPosition::from_cursor(text, 99, 0, 8);
+        assert_eq!((last.line, last.column), (3, 10));
+        let start = CursorPosition::from_cursor(text, 0, 0, 4);
+        assert_eq!((start.line, start.column), (1, 1));
+    }
+
     // ── bytecount_newlines ────────────────────────────────────────────
 
     #[test]
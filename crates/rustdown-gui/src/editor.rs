@@ -1,3 +1,5 @@
+use std::ops::Range;
+
 use eframe::egui;
 
 /// Build a `(row_y, row_start_byte)` table from galley rows.
@@ -56,14 +58,263 @@ pub fn row_y_to_byte_offset(rows: &[(f32, u32)], y: f32) -> usize {
     rows[idx].1 as usize
 }
 
+/// Indentation width used by Tab when `.editorconfig` sets none.
+pub const DEFAULT_INDENT_SIZE: usize = 4;
+
 /// Convert a character index to a byte offset in `text`.
-#[cfg(test)]
 pub fn char_index_to_byte(text: &str, char_index: usize) -> usize {
     text.char_indices()
         .nth(char_index)
         .map_or(text.len(), |(i, _)| i)
 }
 
+/// Byte offset where zero-based `line` of `text` starts, or the end of the
+/// text past its last line.
+pub fn line_start(text: &str, line: usize) -> usize {
+    line.checked_sub(1).map_or(0, |last| {
+        memchr::memchr_iter(b'\n', text.as_bytes())
+            .nth(last)
+            .map_or(text.len(), |i| i + 1)
+    })
+}
+
+/// Display width of `line_prefix`, the text between a line start and the
+/// cursor, where a tab advances to the next multiple of `tab_width`.
+#[must_use]
+pub fn visual_column(line_prefix: &str, tab_width: usize) -> usize {
+    let tab_width = tab_width.max(1);
+    line_prefix.chars().fold(0, |column, ch| {
+        if ch == '\t' {
+            column + tab_width - column % tab_width
+        } else {
+            column + 1
+        }
+    })
+}
+
+/// The result of an editor command: the new text and the new selection
+/// (as a char range, matching egui's cursor indices).
+#[derive(Debug, PartialEq, Eq)]
+pub struct SelectionEdit {
+    pub text: String,
+    pub selection: Range<usize>,
+}
+
+/// Byte offsets of the starts of every line touched by `start..end`.
+/// A selection ending at the very start of a line does not include it.
+fn selected_line_starts(text: &str, start: usize, end: usize) -> Vec<usize> {
+    let first = memchr::memrchr(b'\n', &text.as_bytes()[..start]).map_or(0, |i| i + 1);
+    let end = if end > start && text[..end].ends_with('\n') {
+        end - 1
+    } else {
+        end
+    };
+    let mut starts = vec![first];
+    starts.extend(memchr::memchr_iter(b'\n', &text.as_bytes()[start..end]).map(|i| start + i + 1));
+    starts
+}
+
+/// Tab: replace a single-line selection with `indent`, or prefix every
+/// line of a multi-line selection with it.
+#[must_use]
+pub fn indent_selection(text: &str, selection: Range<usize>, indent: &str) -> SelectionEdit {
+    let start = char_index_to_byte(text, selection.start);
+    let end = char_index_to_byte(text, selection.end).max(start);
+    let shift = indent.chars().count();
+
+    if memchr::memchr(b'\n', &text.as_bytes()[start..end]).is_none() {
+        let mut out = String::with_capacity(text.len() + indent.len());
+        out.push_str(&text[..start]);
+        out.push_str(indent);
+        out.push_str(&text[end..]);
+        let cursor = selection.start + shift;
+        return SelectionEdit {
+            text: out,
+            selection: cursor..cursor,
+        };
+    }
+
+    let starts = selected_line_starts(text, start, end);
+    let mut out = String::with_capacity(text.len() + indent.len() * starts.len());
+    let mut prev = 0;
+    for &line in &starts {
+        out.push_str(&text[prev..line]);
+        out.push_str(indent);
+        prev = line;
+    }
+    out.push_str(&text[prev..]);
+    // Keep a selection that starts at column 0 anchored there.
+    let start_shift = if starts.first().is_some_and(|&line| line < start) {
+        shift
+    } else {
+        0
+    };
+    SelectionEdit {
+        text: out,
+        selection: selection.start + start_shift..selection.end + shift * starts.len(),
+    }
+}
+
+/// Count the run of `*` at the end (`from_end`) or start of `s`.
+fn star_run(s: &str, from_end: bool) -> usize {
+    let bytes = s.as_bytes().iter();
+    if from_end {
+        bytes.rev().take_while(|&&b| b == b'*').count()
+    } else {
+        bytes.take_while(|&&b| b == b'*').count()
+    }
+}
+
+/// Toggle `*`/`**` emphasis around the selection, like a word processor.
+///
+/// An empty selection inserts a marker pair with the cursor between them.
+/// A selection already wrapped in `marker` — inside or just outside it —
+/// is unwrapped; anything else is wrapped.  A run of `*` counts as italic
+/// when its length is odd and as bold when it is at least two, so `**x**`
+/// is not treated as italic.
+#[must_use]
+pub fn toggle_emphasis(text: &str, selection: Range<usize>, marker: &str) -> SelectionEdit {
+    let start = char_index_to_byte(text, selection.start);
+    let end = char_index_to_byte(text, selection.end).max(start);
+    let len = marker.len();
+    let has_marker = |run: usize| if len == 1 { run % 2 == 1 } else { run >= len };
+    let splice = |from: usize, to: usize, replacement: &str| {
+        let mut out = String::with_capacity(text.len() + 2 * len);
+        out.push_str(&text[..from]);
+        out.push_str(replacement);
+        out.push_str(&text[to..]);
+        out
+    };
+
+    let selected = &text[start..end];
+    let inner_run = star_run(selected, false).min(star_run(selected, true));
+    if selected.len() > 2 * len && inner_run < selected.len() && has_marker(inner_run) {
+        let inner = &selected[len..selected.len() - len];
+        return SelectionEdit {
+            text: splice(start, end, inner),
+            selection: selection.start..selection.end - 2 * len,
+        };
+    }
+
+    let outer_run = star_run(&text[..start], true).min(star_run(&text[end..], false));
+    if start < end && has_marker(outer_run) {
+        return SelectionEdit {
+            text: splice(start - len, end + len, selected),
+            selection: selection.start - len..selection.end - len,
+        };
+    }
+
+    SelectionEdit {
+        text: splice(start, end, &format!("{marker}{selected}{marker}")),
+        selection: selection.start + len..selection.end + len,
+    }
+}
+
+/// Replace the selection with `insert`, leaving the cursor after it.
+#[must_use]
+pub fn replace_selection(text: &str, selection: Range<usize>, insert: &str) -> SelectionEdit {
+    let start = char_index_to_byte(text, selection.start);
+    let end = char_index_to_byte(text, selection.end).max(start);
+    let cursor = selection.start + insert.chars().count();
+    SelectionEdit {
+        text: format!("{}{insert}{}", &text[..start], &text[end..]),
+        selection: cursor..cursor,
+    }
+}
+
+/// Wrap the lines touched by the selection in a code fence tagged with
+/// `language`, or insert an empty fenced block on its own line at the
+/// cursor.  The fence is longer than any backtick run inside it, and the
+/// selection ends up on the wrapped code or the empty line in the block.
+#[must_use]
+pub fn insert_code_fence(text: &str, selection: Range<usize>, language: &str) -> SelectionEdit {
+    let start = char_index_to_byte(text, selection.start);
+    let end = char_index_to_byte(text, selection.end).max(start);
+    let chars = |s: &str| s.chars().count();
+
+    if start == end {
+        let prefix = if start == 0 || text[..start].ends_with('\n') {
+            ""
+        } else {
+            "\n"
+        };
+        let suffix = if text[start..].starts_with('\n') {
+            ""
+        } else {
+            "\n"
+        };
+        let open = format!("{prefix}```{language}\n");
+        let mut out = String::with_capacity(text.len() + open.len() + 5);
+        out.push_str(&text[..start]);
+        out.push_str(&open);
+        out.push_str("\n```");
+        out.push_str(suffix);
+        out.push_str(&text[start..]);
+        let cursor = selection.start + chars(&open);
+        return SelectionEdit {
+            text: out,
+            selection: cursor..cursor,
+        };
+    }
+
+    let line_start = memchr::memrchr(b'\n', &text.as_bytes()[..start]).map_or(0, |i| i + 1);
+    let content_end = if text[..end].ends_with('\n') {
+        end - 1
+    } else {
+        memchr::memchr(b'\n', &text.as_bytes()[end..]).map_or(text.len(), |i| end + i)
+    };
+    let content = &text[line_start..content_end.max(line_start)];
+    let longest_run = content
+        .split(|c| c != '`')
+        .map(str::len)
+        .max()
+        .unwrap_or_default();
+    let fence = "`".repeat((longest_run + 1).max(3));
+    let open = format!("{fence}{language}\n");
+
+    let mut out = String::with_capacity(text.len() + 2 * fence.len() + language.len() + 2);
+    out.push_str(&text[..line_start]);
+    out.push_str(&open);
+    out.push_str(content);
+    out.push('\n');
+    out.push_str(&fence);
+    out.push_str(&text[line_start + content.len()..]);
+    let code_start = chars(&text[..line_start]) + chars(&open);
+    SelectionEdit {
+        text: out,
+        selection: code_start..code_start + chars(content),
+    }
+}
+
+/// Shift+Tab: remove one level of indentation (a tab, or up to `width`
+/// spaces) from every line touched by the selection.
+#[must_use]
+pub fn dedent_selection(text: &str, selection: Range<usize>, width: usize) -> SelectionEdit {
+    let start = char_index_to_byte(text, selection.start);
+    let end = char_index_to_byte(text, selection.end).max(start);
+    let mut out = String::with_capacity(text.len());
+    let (mut prev, mut start_shift, mut end_shift) = (0, 0, 0);
+    for line in selected_line_starts(text, start, end) {
+        let rest = &text.as_bytes()[line..];
+        let removed = if rest.first() == Some(&b'\t') {
+            1
+        } else {
+            rest.iter().take(width).take_while(|&&b| b == b' ').count()
+        };
+        out.push_str(&text[prev..line]);
+        prev = line + removed;
+        // Removed indentation is ASCII, so byte counts equal char counts.
+        start_shift += removed.min(start.saturating_sub(line));
+        end_shift += removed.min(end.saturating_sub(line));
+    }
+    out.push_str(&text[prev..]);
+    SelectionEdit {
+        text: out,
+        selection: selection.start.saturating_sub(start_shift)
+            ..selection.end.saturating_sub(end_shift),
+    }
+}
+
 #[cfg(test)]
 #[allow(clippy::float_cmp)]
 mod tests {
@@ -161,6 +412,17 @@ mod tests {
 
     // ── char_index_to_byte extended ─────────────────────────────────
 
+    #[test]
+    fn visual_column_expands_tabs_to_tab_stops() {
+        assert_eq!(visual_column("", 4), 0);
+        assert_eq!(visual_column("\t", 4), 4);
+        assert_eq!(visual_column("ab\t", 4), 4);
+        assert_eq!(visual_column("abcd\tx", 4), 9);
+        assert_eq!(visual_column("\t\t", 8), 16);
+        assert_eq!(visual_column("é\t", 2), 2);
+        assert_eq!(visual_column("\t", 0), 1, "zero width acts as one");
+    }
+
     #[test]
     fn char_index_to_byte_edge_cases() {
         // Empty string
@@ -174,6 +436,113 @@ mod tests {
         assert_eq!(char_index_to_byte(text, 3), 8);
     }
 
+    // ── Indent / dedent ─────────────────────────────────────────────
+
+    #[test]
+    fn indent_selection_inserts_at_cursor_or_prefixes_lines() {
+        // Cursor / single-line selection: replaced by the indent.
+        let edit = indent_selection("ab", 1..1, "  ");
+        assert_eq!(edit.text, "a  b");
+        assert_eq!(edit.selection, 3..3);
+        let edit = indent_selection("abcd", 1..3, "\t");
+        assert_eq!(edit.text, "a\td");
+        assert_eq!(edit.selection, 2..2);
+
+        // Multi-line: every touched line is indented, selection follows.
+        let edit = indent_selection("one\ntwo\nthree", 1..6, "  ");
+        assert_eq!(edit.text, "  one\n  two\nthree");
+        assert_eq!(edit.selection, 3..10);
+
+        // A selection ending at column 0 leaves that line alone.
+        let edit = indent_selection("é\nb\nc", 0..4, "\t");
+        assert_eq!(edit.text, "\té\n\tb\nc");
+        assert_eq!(edit.selection, 0..6);
+    }
+
+    #[test]
+    fn dedent_selection_removes_one_level_per_line() {
+        let edit = dedent_selection("    a\n  b\n\tc\nd", 2..12, 4);
+        assert_eq!(edit.text, "a\nb\nc\nd");
+        assert_eq!(edit.selection, 0..5);
+
+        // Cursor only: dedents its own line, cursor moves with the text.
+        let edit = dedent_selection("x\n      y", 8..8, 4);
+        assert_eq!(edit.text, "x\n  y");
+        assert_eq!(edit.selection, 4..4);
+
+        // Nothing to remove.
+        let edit = dedent_selection("abc", 1..2, 4);
+        assert_eq!(edit.text, "abc");
+        assert_eq!(edit.selection, 1..2);
+    }
+
+    #[test]
+    fn toggle_emphasis_wraps_unwraps_and_inserts() {
+        // No selection: markers inserted, cursor between them.
+        let edit = toggle_emphasis("a  b", 2..2, "**");
+        assert_eq!(edit.text, "a **** b");
+        assert_eq!(edit.selection, 4..4);
+
+        // Wrap, keeping the word selected.
+        let edit = toggle_emphasis("say hi", 4..6, "*");
+        assert_eq!(edit.text, "say *hi*");
+        assert_eq!(edit.selection, 5..7);
+
+        // Unwrap when the markers surround the selection…
+        let edit = toggle_emphasis("say **hi**", 6..8, "**");
+        assert_eq!(edit.text, "say hi");
+        assert_eq!(edit.selection, 4..6);
+
+        // …or are part of it.
+        let edit = toggle_emphasis("say *hi*", 4..8, "*");
+        assert_eq!(edit.text, "say hi");
+        assert_eq!(edit.selection, 4..6);
+
+        // Bold is not mistaken for italic, and vice versa.
+        let edit = toggle_emphasis("**hi**", 2..4, "*");
+        assert_eq!(edit.text, "***hi***");
+        let edit = toggle_emphasis("***hi***", 3..5, "*");
+        assert_eq!(edit.text, "**hi**");
+        let edit = toggle_emphasis("*hi*", 1..3, "**");
+        assert_eq!(edit.text, "***hi***");
+    }
+
+    #[test]
+    fn insert_code_fence_at_cursor_and_around_lines() {
+        // Empty selection: a block on its own lines, cursor inside.
+        let edit = insert_code_fence("", 0..0, "rust");
+        assert_eq!(edit.text, "```rust\n\n```\n");
+        assert_eq!(edit.selection, 8..8);
+        let edit = insert_code_fence("ab\ncd", 1..1, "");
+        assert_eq!(edit.text, "a\n```\n\n```\nb\ncd");
+        assert_eq!(edit.selection, 6..6);
+        let edit = insert_code_fence("x\n\ny", 2..2, "sh");
+        assert_eq!(edit.text, "x\n```sh\n\n```\ny");
+
+        // Selection: whole touched lines are wrapped and stay selected.
+        let text = "intro\nlet é = 1;\nlet b = 2;\nouter";
+        let edit = insert_code_fence(text, 9..20, "rust");
+        assert_eq!(
+            edit.text,
+            "intro\n```rust\nlet é = 1;\nlet b = 2;\n```\nouter"
+        );
+        let code: String = edit
+            .text
+            .chars()
+            .skip(edit.selection.start)
+            .take(edit.selection.len())
+            .collect();
+        assert_eq!(code, "let é = 1;\nlet b = 2;");
+
+        // A selection ending after a newline stops at that line.
+        let edit = insert_code_fence("a\nb\n", 0..2, "");
+        assert_eq!(edit.text, "```\na\n```\nb\n");
+
+        // Backtick runs inside get a longer fence.
+        let edit = insert_code_fence("use ```x```", 0..3, "md");
+        assert_eq!(edit.text, "````md\nuse ```x```\n````");
+    }
+
     // ── Chaos tests ──────────────────────────────────────────────────
 
     #[test]
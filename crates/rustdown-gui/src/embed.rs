@@ -0,0 +1,91 @@
+//! Hosting the editor as a pane inside another eframe app.
+
+use std::path::{Path, PathBuf};
+
+use eframe::egui;
+
+use crate::{Mode, RustdownApp, preferences::UserPreferences};
+
+/// Builds a [`RustdownApp`] for embedding, with default preferences and
+/// an initial buffer.  The file at `path` is neither read nor watched; it
+/// only resolves relative image and link targets.
+#[derive(Debug, Default)]
+#[must_use]
+pub struct RustdownAppBuilder {
+    text: String,
+    path: Option<PathBuf>,
+    mode: Mode,
+}
+
+impl RustdownAppBuilder {
+    /// Initial contents of the buffer.
+    pub fn text(mut self, text: impl Into<String>) -> Self {
+        self.text = text.into();
+        self
+    }
+
+    /// Path the buffer belongs to.
+    pub fn path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.path = Some(path.into());
+        self
+    }
+
+    /// Panes shown by [`RustdownApp::show`].
+    pub const fn mode(mut self, mode: Mode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    #[must_use]
+    pub fn build(self) -> RustdownApp {
+        let mut app = RustdownApp::from_preferences(UserPreferences::default());
+        app.mode = self.mode;
+        app.init_document(self.path, self.text, None);
+        app
+    }
+}
+
+impl RustdownApp {
+    pub fn builder() -> RustdownAppBuilder {
+        RustdownAppBuilder::default()
+    }
+
+    /// The current buffer.
+    #[must_use]
+    pub fn text(&self) -> &str {
+        self.doc.text.as_str()
+    }
+
+    #[must_use]
+    pub fn path(&self) -> Option<&Path> {
+        self.doc.path.as_deref()
+    }
+
+    /// Whether the buffer has changed since it was built or last saved.
+    #[must_use]
+    pub const fn is_dirty(&self) -> bool {
+        self.doc.dirty
+    }
+
+    #[must_use]
+    pub const fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    /// Draw the panes for the current mode into `ui`, side by side in
+    /// [`Mode::SideBySide`].  Unlike the full app this shows no toolbar,
+    /// status bar, nav panel or dialogs, and handles no app shortcuts.
+    pub fn show(&mut self, ui: &mut egui::Ui) {
+        self.refresh_stats_if_due(ui.ctx());
+        match self.mode {
+            Mode::Edit => self.show_editor(ui),
+            Mode::Preview => self.show_preview(ui),
+            Mode::SideBySide => {
+                ui.columns(2, |columns| {
+                    self.show_editor(&mut columns[0]);
+                    self.show_preview(&mut columns[1]);
+                });
+            }
+        }
+    }
+}
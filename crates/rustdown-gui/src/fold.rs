@@ -0,0 +1,326 @@
+//! Folding of heading sections in the editor.
+//!
+//! The editor is a single `TextEdit`, so its galley must always hold the
+//! whole buffer.  A folded section's body is therefore laid out with a
+//! zero line height and no visible glyphs instead of being removed; the
+//! heading line stays visible and gets a `…` marker painted after it.
+
+use std::ops::Range;
+
+use eframe::egui;
+
+use crate::nav::outline::HeadingEntry;
+
+/// A heading whose body can be folded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FoldSection {
+    pub key: FoldKey,
+    /// Char index of the start of the heading's first line.
+    pub heading_char: usize,
+    /// Char index of the end of the heading's last line (before its newline).
+    pub heading_end_char: usize,
+    /// Bytes hidden when folded: the lines after the heading up to the next
+    /// heading of the same or a higher level.
+    pub body: Range<usize>,
+    /// `body` as char indices.  At the end of the text it extends one past
+    /// the last char, where a caret after the hidden lines would sit.
+    pub body_chars: Range<usize>,
+}
+
+/// Identifies a folded heading across edits: its line text and how many
+/// identical heading lines precede it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FoldKey {
+    line: String,
+    occurrence: usize,
+}
+
+/// Per-document fold state: the folded headings and the sections of the
+/// text they were last resolved against.
+#[derive(Debug, Default)]
+pub struct Folding {
+    pub folded: Vec<FoldKey>,
+    pub sections: Vec<FoldSection>,
+    /// `edit_seq` the sections were computed for.
+    sections_seq: Option<u64>,
+    /// Merged, sorted body ranges of the folded sections.
+    hidden: Vec<Range<usize>>,
+}
+
+impl Folding {
+    /// Recompute sections from `headings` (extracted from `source` at
+    /// `edit_seq`), dropping folds whose heading no longer exists.
+    pub fn refresh(&mut self, source: &str, headings: &[HeadingEntry], edit_seq: u64) {
+        if self.sections_seq == Some(edit_seq) {
+            return;
+        }
+        self.sections_seq = Some(edit_seq);
+        self.sections = fold_sections(source, headings);
+        let sections = &self.sections;
+        self.folded
+            .retain(|key| sections.iter().any(|section| section.key == *key));
+        self.rebuild_hidden();
+    }
+
+    /// Whether the sections match the text at `edit_seq`.
+    pub fn is_current(&self, edit_seq: u64) -> bool {
+        self.sections_seq == Some(edit_seq)
+    }
+
+    pub fn is_folded(&self, section: &FoldSection) -> bool {
+        self.folded.contains(&section.key)
+    }
+
+    /// Fold or unfold the section at `index`.
+    pub fn toggle(&mut self, index: usize) {
+        let Some(section) = self.sections.get(index) else {
+            return;
+        };
+        if let Some(pos) = self.folded.iter().position(|key| *key == section.key) {
+            self.folded.remove(pos);
+        } else {
+            self.folded.push(section.key.clone());
+        }
+        self.rebuild_hidden();
+    }
+
+    /// Unfold every folded section whose body contains the char index
+    /// `cursor`, so the caret never sits in hidden text.  Returns whether
+    /// any did.
+    pub fn unfold_at(&mut self, cursor: usize) -> bool {
+        let sections = &self.sections;
+        let before = self.folded.len();
+        self.folded.retain(|key| {
+            !sections
+                .iter()
+                .any(|section| section.key == *key && section.body_chars.contains(&cursor))
+        });
+        let changed = self.folded.len() != before;
+        if changed {
+            self.rebuild_hidden();
+        }
+        changed
+    }
+
+    /// Byte ranges laid out hidden, sorted and non-overlapping.
+    pub fn hidden(&self) -> &[Range<usize>] {
+        &self.hidden
+    }
+
+    fn rebuild_hidden(&mut self) {
+        self.hidden.clear();
+        for section in &self.sections {
+            if !self.folded.contains(&section.key) {
+                continue;
+            }
+            match self.hidden.last_mut() {
+                Some(last) if section.body.start <= last.end => {
+                    last.end = last.end.max(section.body.end);
+                }
+                _ => self.hidden.push(section.body.clone()),
+            }
+        }
+    }
+}
+
+/// Foldable sections of `source`, one per heading with a non-empty body.
+pub fn fold_sections(source: &str, headings: &[HeadingEntry]) -> Vec<FoldSection> {
+    let line_start = |offset: usize| source[..offset].rfind('\n').map_or(0, |i| i + 1);
+    let mut sections = Vec::new();
+    let mut occurrences: Vec<(&str, usize)> = Vec::new();
+    // Running byte → char conversion.  Headings are in document order, so
+    // it only steps back from a body's end to the next nested heading, and
+    // the total walk stays within the heading depth times the text length.
+    let (mut byte, mut chars) = (0, 0);
+    let mut char_at = |offset: usize| {
+        if offset >= byte {
+            chars += source[byte..offset].chars().count();
+        } else {
+            chars -= source[offset..byte].chars().count();
+        }
+        byte = offset;
+        chars
+    };
+    for (i, heading) in headings.iter().enumerate() {
+        let start = line_start(heading.byte_offset);
+        let last = heading
+            .end_offset
+            .saturating_sub(1)
+            .max(heading.byte_offset);
+        let end = source[last..].find('\n').map_or(source.len(), |i| last + i);
+        let next = headings[i + 1..]
+            .iter()
+            .find(|next| next.level <= heading.level)
+            .map_or(source.len(), |next| line_start(next.byte_offset));
+        let body = (end + 1).min(source.len())..next;
+        if body.start >= body.end {
+            continue;
+        }
+        let line = source[start..end].trim_end();
+        let occurrence =
+            if let Some((_, count)) = occurrences.iter_mut().find(|(seen, _)| *seen == line) {
+                *count += 1;
+                *count
+            } else {
+                occurrences.push((line, 0));
+                0
+            };
+        let heading_char = char_at(start);
+        let heading_end_char = char_at(end);
+        let body_start = char_at(body.start);
+        let body_end = char_at(body.end) + usize::from(body.end == source.len());
+        sections.push(FoldSection {
+            key: FoldKey {
+                line: line.to_owned(),
+                occurrence,
+            },
+            heading_char,
+            heading_end_char,
+            body,
+            body_chars: body_start..body_end,
+        });
+    }
+    sections
+}
+
+/// Split `sections` so the bytes in `hidden` (sorted, non-overlapping) are
+/// laid out with zero height and transparent glyphs.
+pub fn hide_ranges(sections: &mut Vec<egui::text::LayoutSection>, hidden: &[Range<usize>]) {
+    if hidden.is_empty() {
+        return;
+    }
+    let mut out = Vec::with_capacity(sections.len() + hidden.len() * 2);
+    let mut h = 0;
+    for section in sections.drain(..) {
+        let mut start = section.byte_range.start;
+        while start < section.byte_range.end {
+            while hidden.get(h).is_some_and(|range| range.end <= start) {
+                h += 1;
+            }
+            let (end, hide) = match hidden.get(h) {
+                Some(range) if range.start <= start => (range.end, true),
+                Some(range) => (range.start, false),
+                None => (section.byte_range.end, false),
+            };
+            let end = end.min(section.byte_range.end);
+            out.push(egui::text::LayoutSection {
+                leading_space: if start == section.byte_range.start {
+                    section.leading_space
+                } else {
+                    0.0
+                },
+                byte_range: start..end,
+                format: if hide {
+                    hidden_format(&section.format)
+                } else {
+                    section.format.clone()
+                },
+            });
+            start = end;
+        }
+    }
+    *sections = out;
+}
+
+fn hidden_format(format: &egui::TextFormat) -> egui::TextFormat {
+    egui::TextFormat {
+        font_id: egui::FontId::new(1.0, format.font_id.family.clone()),
+        line_height: Some(0.0),
+        color: egui::Color32::TRANSPARENT,
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nav::outline::extract_headings;
+
+    fn sections(md: &str) -> Vec<FoldSection> {
+        fold_sections(md, &extract_headings(md))
+    }
+
+    #[test]
+    fn sections_end_at_next_same_or_higher_heading() {
+        let md = "# A\nintro\n## B\nb text\n\n## C\n# D\n";
+        let found = sections(md);
+        let bodies: Vec<&str> = found.iter().map(|s| &md[s.body.clone()]).collect();
+        // `## C` and `# D` have nothing under them before the next heading.
+        assert_eq!(bodies, ["intro\n## B\nb text\n\n## C\n", "b text\n\n"]);
+        assert_eq!(found[1].heading_char, md.find("## B").unwrap_or_default());
+        assert_eq!(found[1].heading_end_char, found[1].heading_char + 4);
+    }
+
+    #[test]
+    fn setext_underline_stays_visible_and_keys_count_duplicates() {
+        let md = "Title\n=====\nbody\n\n## Notes\none\n## Notes\ntwo";
+        let found = sections(md);
+        assert_eq!(
+            &md[found[0].body.clone()],
+            "body\n\n## Notes\none\n## Notes\ntwo"
+        );
+        assert_eq!(found[0].heading_end_char, "Title\n=====".len());
+        assert_eq!(&md[found[2].body.clone()], "two");
+        assert_eq!(found[1].key.line, found[2].key.line);
+        assert_ne!(found[1].key, found[2].key);
+    }
+
+    #[test]
+    fn folds_survive_edits_elsewhere_and_unfold_under_cursor() {
+        let md = "# A\none\n## B\ntwo\n";
+        let mut folding = Folding::default();
+        folding.refresh(md, &extract_headings(md), 1);
+        folding.toggle(1);
+        folding.toggle(0);
+        assert_eq!(folding.hidden(), std::slice::from_ref(&(4..md.len())));
+
+        let edited = "intro\n\n# A\none\n## B\ntwo\n";
+        folding.refresh(edited, &extract_headings(edited), 2);
+        assert_eq!(folding.folded.len(), 2);
+        assert_eq!(folding.hidden(), std::slice::from_ref(&(11..edited.len())));
+
+        assert!(folding.unfold_at(edited.find("two").unwrap_or_default()));
+        assert!(folding.folded.is_empty());
+        assert!(!folding.unfold_at(0));
+        folding.toggle(0);
+        assert!(
+            folding.unfold_at(edited.len()),
+            "caret at the end of a folded tail"
+        );
+
+        folding.toggle(1);
+        let renamed = "# A\none\n## Bee\ntwo\n";
+        folding.refresh(renamed, &extract_headings(renamed), 3);
+        assert!(folding.folded.is_empty());
+        assert!(folding.hidden().is_empty());
+    }
+
+    #[test]
+    fn hide_ranges_splits_sections_at_fold_edges() {
+        let format =
+            |size| egui::TextFormat::simple(egui::FontId::monospace(size), egui::Color32::WHITE);
+        let section = |range: Range<usize>, size| egui::text::LayoutSection {
+            leading_space: 0.0,
+            byte_range: range,
+            format: format(size),
+        };
+        // "# A\none\ntwo\n# B\n" with the heading styled apart.
+        let mut sections = vec![section(0..6, 20.0), section(6..16, 12.0)];
+        hide_ranges(&mut sections, std::slice::from_ref(&(4..12)));
+        let ranges: Vec<_> = sections.iter().map(|s| s.byte_range.clone()).collect();
+        assert_eq!(ranges, [0..4, 4..6, 6..12, 12..16]);
+        let hidden: Vec<_> = sections
+            .iter()
+            .map(|s| {
+                s.format.line_height == Some(0.0) && s.format.color == egui::Color32::TRANSPARENT
+            })
+            .collect();
+        assert_eq!(hidden, [false, true, true, false]);
+        assert_eq!(sections[0].format, format(20.0));
+        assert_eq!(sections[3].format, format(12.0));
+
+        let mut untouched = vec![section(0..16, 12.0)];
+        hide_ranges(&mut untouched, &[]);
+        assert_eq!(untouched.len(), 1);
+    }
+}
@@ -1,8 +1,9 @@
 #![forbid(unsafe_code)]
 
-use std::{borrow::Cow, fs, path::Path};
+use std::{borrow::Cow, fs, ops::Range, path::Path};
 
-use crate::markdown_fence::{FenceState, consume_fence_delimiter};
+use pulldown_cmark::{Alignment, Event, Options, Parser, Tag, TagEnd};
+use rustdown_md::{FenceState, consume_fence_delimiter};
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum EndOfLine {
@@ -14,13 +15,34 @@ pub enum EndOfLine {
 pub struct FormatOptions {
     pub trim_trailing_whitespace: bool,
     pub insert_final_newline: bool,
+    /// Collapse blank lines at the end of the file into the final newline.
+    pub trim_final_newlines: bool,
+    /// Longest run of consecutive blank lines kept outside code blocks and
+    /// front matter.
+    pub max_blank_lines: Option<usize>,
     pub end_of_line: Option<EndOfLine>,
+    /// Editor indentation width (`indent_size`); not used when formatting.
+    pub indent_size: Option<usize>,
+    /// Columns a tab advances the cursor column to a multiple of
+    /// (`tab_width`, else `indent_size`); not used when formatting.
+    pub tab_width: Option<usize>,
+    /// Hard-wrap column (`max_line_length`), shown as an editor ruler; not
+    /// used when formatting.
+    pub max_line_length: Option<usize>,
 }
 
+/// Upper bound on directories visited while searching for `.editorconfig`.
+const MAX_EDITORCONFIG_DEPTH: usize = 64;
+
 const DEFAULT_OPTIONS: FormatOptions = FormatOptions {
     trim_trailing_whitespace: true,
     insert_final_newline: true,
+    trim_final_newlines: true,
+    max_blank_lines: Some(1),
     end_of_line: None,
+    indent_size: None,
+    tab_width: None,
+    max_line_length: None,
 };
 
 #[must_use]
@@ -61,12 +83,42 @@ pub fn format_markdown(source: &str, options: FormatOptions) -> String {
     };
     let mut out = String::with_capacity(normalized.len() + extra);
     let mut in_fence: Option<FenceState> = None;
+    let mut blank_run = 0;
+    let verbatim = verbatim_ranges(&normalized);
+    let mut next_verbatim = 0;
+    let mut line_start = 0;
     let mut segments = normalized.split('\n').peekable();
     while let Some(line) = segments.next() {
         let is_fence_delimiter = consume_fence_delimiter(line, &mut in_fence);
+        while verbatim
+            .get(next_verbatim)
+            .is_some_and(|range| range.end <= line_start)
+        {
+            next_verbatim += 1;
+        }
+        let in_verbatim = verbatim
+            .get(next_verbatim)
+            .is_some_and(|range| range.contains(&line_start));
+        line_start += line.len() + 1;
+        if in_fence.is_none()
+            && !is_fence_delimiter
+            && !in_verbatim
+            && line.trim_matches([' ', '\t']).is_empty()
+        {
+            blank_run += 1;
+            if options.max_blank_lines.is_some_and(|max| blank_run > max) {
+                continue;
+            }
+        } else {
+            blank_run = 0;
+        }
         if options.trim_trailing_whitespace && in_fence.is_none() && !is_fence_delimiter {
-            let hard_break = line.ends_with("  ");
-            out.push_str(line.trim_end_matches([' ', '\t']));
+            let trimmed = line.trim_end_matches([' ', '\t']);
+            // The preview's parser reads any run of two or more trailing
+            // spaces or tabs as a hard break, so keep one (as two spaces)
+            // wherever it would render; whitespace-only lines never break.
+            let hard_break = !trimmed.is_empty() && line.len() - trimmed.len() >= 2;
+            out.push_str(trimmed);
             if hard_break {
                 out.push_str("  ");
             }
@@ -78,12 +130,190 @@ pub fn format_markdown(source: &str, options: FormatOptions) -> String {
             out.push_str(eol);
         }
     }
+    if options.trim_final_newlines && in_fence.is_none() {
+        let body_len = out.trim_end_matches(eol).len();
+        out.truncate((body_len + eol.len()).min(out.len()));
+    }
     if options.insert_final_newline && !out.ends_with(eol) {
         out.push_str(eol);
     }
     out
 }
 
+/// Byte ranges of `source` whose blank lines are content rather than
+/// spacing: the front matter block and code blocks, fenced or indented.
+fn verbatim_ranges(source: &str) -> Vec<Range<usize>> {
+    let front = rustdown_md::front_matter_len(source).unwrap_or(0);
+    let code = Parser::new(&source[front..])
+        .into_offset_iter()
+        .filter_map(|(event, range)| {
+            matches!(event, Event::Start(Tag::CodeBlock(_)))
+                .then(|| range.start + front..range.end + front)
+        });
+    std::iter::once(0..front).chain(code).collect()
+}
+
+/// Map the char index `cursor` in `before` to the equivalent position in
+/// `after`, its formatted version.  Formatting only trims lines and drops
+/// blank ones, so the cursor keeps its line (found through a line diff)
+/// and its column, clamped to the line's new length.  On a dropped line
+/// it moves to the next line kept.
+#[must_use]
+pub fn remap_char_index(before: &str, after: &str, cursor: usize) -> usize {
+    let prefix = &before[..crate::editor::char_index_to_byte(before, cursor)];
+    let old_line = prefix.matches('\n').count();
+    let column = prefix[prefix.rfind('\n').map_or(0, |i| i + 1)..]
+        .chars()
+        .count();
+
+    let mut new_line = old_line;
+    for hunk in rustdown_core::diff_hunks(before, after) {
+        let start = hunk.line - 1;
+        if old_line < start {
+            break;
+        }
+        if old_line < start + hunk.removed.len() {
+            // Lines the format dropped go to the line that follows them.
+            let offset = (old_line - start).min(hunk.added.len());
+            new_line = new_line + start + offset - old_line;
+            break;
+        }
+        new_line = new_line + hunk.added.len() - hunk.removed.len();
+    }
+
+    let mut chars = 0;
+    for (index, line) in after.split('\n').enumerate() {
+        let len = line.chars().count();
+        if index == new_line {
+            return chars + column.min(len - usize::from(line.ends_with('\r')));
+        }
+        chars += len + 1;
+    }
+    after.chars().count()
+}
+
+/// Rewrite every top-level table in `source` with its pipes aligned: each
+/// column padded to its widest cell, and the delimiter row rebuilt from
+/// the column's alignment (`:--`, `:-:`, `--:`).  Tables and cells come
+/// from the parser, so pipes in code spans, escaped pipes and table-like
+/// text in code blocks are left alone.  Tables inside lists or block
+/// quotes, and tables with a row of more cells than the header (which the
+/// parser drops), are skipped; line count and line endings are kept.
+#[must_use]
+pub fn reformat_tables(source: &str) -> String {
+    let mut tables: Vec<ParsedTable> = Vec::new();
+    let mut nesting = 0usize;
+    let mut current = None;
+    let mut cell_end = 0;
+    for (event, range) in Parser::new_ext(source, Options::ENABLE_TABLES).into_offset_iter() {
+        match event {
+            Event::Start(Tag::BlockQuote(_) | Tag::List(_)) => nesting += 1,
+            Event::End(TagEnd::BlockQuote(_) | TagEnd::List(_)) => {
+                nesting = nesting.saturating_sub(1);
+            }
+            Event::Start(Tag::Table(alignments)) if nesting == 0 => {
+                current = Some((range, alignments, Vec::new()));
+            }
+            Event::Start(Tag::TableHead | Tag::TableRow) => {
+                if let Some((_, _, rows)) = current.as_mut() {
+                    rows.push(Vec::new());
+                }
+            }
+            Event::Start(Tag::TableCell) => {
+                cell_end = range.end;
+                if let Some(row) = current.as_mut().and_then(|(_, _, rows)| rows.last_mut()) {
+                    row.push(source[range].trim_matches([' ', '\t']));
+                }
+            }
+            Event::End(TagEnd::TableHead | TagEnd::TableRow) => {
+                // Anything but the closing pipe after the last cell is a
+                // cell beyond the header's columns.
+                let rest = source[cell_end.min(range.end)..range.end].trim();
+                let rest = rest.strip_prefix('|').unwrap_or(rest);
+                if !rest.trim().is_empty() {
+                    current = None;
+                }
+            }
+            Event::End(TagEnd::Table) => tables.extend(current.take()),
+            _ => {}
+        }
+    }
+
+    let mut out = String::with_capacity(source.len() + source.len() / 8);
+    let mut copied = 0;
+    for (range, alignments, rows) in tables {
+        let start = source[..range.start].rfind('\n').map_or(0, |i| i + 1);
+        let end = start
+            + source[start..range.end]
+                .trim_end_matches(['\r', '\n'])
+                .len();
+        let block = &source[start..end];
+        if rows.len() + 1 != block.lines().count() || block.starts_with([' ', '\t']) {
+            continue;
+        }
+        let eol = if block.contains('\r') { "\r\n" } else { "\n" };
+        let mut widths = vec![3; alignments.len()];
+        for row in &rows {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.chars().count());
+            }
+        }
+        out.push_str(&source[copied..start]);
+        for (index, row) in rows.iter().enumerate() {
+            if index > 0 {
+                out.push_str(eol);
+            }
+            push_table_row(&mut out, &widths, &alignments, row);
+            if index == 0 {
+                out.push_str(eol);
+                push_delimiter_row(&mut out, &widths, &alignments);
+            }
+        }
+        copied = end;
+    }
+    out.push_str(&source[copied..]);
+    out
+}
+
+/// A table's source range, column alignments and the trimmed source of
+/// each cell, header row first.
+type ParsedTable<'a> = (Range<usize>, Vec<Alignment>, Vec<Vec<&'a str>>);
+
+fn push_table_row(out: &mut String, widths: &[usize], alignments: &[Alignment], row: &[&str]) {
+    out.push('|');
+    for (column, (&width, alignment)) in widths.iter().zip(alignments).enumerate() {
+        let cell = row.get(column).copied().unwrap_or_default();
+        let pad = width - cell.chars().count().min(width);
+        let before = match alignment {
+            Alignment::Right => pad,
+            Alignment::Center => pad / 2,
+            Alignment::None | Alignment::Left => 0,
+        };
+        out.push(' ');
+        out.extend(std::iter::repeat_n(' ', before));
+        out.push_str(cell);
+        out.extend(std::iter::repeat_n(' ', pad - before));
+        out.push_str(" |");
+    }
+}
+
+fn push_delimiter_row(out: &mut String, widths: &[usize], alignments: &[Alignment]) {
+    out.push('|');
+    for (&width, alignment) in widths.iter().zip(alignments) {
+        let (left, right) = match alignment {
+            Alignment::None => ("-", "-"),
+            Alignment::Left => (":", "-"),
+            Alignment::Center => (":", ":"),
+            Alignment::Right => ("-", ":"),
+        };
+        out.push(' ');
+        out.push_str(left);
+        out.extend(std::iter::repeat_n('-', width - 2));
+        out.push_str(right);
+        out.push_str(" |");
+    }
+}
+
 #[must_use]
 pub fn options_for_path(path: Option<&Path>) -> FormatOptions {
     let mut opts = DEFAULT_OPTIONS;
@@ -98,13 +328,27 @@ pub fn options_for_path(path: Option<&Path>) -> FormatOptions {
         return opts;
     };
 
-    let (mut trim, mut insert, mut eol) = (None, None, None);
-    loop {
-        if let Ok(contents) = fs::read_to_string(dir.join(".editorconfig")) {
+    let (mut trim, mut insert, mut eol, mut indent) = (None, None, None, None);
+    let (mut trim_final, mut max_blank_lines, mut max_line_length) = (None, None, None);
+    let mut tab = None;
+    // `..` components and symlinked directories can lead the lexical walk
+    // back to a directory it already read; skip those so no file is applied
+    // twice, and cap the walk so pathological paths can't spin.
+    let mut visited = Vec::new();
+    for _ in 0..MAX_EDITORCONFIG_DEPTH {
+        let canonical = fs::canonicalize(dir).unwrap_or_else(|_| dir.to_path_buf());
+        let first_visit = !visited.contains(&canonical);
+        visited.push(canonical);
+        if first_visit && let Ok(contents) = fs::read_to_string(dir.join(".editorconfig")) {
             let overrides = editorconfig_overrides(contents.as_str(), file);
             trim = trim.or(overrides.trim);
             insert = insert.or(overrides.insert);
+            trim_final = trim_final.or(overrides.trim_final);
+            max_blank_lines = max_blank_lines.or(overrides.max_blank_lines);
             eol = eol.or(overrides.eol);
+            indent = indent.or(overrides.indent_size);
+            tab = tab.or(overrides.tab_width);
+            max_line_length = max_line_length.or(overrides.max_line_length);
             if overrides.root {
                 break;
             }
@@ -121,7 +365,16 @@ pub fn options_for_path(path: Option<&Path>) -> FormatOptions {
     if let Some(v) = insert {
         opts.insert_final_newline = v;
     }
+    if let Some(v) = trim_final {
+        opts.trim_final_newlines = v;
+    }
+    if let Some(v) = max_blank_lines {
+        opts.max_blank_lines = (v > 0).then_some(v);
+    }
     opts.end_of_line = eol;
+    opts.indent_size = indent;
+    opts.tab_width = tab.or(indent);
+    opts.max_line_length = max_line_length.filter(|&column| column > 0);
     opts
 }
 
@@ -130,7 +383,14 @@ struct Overrides {
     root: bool,
     trim: Option<bool>,
     insert: Option<bool>,
+    trim_final: Option<bool>,
+    /// `Some(0)` keeps every blank line.
+    max_blank_lines: Option<usize>,
     eol: Option<EndOfLine>,
+    indent_size: Option<usize>,
+    tab_width: Option<usize>,
+    /// `Some(0)` for `off`, so a nearer file can unset a parent's column.
+    max_line_length: Option<usize>,
 }
 
 fn editorconfig_overrides(contents: &str, file: &str) -> Overrides {
@@ -162,7 +422,26 @@ fn editorconfig_overrides(contents: &str, file: &str) -> Overrides {
             key if key.eq_ignore_ascii_case("insert_final_newline") => {
                 overrides.insert = parse_bool(value);
             }
+            key if key.eq_ignore_ascii_case("trim_final_newlines") => {
+                overrides.trim_final = parse_bool(value);
+            }
+            key if key.eq_ignore_ascii_case("max_blank_lines") => {
+                overrides.max_blank_lines = parse_max_blank_lines(value);
+            }
             key if key.eq_ignore_ascii_case("end_of_line") => overrides.eol = parse_eol(value),
+            key if key.eq_ignore_ascii_case("max_line_length") => {
+                overrides.max_line_length = if value.eq_ignore_ascii_case("off") {
+                    Some(0)
+                } else {
+                    value.parse().ok()
+                };
+            }
+            key if key.eq_ignore_ascii_case("indent_size") => {
+                overrides.indent_size = value.parse().ok().filter(|&size| size > 0);
+            }
+            key if key.eq_ignore_ascii_case("tab_width") => {
+                overrides.tab_width = value.parse().ok().filter(|&width| width > 0);
+            }
             _ => {}
         }
     }
@@ -179,6 +458,14 @@ const fn parse_bool(value: &str) -> Option<bool> {
     }
 }
 
+/// A line count, with `0` or `off` keeping every blank line.
+fn parse_max_blank_lines(value: &str) -> Option<usize> {
+    if value.eq_ignore_ascii_case("off") {
+        return Some(0);
+    }
+    value.parse().ok()
+}
+
 fn parse_eol(value: &str) -> Option<EndOfLine> {
     value
         .eq_ignore_ascii_case("lf")
@@ -260,7 +547,12 @@ mod tests {
         let explicit_lf = FormatOptions {
             trim_trailing_whitespace: false,
             insert_final_newline: false,
+            trim_final_newlines: false,
+            max_blank_lines: None,
             end_of_line: Some(EndOfLine::Lf),
+            indent_size: None,
+            tab_width: None,
+            max_line_length: None,
         };
         for (source, options, expected) in [
             (
@@ -280,6 +572,108 @@ mod tests {
         }
     }
 
+    #[test]
+    fn format_hard_breaks_with_tabs_and_mixed_whitespace() {
+        for (source, expected) in [
+            ("a\t\nb\n", "a\nb\n"),
+            ("a \nb\n", "a\nb\n"),
+            ("a  \t\nb\n", "a  \nb\n"),
+            ("a\t  \nb\n", "a  \nb\n"),
+            ("a \t  \nb\n", "a  \nb\n"),
+            ("a \t\nb\n", "a  \nb\n"),
+            ("a     \nb\n", "a  \nb\n"),
+            ("a\n  \n\t \nb\n", "a\n\nb\n"),
+        ] {
+            let formatted = format_markdown(source, DEFAULT_OPTIONS);
+            assert_eq!(formatted, expected, "{source:?}");
+            let breaks = |text: &str| {
+                rustdown_md::markdown_to_html(text, rustdown_md::ParseOptions::default())
+                    .matches("<br")
+                    .count()
+            };
+            assert_eq!(breaks(&formatted), breaks(source), "{source:?}");
+        }
+    }
+
+    #[test]
+    fn format_collapses_blank_line_runs_outside_code_and_front_matter() {
+        for (source, expected) in [
+            ("a\n\n\n\nb\n", "a\n\nb\n"),
+            ("a\n \n\t\n\nb\n", "a\n\nb\n"),
+            ("a\n\n\n\n", "a\n"),
+            ("a\r\n\r\n\r\n", "a\r\n"),
+            ("```\n\n\n\ncode\n```\n\n\n", "```\n\n\n\ncode\n```\n"),
+            ("```\nunclosed\n\n\n", "```\nunclosed\n\n\n"),
+            ("    code\n\n\n\n    more\n", "    code\n\n\n\n    more\n"),
+            (
+                "- a\n\n      code\n\n\n      more\n",
+                "- a\n\n      code\n\n\n      more\n",
+            ),
+            (
+                "---\nkey: 1\n\n\nnext: 2\n---\n\n\n\ntext\n",
+                "---\nkey: 1\n\n\nnext: 2\n---\n\ntext\n",
+            ),
+            ("\n\n\n", "\n"),
+        ] {
+            assert_eq!(
+                format_markdown(source, DEFAULT_OPTIONS),
+                expected,
+                "{source:?}"
+            );
+        }
+        let two = FormatOptions {
+            max_blank_lines: Some(2),
+            ..DEFAULT_OPTIONS
+        };
+        assert_eq!(format_markdown("a\n\n\n\n\nb\n", two), "a\n\n\nb\n");
+        let keep = FormatOptions {
+            trim_final_newlines: false,
+            max_blank_lines: None,
+            ..DEFAULT_OPTIONS
+        };
+        assert_eq!(format_markdown("a\n\n\n\nb\n\n", keep), "a\n\n\n\nb\n\n");
+    }
+
+    #[test]
+    fn editorconfig_blank_line_settings() {
+        let dir = temp_dir_path("editorconfig-blank-lines");
+        assert!(fs::create_dir_all(&dir).is_ok());
+        write_text(
+            &dir.join(".editorconfig"),
+            "root = true\n[*.md]\ntrim_final_newlines = false\nmax_blank_lines = 3\n",
+        );
+        let file = dir.join("note.md");
+        let options = options_for_path(Some(&file));
+        assert!(!options.trim_final_newlines);
+        assert_eq!(options.max_blank_lines, Some(3));
+
+        write_text(
+            &dir.join(".editorconfig"),
+            "root = true\n[*]\nmax_blank_lines = off\n",
+        );
+        let options = options_for_path(Some(&file));
+        assert!(options.trim_final_newlines);
+        assert_eq!(options.max_blank_lines, None);
+        assert_eq!(parse_max_blank_lines("0"), Some(0));
+        assert_eq!(options.max_line_length, None);
+
+        let nested = dir.join("nested");
+        assert!(fs::create_dir_all(&nested).is_ok());
+        write_text(
+            &dir.join(".editorconfig"),
+            "root = true\n[*.md]\nmax_line_length = 80\n",
+        );
+        assert_eq!(options_for_path(Some(&file)).max_line_length, Some(80));
+        write_text(
+            &nested.join(".editorconfig"),
+            "[*]\nmax_line_length = off\n",
+        );
+        let nested_file = nested.join("note.md");
+        assert_eq!(options_for_path(Some(&nested_file)).max_line_length, None);
+        assert_eq!(parse_max_blank_lines("many"), None);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
     #[test]
     fn options_for_path_editorconfig_resolution() {
         // Nearest editorconfig with root=true stops upward search.
@@ -292,7 +686,7 @@ mod tests {
         );
         write_text(
             &nested.join(".editorconfig"),
-            "root = true\n[*.md]\ntrim_trailing_whitespace = false\nend_of_line = crlf\n",
+            "root = true\n[*.md]\ntrim_trailing_whitespace = false\nend_of_line = crlf\nindent_size = 2\n",
         );
         let file = nested.join("note.md");
         write_text(&file, "# note");
@@ -300,6 +694,12 @@ mod tests {
         assert!(!options.trim_trailing_whitespace);
         assert!(options.insert_final_newline);
         assert_eq!(options.end_of_line, Some(EndOfLine::CrLf));
+        assert_eq!(options.indent_size, Some(2));
+        assert_eq!(
+            options.tab_width,
+            Some(2),
+            "tab_width falls back to indent_size"
+        );
         let _ = fs::remove_dir_all(&root);
 
         // Braced markdown pattern.
@@ -307,14 +707,54 @@ mod tests {
         assert!(fs::create_dir_all(&root).is_ok());
         write_text(
             &root.join(".editorconfig"),
-            "[*.{md,markdown}]\ninsert_final_newline = false\n",
+            "[*.{md,markdown}]\ninsert_final_newline = false\nindent_size = 2\ntab_width = 8\n",
         );
         let file = root.join("readme.markdown");
         write_text(&file, "content");
-        assert!(!options_for_path(Some(&file)).insert_final_newline);
+        let options = options_for_path(Some(&file));
+        assert!(!options.insert_final_newline);
+        assert_eq!(options.tab_width, Some(8));
         let _ = fs::remove_dir_all(root);
     }
 
+    #[test]
+    fn options_for_path_skips_revisited_directories() {
+        let root = temp_dir_path("editorconfig-revisit");
+        let nested = root.join("nested");
+        assert!(fs::create_dir_all(&nested).is_ok());
+        write_text(
+            &root.join(".editorconfig"),
+            "[*.md]\ninsert_final_newline = false\n",
+        );
+        write_text(&nested.join(".editorconfig"), "[*.md]\nindent_size = 3\n");
+        // `nested/../nested/../nested` walks through `nested` and `root` twice.
+        let file = nested
+            .join("..")
+            .join("nested")
+            .join("..")
+            .join("nested")
+            .join("note.md");
+        write_text(&file, "# note");
+        let options = options_for_path(Some(&file));
+        assert!(!options.insert_final_newline);
+        assert_eq!(options.indent_size, Some(3));
+
+        #[cfg(unix)]
+        {
+            // A symlink pointing back at its own directory.
+            let link = nested.join("loop");
+            assert!(std::os::unix::fs::symlink(&nested, &link).is_ok());
+            let mut deep = link;
+            for _ in 0..10 {
+                deep.push("loop");
+            }
+            let options = options_for_path(Some(&deep.join("note.md")));
+            assert!(!options.insert_final_newline);
+            assert_eq!(options.indent_size, Some(3));
+        }
+        let _ = fs::remove_dir_all(&root);
+    }
+
     #[test]
     fn glob_match_parameterized() {
         for (label, pattern, input, expected) in [
@@ -388,7 +828,12 @@ mod tests {
         let opts = FormatOptions {
             trim_trailing_whitespace: false,
             insert_final_newline: false,
+            trim_final_newlines: false,
+            max_blank_lines: None,
             end_of_line: Some(EndOfLine::Lf),
+            indent_size: None,
+            tab_width: None,
+            max_line_length: None,
         };
         let cases = [
             // (input, expected, description)
@@ -422,7 +867,12 @@ mod tests {
         let opts_no_nl = FormatOptions {
             trim_trailing_whitespace: true,
             insert_final_newline: false,
+            trim_final_newlines: false,
+            max_blank_lines: None,
             end_of_line: Some(EndOfLine::Lf),
+            indent_size: None,
+            tab_width: None,
+            max_line_length: None,
         };
         assert_eq!(format_markdown("", opts_no_nl), "");
         // Hard break preserved with no final newline.
@@ -431,7 +881,12 @@ mod tests {
         let opts_nl = FormatOptions {
             trim_trailing_whitespace: true,
             insert_final_newline: true,
+            trim_final_newlines: false,
+            max_blank_lines: None,
             end_of_line: Some(EndOfLine::Lf),
+            indent_size: None,
+            tab_width: None,
+            max_line_length: None,
         };
         assert_eq!(format_markdown("", opts_nl), "\n");
 
@@ -439,7 +894,12 @@ mod tests {
         let opts_crlf = FormatOptions {
             trim_trailing_whitespace: false,
             insert_final_newline: true,
+            trim_final_newlines: false,
+            max_blank_lines: None,
             end_of_line: Some(EndOfLine::CrLf),
+            indent_size: None,
+            tab_width: None,
+            max_line_length: None,
         };
         assert_eq!(format_markdown("a\nb", opts_crlf), "a\r\nb\r\n");
 
@@ -447,7 +907,12 @@ mod tests {
         let opts_lf = FormatOptions {
             trim_trailing_whitespace: false,
             insert_final_newline: false,
+            trim_final_newlines: false,
+            max_blank_lines: None,
             end_of_line: Some(EndOfLine::Lf),
+            indent_size: None,
+            tab_width: None,
+            max_line_length: None,
         };
         assert_eq!(
             format_markdown("line1\r\nline2\rline3\n", opts_lf),
@@ -460,7 +925,12 @@ mod tests {
         let opts = FormatOptions {
             trim_trailing_whitespace: true,
             insert_final_newline: true,
+            trim_final_newlines: false,
+            max_blank_lines: None,
             end_of_line: Some(EndOfLine::Lf),
+            indent_size: None,
+            tab_width: None,
+            max_line_length: None,
         };
         for (label, source, expected) in [
             (
@@ -539,7 +1009,12 @@ mod tests {
         let opts = FormatOptions {
             trim_trailing_whitespace: true,
             insert_final_newline: true,
+            trim_final_newlines: false,
+            max_blank_lines: None,
             end_of_line: Some(EndOfLine::Lf),
+            indent_size: None,
+            tab_width: None,
+            max_line_length: None,
         };
         let first = format_markdown(demo, opts);
         let second = format_markdown(&first, opts);
@@ -555,7 +1030,12 @@ mod tests {
         let opts = FormatOptions {
             trim_trailing_whitespace: true,
             insert_final_newline: true,
+            trim_final_newlines: false,
+            max_blank_lines: None,
             end_of_line: Some(EndOfLine::Lf),
+            indent_size: None,
+            tab_width: None,
+            max_line_length: None,
         };
         let first = format_markdown(verif, opts);
         let second = format_markdown(&first, opts);
@@ -565,6 +1045,49 @@ mod tests {
         );
     }
 
+    #[test]
+    fn reformat_tables_aligns_columns_and_keeps_alignment_row() {
+        let src = "Intro\n\n| a | `x\\|y` | c\\| |\n|:-|:-:|--:|\n|1|2|\n\nafter\n";
+        assert_eq!(
+            reformat_tables(src),
+            "Intro\n\n\
+             | a   | `x\\|y` | c\\| |\n\
+             | :-- | :----: | --: |\n\
+             | 1   |   2    |     |\n\nafter\n"
+        );
+
+        let crlf = "h|longer\r\n-|-\r\nvalue|x\r\n";
+        assert_eq!(
+            reformat_tables(crlf),
+            "| h     | longer |\r\n| ----- | ------ |\r\n| value | x      |\r\n"
+        );
+        let formatted = reformat_tables(crlf);
+        assert_eq!(reformat_tables(&formatted), formatted);
+    }
+
+    #[test]
+    fn reformat_tables_leaves_code_and_nested_tables_alone() {
+        let src =
+            "```\n|a|b|\n|-|-|\n```\n\n> |a|b|\n> |-|-|\n\n- |a|b|\n  |-|-|\n\nnot | a table\n";
+        assert_eq!(reformat_tables(src), src);
+        assert_eq!(reformat_tables(""), "");
+    }
+
+    #[test]
+    fn reformat_tables_leaves_rows_wider_than_the_header_alone() {
+        for src in [
+            "|a|b|\n|-|-|\n|1|2|3|\n",
+            "a|b\n-|-\n1|2|3\n",
+            "|a|\n|-|\n|1|2\n",
+        ] {
+            assert_eq!(reformat_tables(src), src);
+        }
+        assert_eq!(
+            reformat_tables("|a|b|\n|-|-|\n|1|\n|1|2|  \n"),
+            "| a   | b   |\n| --- | --- |\n| 1   |     |\n| 1   | 2   |\n"
+        );
+    }
+
     #[test]
     fn diag_format_preserves_tables() {
         let table =
@@ -632,4 +1155,48 @@ mod tests {
             "hard break inside blockquote lost: {result:?}"
         );
     }
+
+    #[test]
+    fn remap_char_index_keeps_line_and_column() {
+        let before = "# Title \n\n\n\n\npara one  x\nlast line ";
+        let options = FormatOptions {
+            max_blank_lines: Some(1),
+            ..DEFAULT_OPTIONS
+        };
+        let after = format_markdown(before, options);
+        assert_eq!(after, "# Title\n\npara one  x\nlast line\n");
+        let char_of =
+            |text: &str, needle: &str| text[..text.find(needle).unwrap_or(0)].chars().count();
+
+        // Same column on an unchanged line that moved up.
+        let old = char_of(before, "one");
+        assert_eq!(
+            remap_char_index(before, &after, old),
+            char_of(&after, "one")
+        );
+        // Past trimmed whitespace: clamped to the new end of the line.
+        assert_eq!(
+            remap_char_index(before, &after, "# Title ".len()),
+            "# Title".len()
+        );
+        assert_eq!(
+            remap_char_index(before, &after, before.chars().count()),
+            char_of(&after, "last line") + "last line".len()
+        );
+        // On a dropped blank line: the line after the blank run.
+        assert_eq!(remap_char_index(before, &after, 11), "# Title\n".len());
+    }
+
+    #[test]
+    fn remap_char_index_handles_crlf_and_unicode() {
+        let before = "é \r\nnaïve text  \r\n";
+        let after = format_markdown(before, DEFAULT_OPTIONS);
+        assert_eq!(after, "é\r\nnaïve text  \r\n");
+        let cursor = "é \r\nnaïve".chars().count();
+        assert_eq!(
+            remap_char_index(before, &after, cursor),
+            "é\r\nnaïve".chars().count()
+        );
+        assert_eq!(remap_char_index(before, &after, 2), 1);
+    }
 }
@@ -0,0 +1,793 @@
+//! rustdown — a native Markdown editor built on eframe/egui.
+//!
+//! [`run`] starts the desktop app.  To host the editor inside another
+//! eframe app, build a [`RustdownApp`] with [`RustdownApp::builder`] and
+//! draw it into any `Ui` with [`RustdownApp::show`].
+
+#[cfg(target_arch = "wasm32")]
+compile_error!("rustdown is a native desktop app; web/wasm builds are not supported.");
+
+use std::{
+    path::{Component, Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use eframe::egui;
+use rustdown_md::{MarkdownCache, MarkdownStyle};
+
+mod app_actions;
+mod app_panels;
+mod app_scroll;
+#[cfg(test)]
+#[allow(clippy::float_cmp)]
+#[allow(clippy::wildcard_imports)]
+mod app_tests;
+mod changes;
+mod cli;
+mod completion;
+mod diagnostics;
+mod disk;
+mod document;
+mod editor;
+mod embed;
+mod fold;
+mod format;
+mod mergetool;
+mod minimap;
+mod nav;
+mod pasted_image;
+mod preferences;
+mod quick_open;
+mod scroll_math;
+mod search;
+mod session;
+mod ui_style;
+
+use disk::sync::DiskSyncState;
+pub(crate) use document::{CursorPosition, Document, DocumentStats, SelectionStats};
+pub(crate) use search::{SearchState, find_match_count};
+use session::ScrollPositions;
+
+pub use embed::RustdownAppBuilder;
+
+const DISK_RELOAD_DEBOUNCE: Duration = Duration::from_millis(75);
+const ZOOM_STEP: f32 = 0.1;
+const MIN_ZOOM_FACTOR: f32 = 0.5;
+const MAX_ZOOM_FACTOR: f32 = 3.0;
+const PANEL_EDGE_PADDING: f32 = 8.0;
+const SCROLL_WHEEL_MULTIPLIER: f32 = 1.15;
+const SIDE_BY_SIDE_SCROLL_LERP: f32 = 0.35;
+const DIAGNOSTICS_DEFAULT_ITERATIONS: usize = 200;
+const DIAGNOSTICS_DEFAULT_RUNS: usize = 1;
+
+use cli::{Command, DiagnosticsFormat, DiagnosticsMode, app_version, parse_launch_options};
+
+/// Run the rustdown desktop app with the process's command-line arguments.
+///
+/// # Errors
+///
+/// Returns an error when the native window can't be created.
+pub fn run() -> eframe::Result {
+    let launch_options = parse_launch_options(std::env::args_os().skip(1));
+    if launch_options.print_version {
+        // On Windows, GUI-subsystem binaries have no console by default.
+        // Attach to the parent console so the output is visible in
+        // PowerShell / cmd.
+        #[cfg(windows)]
+        cli::attach_parent_console();
+
+        println!("{}", app_version());
+        return Ok(());
+    }
+    if let Some(command) = &launch_options.command {
+        #[cfg(windows)]
+        cli::attach_parent_console();
+
+        let code = match command {
+            Command::Diff { old, new } => cli::run_diff(old, new),
+            Command::Outline { path, json } => cli::run_outline(path, *json),
+            Command::Invalid(message) => {
+                eprintln!("{message}");
+                2
+            }
+        };
+        std::process::exit(code);
+    }
+
+    let merge_inputs = match launch_options
+        .merge
+        .as_ref()
+        .map(mergetool::MergeInputs::read)
+        .transpose()
+    {
+        Ok(inputs) => inputs,
+        Err((path, err)) => {
+            #[cfg(windows)]
+            cli::attach_parent_console();
+
+            eprintln!("rustdown: could not read {}: {err}", path.display());
+            std::process::exit(2);
+        }
+    };
+
+    #[cfg(target_os = "linux")]
+    cli::apply_wsl_workarounds();
+
+    let pipeline_diagnostics = match launch_options.diagnostics {
+        DiagnosticsMode::OpenPipeline => {
+            Some(diagnostics::run_open_pipeline_diagnostics as fn(_, _, _) -> _)
+        }
+        DiagnosticsMode::SavePipeline => Some(diagnostics::run_save_pipeline_diagnostics as _),
+        _ => None,
+    };
+    if let Some(run_diagnostics) = pipeline_diagnostics {
+        for run in 0..launch_options.diagnostics_runs {
+            if launch_options.diagnostics_runs > 1
+                && launch_options.diagnostics_format == DiagnosticsFormat::Text
+            {
+                println!(
+                    "diagnostics_run={}/{}",
+                    run + 1,
+                    launch_options.diagnostics_runs
+                );
+            }
+            if let Err(err) = run_diagnostics(
+                launch_options.path.as_deref(),
+                launch_options.diagnostics_iterations,
+                launch_options.diagnostics_format,
+            ) {
+                eprintln!("Diagnostics failed: {err}");
+                break;
+            }
+        }
+        return Ok(());
+    }
+    #[cfg(debug_assertions)]
+    if launch_options.diagnostics == DiagnosticsMode::NavPipeline {
+        if let Err(err) = nav::debug::run_nav_diagnostics(launch_options.path.as_deref()) {
+            eprintln!("Nav diagnostics failed: {err}");
+        }
+        return Ok(());
+    }
+    let explicit_mode = launch_options.mode_explicit.then_some(launch_options.mode);
+    for path in &launch_options.extra_paths {
+        if let Err(err) = cli::spawn_window(path, explicit_mode) {
+            eprintln!("rustdown: could not open {}: {err}", path.display());
+        }
+    }
+    let mut app = RustdownApp::from_launch_options(launch_options);
+    if let Some(inputs) = merge_inputs {
+        app.start_merge_tool(inputs);
+    }
+    let merge_resolved = app
+        .merge_tool
+        .as_ref()
+        .map(|tool| std::sync::Arc::clone(&tool.resolved));
+    let font_settings = app.fonts.clone();
+    let font_size = app.font_size;
+
+    let options = eframe::NativeOptions {
+        viewport: native_viewport(app.window),
+        // The window state lives in our own preferences.
+        persist_window: false,
+        ..Default::default()
+    };
+    let result = eframe::run_native(
+        "rustdown",
+        options,
+        Box::new(move |cc| {
+            egui_extras::install_image_loaders(&cc.egui_ctx);
+            let mut app = app;
+            if let Some(notice) = ui_style::configure_fonts(&cc.egui_ctx, &font_settings) {
+                app.error = Some(notice);
+            }
+            ui_style::configure_style(&cc.egui_ctx);
+            ui_style::apply_font_size(&cc.egui_ctx, font_size);
+            Ok(Box::new(app))
+        }),
+    );
+    if let Some(resolved) = merge_resolved {
+        result?;
+        std::process::exit(mergetool::exit_code(&resolved));
+    }
+    result
+}
+
+/// The main window, stacked, maximized and fullscreen as `window` says.
+/// Sizes are in points, so they scale with the OS DPI factor.
+fn native_viewport(window: preferences::WindowSettings) -> egui::ViewportBuilder {
+    egui::ViewportBuilder::default()
+        .with_inner_size([1024.0, 768.0])
+        .with_min_inner_size([480.0, 320.0])
+        .with_window_level(window_level(window.always_on_top))
+        .with_maximized(window.maximized)
+        .with_fullscreen(window.fullscreen)
+}
+
+const fn window_level(always_on_top: bool) -> egui::WindowLevel {
+    if always_on_top {
+        egui::WindowLevel::AlwaysOnTop
+    } else {
+        egui::WindowLevel::Normal
+    }
+}
+
+/// Files at least this large are opened on a worker thread so the UI
+/// stays responsive while they load.
+const ASYNC_OPEN_MIN_BYTES: u64 = 1024 * 1024;
+
+/// File dialog for Markdown, with plain text as an extra filter, or
+/// unfiltered with `any_file`: a `*` extension filter would hide files
+/// without an extension on GTK.
+#[must_use]
+fn markdown_file_dialog(any_file: bool) -> rfd::FileDialog {
+    let dialog = rfd::FileDialog::new();
+    if any_file {
+        return dialog;
+    }
+    dialog
+        .add_filter("Markdown", &["md", "markdown"])
+        .add_filter("Text", &["txt", "text"])
+}
+
+#[must_use]
+fn is_markdown_path(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("md") || ext.eq_ignore_ascii_case("markdown"))
+}
+
+/// The local Markdown file a preview link points at: a relative path
+/// (resolved against `doc_dir`), an absolute path or a `file://` URL, with
+/// any `#fragment` or `?query` dropped.  Other schemes yield `None`.
+#[must_use]
+fn markdown_link_target(url: &str, doc_dir: Option<&Path>) -> Option<PathBuf> {
+    let link = &url[..url.find(['#', '?']).unwrap_or(url.len())];
+    let decoded = rustdown_md::percent_decode(link);
+    let link = decoded.as_deref().unwrap_or(link);
+    let path = if let Some(rest) = link.strip_prefix("file://") {
+        let rest = rest.strip_prefix("localhost").unwrap_or(rest);
+        // `file:///C:/notes.md` names `C:/notes.md` on Windows.
+        let rest = match rest.as_bytes() {
+            [b'/', drive, b':', ..] if cfg!(windows) && drive.is_ascii_alphabetic() => &rest[1..],
+            _ => rest,
+        };
+        Some(PathBuf::from(rest)).filter(|path| path.is_absolute())?
+    } else if has_url_scheme(link) {
+        return None;
+    } else if Path::new(link).is_absolute() {
+        PathBuf::from(link)
+    } else if link.is_empty() {
+        return None;
+    } else {
+        doc_dir?.join(link)
+    };
+    is_markdown_path(&path).then_some(path)
+}
+
+/// Where the note a wiki link names lives in `dir`: `Name.md`, unless the
+/// name already has a Markdown extension.  `None` for names that would
+/// leave `dir`: absolute ones and those with `..` or `.` parts.
+#[must_use]
+fn wiki_note_path(dir: &Path, name: &str) -> Option<PathBuf> {
+    let inside = Path::new(name)
+        .components()
+        .all(|component| matches!(component, Component::Normal(_)));
+    if !inside {
+        return None;
+    }
+    let path = dir.join(name);
+    Some(if is_markdown_path(&path) {
+        path
+    } else {
+        dir.join(format!("{name}.md"))
+    })
+}
+
+/// Returns `true` if `link` starts with a URL scheme such as `https:`.
+/// Single letters are treated as Windows drive letters, not schemes.
+fn has_url_scheme(link: &str) -> bool {
+    link.split_once(':').is_some_and(|(scheme, _)| {
+        scheme.len() > 1
+            && scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+            && scheme
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+    })
+}
+
+/// The dropped file to open: the first Markdown file, else with
+/// `any_file` the first file of any other kind.
+#[must_use]
+fn first_dropped_path<'a>(
+    paths: impl IntoIterator<Item = &'a Path>,
+    any_file: bool,
+) -> Option<PathBuf> {
+    let mut fallback = None;
+    for path in paths {
+        if is_markdown_path(path) {
+            return Some(path.to_path_buf());
+        }
+        if any_file && fallback.is_none() && !path.is_dir() {
+            fallback = Some(path);
+        }
+    }
+    fallback.map(Path::to_path_buf)
+}
+
+#[must_use]
+pub fn default_image_uri_scheme(path: Option<&Path>) -> String {
+    let Some(parent) = path.and_then(Path::parent) else {
+        return "file://".to_owned();
+    };
+
+    let needs_canonicalize = !parent.is_absolute()
+        || parent
+            .components()
+            .any(|component| matches!(component, Component::CurDir | Component::ParentDir));
+    let base = if needs_canonicalize {
+        parent
+            .canonicalize()
+            .unwrap_or_else(|_| parent.to_path_buf())
+    } else {
+        parent.to_path_buf()
+    };
+    file_url_for_dir(&base.to_string_lossy())
+}
+
+/// Build a `file://` URL (ending in `/`) for the directory `dir`, accepting
+/// Unix, drive-letter (`C:\…`), UNC (`\\server\share`) and verbatim
+/// (`\\?\…`) paths.  Path bytes outside RFC 3986 `pchar` are
+/// percent-encoded.
+#[must_use]
+fn file_url_for_dir(dir: &str) -> String {
+    let slashed = dir.replace('\\', "/");
+    let slashed = slashed
+        .strip_prefix("//?/UNC/")
+        .map(|unc| format!("//{unc}"))
+        .or_else(|| slashed.strip_prefix("//?/").map(str::to_owned))
+        .unwrap_or(slashed);
+
+    let (host, path) = match slashed.strip_prefix("//") {
+        Some(unc) => unc.split_at(unc.find('/').unwrap_or(unc.len())),
+        None => ("", slashed.as_str()),
+    };
+
+    let mut url = String::with_capacity(path.len() + host.len() + 16);
+    url.push_str("file://");
+    url.push_str(host);
+    if !path.starts_with('/') {
+        url.push('/');
+    }
+    url.push_str(&rustdown_md::percent_encode_path(path));
+    if !url.ends_with('/') {
+        url.push('/');
+    }
+    url
+}
+
+#[derive(Default)]
+struct PreviewStyleCache {
+    style: Option<MarkdownStyle>,
+    dark_mode: bool,
+    colored: bool,
+    code_wrap: bool,
+    image_uri: String,
+    image_max_width: rustdown_md::ImageMaxWidth,
+}
+
+/// A snapshot of the editor selection, rendered on its own in a floating
+/// window until it is closed.
+struct SelectionPreview {
+    text: String,
+    cache: MarkdownCache,
+}
+
+/// The editor for one document: its text, view mode, preview, navigation
+/// and disk-sync state.
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Default)]
+pub struct RustdownApp {
+    doc: Document,
+    mode: Mode,
+    search: SearchState,
+    nav: nav::panel::NavState,
+    error: Option<String>,
+    /// System clipboard for rich (HTML) copies, opened on first use and kept
+    /// so X11 can keep serving the copied data.
+    clipboard: Option<arboard::Clipboard>,
+    pending_action: Option<PendingAction>,
+    last_viewport_title: String,
+    focus_search: bool,
+    heading_color_mode: bool,
+    side_by_side_scroll_sync: bool,
+    /// Wrap long code-block lines in the preview instead of scrolling.
+    preview_code_wrap: bool,
+    /// Render single newlines in paragraphs as line breaks, not spaces.
+    preview_preserve_line_breaks: bool,
+    /// Render safe inline HTML in the preview.
+    preview_render_html: bool,
+    /// Tab inserts a tab character rather than spaces in the editor.
+    editor_indent_with_tabs: bool,
+    /// Heading size multipliers shared by the editor and the preview.
+    heading_scales: preferences::HeadingScales,
+    /// Cap the text column at `reading_width_chars` and center it.
+    reading_width: bool,
+    reading_width_chars: u16,
+    /// Where conflict merge files are written and how they are named.
+    merge_sidecar: disk::io::MergeSidecarOptions,
+    /// Default way of detecting external changes; see `DiskSyncState::effective_mode`.
+    disk_sync_mode: disk::sync::DiskSyncMode,
+    /// Ask before applying external changes that merge cleanly.
+    confirm_disk_merges: bool,
+    /// Preview/stats debounce and disk poll intervals.
+    timings: preferences::Timings,
+    /// Ask before saving while conflict markers remain.
+    confirm_save_with_conflicts: bool,
+    /// A save (`true` for Save As) waiting on the conflict-marker prompt.
+    pending_conflict_save: Option<bool>,
+    /// Font overrides from the settings file, applied at startup.
+    fonts: preferences::FontSettings,
+    /// Base body text size in points; zoom multiplies on top of it.
+    font_size: f32,
+    /// Open clicked preview links to local Markdown files in this window.
+    follow_markdown_links: bool,
+    /// Stats appended to the window title, taken from the debounced stats.
+    title_stats: preferences::TitleStats,
+    /// Stylesheet preset for exported and copied HTML.
+    export_theme: preferences::ExportTheme,
+    /// Custom CSS file used instead of `export_theme`.
+    export_css: Option<PathBuf>,
+    /// Which outputs convert quotes and dashes to typographic ones.
+    smart_punctuation: preferences::SmartPunctuation,
+    /// Save file-backed documents once editing pauses for the autosave delay.
+    autosave: bool,
+    /// `edit_seq` of the last autosave that failed, so it isn't retried
+    /// every frame until the text changes again.
+    autosave_failed_seq: Option<u64>,
+    /// Format the document before it is written.
+    format_on_save: bool,
+    /// Open dropped files of any type, not only Markdown.
+    open_any_file: bool,
+    /// Language of the last inserted code block.
+    code_fence_language: String,
+    /// Show the minimap strip beside the editor.
+    minimap: bool,
+    /// Minimap marks for the current document.
+    minimap_marks: minimap::Minimap,
+    /// Widest an image is drawn in the preview.
+    image_max_width: preferences::ImageMaxWidth,
+    /// Front matter handling in HTML exports and plain-text copies.
+    export_front_matter: preferences::FrontMatterExport,
+    /// Window stacking and size state, restored on the next launch.
+    window: preferences::WindowSettings,
+    /// Folder quick open searches; unset searches the document's folder.
+    notes_root: Option<PathBuf>,
+    /// Parse `[[wiki links]]` in the preview.
+    wiki_links: bool,
+    /// Inline code and code block colours from the settings file.
+    code_colors: preferences::CodeColorSettings,
+    /// A note a wiki link named that doesn't exist, waiting on the prompt
+    /// to create it.
+    pending_new_note: Option<PathBuf>,
+    /// An image pasted into a document without a file, waiting on the
+    /// prompt to save it first.
+    pending_image_paste: Option<pasted_image::PastedImage>,
+    /// Text was pasted since the paste keys were last released, so the
+    /// release isn't an image paste.
+    text_pasted: bool,
+    selection_preview: Option<SelectionPreview>,
+    quick_open: quick_open::QuickOpenState,
+    /// Note name and link path completions at the editor cursor.
+    completion: completion::Completion,
+    /// 1-based line from the command line to put the cursor on once the
+    /// document has loaded.
+    pending_line: Option<usize>,
+    /// Set while running as a git mergetool.
+    merge_tool: Option<mergetool::MergeToolSession>,
+    /// Disk revisions of the local images the preview has drawn.
+    image_revisions: disk::images::ImageRevisions,
+    /// Text before the last format, until the editor maps its cursor
+    /// onto the formatted text.
+    format_cursor_base: Option<std::sync::Arc<String>>,
+
+    /// Zoom factor loaded from preferences, applied on first frame.
+    persisted_zoom: f32,
+
+    /// Last editor scroll byte offset observed by the side-by-side sync loop.
+    last_sync_editor_byte: Option<usize>,
+    /// Last preview scroll byte offset observed by the side-by-side sync loop.
+    last_sync_preview_byte: Option<usize>,
+    /// Set to `true` when `resolve_nav_scroll_target` applies a target this
+    /// frame; prevents `sync_side_by_side_scroll` from overriding it.
+    nav_scroll_applied_this_frame: bool,
+    /// Current side-by-side sync source while a follower pane is animating.
+    side_by_side_scroll_source: Option<SideBySideScrollSource>,
+    /// Target scroll Y for the follower pane in `SideBySide` mode.
+    side_by_side_scroll_target: Option<f32>,
+
+    /// Stats for the current editor selection, shown in the status bar.
+    selection_stats: Option<SelectionStats>,
+    /// Line and column of the editor cursor, for the status bar.
+    cursor_position: Option<CursorPosition>,
+
+    /// Remembered scroll positions of recently viewed documents.
+    scroll_positions: ScrollPositions,
+    /// Scroll offset last recorded into `scroll_positions`, so the byte
+    /// offset is only recomputed when the view actually moves.
+    remembered_scroll_y: Option<f32>,
+
+    /// Cached preview style; rebuilt only when theme/colour-mode/URI changes.
+    preview_style_cache: PreviewStyleCache,
+
+    disk: DiskSyncState,
+}
+
+/// Which panes are shown: the editor, the rendered preview, or both.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Mode {
+    #[default]
+    Edit,
+    Preview,
+    SideBySide,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum SideBySideScrollSource {
+    Editor,
+    Preview,
+}
+
+impl Mode {
+    /// Every mode, in toolbar and `Cmd+1/2/3` shortcut order.
+    const ALL: [Self; 3] = [Self::Edit, Self::Preview, Self::SideBySide];
+
+    #[must_use]
+    const fn cycle(self) -> Self {
+        match self {
+            Self::Edit => Self::Preview,
+            Self::Preview => Self::SideBySide,
+            Self::SideBySide => Self::Edit,
+        }
+    }
+
+    #[must_use]
+    const fn icon(self) -> &'static str {
+        match self {
+            Self::Edit => "Ed",
+            Self::Preview => "Pr",
+            Self::SideBySide => "S|S",
+        }
+    }
+
+    /// `Cmd` + this key switches straight to the mode.
+    #[must_use]
+    const fn shortcut_key(self) -> egui::Key {
+        match self {
+            Self::Edit => egui::Key::Num1,
+            Self::Preview => egui::Key::Num2,
+            Self::SideBySide => egui::Key::Num3,
+        }
+    }
+
+    #[must_use]
+    const fn tooltip(self) -> &'static str {
+        match self {
+            Self::Edit => "Edit",
+            Self::Preview => "Preview",
+            Self::SideBySide => "Side-by-Side",
+        }
+    }
+
+    #[must_use]
+    const fn as_str(self) -> &'static str {
+        match self {
+            Self::Edit => "edit",
+            Self::Preview => "preview",
+            Self::SideBySide => "sidebyside",
+        }
+    }
+
+    #[must_use]
+    fn from_str_lossy(s: &str) -> Option<Self> {
+        match s {
+            "edit" => Some(Self::Edit),
+            "preview" => Some(Self::Preview),
+            "sidebyside" => Some(Self::SideBySide),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+enum PendingAction {
+    NewBlank,
+    Open(PathBuf),
+    OpenBundled(BundledDoc),
+}
+
+#[derive(Clone, Copy, Debug)]
+enum BundledDoc {
+    Demo,
+    Verification,
+}
+
+impl BundledDoc {
+    const fn content(self) -> &'static str {
+        match self {
+            Self::Demo => include_str!("bundled/demo.md"),
+            Self::Verification => include_str!("bundled/verification.md"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum SaveTrigger {
+    Save,
+    SaveAs,
+}
+
+#[must_use]
+const fn save_trigger_from_shortcut(
+    command: bool,
+    shift: bool,
+    key_s: bool,
+) -> Option<SaveTrigger> {
+    if !(command && key_s) {
+        return None;
+    }
+    if shift {
+        Some(SaveTrigger::SaveAs)
+    } else {
+        Some(SaveTrigger::Save)
+    }
+}
+
+#[must_use]
+const fn clamped_zoom_factor(zoom_factor: f32) -> f32 {
+    zoom_factor.clamp(MIN_ZOOM_FACTOR, MAX_ZOOM_FACTOR)
+}
+
+#[must_use]
+fn zoom_with_step(current_zoom: f32, delta: f32) -> f32 {
+    clamped_zoom_factor(current_zoom + delta)
+}
+
+#[must_use]
+fn zoom_with_factor(current_zoom: f32, factor: f32) -> f32 {
+    if !factor.is_finite() || factor <= 0.0 {
+        return clamped_zoom_factor(current_zoom);
+    }
+    clamped_zoom_factor(current_zoom * factor)
+}
+
+/// Format `n` with comma thousands separators, e.g. `1,204`.
+fn group_thousands(n: usize) -> String {
+    let digits = n.to_string();
+    let mut out = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            out.push(',');
+        }
+        out.push(digit);
+    }
+    out
+}
+
+/// Status-bar summary of the file on disk, e.g. `saved 2m ago · 14 KB`,
+/// and how long until the age shown next changes.
+fn disk_rev_label(rev: &disk::io::DiskRevision, now: SystemTime) -> (String, Duration) {
+    // A timestamp in the future (clock skew, network shares) reads as new.
+    let elapsed = now.duration_since(rev.modified).unwrap_or_default();
+    let secs = elapsed.as_secs();
+    let (age, unit) = match secs {
+        0..10 => (String::from("just now"), 10),
+        10..60 => (format!("{secs}s ago"), 1),
+        60..3_600 => (format!("{}m ago", secs / 60), 60),
+        3_600..86_400 => (format!("{}h ago", secs / 3_600), 3_600),
+        _ => (format!("{}d ago", secs / 86_400), 86_400),
+    };
+    let next = Duration::from_secs(unit - secs % unit)
+        .saturating_sub(Duration::from_nanos(u64::from(elapsed.subsec_nanos())));
+    (
+        format!("saved {age} · {}", format_size(rev.len)),
+        next.max(Duration::from_millis(100)),
+    )
+}
+
+/// Human-readable file size: bytes, then KB/MB with one decimal below ten.
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 3] = ["KB", "MB", "GB"];
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit + 1 < UNITS.len() {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if size < 10.0 {
+        format!("{size:.1} {}", UNITS[unit])
+    } else {
+        format!("{size:.0} {}", UNITS[unit])
+    }
+}
+
+/// The stats portion of the window title, empty when disabled.
+fn title_stats_suffix(title_stats: preferences::TitleStats, stats: &DocumentStats) -> String {
+    match title_stats {
+        preferences::TitleStats::Off => String::new(),
+        preferences::TitleStats::Words => format!(
+            " - {} word{}",
+            group_thousands(stats.words),
+            if stats.words == 1 { "" } else { "s" }
+        ),
+        preferences::TitleStats::ReadingTime => {
+            format!(" - {} min read", group_thousands(stats.reading_minutes()))
+        }
+    }
+}
+
+impl eframe::App for RustdownApp {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        // Apply persisted zoom on the first frame (needs ctx to be available).
+        if self.persisted_zoom != 0.0 {
+            ctx.set_zoom_factor(clamped_zoom_factor(self.persisted_zoom));
+            self.persisted_zoom = 0.0;
+        }
+        self.go_to_pending_line(ctx);
+        self.tick_disk_sync(ctx);
+        self.refresh_stats_if_due(ctx);
+        self.autosave_if_due(ctx);
+        self.handle_keyboard_shortcuts(ctx);
+        self.show_status_bar(ctx);
+        if self.search.visible {
+            self.show_search_bar(ctx);
+        }
+        self.show_toolbar(ctx);
+        self.show_content_panels(ctx);
+        rustdown_md::show_image_zoom(ctx);
+        self.reload_changed_images(ctx);
+        self.follow_markdown_links(ctx);
+        self.show_dialogs(ctx);
+        self.show_disk_conflict_dialog(ctx);
+        self.show_deleted_file_dialog(ctx);
+        self.show_quick_open(ctx);
+        self.show_new_note_dialog(ctx);
+        self.show_image_paste_dialog(ctx);
+        // Last, so it sits above the prompt whose save it is confirming.
+        self.show_conflict_save_dialog(ctx);
+        self.show_selection_preview(ctx);
+        self.update_viewport_title(ctx);
+        if self.merge_tool.as_ref().is_some_and(|tool| tool.saved) {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+        }
+        self.remember_window_state(ctx);
+        self.remember_scroll_position(ctx);
+    }
+
+    /// Called periodically and on shutdown, so the session survives a crash.
+    fn save(&mut self, _storage: &mut dyn eframe::Storage) {
+        // A mergetool's output is git's scratch file, not one to reopen.
+        if self.merge_tool.is_none()
+            && let Err(err) = self.session_state().save()
+        {
+            eprintln!("rustdown: could not save the session: {err}");
+        }
+    }
+
+    fn persist_egui_memory(&self) -> bool {
+        false
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+enum ConflictChoice {
+    OpenConflictMerge,
+    KeepMineWriteSidecar,
+    KeepBoth,
+    SaveAs,
+    ReloadDisk,
+    OverwriteDisk,
+}
@@ -0,0 +1,107 @@
+//! Running as a git mergetool: `rustdown --merge BASE OURS THEIRS OUTPUT`.
+//!
+//! The three inputs are merged with [`rustdown_core::merge_three_way`] and
+//! the result opened as an unsaved edit of OUTPUT, conflict markers and
+//! all.  Saving a resolved OUTPUT closes the window, and the process exit
+//! code tells git whether conflicts were left in the saved file.  Autosave
+//! and format-on-save are off for the run, so only a deliberate save ends
+//! it.
+
+use std::{
+    io,
+    path::PathBuf,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+};
+
+use rustdown_core::Merge3Outcome;
+
+use crate::cli::MergeToolPaths;
+use crate::disk::io::{DiskRevision, read_stable_utf8};
+use crate::document::conflict_starts_outside_fences;
+
+/// Inputs of a mergetool run, read and merged.
+#[derive(Debug)]
+pub struct MergeInputs {
+    pub output: PathBuf,
+    /// OUTPUT as git left it, and its revision; empty when it is missing.
+    pub output_text: String,
+    pub output_rev: Option<DiskRevision>,
+    /// The three-way merge of the inputs, with conflict markers.
+    pub merged: String,
+}
+
+impl MergeInputs {
+    /// Read the files of `paths` and merge OURS and THEIRS against BASE.
+    ///
+    /// # Errors
+    ///
+    /// Returns the path and error of the first input that can't be read.
+    pub fn read(paths: &MergeToolPaths) -> Result<Self, (PathBuf, io::Error)> {
+        let read = |path: &PathBuf| {
+            read_stable_utf8(path)
+                .map(|(text, _)| text)
+                .map_err(|err| (path.clone(), err))
+        };
+        // git passes an empty BASE when the sides share no ancestor.
+        let base = read(&paths.base)?;
+        let ours = read(&paths.ours)?;
+        let theirs = read(&paths.theirs)?;
+        let (output_text, output_rev) = match read_stable_utf8(&paths.output) {
+            Ok((text, rev)) => (text, Some(rev)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => (String::new(), None),
+            Err(err) => return Err((paths.output.clone(), err)),
+        };
+        let merged = match rustdown_core::merge_three_way(&base, &ours, &theirs) {
+            Merge3Outcome::Clean(merged) => merged,
+            Merge3Outcome::Conflicted {
+                conflict_marked, ..
+            } => conflict_marked,
+        };
+        Ok(Self {
+            output: paths.output.clone(),
+            output_text,
+            output_rev,
+            merged,
+        })
+    }
+}
+
+/// A mergetool run in progress.
+#[derive(Debug)]
+pub struct MergeToolSession {
+    pub output: PathBuf,
+    /// Set once OUTPUT is saved free of conflict markers, when the window
+    /// should close.  A save with markers left keeps it open to finish.
+    pub saved: bool,
+    /// Whether the saved OUTPUT was free of conflict markers; shared with
+    /// [`crate::run`] to pick the exit code after the window closes.
+    pub resolved: Arc<AtomicBool>,
+}
+
+impl MergeToolSession {
+    pub fn new(output: PathBuf) -> Self {
+        Self {
+            output,
+            saved: false,
+            resolved: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Record a save of `text` to OUTPUT; markers shown in fenced code
+    /// blocks don't hold the merge open.
+    pub fn note_saved(&mut self, text: &str) {
+        let resolved = conflict_starts_outside_fences(text).is_empty();
+        self.saved = resolved;
+        self.resolved.store(resolved, Ordering::Relaxed);
+    }
+}
+
+/// Process exit code for a finished mergetool run: 0 when OUTPUT was saved
+/// without conflict markers, 1 otherwise.
+#[must_use]
+pub fn exit_code(resolved: &AtomicBool) -> i32 {
+    i32::from(!resolved.load(Ordering::Relaxed))
+}
@@ -0,0 +1,212 @@
+//! Overview strip beside the editor with ticks for headings, search
+//! matches and conflict blocks, scaled to the whole document.
+//!
+//! Marks are collected once per outline refresh or query change, from the
+//! outline and the search needle; each frame only maps their byte offsets
+//! to rows through the editor's cached row offsets.
+
+use eframe::egui;
+
+use crate::{
+    document::conflict_starts_outside_fences, nav::outline::HeadingEntry, search::Matches,
+};
+
+/// Width of the strip in points.
+pub const MINIMAP_WIDTH: f32 = 14.0;
+
+/// Search matches beyond this many are left off the strip.
+const MAX_MATCH_MARKS: usize = 2_000;
+
+/// Distance in points within which a click snaps to a mark.
+const SNAP_DISTANCE: f32 = 4.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MarkKind {
+    Heading(u8),
+    Match,
+    Conflict,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Mark {
+    pub byte_offset: usize,
+    pub kind: MarkKind,
+}
+
+/// Marks for the current text, cached by outline `edit_seq` and query.
+#[derive(Debug, Default)]
+pub struct Minimap {
+    pub marks: Vec<Mark>,
+    seq: Option<u64>,
+    query: String,
+    conflicts: bool,
+}
+
+impl Minimap {
+    /// Rebuild the marks unless `seq`, `query` and `conflicts` match the
+    /// last call.  Conflict blocks are only searched for when `conflicts`
+    /// says the document has some.
+    pub fn refresh(
+        &mut self,
+        text: &str,
+        seq: u64,
+        headings: &[HeadingEntry],
+        query: &str,
+        conflicts: bool,
+    ) {
+        if self.seq == Some(seq) && self.query == query && self.conflicts == conflicts {
+            return;
+        }
+        self.seq = Some(seq);
+        query.clone_into(&mut self.query);
+        self.conflicts = conflicts;
+
+        self.marks.clear();
+        self.marks.extend(headings.iter().map(|heading| Mark {
+            byte_offset: heading.byte_offset,
+            kind: MarkKind::Heading(heading.level),
+        }));
+        if !query.is_empty() {
+            self.marks.extend(
+                Matches::new(text, query)
+                    .take(MAX_MATCH_MARKS)
+                    .map(|range| Mark {
+                        byte_offset: range.start,
+                        kind: MarkKind::Match,
+                    }),
+            );
+        }
+        if conflicts {
+            self.marks
+                .extend(
+                    conflict_starts_outside_fences(text)
+                        .into_iter()
+                        .map(|byte_offset| Mark {
+                            byte_offset,
+                            kind: MarkKind::Conflict,
+                        }),
+                );
+        }
+    }
+}
+
+/// Paint `marks` into `rect`, with `y_of` giving a mark's content y, and
+/// the `viewport` band (content y range).  Returns the content y to
+/// scroll to when the strip is clicked or dragged: a mark's row when the
+/// pointer is on one, else the clicked spot centred in the viewport.
+pub fn show(
+    ui: &egui::Ui,
+    rect: egui::Rect,
+    marks: &[Mark],
+    y_of: impl Fn(usize) -> f32,
+    content_height: f32,
+    viewport: egui::Rangef,
+) -> Option<f32> {
+    let response = ui
+        .interact(rect, ui.id().with("minimap"), egui::Sense::click_and_drag())
+        .on_hover_text("Click to scroll");
+    let scale = rect.height() / content_height.max(1.0);
+    let to_screen = |y: f32| y.mul_add(scale, rect.top());
+    let visuals = ui.visuals();
+    let strip = ui.painter_at(rect);
+    strip.rect_filled(rect, 0.0, visuals.faint_bg_color);
+    strip.rect_filled(
+        egui::Rect::from_x_y_ranges(
+            rect.x_range(),
+            to_screen(viewport.min)..=to_screen(viewport.max).max(to_screen(viewport.min) + 2.0),
+        ),
+        0.0,
+        visuals.widgets.inactive.weak_bg_fill,
+    );
+
+    let mut nearest: Option<(f32, f32)> = None;
+    let pointer = response.interact_pointer_pos().map(|pos| pos.y);
+    for mark in marks {
+        let content_y = y_of(mark.byte_offset);
+        let y = to_screen(content_y);
+        let (inset, height, color) = match mark.kind {
+            MarkKind::Heading(level) => (
+                rect.width() * 0.15 * f32::from(level.clamp(1, 6) - 1) / 5.0,
+                1.0,
+                visuals.text_color(),
+            ),
+            MarkKind::Match => (0.0, 2.0, visuals.selection.bg_fill),
+            MarkKind::Conflict => (0.0, 3.0, visuals.error_fg_color),
+        };
+        strip.rect_filled(
+            egui::Rect::from_x_y_ranges(rect.left() + inset..=rect.right(), y..=y + height),
+            0.0,
+            color,
+        );
+        if let Some(pointer) = pointer {
+            let distance = (pointer - y).abs();
+            if distance <= SNAP_DISTANCE && nearest.is_none_or(|(best, _)| distance < best) {
+                nearest = Some((distance, content_y));
+            }
+        }
+    }
+
+    let pointer = pointer?;
+    if !(response.clicked() || response.dragged()) {
+        return None;
+    }
+    Some(nearest.map_or_else(
+        || ((pointer - rect.top()) / scale - viewport.span() / 2.0).max(0.0),
+        |(_, y)| y,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nav::outline::extract_headings;
+
+    fn kinds(minimap: &Minimap) -> Vec<(usize, MarkKind)> {
+        minimap
+            .marks
+            .iter()
+            .map(|mark| (mark.byte_offset, mark.kind))
+            .collect()
+    }
+
+    #[test]
+    fn refresh_collects_headings_matches_and_conflicts() {
+        let text = "# A\nfoo\n## B\nfoo\n<<<<<<< ours\nx\n=======\ny\n>>>>>>> theirs\n";
+        let headings = extract_headings(text);
+        let mut minimap = Minimap::default();
+        minimap.refresh(text, 1, &headings, "foo", true);
+        assert_eq!(
+            kinds(&minimap),
+            [
+                (0, MarkKind::Heading(1)),
+                (8, MarkKind::Heading(2)),
+                // The `=======` line underlines the paragraph above it.
+                (13, MarkKind::Heading(1)),
+                (4, MarkKind::Match),
+                (13, MarkKind::Match),
+                (17, MarkKind::Conflict),
+            ]
+        );
+
+        minimap.refresh(text, 1, &headings, "", false);
+        assert_eq!(
+            kinds(&minimap),
+            [
+                (0, MarkKind::Heading(1)),
+                (8, MarkKind::Heading(2)),
+                (13, MarkKind::Heading(1)),
+            ]
+        );
+    }
+
+    #[test]
+    fn refresh_is_cached_by_seq_and_query() {
+        let mut minimap = Minimap::default();
+        minimap.refresh("# A\n", 1, &extract_headings("# A\n"), "", false);
+        // Same key: the stale text is not rescanned.
+        minimap.refresh("no headings\n", 1, &[], "", false);
+        assert_eq!(minimap.marks.len(), 1);
+        minimap.refresh("no headings\n", 2, &[], "", false);
+        assert!(minimap.marks.is_empty());
+    }
+}
@@ -9,8 +9,8 @@ use eframe::egui;
 use rustdown_md::MarkdownCache;
 
 use crate::{
-    Document, DocumentStats, Mode, RustdownApp, default_image_uri_scheme,
-    disk::io::read_stable_utf8, nav::panel::NavScrollTarget,
+    Document, DocumentStats, Mode, RustdownApp, changes::LineChanges, default_image_uri_scheme,
+    disk::io::read_stable_utf8, fold::Folding, nav::panel::NavScrollTarget,
 };
 
 /// Render one simulated frame using the same layout as the real app.
@@ -42,7 +42,11 @@ pub fn run_nav_diagnostics(path: Option<&Path>) -> io::Result<()> {
     let base_text = text.clone();
     let stats = DocumentStats::from_text(text.as_str());
     let ctx = egui::Context::default();
-    crate::ui_style::configure_fonts(&ctx).map_err(io::Error::other)?;
+    if let Some(notice) =
+        crate::ui_style::configure_fonts(&ctx, &crate::preferences::FontSettings::default())
+    {
+        eprintln!("{notice}");
+    }
     crate::ui_style::configure_style(&ctx);
     // Warm up: egui needs one frame for fonts.
     let _ = ctx.run(egui::RawInput::default(), |_ctx| {});
@@ -61,6 +65,14 @@ pub fn run_nav_diagnostics(path: Option<&Path>) -> io::Result<()> {
         last_edit_at: None,
         edit_seq: 1,
         editor_galley_cache: None,
+        wrap_column: None,
+        folding: Folding::default(),
+        line_changes: LineChanges::default(),
+        indent_size: crate::editor::DEFAULT_INDENT_SIZE,
+        tab_width: crate::editor::DEFAULT_INDENT_SIZE,
+        stats_job: None,
+        detected_title: None,
+        unresolved_conflicts: None,
     };
 
     let mut app = RustdownApp {
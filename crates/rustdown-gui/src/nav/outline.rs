@@ -14,6 +14,8 @@ pub struct HeadingEntry {
     pub level: u8,
     /// Byte offset of the heading start in the source text.
     pub byte_offset: usize,
+    /// Byte offset just past the heading markup (after a setext underline).
+    pub end_offset: usize,
     /// Start of the plain-text label within the source.
     label_start: u32,
     /// Length of the plain-text label in bytes.
@@ -71,6 +73,7 @@ pub fn extract_headings(source: &str) -> Vec<HeadingEntry> {
                 label_buf.clear();
             }
             Event::End(TagEnd::Heading(_)) => {
+                let end_offset = range.end;
                 if let Some((level, byte_offset)) = in_heading.take()
                     && label_has_content
                 {
@@ -81,6 +84,7 @@ pub fn extract_headings(source: &str) -> Vec<HeadingEntry> {
                             entries.push(HeadingEntry {
                                 level,
                                 byte_offset,
+                                end_offset,
                                 label_start: 0,
                                 label_len: 0,
                                 label_owned: Some(trimmed.into()),
@@ -96,6 +100,7 @@ pub fn extract_headings(source: &str) -> Vec<HeadingEntry> {
                             entries.push(HeadingEntry {
                                 level,
                                 byte_offset,
+                                end_offset,
                                 label_start: trim_off as u32,
                                 label_len: trim_len.min(u16::MAX as usize) as u16,
                                 label_owned: None,
@@ -126,14 +131,12 @@ pub fn extract_headings(source: &str) -> Vec<HeadingEntry> {
                     label_buf.push('`');
                 }
             }
-            Event::Start(Tag::Link { .. }) if in_heading.is_some() => {
-                if !has_link {
-                    // Retroactively fill label_buf with text collected so far.
-                    has_link = true;
-                    if label_has_content {
-                        let prior = source.get(label_start..label_end).unwrap_or("");
-                        label_buf.push_str(prior);
-                    }
+            Event::Start(Tag::Link { .. }) if in_heading.is_some() && !has_link => {
+                // Retroactively fill label_buf with text collected so far.
+                has_link = true;
+                if label_has_content {
+                    let prior = source.get(label_start..label_end).unwrap_or("");
+                    label_buf.push_str(prior);
                 }
             }
             _ => {}
@@ -169,6 +172,28 @@ pub fn active_heading_index(
     (0..=upper).rev().find(|&i| entries[i].level <= max_depth)
 }
 
+/// Indices of the headings enclosing `position`, outermost first: the
+/// active heading and each earlier heading of a lower level above it.
+pub fn heading_path(entries: &[HeadingEntry], position: usize) -> Vec<usize> {
+    let mut path = Vec::new();
+    let Some(active) = active_heading_index(entries, 6, position) else {
+        return path;
+    };
+    path.push(active);
+    let mut level = entries[active].level;
+    for i in (0..active).rev() {
+        if entries[i].level < level {
+            level = entries[i].level;
+            path.push(i);
+            if level == 1 {
+                break;
+            }
+        }
+    }
+    path.reverse();
+    path
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -242,6 +267,31 @@ mod tests {
         assert!(std::mem::size_of::<HeadingEntry>() <= 40);
     }
 
+    #[test]
+    fn heading_path_lists_enclosing_sections() {
+        let md = "# Intro\n\n## Setup\n\n### Linux\n\ntext\n\n## Usage\n\n#### Deep\n";
+        let headings = extract_headings(md);
+        let labels = |position| {
+            heading_path(&headings, position)
+                .into_iter()
+                .map(|i| headings[i].label(md))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(labels(0), ["Intro"]);
+        assert_eq!(
+            labels(md.find("text").unwrap_or_default()),
+            ["Intro", "Setup", "Linux"]
+        );
+        assert_eq!(
+            labels(md.find("## Usage").unwrap_or_default()),
+            ["Intro", "Usage"]
+        );
+        assert_eq!(labels(md.len()), ["Intro", "Usage", "Deep"]);
+        assert!(heading_path(&extract_headings("no headings\n"), 5).is_empty());
+        let before = extract_headings("text\n\n# Later\n");
+        assert!(heading_path(&before, 0).is_empty());
+    }
+
     #[test]
     fn active_heading_index_covers_all_boundary_cases() {
         // At start.
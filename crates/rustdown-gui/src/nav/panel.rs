@@ -2,7 +2,6 @@ use std::sync::Arc;
 
 use eframe::egui;
 
-use crate::highlight;
 use crate::nav::outline::{self, HeadingEntry};
 
 /// What the nav panel wants the host to scroll to.
@@ -56,6 +55,12 @@ pub struct NavState {
     /// The `outline_seq` and `max_depth` when `cached_min_level` was computed.
     min_level_seq: u64,
     min_level_depth: u8,
+    /// Cursor char index and `outline_seq` the breadcrumb was looked up for.
+    breadcrumb_key: (usize, u64),
+    /// Innermost heading of the breadcrumb, with the `outline_seq` it
+    /// indexes, so the label is only rebuilt when the section changes.
+    breadcrumb_heading: (Option<usize>, u64),
+    breadcrumb: String,
 }
 
 impl Default for NavState {
@@ -81,6 +86,9 @@ impl Default for NavState {
             cached_min_level: 1,
             min_level_seq: u64::MAX,
             min_level_depth: 0,
+            breadcrumb_key: (usize::MAX, u64::MAX),
+            breadcrumb_heading: (None, u64::MAX),
+            breadcrumb: String::new(),
         }
     }
 }
@@ -96,7 +104,12 @@ impl NavState {
         if edit_seq == self.outline_seq {
             return;
         }
-        self.outline = outline::extract_headings(source.as_str());
+        self.set_outline(source, edit_seq, outline::extract_headings(source.as_str()));
+    }
+
+    /// Use `outline`, the headings of `source` at `edit_seq`, as the outline.
+    pub fn set_outline(&mut self, source: &Arc<String>, edit_seq: u64, outline: Vec<HeadingEntry>) {
+        self.outline = outline;
         self.outline_source = Arc::clone(source);
         self.outline_seq = edit_seq;
         self.expanded.clear();
@@ -115,6 +128,11 @@ impl NavState {
         }
     }
 
+    /// `edit_seq` the outline was last extracted for.
+    pub const fn outline_seq(&self) -> u64 {
+        self.outline_seq
+    }
+
     /// Force the next `refresh_outline` call to re-extract headings.
     pub const fn invalidate_outline(&mut self) {
         self.outline_seq = u64::MAX;
@@ -126,6 +144,34 @@ impl NavState {
             outline::active_heading_index(&self.outline, self.max_depth, byte_position);
     }
 
+    /// Heading path of the section containing the char index `cursor`, e.g.
+    /// `Intro › Setup › Linux`; empty before the first heading.  The label
+    /// is rebuilt only when the cursor moves into another section.
+    pub fn breadcrumb(&mut self, cursor: usize) -> &str {
+        if self.breadcrumb_key == (cursor, self.outline_seq) {
+            return &self.breadcrumb;
+        }
+        self.breadcrumb_key = (cursor, self.outline_seq);
+        let source = self.outline_source.as_str();
+        let position = crate::editor::char_index_to_byte(source, cursor);
+        let active = outline::active_heading_index(&self.outline, 6, position);
+        if self.breadcrumb_heading == (active, self.outline_seq) {
+            return &self.breadcrumb;
+        }
+        self.breadcrumb_heading = (active, self.outline_seq);
+        self.breadcrumb.clear();
+        for (n, i) in outline::heading_path(&self.outline, position)
+            .into_iter()
+            .enumerate()
+        {
+            if n > 0 {
+                self.breadcrumb.push_str(" › ");
+            }
+            self.breadcrumb.push_str(self.outline[i].label(source));
+        }
+        &self.breadcrumb
+    }
+
     /// Decrease `max_depth` by one (clamped to 1).
     pub fn decrease_depth(&mut self) {
         self.max_depth = self.max_depth.saturating_sub(1).max(1);
@@ -452,7 +498,7 @@ fn render_heading_row(
                 }
                 if heading_color_mode {
                     let color =
-                        highlight::heading_color(ui.visuals(), heading.level as usize, true);
+                        rustdown_md::heading_color(ui.visuals(), heading.level as usize, true);
                     text = text.color(color);
                 }
 
@@ -0,0 +1,105 @@
+//! Pasting an image from the clipboard: it is saved as a PNG beside the
+//! document, named after it (`notes-image-1.png`, `notes-image-2.png`, …),
+//! and linked from the cursor.
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// An image taken from the clipboard, as 8-bit RGBA rows.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PastedImage {
+    pub width: usize,
+    pub height: usize,
+    pub rgba: Vec<u8>,
+}
+
+impl From<arboard::ImageData<'_>> for PastedImage {
+    fn from(image: arboard::ImageData<'_>) -> Self {
+        Self {
+            width: image.width,
+            height: image.height,
+            rgba: image.bytes.into_owned(),
+        }
+    }
+}
+
+/// `image` encoded as a PNG file.
+///
+/// # Errors
+///
+/// Fails when the pixels don't fill the image's size or can't be encoded.
+pub fn encode_png(image: &PastedImage) -> io::Result<Vec<u8>> {
+    let size = |n: usize| u32::try_from(n).map_err(io::Error::other);
+    let pixels =
+        image::RgbaImage::from_raw(size(image.width)?, size(image.height)?, image.rgba.clone())
+            .ok_or_else(|| {
+                io::Error::new(io::ErrorKind::InvalidData, "clipboard image is truncated")
+            })?;
+    let mut png = io::Cursor::new(Vec::new());
+    pixels
+        .write_to(&mut png, image::ImageFormat::Png)
+        .map_err(io::Error::other)?;
+    Ok(png.into_inner())
+}
+
+/// The first `<stem>-image-<n>.png` beside `doc_path` that doesn't exist.
+pub fn next_image_path(doc_path: &Path) -> PathBuf {
+    let dir = doc_path.parent().unwrap_or_else(|| Path::new(""));
+    let stem = doc_path
+        .file_stem()
+        .map_or_else(|| "pasted".into(), |stem| stem.to_string_lossy());
+    (1..=9_999)
+        .map(|n| dir.join(format!("{stem}-image-{n}.png")))
+        .find(|path| !path.exists())
+        .unwrap_or_else(|| dir.join(format!("{stem}-image.png")))
+}
+
+/// Save `image` as the next free PNG beside `doc_path`, returning the
+/// Markdown that embeds it from the document.
+///
+/// # Errors
+///
+/// Returns the error encoding or writing the file.
+pub fn save_beside(doc_path: &Path, image: &PastedImage) -> io::Result<String> {
+    let path = next_image_path(doc_path);
+    fs::write(&path, encode_png(image)?)?;
+    let name = path
+        .file_name()
+        .map(|name| rustdown_md::percent_encode_path(&name.to_string_lossy()))
+        .unwrap_or_default();
+    Ok(format!("![]({name})"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn images_are_saved_as_numbered_pngs_beside_the_document() {
+        let dir = std::env::temp_dir().join(format!("rustdown-paste-{}", std::process::id()));
+        let _ = fs::create_dir_all(&dir);
+        let doc = dir.join("my notes.md");
+        let image = PastedImage {
+            width: 2,
+            height: 1,
+            rgba: vec![255, 0, 0, 255, 0, 0, 255, 255],
+        };
+
+        assert_eq!(
+            save_beside(&doc, &image).ok().as_deref(),
+            Some("![](my%20notes-image-1.png)")
+        );
+        assert_eq!(next_image_path(&doc), dir.join("my notes-image-2.png"));
+        let png = fs::read(dir.join("my notes-image-1.png")).unwrap_or_default();
+        assert!(png.starts_with(b"\x89PNG"));
+
+        let truncated = PastedImage {
+            rgba: vec![0; 3],
+            ..image
+        };
+        assert!(encode_png(&truncated).is_err());
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
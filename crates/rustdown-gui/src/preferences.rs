@@ -1,11 +1,285 @@
-use std::{fs, path::PathBuf};
+use std::{fs, path::PathBuf, time::Duration};
 
+use eframe::egui;
 use serde::{Deserialize, Serialize};
 
+use crate::disk::sync::DiskSyncMode;
+
 /// Minimum heading count before nav is auto-shown in Preview/SideBySide.
 pub const AUTO_NAV_MIN_HEADINGS: usize = 5;
 
+/// Default reading column width, in characters.
+pub const DEFAULT_READING_WIDTH_CHARS: u16 = 80;
+
+/// Accepted range for a configured heading font scale.
+const HEADING_SCALE_RANGE: std::ops::RangeInclusive<f32> = 0.5..=4.0;
+
+/// H1–H6 font-size multipliers, shared by the editor highlighter and the
+/// preview so headings look the same in every mode.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct HeadingScales(pub [f32; 6]);
+
+impl Default for HeadingScales {
+    fn default() -> Self {
+        Self(rustdown_md::HEADING_FONT_SCALES)
+    }
+}
+
+impl HeadingScales {
+    /// Replace non-finite or out-of-range entries with the default scale
+    /// for that level, so a bad config value can't break layout.
+    #[must_use]
+    pub fn sanitized(self) -> Self {
+        let defaults = rustdown_md::HEADING_FONT_SCALES;
+        Self(std::array::from_fn(|i| {
+            let scale = self.0[i];
+            if HEADING_SCALE_RANGE.contains(&scale) {
+                scale
+            } else {
+                defaults[i]
+            }
+        }))
+    }
+}
+
+/// Longest accepted preview or stats debounce.
+const MAX_DEBOUNCE_MS: u64 = 5_000;
+/// Accepted range for the disk poll interval.
+const DISK_POLL_RANGE_MS: std::ops::RangeInclusive<u64> = 50..=60_000;
+/// Accepted range for the autosave delay.
+const AUTOSAVE_DELAY_RANGE_MS: std::ops::RangeInclusive<u64> = 500..=600_000;
+
+/// Debounce and polling intervals, in milliseconds.
+#[allow(clippy::struct_field_names)] // the unit suffix is part of the settings key
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Timings {
+    /// Side-by-side preview re-render delay after an edit.
+    pub preview_debounce_ms: u64,
+    /// Word/line count recompute delay after an edit.
+    pub stats_debounce_ms: u64,
+    /// How often the file is checked when it isn't being watched.
+    pub disk_poll_interval_ms: u64,
+    /// Pause in editing before an autosave.
+    pub autosave_delay_ms: u64,
+}
+
+impl Default for Timings {
+    fn default() -> Self {
+        Self {
+            preview_debounce_ms: 150,
+            stats_debounce_ms: 120,
+            disk_poll_interval_ms: 250,
+            autosave_delay_ms: 2_000,
+        }
+    }
+}
+
+impl Timings {
+    #[must_use]
+    pub fn preview_debounce(self) -> Duration {
+        Duration::from_millis(self.preview_debounce_ms.min(MAX_DEBOUNCE_MS))
+    }
+
+    #[must_use]
+    pub fn stats_debounce(self) -> Duration {
+        Duration::from_millis(self.stats_debounce_ms.min(MAX_DEBOUNCE_MS))
+    }
+
+    #[must_use]
+    pub fn disk_poll_interval(self) -> Duration {
+        Duration::from_millis(
+            self.disk_poll_interval_ms
+                .clamp(*DISK_POLL_RANGE_MS.start(), *DISK_POLL_RANGE_MS.end()),
+        )
+    }
+
+    #[must_use]
+    pub fn autosave_delay(self) -> Duration {
+        Duration::from_millis(self.autosave_delay_ms.clamp(
+            *AUTOSAVE_DELAY_RANGE_MS.start(),
+            *AUTOSAVE_DELAY_RANGE_MS.end(),
+        ))
+    }
+}
+
+/// How the main window is placed, restored on the next launch.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WindowSettings {
+    /// Keep the window above other windows.
+    pub always_on_top: bool,
+    pub maximized: bool,
+    pub fullscreen: bool,
+}
+
+/// Fonts chosen in the settings file.  Each is a font file path or a family
+/// name looked up in the system font directories.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FontSettings {
+    /// Font for the interface, editor prose and preview body text.
+    pub ui: Option<String>,
+    /// Font for the editor's code and preview code spans and blocks.
+    pub monospace: Option<String>,
+}
+
+/// Inline code and code block colours for the editor and preview, each a
+/// `"#rrggbb"` or `"#rrggbbaa"` hex string.  Unset or invalid ones keep
+/// the theme's default.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CodeColorSettings {
+    pub inline_background: Option<String>,
+    pub inline_text: Option<String>,
+    pub block_background: Option<String>,
+    pub block_text: Option<String>,
+}
+
+impl CodeColorSettings {
+    /// The configured colours that parse.
+    #[must_use]
+    pub fn resolve(&self) -> rustdown_md::CodeColors {
+        let parse = |hex: &Option<String>| {
+            hex.as_deref()
+                .and_then(|hex| egui::Color32::from_hex(hex.trim()).ok())
+        };
+        rustdown_md::CodeColors {
+            inline_bg: parse(&self.inline_background),
+            inline_text: parse(&self.inline_text),
+            block_bg: parse(&self.block_background),
+            block_text: parse(&self.block_text),
+        }
+    }
+}
+
+/// Where straight quotes, `--` and `...` become typographic punctuation.
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SmartPunctuation {
+    pub preview: bool,
+    /// Exported and copied HTML.
+    pub html: bool,
+    /// Plain-text copies.
+    pub plain_text: bool,
+}
+
+/// Document stats appended to the window title.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TitleStats {
+    #[default]
+    Off,
+    Words,
+    ReadingTime,
+}
+
+impl TitleStats {
+    #[must_use]
+    pub const fn label(self) -> &'static str {
+        match self {
+            Self::Off => "Nothing",
+            Self::Words => "Word count",
+            Self::ReadingTime => "Reading time",
+        }
+    }
+}
+
+/// Built-in stylesheet for exported and copied HTML documents.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportTheme {
+    #[default]
+    Light,
+    Dark,
+    Github,
+}
+
+impl ExportTheme {
+    #[must_use]
+    pub const fn label(self) -> &'static str {
+        match self {
+            Self::Light => "Light",
+            Self::Dark => "Dark",
+            Self::Github => "GitHub",
+        }
+    }
+
+    #[must_use]
+    pub const fn html_theme(self) -> rustdown_md::HtmlTheme {
+        match self {
+            Self::Light => rustdown_md::HtmlTheme::Light,
+            Self::Dark => rustdown_md::HtmlTheme::Dark,
+            Self::Github => rustdown_md::HtmlTheme::Github,
+        }
+    }
+}
+
+/// What HTML exports and plain-text copies do with front matter.  The
+/// editor always keeps it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FrontMatterExport {
+    #[default]
+    Strip,
+    /// Standalone HTML gets its fields as `<meta>` tags and its title.
+    Metadata,
+    Keep,
+}
+
+impl FrontMatterExport {
+    pub const ALL: [Self; 3] = [Self::Strip, Self::Metadata, Self::Keep];
+
+    #[must_use]
+    pub const fn label(self) -> &'static str {
+        match self {
+            Self::Strip => "Leave out",
+            Self::Metadata => "Use as metadata",
+            Self::Keep => "Keep as text",
+        }
+    }
+
+    #[must_use]
+    pub const fn front_matter(self) -> rustdown_md::FrontMatter {
+        match self {
+            Self::Strip => rustdown_md::FrontMatter::Strip,
+            Self::Metadata => rustdown_md::FrontMatter::Metadata,
+            Self::Keep => rustdown_md::FrontMatter::Keep,
+        }
+    }
+}
+
+/// Widest an image is drawn in the preview, written as
+/// `image_max_width = { fraction = 0.8 }` or `{ points = 600.0 }`.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImageMaxWidth {
+    /// Share of the preview column, from 0.1 to 1.0.
+    Fraction(f32),
+    /// Width in points, at most the preview column.
+    Points(f32),
+}
+
+impl Default for ImageMaxWidth {
+    fn default() -> Self {
+        Self::Fraction(1.0)
+    }
+}
+
+impl ImageMaxWidth {
+    #[must_use]
+    pub const fn preview(self) -> rustdown_md::ImageMaxWidth {
+        match self {
+            Self::Fraction(f) => rustdown_md::ImageMaxWidth::Fraction(f),
+            Self::Points(p) => rustdown_md::ImageMaxWidth::Points(p),
+        }
+    }
+}
+
 /// User preferences persisted between sessions.
+#[allow(clippy::struct_excessive_bools)]
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(default)]
 pub struct UserPreferences {
@@ -14,6 +288,72 @@ pub struct UserPreferences {
     pub side_by_side_scroll_sync: bool,
     pub zoom_factor: f32,
     pub mode: String,
+    /// Wrap long code-block lines in the preview instead of scrolling.
+    pub preview_code_wrap: bool,
+    /// Keep single newlines in paragraphs as line breaks in the preview.
+    pub preview_preserve_line_breaks: bool,
+    /// Render safe inline HTML tags in the preview instead of showing them raw.
+    pub preview_render_html: bool,
+    /// Make Tab insert a tab character instead of spaces in the editor.
+    pub editor_indent_with_tabs: bool,
+    /// Heading font-size multipliers for H1–H6.
+    pub heading_scales: HeadingScales,
+    /// Cap the editor and preview text column at `reading_width_chars`.
+    pub reading_width: bool,
+    /// Reading column width, in average characters of the body font.
+    pub reading_width_chars: u16,
+    /// Directory for conflict merge files; unset writes next to the document.
+    pub merge_sidecar_dir: Option<PathBuf>,
+    /// Merge file name template using `{stem}`, `{n}` and `{ext}`.
+    pub merge_sidecar_template: Option<String>,
+    /// How external changes to the open document are detected.
+    pub disk_sync_mode: DiskSyncMode,
+    /// Ask what to do about every external change to a modified document,
+    /// instead of merging it in when it doesn't conflict.
+    pub confirm_disk_merges: bool,
+    /// Debounce and polling intervals.
+    pub timings: Timings,
+    /// Ask before saving a document that still has conflict markers.
+    pub confirm_save_with_conflicts: bool,
+    /// UI and monospace font overrides.
+    pub fonts: FontSettings,
+    /// Body text size in points, before zoom.
+    pub font_size: f32,
+    /// Word count or reading time shown in the window title.
+    pub title_stats: TitleStats,
+    /// Open preview links to local Markdown files in rustdown instead of
+    /// the system handler.
+    pub follow_markdown_links: bool,
+    /// Stylesheet preset embedded in exported and copied HTML.
+    pub export_theme: ExportTheme,
+    /// CSS file embedded instead of the preset, when set.
+    pub export_css: Option<PathBuf>,
+    /// Typographic quotes and dashes, per output.
+    pub smart_punctuation: SmartPunctuation,
+    /// Save file-backed documents once editing pauses.
+    pub autosave: bool,
+    /// Run the formatter on the document before every save but autosaves.
+    pub format_on_save: bool,
+    /// Open files of any type as Markdown, from the Open dialog or dropped,
+    /// not only Markdown and text files.
+    pub open_any_file: bool,
+    /// Info string of the last inserted code block, reused for the next.
+    pub code_fence_language: String,
+    /// Show the minimap strip beside the editor.
+    pub minimap: bool,
+    /// Widest an image is drawn in the preview.
+    pub image_max_width: ImageMaxWidth,
+    /// Front matter in HTML exports and plain-text copies.
+    pub export_front_matter: FrontMatterExport,
+    /// Window stacking and size state.
+    pub window: WindowSettings,
+    /// Folder of notes searched by quick open; unset uses the open
+    /// document's folder.
+    pub notes_root: Option<PathBuf>,
+    /// Parse `[[Other Note]]` as a link to `Other Note.md` in the preview.
+    pub wiki_links: bool,
+    /// Inline code and code block colours.
+    pub code_colors: CodeColorSettings,
 }
 
 impl Default for UserPreferences {
@@ -24,6 +364,37 @@ impl Default for UserPreferences {
             side_by_side_scroll_sync: true,
             zoom_factor: 1.0,
             mode: String::new(),
+            preview_code_wrap: false,
+            preview_preserve_line_breaks: false,
+            preview_render_html: false,
+            editor_indent_with_tabs: false,
+            heading_scales: HeadingScales::default(),
+            reading_width: false,
+            reading_width_chars: DEFAULT_READING_WIDTH_CHARS,
+            merge_sidecar_dir: None,
+            merge_sidecar_template: None,
+            disk_sync_mode: DiskSyncMode::Watch,
+            confirm_disk_merges: false,
+            timings: Timings::default(),
+            confirm_save_with_conflicts: true,
+            fonts: FontSettings::default(),
+            font_size: crate::ui_style::DEFAULT_BODY_BUTTON_FONT_SIZE,
+            title_stats: TitleStats::Off,
+            follow_markdown_links: true,
+            export_theme: ExportTheme::Light,
+            export_css: None,
+            smart_punctuation: SmartPunctuation::default(),
+            autosave: false,
+            format_on_save: false,
+            open_any_file: false,
+            code_fence_language: String::new(),
+            minimap: false,
+            image_max_width: ImageMaxWidth::default(),
+            export_front_matter: FrontMatterExport::Strip,
+            window: WindowSettings::default(),
+            notes_root: None,
+            wiki_links: true,
+            code_colors: CodeColorSettings::default(),
         }
     }
 }
@@ -71,12 +442,34 @@ fn config_file_path() -> Option<PathBuf> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn code_colors_parse_hex_and_skip_invalid_ones() {
+        let settings = CodeColorSettings {
+            inline_background: Some(" #ffeeaa ".to_owned()),
+            inline_text: Some("tomato".to_owned()),
+            block_background: Some("#00000080".to_owned()),
+            block_text: None,
+        };
+        let colors = settings.resolve();
+        assert_eq!(
+            colors.inline_bg,
+            Some(egui::Color32::from_rgb(0xff, 0xee, 0xaa))
+        );
+        assert_eq!(colors.inline_text, None);
+        assert_eq!(
+            colors.block_bg,
+            Some(egui::Color32::from_rgba_unmultiplied(0, 0, 0, 0x80))
+        );
+        assert_eq!(colors.block_text, None);
+    }
+
     #[test]
     fn default_preferences_are_sensible() {
         let prefs = UserPreferences::default();
         assert!(!prefs.nav_visible);
         assert!(prefs.heading_color_mode);
         assert!(prefs.side_by_side_scroll_sync);
+        assert!(!prefs.preview_code_wrap);
     }
 
     #[test]
@@ -87,13 +480,111 @@ mod tests {
             side_by_side_scroll_sync: false,
             zoom_factor: 1.5,
             mode: "preview".to_owned(),
+            preview_code_wrap: true,
+            preview_preserve_line_breaks: true,
+            preview_render_html: true,
+            editor_indent_with_tabs: true,
+            heading_scales: HeadingScales([2.5, 1.8, 1.4, 1.2, 1.1, 1.0]),
+            reading_width: true,
+            reading_width_chars: 72,
+            merge_sidecar_dir: None,
+            merge_sidecar_template: None,
+            disk_sync_mode: DiskSyncMode::Watch,
+            confirm_disk_merges: true,
+            timings: Timings::default(),
+            confirm_save_with_conflicts: false,
+            fonts: FontSettings::default(),
+            font_size: 22.0,
+            title_stats: TitleStats::ReadingTime,
+            follow_markdown_links: false,
+            export_theme: ExportTheme::Github,
+            export_css: None,
+            smart_punctuation: SmartPunctuation {
+                preview: true,
+                html: false,
+                plain_text: true,
+            },
+            autosave: true,
+            format_on_save: false,
+            open_any_file: true,
+            code_fence_language: "rust".to_owned(),
+            minimap: true,
+            image_max_width: ImageMaxWidth::Points(480.0),
+            export_front_matter: FrontMatterExport::Metadata,
+            window: WindowSettings {
+                always_on_top: true,
+                maximized: true,
+                fullscreen: false,
+            },
+            notes_root: Some(PathBuf::from("/home/me/notes")),
+            wiki_links: false,
+            code_colors: CodeColorSettings {
+                inline_background: Some("#ffeeaa".to_owned()),
+                block_text: Some("#102030".to_owned()),
+                ..CodeColorSettings::default()
+            },
         };
         let serialized = toml::to_string_pretty(&prefs).unwrap_or_default();
         assert!(!serialized.is_empty(), "serialize should produce output");
+        assert!(serialized.contains("title_stats = \"reading_time\""));
+        assert!(serialized.contains("export_theme = \"github\""));
         let deserialized: UserPreferences = toml::from_str(&serialized).unwrap_or_default();
         assert!(deserialized.nav_visible);
         assert!(!deserialized.heading_color_mode);
         assert!(!deserialized.side_by_side_scroll_sync);
+        assert!(deserialized.preview_code_wrap);
+        assert!(deserialized.preview_preserve_line_breaks);
+        assert!(deserialized.preview_render_html);
+        assert!(!deserialized.confirm_save_with_conflicts);
+        assert!(deserialized.confirm_disk_merges);
+        assert_eq!(deserialized.title_stats, TitleStats::ReadingTime);
+        assert!(!deserialized.follow_markdown_links);
+        assert_eq!(deserialized.export_theme, ExportTheme::Github);
+        assert_eq!(deserialized.smart_punctuation, prefs.smart_punctuation);
+        assert!(deserialized.autosave);
+        assert!(!deserialized.format_on_save);
+        assert!(deserialized.open_any_file);
+        assert_eq!(deserialized.code_fence_language, "rust");
+        assert!(deserialized.minimap);
+        assert_eq!(deserialized.image_max_width, ImageMaxWidth::Points(480.0));
+        assert_eq!(
+            deserialized.export_front_matter,
+            FrontMatterExport::Metadata
+        );
+        assert!(serialized.contains("[window]"));
+        assert_eq!(deserialized.window, prefs.window);
+        assert_eq!(deserialized.notes_root, prefs.notes_root);
+        assert!(!deserialized.wiki_links);
+        assert!(serialized.contains("[code_colors]"));
+        assert_eq!(deserialized.code_colors, prefs.code_colors);
+    }
+
+    #[test]
+    fn timings_default_and_clamp() {
+        let timings = Timings::default();
+        assert_eq!(timings.preview_debounce(), Duration::from_millis(150));
+        assert_eq!(timings.stats_debounce(), Duration::from_millis(120));
+        assert_eq!(timings.disk_poll_interval(), Duration::from_millis(250));
+        assert_eq!(timings.autosave_delay(), Duration::from_secs(2));
+
+        let extreme = Timings {
+            preview_debounce_ms: u64::MAX,
+            stats_debounce_ms: 0,
+            disk_poll_interval_ms: 1,
+            autosave_delay_ms: 0,
+        };
+        assert_eq!(
+            extreme.preview_debounce(),
+            Duration::from_millis(MAX_DEBOUNCE_MS)
+        );
+        assert_eq!(extreme.stats_debounce(), Duration::ZERO);
+        assert_eq!(extreme.disk_poll_interval(), Duration::from_millis(50));
+        assert_eq!(extreme.autosave_delay(), Duration::from_millis(500));
+
+        let partial: UserPreferences =
+            toml::from_str("[timings]\npreview_debounce_ms = 40\n").unwrap_or_default();
+        assert_eq!(partial.timings.preview_debounce_ms, 40);
+        assert_eq!(partial.timings.stats_debounce_ms, 120);
     }
 
     #[test]
@@ -150,6 +641,56 @@ mod tests {
             side_by_side_scroll_sync: false,
             zoom_factor: 1.5,
             mode: "preview".to_owned(),
+            preview_code_wrap: true,
+            preview_preserve_line_breaks: true,
+            preview_render_html: false,
+            editor_indent_with_tabs: true,
+            heading_scales: HeadingScales([2.5, 1.8, 1.4, 1.2, 1.1, 1.0]),
+            reading_width: true,
+            reading_width_chars: 72,
+            merge_sidecar_dir: Some(PathBuf::from("/tmp/rustdown-merges")),
+            merge_sidecar_template: Some("{stem}{n}.merge{ext}".to_owned()),
+            disk_sync_mode: DiskSyncMode::Poll,
+            confirm_disk_merges: true,
+            timings: Timings {
+                preview_debounce_ms: 0,
+                stats_debounce_ms: 300,
+                disk_poll_interval_ms: 1_000,
+                autosave_delay_ms: 5_000,
+            },
+            confirm_save_with_conflicts: false,
+            fonts: FontSettings {
+                ui: Some("Inter".to_owned()),
+                monospace: Some("/opt/fonts/Mono.ttf".to_owned()),
+            },
+            font_size: 16.0,
+            title_stats: TitleStats::Words,
+            follow_markdown_links: false,
+            export_theme: ExportTheme::Dark,
+            export_css: Some(PathBuf::from("/home/me/export.css")),
+            smart_punctuation: SmartPunctuation {
+                preview: false,
+                html: true,
+                plain_text: false,
+            },
+            autosave: false,
+            format_on_save: true,
+            open_any_file: false,
+            code_fence_language: String::new(),
+            minimap: false,
+            image_max_width: ImageMaxWidth::Fraction(0.5),
+            export_front_matter: FrontMatterExport::Keep,
+            window: WindowSettings {
+                always_on_top: false,
+                maximized: false,
+                fullscreen: true,
+            },
+            notes_root: None,
+            wiki_links: false,
+            code_colors: CodeColorSettings {
+                block_background: Some("#000000".to_owned()),
+                ..CodeColorSettings::default()
+            },
         };
         if let Ok(contents) = toml::to_string_pretty(&prefs) {
             let _ = fs::write(&path, &contents);
@@ -160,8 +701,50 @@ mod tests {
             assert!(loaded.nav_visible);
             assert!(!loaded.heading_color_mode);
             assert!(!loaded.side_by_side_scroll_sync);
+            assert_eq!(loaded.heading_scales, prefs.heading_scales);
+            assert!(loaded.reading_width);
+            assert_eq!(loaded.reading_width_chars, 72);
+            assert_eq!(loaded.merge_sidecar_dir, prefs.merge_sidecar_dir);
+            assert_eq!(loaded.merge_sidecar_template, prefs.merge_sidecar_template);
+            assert_eq!(loaded.disk_sync_mode, DiskSyncMode::Poll);
+            assert_eq!(loaded.timings, prefs.timings);
+            assert!(!loaded.confirm_save_with_conflicts);
+            assert!(loaded.confirm_disk_merges);
+            assert_eq!(loaded.fonts, prefs.fonts);
+            assert!((loaded.font_size - 16.0).abs() < f32::EPSILON);
+            assert_eq!(loaded.title_stats, TitleStats::Words);
+            assert!(!loaded.follow_markdown_links);
+            assert_eq!(loaded.export_theme, ExportTheme::Dark);
+            assert_eq!(loaded.export_css, prefs.export_css);
+            assert_eq!(loaded.smart_punctuation, prefs.smart_punctuation);
+            assert!(!loaded.autosave);
+            assert!(loaded.format_on_save);
+            assert!(!loaded.open_any_file);
+            assert!(loaded.code_fence_language.is_empty());
+            assert!(!loaded.minimap);
+            assert_eq!(loaded.image_max_width, ImageMaxWidth::Fraction(0.5));
+            assert_eq!(loaded.export_front_matter, FrontMatterExport::Keep);
+            assert_eq!(loaded.window, prefs.window);
+            assert!(loaded.notes_root.is_none());
+            assert!(!loaded.wiki_links);
+            assert_eq!(loaded.code_colors, prefs.code_colors);
         }
 
         let _ = fs::remove_dir_all(&dir);
     }
+
+    #[test]
+    fn heading_scales_fall_back_per_level_when_invalid() {
+        let defaults = rustdown_md::HEADING_FONT_SCALES;
+        let parsed: UserPreferences =
+            toml::from_str("heading_scales = [3.0, 1.5, 1.25, 1.1, 1.05, 1.0]").unwrap_or_default();
+        assert!((parsed.heading_scales.sanitized().0[0] - 3.0).abs() < f32::EPSILON);
+
+        let bad = HeadingScales([f32::NAN, 0.1, 9.0, 1.1, f32::INFINITY, 1.0]).sanitized();
+        assert_eq!(bad, HeadingScales(defaults));
+
+        // Missing or malformed tables fall back to the defaults.
+        let parsed: UserPreferences = toml::from_str("heading_scales = [2.0]").unwrap_or_default();
+        assert_eq!(parsed.heading_scales, HeadingScales::default());
+    }
 }
@@ -0,0 +1,302 @@
+//! Quick open: fuzzy-find a Markdown file under a notes folder by name.
+//!
+//! The folder's Markdown files are listed on a worker thread the first time
+//! the finder is shown for it, and the list is kept until it is marked
+//! stale — by a watch event adding or removing a Markdown file, a save to a
+//! new path, or a manual rescan — when the next showing lists it afresh.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::mpsc,
+};
+
+use eframe::egui;
+
+/// Most files listed; a larger tree is cut off here.
+const MAX_INDEXED_FILES: usize = 20_000;
+
+/// Most matches shown for a query.
+pub const MAX_RESULTS: usize = 50;
+
+#[derive(Default)]
+pub struct QuickOpenState {
+    pub visible: bool,
+    pub query: String,
+    /// Row of the highlighted match.
+    pub selected: usize,
+    /// Focus the query field on the next frame.
+    pub focus: bool,
+    index: Option<FileIndex>,
+    /// Folder being listed and where its files arrive.
+    scan: Option<(PathBuf, mpsc::Receiver<Vec<String>>)>,
+    stale: bool,
+}
+
+/// The Markdown files under `root`, as `/`-separated paths relative to it.
+struct FileIndex {
+    root: PathBuf,
+    files: Vec<String>,
+}
+
+impl QuickOpenState {
+    /// List the files under `root` unless a current list of them is
+    /// already kept or on its way.
+    pub fn ensure_index(&mut self, ctx: &egui::Context, root: &Path) {
+        self.drain_scan();
+        let current = self
+            .index
+            .as_ref()
+            .is_some_and(|index| index.root == root && !self.stale);
+        let scanning = self.scan.as_ref().is_some_and(|(dir, _)| dir == root);
+        if current || scanning {
+            return;
+        }
+
+        let (tx, rx) = mpsc::channel();
+        let dir = root.to_path_buf();
+        let ctx = ctx.clone();
+        std::thread::spawn(move || {
+            let _ = tx.send(markdown_files(&dir));
+            ctx.request_repaint();
+        });
+        self.scan = Some((root.to_path_buf(), rx));
+        self.stale = false;
+    }
+
+    /// Take a finished listing, if one has arrived.
+    pub fn drain_scan(&mut self) {
+        let Some((root, rx)) = &self.scan else {
+            return;
+        };
+        match rx.try_recv() {
+            Ok(files) => {
+                self.index = Some(FileIndex {
+                    root: root.clone(),
+                    files,
+                });
+                self.scan = None;
+            }
+            Err(mpsc::TryRecvError::Empty) => {}
+            Err(mpsc::TryRecvError::Disconnected) => self.scan = None,
+        }
+    }
+
+    /// Have the next showing list the files again.
+    pub const fn mark_stale(&mut self) {
+        self.stale = true;
+    }
+
+    pub const fn is_scanning(&self) -> bool {
+        self.scan.is_some()
+    }
+
+    /// Number of files listed for `root`.
+    pub fn file_count(&self, root: &Path) -> usize {
+        self.index
+            .as_ref()
+            .filter(|index| index.root == root)
+            .map_or(0, |index| index.files.len())
+    }
+
+    /// The files listed for `root`, if it has been listed.
+    pub fn files(&self, root: &Path) -> Option<&[String]> {
+        self.index
+            .as_ref()
+            .filter(|index| index.root == root)
+            .map(|index| index.files.as_slice())
+    }
+
+    /// The listed files under `root` matching the query, best first, as
+    /// paths relative to it.
+    pub fn matches(&self, root: &Path) -> Vec<String> {
+        let Some(index) = self.index.as_ref().filter(|index| index.root == root) else {
+            return Vec::new();
+        };
+        let mut scored: Vec<(i64, &str)> = index
+            .files
+            .iter()
+            .filter_map(|file| Some((fuzzy_score(&self.query, file)?, file.as_str())))
+            .collect();
+        scored.sort_unstable_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(b.1)));
+        scored
+            .into_iter()
+            .take(MAX_RESULTS)
+            .map(|(_, file)| file.to_owned())
+            .collect()
+    }
+
+    /// The note a wiki link names: a listed file under `root` whose name,
+    /// or path, without its extension is `name` (ignoring case), the least
+    /// nested first.  Lists the folder on the spot if it isn't listed yet.
+    pub fn find_note(&mut self, root: &Path, name: &str) -> Option<PathBuf> {
+        self.drain_scan();
+        if self.index.as_ref().is_none_or(|index| index.root != root) {
+            self.index = Some(FileIndex {
+                root: root.to_path_buf(),
+                files: markdown_files(root),
+            });
+        }
+        let index = self.index.as_ref()?;
+        index
+            .files
+            .iter()
+            .filter(|file| {
+                let stem = file
+                    .rsplit_once('.')
+                    .map_or(file.as_str(), |(stem, _)| stem);
+                let file_stem = stem.rsplit('/').next().unwrap_or(stem);
+                stem.eq_ignore_ascii_case(name) || file_stem.eq_ignore_ascii_case(name)
+            })
+            .min_by_key(|file| file.matches('/').count())
+            .map(|file| root.join(file))
+    }
+
+    /// Move the highlight by `delta` rows within `len` matches.
+    pub fn move_selection(&mut self, delta: isize, len: usize) {
+        self.selected = self
+            .selected
+            .saturating_add_signed(delta)
+            .min(len.saturating_sub(1));
+    }
+}
+
+/// How well `query` matches `path`, higher being better, or `None` when
+/// its characters (ignoring case and spaces) don't all appear in order.
+/// Runs of adjacent characters, matches at the start of a word and matches
+/// in the file name score higher, and shorter paths win ties.
+pub fn fuzzy_score(query: &str, path: &str) -> Option<i64> {
+    let query: Vec<char> = query.chars().filter(|c| !c.is_whitespace()).collect();
+    let hay: Vec<char> = path.chars().collect();
+    let name_start = hay.iter().rposition(|&c| c == '/').map_or(0, |i| i + 1);
+    let shorter = -i64::try_from(hay.len().min(999)).unwrap_or(999);
+    let Some(&first) = query.first() else {
+        return Some(shorter);
+    };
+    // Matching greedily from each place the query could start finds
+    // "todo" in `notes/todo.md` rather than stopping at the first `t`.
+    let best = (0..hay.len())
+        .filter(|&start| same_letter(hay[start], first))
+        .filter_map(|start| score_from(&hay, &query, start, name_start))
+        .max()?;
+    Some(best * 1_000 + shorter)
+}
+
+fn score_from(hay: &[char], query: &[char], start: usize, name_start: usize) -> Option<i64> {
+    let mut score = 0;
+    let mut from = start;
+    let mut last = None;
+    for &q in query {
+        let found = (from..hay.len()).find(|&i| same_letter(hay[i], q))?;
+        score += 1;
+        if last.is_some_and(|last| last + 1 == found) {
+            score += 5;
+        }
+        if found == 0 || matches!(hay[found - 1], '/' | '-' | '_' | ' ' | '.') {
+            score += 3;
+        }
+        if found >= name_start {
+            score += 2;
+        }
+        last = Some(found);
+        from = found + 1;
+    }
+    Some(score)
+}
+
+fn same_letter(a: char, b: char) -> bool {
+    a == b || a.to_lowercase().eq(b.to_lowercase())
+}
+
+/// The Markdown files under `root`, skipping hidden files and folders and
+/// not following symlinked folders, sorted, as `/`-separated relative paths.
+fn markdown_files(root: &Path) -> Vec<String> {
+    let mut files = Vec::new();
+    let mut dirs = vec![root.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            if entry.file_name().to_string_lossy().starts_with('.') {
+                continue;
+            }
+            let path = entry.path();
+            let is_dir = entry.file_type().is_ok_and(|kind| kind.is_dir());
+            if is_dir {
+                dirs.push(path);
+            } else if crate::is_markdown_path(&path)
+                && let Ok(relative) = path.strip_prefix(root)
+            {
+                let parts: Vec<_> = relative
+                    .components()
+                    .map(|part| part.as_os_str().to_string_lossy())
+                    .collect();
+                files.push(parts.join("/"));
+                if files.len() >= MAX_INDEXED_FILES {
+                    dirs.clear();
+                    break;
+                }
+            }
+        }
+    }
+    files.sort_unstable();
+    files
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fuzzy_score_prefers_names_runs_and_word_starts() {
+        assert!(fuzzy_score("rdme", "README.md").is_some());
+        assert!(fuzzy_score("xyz", "README.md").is_none());
+        assert!(fuzzy_score("", "a.md").is_some());
+
+        let rank = |query: &str, paths: &[&'static str]| {
+            let mut scored: Vec<_> = paths
+                .iter()
+                .filter_map(|p| Some((fuzzy_score(query, p)?, *p)))
+                .collect();
+            scored.sort_by_key(|&(score, _)| std::cmp::Reverse(score));
+            scored.into_iter().map(|(_, p)| p).collect::<Vec<_>>()
+        };
+        assert_eq!(
+            rank("todo", &["t/o/d/o.md", "notes/todo.md", "todo/index.md"]),
+            ["notes/todo.md", "todo/index.md", "t/o/d/o.md"]
+        );
+        assert_eq!(
+            rank("ab", &["a_long_b.md", "ab.md"]),
+            ["ab.md", "a_long_b.md"]
+        );
+        assert_eq!(rank("Week plan", &["week-plan.md"]), ["week-plan.md"]);
+    }
+
+    #[test]
+    fn markdown_files_skip_hidden_and_other_files() {
+        let root = std::env::temp_dir().join(format!("rustdown-quick-open-{}", std::process::id()));
+        let _ = fs::create_dir_all(root.join("sub/deeper"));
+        let _ = fs::create_dir_all(root.join(".git"));
+        for file in [
+            "a.md",
+            "sub/b.markdown",
+            "sub/deeper/c.md",
+            ".git/d.md",
+            "e.txt",
+        ] {
+            let _ = fs::write(root.join(file), "x");
+        }
+        assert_eq!(
+            markdown_files(&root),
+            ["a.md", "sub/b.markdown", "sub/deeper/c.md"]
+        );
+
+        let mut state = QuickOpenState::default();
+        state.move_selection(-1, 3);
+        assert_eq!(state.selected, 0);
+        state.move_selection(5, 3);
+        assert_eq!(state.selected, 2);
+        let _ = fs::remove_dir_all(&root);
+    }
+}
@@ -113,3 +113,35 @@ pub(crate) fn preview_scroll_y_to_byte(
     let byte_range = after.byte_offset - before.byte_offset;
     before.byte_offset + (frac * byte_range as f32) as usize
 }
+
+/// Map `byte_offset` in `old` to the equivalent offset in `new`, treating
+/// the change as one replaced region between their common prefix and
+/// suffix.  Offsets before the change stay put, offsets after it shift by
+/// the length difference, and offsets inside it move to the region start.
+pub(crate) fn map_byte_through_edit(old: &str, new: &str, byte_offset: usize) -> usize {
+    let prefix = old
+        .bytes()
+        .zip(new.bytes())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let max_suffix = old.len().min(new.len()) - prefix;
+    let suffix = old
+        .bytes()
+        .rev()
+        .zip(new.bytes().rev())
+        .take(max_suffix)
+        .take_while(|(a, b)| a == b)
+        .count();
+    let mapped = if byte_offset <= prefix {
+        byte_offset
+    } else if byte_offset >= old.len() - suffix {
+        byte_offset - (old.len() - suffix) + (new.len() - suffix)
+    } else {
+        prefix
+    };
+    let mut mapped = mapped.min(new.len());
+    while !new.is_char_boundary(mapped) {
+        mapped -= 1;
+    }
+    mapped
+}
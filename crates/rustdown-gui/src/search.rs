@@ -1,4 +1,4 @@
-use std::borrow::Cow;
+use std::{borrow::Cow, ops::Range};
 
 #[derive(Default)]
 pub struct SearchState {
@@ -35,6 +35,47 @@ impl SearchState {
     }
 }
 
+/// Non-overlapping matches of a needle, as byte ranges, in left-to-right
+/// order.  Each search resumes at the end of the previous match, and an
+/// empty match still advances by one char, so iteration always terminates
+/// and a replacement is never re-scanned.
+pub struct Matches<'a> {
+    haystack: &'a str,
+    finder: memchr::memmem::Finder<'a>,
+    pos: usize,
+}
+
+impl<'a> Matches<'a> {
+    #[must_use]
+    pub fn new(haystack: &'a str, needle: &'a str) -> Self {
+        Self {
+            haystack,
+            finder: memchr::memmem::Finder::new(needle.as_bytes()),
+            pos: 0,
+        }
+    }
+}
+
+impl Iterator for Matches<'_> {
+    type Item = Range<usize>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let rest = self.haystack.get(self.pos..)?;
+        let start = self.pos + self.finder.find(rest.as_bytes())?;
+        let end = start + self.finder.needle().len();
+        self.pos = if end > start {
+            end
+        } else {
+            // Step over the next char so an empty match can't repeat.
+            self.haystack[end..]
+                .chars()
+                .next()
+                .map_or(self.haystack.len() + 1, |ch| end + ch.len_utf8())
+        };
+        Some(start..end)
+    }
+}
+
 #[inline]
 #[must_use]
 pub fn find_match_count(haystack: &str, needle: &str) -> usize {
@@ -44,9 +85,12 @@ pub fn find_match_count(haystack: &str, needle: &str) -> usize {
     if needle.len() == 1 {
         return memchr::memchr_iter(needle.as_bytes()[0], haystack.as_bytes()).count();
     }
-    memchr::memmem::find_iter(haystack.as_bytes(), needle.as_bytes()).count()
+    Matches::new(haystack, needle).count()
 }
 
+/// Replace every match of `needle` with `replacement` in one pass over
+/// [`Matches`], returning the new text and the number of replacements.
+/// The text is borrowed when nothing changes.
 #[must_use]
 pub fn replace_all_occurrences<'a>(
     haystack: &'a str,
@@ -57,13 +101,10 @@ pub fn replace_all_occurrences<'a>(
         return (Cow::Borrowed(haystack), 0);
     }
 
-    // Single-pass: find all occurrences and build the result in one scan.
-    let needle_bytes = needle.as_bytes();
-    let haystack_bytes = haystack.as_bytes();
-    let mut iter = memchr::memmem::find_iter(haystack_bytes, needle_bytes);
-    let Some(first) = iter.next() else {
+    let mut matches = Matches::new(haystack, needle).peekable();
+    if matches.peek().is_none() {
         return (Cow::Borrowed(haystack), 0);
-    };
+    }
 
     // Pre-allocate with a reasonable estimate.
     let estimated = if replacement.len() >= needle.len() {
@@ -72,17 +113,13 @@ pub fn replace_all_occurrences<'a>(
         haystack.len()
     };
     let mut result = String::with_capacity(estimated);
-    let mut count = 1usize;
+    let mut count = 0usize;
     let mut prev_end = 0;
 
-    result.push_str(&haystack[prev_end..first]);
-    result.push_str(replacement);
-    prev_end = first + needle.len();
-
-    for pos in iter {
-        result.push_str(&haystack[prev_end..pos]);
+    for range in matches {
+        result.push_str(&haystack[prev_end..range.start]);
         result.push_str(replacement);
-        prev_end = pos + needle.len();
+        prev_end = range.end;
         count += 1;
     }
 
@@ -141,6 +178,10 @@ mod tests {
             ("abc def abc", "abc", "xyz", 2, "xyz def xyz", true),
             ("hello world hello", "hello", "hi", 2, "hi world hi", true),
             ("", "abc", "xyz", 0, "", false),
+            ("aaaa", "aa", "b", 2, "bb", true), // adjacent
+            ("aaa", "aa", "b", 1, "ba", true),  // overlapping candidates
+            ("abab", "ab", "abab", 2, "abababab", true), // needle prefixes replacement
+            ("a", "a", "aa", 1, "aa", true),
         ];
         for (haystack, needle, repl, exp_count, exp_result, owned) in cases {
             let (result, count) = replace_all_occurrences(haystack, needle, repl);
@@ -169,6 +210,25 @@ mod tests {
         assert_eq!(find_match_count("[x][y][x]", "[x]"), 2);
     }
 
+    // ── Matches ─────────────────────────────────────────────────────
+
+    #[test]
+    fn matches_skip_overlapping_candidates() {
+        let ranges: Vec<_> = Matches::new("aaaaa", "aa").collect();
+        assert_eq!(ranges, [0..2, 2..4]);
+        let ranges: Vec<_> = Matches::new("xyxyx", "xyx").collect();
+        assert_eq!(ranges, std::slice::from_ref(&(0..3)));
+        let ranges: Vec<_> = Matches::new("日本日本", "日本").collect();
+        assert_eq!(ranges, [0..6, 6..12]);
+    }
+
+    #[test]
+    fn empty_needle_matches_advance_per_char() {
+        let ranges: Vec<_> = Matches::new("aé", "").collect();
+        assert_eq!(ranges, [0..0, 1..1, 3..3]);
+        assert_eq!(Matches::new("", "").count(), 1);
+    }
+
     // ── SearchState::match_count (caching) ──────────────────────────
 
     #[test]
@@ -0,0 +1,184 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::disk::io::atomic_write_utf8;
+
+/// How many documents' scroll positions are remembered.
+const MAX_SCROLL_POSITIONS: usize = 50;
+
+/// The last open document, persisted while running and on exit, and
+/// restored on next launch.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SessionState {
+    /// Path of the active document, or `None` for an untitled buffer.
+    pub path: Option<PathBuf>,
+    /// Unsaved buffer text; only stored when the document was dirty.
+    pub text: Option<String>,
+    /// The on-disk text the unsaved edits were based on, used as the merge
+    /// base when the file changed on disk since the session was saved.
+    pub base_text: Option<String>,
+    /// Where recently viewed documents were scrolled to.
+    pub scroll_positions: ScrollPositions,
+}
+
+/// A document's scroll position, as a byte offset into its text so it
+/// survives zoom, window-size, and mode changes.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScrollPosition {
+    pub path: PathBuf,
+    pub byte_offset: usize,
+}
+
+/// Per-document scroll positions, most recently viewed first.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ScrollPositions(Vec<ScrollPosition>);
+
+impl ScrollPositions {
+    /// The remembered scroll position for `path`.
+    #[must_use]
+    pub fn get(&self, path: &Path) -> Option<usize> {
+        self.0
+            .iter()
+            .find(|entry| entry.path == path)
+            .map(|entry| entry.byte_offset)
+    }
+
+    /// Remember `byte_offset` for `path`, evicting the least recently
+    /// viewed entry when full.
+    pub fn remember(&mut self, path: &Path, byte_offset: usize) {
+        if let Some(first) = self.0.first_mut()
+            && first.path == path
+        {
+            first.byte_offset = byte_offset;
+            return;
+        }
+        self.0.retain(|entry| entry.path != path);
+        self.0.insert(
+            0,
+            ScrollPosition {
+                path: path.to_path_buf(),
+                byte_offset,
+            },
+        );
+        self.0.truncate(MAX_SCROLL_POSITIONS);
+    }
+}
+
+impl SessionState {
+    /// Load the saved session, falling back to an empty session on any
+    /// error (missing file, parse error, etc.).
+    #[must_use]
+    pub fn load() -> Self {
+        let Some(path) = session_file_path() else {
+            return Self::default();
+        };
+        let Ok(contents) = fs::read_to_string(&path) else {
+            return Self::default();
+        };
+        toml::from_str(&contents).unwrap_or_default()
+    }
+
+    /// Persist the session to the standard config path.  Without a config
+    /// directory there is nowhere to keep it, which isn't an error.
+    pub fn save(&self) -> io::Result<()> {
+        session_file_path().map_or(Ok(()), |path| self.save_to(&path))
+    }
+
+    /// Write the session to `path` atomically, so a crash mid-write leaves
+    /// the previous session rather than a truncated one.
+    fn save_to(&self, path: &Path) -> io::Result<()> {
+        let contents = toml::to_string_pretty(self).map_err(io::Error::other)?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        atomic_write_utf8(path, &contents)
+    }
+}
+
+/// Returns the path to `session.toml`, next to `settings.toml` in the
+/// platform config directory.
+fn session_file_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("rustdown").join("session.toml"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip_toml() {
+        let session = SessionState {
+            path: Some(PathBuf::from("/tmp/notes.md")),
+            text: Some("# Draft\n\nline \"quoted\"\n".to_owned()),
+            base_text: Some("# Draft\n".to_owned()),
+            scroll_positions: ScrollPositions(vec![ScrollPosition {
+                path: PathBuf::from("/tmp/notes.md"),
+                byte_offset: 42,
+            }]),
+        };
+        let toml_str = toml::to_string_pretty(&session).unwrap_or_default();
+        let parsed: SessionState = toml::from_str(&toml_str).unwrap_or_default();
+        assert_eq!(parsed, session);
+    }
+
+    #[test]
+    fn save_to_replaces_the_file_whole_and_reports_failures() {
+        let dir =
+            std::env::temp_dir().join(format!("rustdown-session-save-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let path = dir.join("rustdown").join("session.toml");
+        let mut session = SessionState {
+            text: Some("first draft".to_owned()),
+            ..SessionState::default()
+        };
+        assert!(session.save_to(&path).is_ok());
+        session.text = Some("second".to_owned());
+        assert!(session.save_to(&path).is_ok());
+        let saved = fs::read_to_string(&path).unwrap_or_default();
+        assert_eq!(
+            toml::from_str::<SessionState>(&saved).ok(),
+            Some(session.clone())
+        );
+        let entries = fs::read_dir(path.parent().unwrap_or(&dir)).map_or(0, Iterator::count);
+        assert_eq!(entries, 1, "no temporary files are left behind");
+
+        // A file where the config directory should be is an error.
+        assert!(session.save_to(&path.join("session.toml")).is_err());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn missing_fields_use_defaults() {
+        let parsed: SessionState = toml::from_str("path = \"/tmp/a.md\"").unwrap_or_default();
+        assert_eq!(parsed.path, Some(PathBuf::from("/tmp/a.md")));
+        assert!(parsed.text.is_none());
+        assert!(parsed.base_text.is_none());
+        assert_eq!(parsed.scroll_positions, ScrollPositions::default());
+    }
+
+    #[test]
+    fn scroll_positions_are_most_recent_first_and_bounded() {
+        let mut positions = ScrollPositions::default();
+        let a = Path::new("/tmp/a.md");
+        let b = Path::new("/tmp/b.md");
+        positions.remember(a, 10);
+        positions.remember(b, 20);
+        positions.remember(a, 30);
+        assert_eq!(positions.get(a), Some(30));
+        assert_eq!(positions.get(b), Some(20));
+        assert_eq!(positions.0.first().map(|e| e.path.as_path()), Some(a));
+        assert_eq!(positions.0.len(), 2);
+
+        for i in 0..MAX_SCROLL_POSITIONS {
+            positions.remember(&PathBuf::from(format!("/tmp/{i}.md")), i);
+        }
+        assert_eq!(positions.0.len(), MAX_SCROLL_POSITIONS);
+        assert_eq!(positions.get(a), None, "oldest entries are evicted");
+    }
+}
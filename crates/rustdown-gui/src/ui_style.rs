@@ -1,8 +1,17 @@
-use std::{fs, sync::Arc};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
 use eframe::egui;
 
+use crate::preferences::FontSettings;
+
 const UI_FONT_NAME: &str = "rustdown-ui-font";
+const MONOSPACE_FONT_NAME: &str = "rustdown-monospace-font";
+/// How deep to look inside a font directory for a family name.
+const FONT_SEARCH_DEPTH: usize = 4;
 const BUNDLED_FONT_FALLBACKS: &[(&str, &[u8])] = &[
     (
         "rustdown-symbola-subset",
@@ -51,29 +60,116 @@ const UI_FONT_FALLBACK_PATHS: &[&str] = &[
     r"C:\Windows\Fonts\seguisym.ttf",
     r"C:\Windows\Fonts\arialuni.ttf",
 ];
+#[cfg(target_os = "linux")]
+const MONOSPACE_FONT_CANDIDATE_PATHS: &[&str] = &[
+    "/usr/share/fonts/truetype/dejavu/DejaVuSansMono.ttf",
+    "/usr/share/fonts/TTF/DejaVuSansMono.ttf",
+    "/usr/share/fonts/truetype/liberation2/LiberationMono-Regular.ttf",
+    "/usr/share/fonts/truetype/noto/NotoSansMono-Regular.ttf",
+];
+#[cfg(target_os = "macos")]
+const MONOSPACE_FONT_CANDIDATE_PATHS: &[&str] = &[
+    "/System/Library/Fonts/Menlo.ttc",
+    "/System/Library/Fonts/Monaco.ttf",
+    "/System/Library/Fonts/Supplemental/Courier New.ttf",
+];
+#[cfg(target_os = "windows")]
+const MONOSPACE_FONT_CANDIDATE_PATHS: &[&str] = &[
+    r"C:\Windows\Fonts\consola.ttf",
+    r"C:\Windows\Fonts\cour.ttf",
+];
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+const MONOSPACE_FONT_CANDIDATE_PATHS: &[&str] = &[];
+
+#[cfg(target_os = "linux")]
+const SYSTEM_FONT_DIRS: &[&str] = &["/usr/share/fonts", "/usr/local/share/fonts"];
+#[cfg(target_os = "macos")]
+const SYSTEM_FONT_DIRS: &[&str] = &["/System/Library/Fonts", "/Library/Fonts"];
+#[cfg(target_os = "windows")]
+const SYSTEM_FONT_DIRS: &[&str] = &[r"C:\Windows\Fonts"];
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+const SYSTEM_FONT_DIRS: &[&str] = &[];
 #[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
 const UI_FONT_CANDIDATE_PATHS: &[&str] = &[];
 #[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
 const UI_FONT_FALLBACK_PATHS: &[&str] = &[];
 
-const DEFAULT_BODY_BUTTON_FONT_SIZE: f32 = 19.0;
+/// Default body text size, in points before zoom.
+pub const DEFAULT_BODY_BUTTON_FONT_SIZE: f32 = 19.0;
+/// Accepted body text sizes, in points before zoom.
+pub const FONT_SIZE_RANGE: std::ops::RangeInclusive<f32> = 10.0..=36.0;
 const DEFAULT_MONOSPACE_FONT_SIZE: f32 = 18.0;
 const DEFAULT_SMALL_FONT_SIZE: f32 = 13.0;
 const DEFAULT_SCROLL_ANIMATION_POINTS_PER_SECOND: f32 = 1150.0;
 
-/// Load the primary system font and any available fallback fonts.
-pub fn configure_fonts(ctx: &egui::Context) -> Result<(), String> {
-    let primary_font_data = load_single_font()?;
-    let primary_font_name = UI_FONT_NAME.to_owned();
+/// Load the primary font (from `settings`, else the system) and any
+/// available fallback fonts.
+///
+/// When no UI font can be loaded, egui's built-in fonts are kept so the app
+/// still starts, and a notice for the status bar is returned. Those fonts are
+/// only embedded when built with the `bundled-font` feature.
+pub fn configure_fonts(ctx: &egui::Context, settings: &FontSettings) -> Option<String> {
+    let primary_font_data = match settings
+        .ui
+        .as_deref()
+        .and_then(|font| load_configured_font("UI", font))
+    {
+        Some(data) => Ok(data),
+        None => load_single_font(),
+    };
+    let monospace_font_data = settings
+        .monospace
+        .as_deref()
+        .and_then(|font| load_configured_font("monospace", font))
+        .or_else(|| load_first_font(MONOSPACE_FONT_CANDIDATE_PATHS));
+    let (fonts, notice) = build_font_definitions(primary_font_data, monospace_font_data);
+    ctx.set_fonts(fonts);
+    notice
+}
+
+fn build_font_definitions(
+    primary_font_data: Result<Vec<u8>, String>,
+    monospace_font_data: Option<Vec<u8>>,
+) -> (egui::FontDefinitions, Option<String>) {
     let mut fonts = egui::FontDefinitions::default();
-    fonts.font_data.clear();
-    fonts.families.clear();
-    fonts.font_data.insert(
-        primary_font_name.clone(),
-        Arc::new(egui::FontData::from_owned(primary_font_data)),
-    );
-    let mut proportional = vec![primary_font_name.clone()];
-    let mut monospace = vec![primary_font_name];
+    let (mut proportional, base_monospace, notice) = match primary_font_data {
+        Ok(data) => {
+            let primary_font_name = UI_FONT_NAME.to_owned();
+            fonts.font_data.clear();
+            fonts.families.clear();
+            fonts.font_data.insert(
+                primary_font_name.clone(),
+                Arc::new(egui::FontData::from_owned(data)),
+            );
+            (
+                vec![primary_font_name.clone()],
+                vec![primary_font_name],
+                None,
+            )
+        }
+        Err(err) => {
+            let mut take = |family| fonts.families.remove(&family).unwrap_or_default();
+            (
+                take(egui::FontFamily::Proportional),
+                take(egui::FontFamily::Monospace),
+                Some(if cfg!(feature = "bundled-font") {
+                    format!("{err}. Using the bundled font.")
+                } else {
+                    format!("{err}. Install a system font or set RUSTDOWN_FONT_PATH.")
+                }),
+            )
+        }
+    };
+    let mut monospace = Vec::new();
+    if let Some(data) = monospace_font_data {
+        fonts.font_data.insert(
+            MONOSPACE_FONT_NAME.to_owned(),
+            Arc::new(egui::FontData::from_owned(data)),
+        );
+        monospace.push(MONOSPACE_FONT_NAME.to_owned());
+    }
+    // The UI font backs the monospace face up for glyphs it lacks.
+    monospace.extend(base_monospace);
     append_embedded_fallbacks(
         &mut fonts,
         &mut proportional,
@@ -92,36 +188,56 @@ pub fn configure_fonts(ctx: &egui::Context) -> Result<(), String> {
     fonts
         .families
         .insert(egui::FontFamily::Monospace, monospace);
-    ctx.set_fonts(fonts);
-    Ok(())
+    (fonts, notice)
 }
 
 /// Apply the default text sizes and visual tweaks.
 pub fn configure_style(ctx: &egui::Context) {
     ctx.style_mut(|style| {
-        for text_style in [egui::TextStyle::Body, egui::TextStyle::Button] {
-            if let Some(font_id) = style.text_styles.get_mut(&text_style) {
-                font_id.size = DEFAULT_BODY_BUTTON_FONT_SIZE;
-            }
-        }
-        if let Some(font_id) = style.text_styles.get_mut(&egui::TextStyle::Heading) {
-            // Set large enough to give egui_commonmark's heading scale factors
-            // (which interpolate between Body and Heading sizes) visible
-            // differentiation across all six heading levels.
-            font_id.size = DEFAULT_BODY_BUTTON_FONT_SIZE * 2.0;
-        }
-        if let Some(font_id) = style.text_styles.get_mut(&egui::TextStyle::Monospace) {
-            font_id.size = DEFAULT_MONOSPACE_FONT_SIZE;
-        }
-        if let Some(font_id) = style.text_styles.get_mut(&egui::TextStyle::Small) {
-            font_id.size = DEFAULT_SMALL_FONT_SIZE;
-        }
+        set_font_sizes(style, DEFAULT_BODY_BUTTON_FONT_SIZE);
         style.scroll_animation.points_per_second = DEFAULT_SCROLL_ANIMATION_POINTS_PER_SECOND;
         // Visible column separators in markdown tables rendered by egui_commonmark.
         style.visuals.widgets.noninteractive.bg_stroke.width = 1.0;
     });
 }
 
+/// Clamp a configured body size into [`FONT_SIZE_RANGE`], using the default
+/// for non-finite values.
+#[must_use]
+pub const fn sanitized_font_size(size: f32) -> f32 {
+    if size.is_finite() {
+        size.clamp(*FONT_SIZE_RANGE.start(), *FONT_SIZE_RANGE.end())
+    } else {
+        DEFAULT_BODY_BUTTON_FONT_SIZE
+    }
+}
+
+/// Resize every text style for a `body_size` base, keeping the default
+/// proportions.  Zoom still multiplies on top of this.
+pub fn apply_font_size(ctx: &egui::Context, body_size: f32) {
+    ctx.style_mut(|style| set_font_sizes(style, sanitized_font_size(body_size)));
+}
+
+fn set_font_sizes(style: &mut egui::Style, body_size: f32) {
+    let scale = body_size / DEFAULT_BODY_BUTTON_FONT_SIZE;
+    for (text_style, size) in [
+        (egui::TextStyle::Body, DEFAULT_BODY_BUTTON_FONT_SIZE),
+        (egui::TextStyle::Button, DEFAULT_BODY_BUTTON_FONT_SIZE),
+        // Large enough that heading scale factors (which interpolate between
+        // Body and Heading sizes) stay distinct across all six levels.
+        (
+            egui::TextStyle::Heading,
+            DEFAULT_BODY_BUTTON_FONT_SIZE * 2.0,
+        ),
+        (egui::TextStyle::Monospace, DEFAULT_MONOSPACE_FONT_SIZE),
+        (egui::TextStyle::Small, DEFAULT_SMALL_FONT_SIZE),
+    ] {
+        if let Some(font_id) = style.text_styles.get_mut(&text_style) {
+            font_id.size = size * scale;
+        }
+    }
+}
+
 fn append_embedded_fallbacks(
     fonts: &mut egui::FontDefinitions,
     proportional: &mut Vec<String>,
@@ -163,6 +279,113 @@ fn append_font_fallbacks(
     loaded
 }
 
+/// Load a font named in the settings file, warning and returning `None` so
+/// the caller falls back when it can't be found, read, or isn't a font.
+fn load_configured_font(kind: &str, font: &str) -> Option<Vec<u8>> {
+    let result = resolve_font(font, &font_search_dirs())
+        .ok_or_else(|| "not found".to_owned())
+        .and_then(|path| fs::read(&path).map_err(|err| format!("{}: {err}", path.display())))
+        .and_then(|data| {
+            if looks_like_font(&data) {
+                Ok(data)
+            } else {
+                Err("not a TrueType/OpenType font".to_owned())
+            }
+        });
+    match result {
+        Ok(data) => Some(data),
+        Err(err) => {
+            eprintln!("rustdown: {kind} font '{font}' ignored ({err}); using the default");
+            None
+        }
+    }
+}
+
+/// Read the first of `paths` that exists and looks like a font.
+fn load_first_font(paths: &[&str]) -> Option<Vec<u8>> {
+    paths
+        .iter()
+        .filter_map(|path| fs::read(path).ok())
+        .find(|data| looks_like_font(data))
+}
+
+fn font_search_dirs() -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = SYSTEM_FONT_DIRS.iter().map(PathBuf::from).collect();
+    dirs.extend(dirs::font_dir());
+    dirs
+}
+
+/// Resolve a configured font to a file: an existing path is used as is,
+/// otherwise `font` is matched as a family name against font file names in
+/// `dirs` (ignoring case, spaces, `-` and `_`; a `Regular` suffix is
+/// preferred).
+fn resolve_font(font: &str, dirs: &[PathBuf]) -> Option<PathBuf> {
+    let font = font.trim();
+    if font.is_empty() {
+        return None;
+    }
+    let path = Path::new(font);
+    if path.is_file() {
+        return Some(path.to_path_buf());
+    }
+    let family = normalize_font_name(font);
+    let mut regular = None;
+    let mut exact = None;
+    for dir in dirs {
+        visit_font_files(dir, FONT_SEARCH_DEPTH, &mut |file| {
+            let Some(stem) = file.file_stem().and_then(|stem| stem.to_str()) else {
+                return;
+            };
+            let stem = normalize_font_name(stem);
+            if regular.is_none() && stem.strip_suffix("regular") == Some(family.as_str()) {
+                regular = Some(file.to_path_buf());
+            } else if exact.is_none() && stem == family {
+                exact = Some(file.to_path_buf());
+            }
+        });
+    }
+    regular.or(exact)
+}
+
+fn normalize_font_name(name: &str) -> String {
+    name.chars()
+        .filter(|c| !matches!(c, ' ' | '-' | '_'))
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+fn visit_font_files(dir: &Path, depth: usize, visit: &mut dyn FnMut(&Path)) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            if depth > 0 {
+                visit_font_files(&path, depth - 1, visit);
+            }
+        } else if path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| {
+                ["ttf", "otf", "ttc"]
+                    .iter()
+                    .any(|font_ext| ext.eq_ignore_ascii_case(font_ext))
+            })
+        {
+            visit(&path);
+        }
+    }
+}
+
+/// Check the file signature so a bad setting can't crash font loading.
+fn looks_like_font(data: &[u8]) -> bool {
+    matches!(
+        data.get(..4),
+        Some([0, 1, 0, 0] | b"OTTO" | b"true" | b"ttcf")
+    )
+}
+
 fn load_single_font() -> Result<Vec<u8>, String> {
     if let Ok(path) = std::env::var("RUSTDOWN_FONT_PATH") {
         if path.trim().is_empty() {
@@ -219,6 +442,36 @@ mod tests {
         }
     }
 
+    #[test]
+    fn apply_font_size_scales_all_text_styles() {
+        let ctx = egui::Context::default();
+        configure_style(&ctx);
+        let size_of = |text_style: &egui::TextStyle| {
+            ctx.style()
+                .text_styles
+                .get(text_style)
+                .map_or(0.0, |f| f.size)
+        };
+
+        apply_font_size(&ctx, DEFAULT_BODY_BUTTON_FONT_SIZE * 1.5);
+        for (text_style, default) in [
+            (egui::TextStyle::Body, DEFAULT_BODY_BUTTON_FONT_SIZE),
+            (egui::TextStyle::Monospace, DEFAULT_MONOSPACE_FONT_SIZE),
+            (egui::TextStyle::Small, DEFAULT_SMALL_FONT_SIZE),
+        ] {
+            let expected = default * 1.5;
+            assert!(
+                (size_of(&text_style) - expected).abs() < 1e-4,
+                "{text_style:?}"
+            );
+        }
+
+        apply_font_size(&ctx, 1000.0);
+        assert!((size_of(&egui::TextStyle::Body) - FONT_SIZE_RANGE.end()).abs() < 1e-4);
+        assert!((sanitized_font_size(f32::NAN) - DEFAULT_BODY_BUTTON_FONT_SIZE).abs() < 1e-4);
+        assert!((sanitized_font_size(1.0) - FONT_SIZE_RANGE.start()).abs() < 1e-4);
+    }
+
     #[test]
     fn append_font_fallbacks_loads_existing_files_only() {
         let mut fonts = egui::FontDefinitions::default();
@@ -333,13 +586,107 @@ mod tests {
     }
 
     #[test]
-    fn configure_fonts_loads_bundled_fallback_glyphs() {
+    fn resolve_font_accepts_paths_and_family_names() {
+        let dir = std::env::temp_dir().join("rustdown_font_resolve_test");
+        let nested = dir.join("truetype").join("acme");
+        let _ = std::fs::create_dir_all(&nested);
+        for name in ["AcmeSans-Bold.ttf", "AcmeSans-Regular.ttf", "Acme Mono.otf"] {
+            let _ = std::fs::write(nested.join(name), b"\0\x01\0\0");
+        }
+        let dirs = [dir.clone()];
+
+        let regular = nested.join("AcmeSans-Regular.ttf");
+        assert_eq!(resolve_font("Acme Sans", &dirs), Some(regular.clone()));
+        assert_eq!(
+            resolve_font("acme_mono", &dirs),
+            Some(nested.join("Acme Mono.otf"))
+        );
+        let path = regular.to_string_lossy().into_owned();
+        assert_eq!(resolve_font(&path, &[]), Some(regular));
+        assert_eq!(resolve_font("Missing Family", &dirs), None);
+        assert_eq!(resolve_font("  ", &dirs), None);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn load_first_font_skips_missing_and_invalid_files() {
+        let dir = std::env::temp_dir().join("rustdown_font_first_test");
+        let _ = std::fs::create_dir_all(&dir);
+        let text = dir.join("notes.ttf");
+        let mono = dir.join("Mono.ttf");
+        let _ = std::fs::write(&text, b"plain text");
+        let _ = std::fs::write(&mono, b"\0\x01\0\0mono");
+        let text = text.to_string_lossy().into_owned();
+        let mono = mono.to_string_lossy().into_owned();
+
+        let loaded = load_first_font(&["/nonexistent/Mono.ttf", &text, &mono]);
+        assert_eq!(loaded.as_deref(), Some(&b"\0\x01\0\0mono"[..]));
+        assert!(load_first_font(&["/nonexistent/Mono.ttf", &text]).is_none());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn invalid_configured_fonts_fall_back() {
+        assert!(looks_like_font(b"\0\x01\0\0rest"));
+        assert!(looks_like_font(b"OTTO"));
+        assert!(!looks_like_font(b"not a font"));
+        assert!(!looks_like_font(b""));
+
+        let dir = std::env::temp_dir().join("rustdown_font_invalid_test");
+        let _ = std::fs::create_dir_all(&dir);
+        let bogus = dir.join("bogus.ttf");
+        let _ = std::fs::write(&bogus, b"definitely not a font");
+        let bogus = bogus.to_string_lossy().into_owned();
+        assert!(load_configured_font("UI", &bogus).is_none());
+        assert!(load_configured_font("UI", "/nonexistent/font.ttf").is_none());
+
         let ctx = egui::Context::default();
-        let configured = configure_fonts(&ctx);
+        let settings = FontSettings {
+            ui: Some(bogus),
+            monospace: Some("No Such Family".to_owned()),
+        };
+        // Only the monospace setting is left unresolved; the system UI font
+        // (or the built-in fallback) still loads.
+        let _ = configure_fonts(&ctx, &settings);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn missing_ui_font_keeps_builtin_fonts_and_reports_it() {
+        let (fonts, notice) =
+            build_font_definitions(Err("No UI font files found".to_owned()), None);
         assert!(
-            configured.is_ok(),
-            "configure_fonts should load bundled glyph fallbacks"
+            notice
+                .as_deref()
+                .is_some_and(|notice| notice.starts_with("No UI font files found. "))
+        );
+        assert!(!fonts.font_data.contains_key(UI_FONT_NAME));
+        for family in [egui::FontFamily::Proportional, egui::FontFamily::Monospace] {
+            let names = fonts.families.get(&family).cloned().unwrap_or_default();
+            assert!(!names.is_empty(), "{family:?}");
+            assert!(names.iter().all(|name| fonts.font_data.contains_key(name)));
+        }
+
+        let ctx = egui::Context::default();
+        ctx.set_fonts(fonts);
+        let _ = ctx.run(egui::RawInput::default(), |_| {});
+
+        let (fonts, notice) = build_font_definitions(Ok(b"\0\x01\0\0".to_vec()), None);
+        assert!(notice.is_none());
+        assert_eq!(
+            fonts
+                .families
+                .get(&egui::FontFamily::Monospace)
+                .and_then(|names| names.first())
+                .map(String::as_str),
+            Some(UI_FONT_NAME)
         );
+    }
+
+    #[test]
+    fn configure_fonts_loads_bundled_fallback_glyphs() {
+        let ctx = egui::Context::default();
+        let _ = configure_fonts(&ctx, &FontSettings::default());
         let _ = ctx.run(egui::RawInput::default(), |_| {});
         let body_font = egui::TextStyle::Body.resolve(&ctx.style());
         let has_glyphs = ctx.fonts_mut(|fonts| {
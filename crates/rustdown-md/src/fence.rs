@@ -1,11 +1,14 @@
 #![forbid(unsafe_code)]
 
+/// An open code fence: its marker byte (`` ` `` or `~`) and length.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct FenceState {
     marker: u8,
     marker_len: usize,
 }
 
+/// Track fenced code blocks line by line.  Opens or closes `state` when
+/// `line` is a matching fence delimiter and returns whether it was one.
 #[inline]
 pub fn consume_fence_delimiter(line: &str, state: &mut Option<FenceState>) -> bool {
     let Some((marker, marker_len, rest)) = parse_fence_marker(line) else {
@@ -32,6 +35,21 @@ pub fn consume_fence_delimiter(line: &str, state: &mut Option<FenceState>) -> bo
     }
 }
 
+/// Return the 1-based line number of a code fence that is still open at the
+/// end of `source`, or `None` when every fence is closed.
+#[must_use]
+pub fn unclosed_fence_line(source: &str) -> Option<usize> {
+    let mut state = None;
+    let mut open_line = 0;
+    for (idx, line) in source.lines().enumerate() {
+        let was_open = state.is_some();
+        if consume_fence_delimiter(line, &mut state) && !was_open {
+            open_line = idx + 1;
+        }
+    }
+    state.map(|_| open_line)
+}
+
 fn parse_fence_marker(line: &str) -> Option<(u8, usize, &str)> {
     // CommonMark §4.5: code fences may be indented 0-3 spaces only.
     let trimmed = line.trim_start();
@@ -136,6 +154,24 @@ mod tests {
         assert!(state.is_none());
     }
 
+    #[test]
+    fn unclosed_fence_line_reports_the_open_fence() {
+        assert_eq!(unclosed_fence_line(""), None);
+        assert_eq!(unclosed_fence_line("# Title\n\ntext\n"), None);
+        assert_eq!(unclosed_fence_line("```rust\nfn f() {}\n```\n"), None);
+        assert_eq!(
+            unclosed_fence_line("intro\n\n```rust\nfn f() {}\n"),
+            Some(3)
+        );
+        // The first fence closes; the second stays open.
+        assert_eq!(
+            unclosed_fence_line("```\na\n```\n\n~~~~\nb\n~~~\n"),
+            Some(5)
+        );
+        // An opener at the very end of the document counts.
+        assert_eq!(unclosed_fence_line("text\n```"), Some(2));
+    }
+
     #[test]
     fn close_fence_four_space_indent_rejected() {
         let mut state = None;
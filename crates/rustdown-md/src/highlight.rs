@@ -1,8 +1,8 @@
 #![forbid(unsafe_code)]
 
-use eframe::egui;
-
-use crate::markdown_fence::{FenceState, consume_fence_delimiter};
+use crate::fence::{FenceState, consume_fence_delimiter};
+use crate::style::CodeColors;
+use crate::title::front_matter_len;
 
 /// Index into a small, pre-built array of `TextFormat` values so that
 /// section construction only needs a cheap copy of the index, not a
@@ -10,20 +10,23 @@ use crate::markdown_fence::{FenceState, consume_fence_delimiter};
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 enum FmtIdx {
     Base,
-    InlineCode,
+    CodeBlock,
     Heading(usize),
     Table,
 }
 
+/// Editor colour for a heading of `level`: the heading palette for the
+/// current theme, or the hyperlink colour when `color_mode` is off.
+#[must_use]
 pub fn heading_color(visuals: &egui::Visuals, level: usize, color_mode: bool) -> egui::Color32 {
     if !color_mode {
         return visuals.hyperlink_color;
     }
 
     let palette = if visuals.dark_mode {
-        &rustdown_md::DARK_HEADING_COLORS
+        &crate::DARK_HEADING_COLORS
     } else {
-        &rustdown_md::LIGHT_HEADING_COLORS
+        &crate::LIGHT_HEADING_COLORS
     };
     palette[level.saturating_sub(1).min(palette.len() - 1)]
 }
@@ -33,13 +36,13 @@ pub fn heading_color(visuals: &egui::Visuals, level: usize, color_mode: bool) ->
 const fn resolve_format_ref<'a>(
     idx: FmtIdx,
     base: &'a egui::TextFormat,
-    inline_code: &'a egui::TextFormat,
+    code_block: &'a egui::TextFormat,
     heading_formats: &'a [egui::TextFormat; 6],
     table_format: &'a egui::TextFormat,
 ) -> &'a egui::TextFormat {
     match idx {
         FmtIdx::Base => base,
-        FmtIdx::InlineCode => inline_code,
+        FmtIdx::CodeBlock => code_block,
         FmtIdx::Heading(level) => &heading_formats[level - 1],
         FmtIdx::Table => table_format,
     }
@@ -62,12 +65,39 @@ fn push_section(
     }
 }
 
+/// Highlight Markdown `source` into a `LayoutJob` holding the whole text,
+/// with headings scaled by `heading_scales` and coloured per
+/// [`heading_color`], and inline code, fences and tables in monospace.
 #[must_use]
 pub fn markdown_layout_job(
     style: &egui::Style,
     visuals: &egui::Visuals,
     source: &str,
     heading_color_mode: bool,
+    heading_scales: &[f32; 6],
+) -> egui::text::LayoutJob {
+    markdown_layout_job_with_code_colors(
+        style,
+        visuals,
+        source,
+        heading_color_mode,
+        heading_scales,
+        CodeColors::default(),
+        false,
+    )
+}
+
+/// [`markdown_layout_job`] with inline code and fenced code lines in
+/// `code_colors`, and with `wiki_links` `[[wiki links]]` in link colour.
+#[must_use]
+pub fn markdown_layout_job_with_code_colors(
+    style: &egui::Style,
+    visuals: &egui::Visuals,
+    source: &str,
+    heading_color_mode: bool,
+    heading_scales: &[f32; 6],
+    code_colors: CodeColors,
+    wiki_links: bool,
 ) -> egui::text::LayoutJob {
     // Set the text once; all sections reference byte ranges into it.
     let mut job = egui::text::LayoutJob {
@@ -84,7 +114,6 @@ pub fn markdown_layout_job(
         w.color = visuals.weak_text_color();
         w
     };
-    let heading_scales = rustdown_md::HEADING_FONT_SCALES;
     let heading_formats = std::array::from_fn(|idx| {
         let mut format = base.clone();
         format.font_id.size *= heading_scales[idx];
@@ -94,7 +123,18 @@ pub fn markdown_layout_job(
 
     let mut inline_code = base.clone();
     inline_code.font_id = code_font.clone();
-    inline_code.background = visuals.faint_bg_color;
+    inline_code.background = code_colors.inline_bg.unwrap_or(visuals.faint_bg_color);
+    inline_code.color = code_colors.inline_text.unwrap_or(base.color);
+
+    let mut code_block = inline_code.clone();
+    code_block.background = code_colors.block_bg.unwrap_or(visuals.faint_bg_color);
+    code_block.color = code_colors.block_text.unwrap_or(base.color);
+
+    let wiki_link = wiki_links.then(|| {
+        let mut format = base.clone();
+        format.color = visuals.hyperlink_color;
+        format
+    });
 
     let mut table_format = base.clone();
     table_format.font_id = code_font;
@@ -110,7 +150,7 @@ pub fn markdown_layout_job(
         |job: &mut egui::text::LayoutJob, fmt: &Option<FmtIdx>, start: usize, end: usize| {
             if let Some(idx) = *fmt {
                 let format =
-                    resolve_format_ref(idx, &base, &inline_code, &heading_formats, &table_format);
+                    resolve_format_ref(idx, &base, &code_block, &heading_formats, &table_format);
                 push_section(job, start..end, format.clone());
             }
         };
@@ -130,6 +170,30 @@ pub fn markdown_layout_job(
     let mut in_fence: Option<FenceState> = None;
     let bytes = source.as_bytes();
     let mut offset = 0usize;
+    // Level of the setext underline expected on the next line, set when a
+    // text line is styled as its heading.
+    let mut setext_underline: Option<usize> = None;
+
+    // Front matter isn't Markdown: its fields are plain text, so the field
+    // above the closing `---` isn't a setext heading, and its delimiters
+    // are dimmed like the thematic breaks they resemble.
+    if let Some(front) = front_matter_len(source) {
+        let open_end = memchr::memchr(b'\n', bytes).map_or(front, |pos| pos + 1);
+        let block = &source[..front];
+        let close_start = block
+            .strip_suffix('\n')
+            .unwrap_or(block)
+            .rfind('\n')
+            .map_or(open_end, |pos| pos + 1);
+        push_section(&mut job, 0..open_end, weak.clone());
+        if open_end < close_start {
+            extend_run!(FmtIdx::Base, open_end, close_start);
+            flush(&mut job, &pending_fmt, pending_start, pending_end);
+            pending_fmt = None;
+        }
+        push_section(&mut job, close_start..front, weak.clone());
+        offset = front;
+    }
 
     while offset < bytes.len() {
         let line_start = offset;
@@ -145,7 +209,11 @@ pub fn markdown_layout_job(
             continue;
         }
         if in_fence.is_some() {
-            extend_run!(FmtIdx::InlineCode, line_start, line_end);
+            extend_run!(FmtIdx::CodeBlock, line_start, line_end);
+            continue;
+        }
+        if let Some(level) = setext_underline.take() {
+            extend_run!(FmtIdx::Heading(level), line_start, line_end);
             continue;
         }
 
@@ -161,18 +229,43 @@ pub fn markdown_layout_job(
             }
         }
 
+        // A `---` directly under text was taken as a setext underline above;
+        // anywhere else it (like `***` and `___`) is a thematic break.
+        if is_thematic_break(line) {
+            flush(&mut job, &pending_fmt, pending_start, pending_end);
+            pending_fmt = None;
+            push_section(&mut job, line_start..line_end, weak.clone());
+            continue;
+        }
+
+        // Setext heading: a paragraph line followed by `===` (H1) or `---`
+        // (H2).  After a blank line `---` is a thematic break instead, which
+        // `is_setext_text` rules out.
+        if indent_ok && is_setext_text(trimmed) {
+            let next_end =
+                memchr::memchr(b'\n', &bytes[offset..]).map_or(bytes.len(), |pos| offset + pos + 1);
+            if let Some(level) = setext_underline_level(&source[offset..next_end]) {
+                extend_run!(FmtIdx::Heading(level), line_start, line_end);
+                setext_underline = Some(level);
+                continue;
+            }
+        }
+
         // Table rows: lines starting with `|` (pipe-delimited).
         if trimmed.as_bytes().first() == Some(&b'|') {
             extend_run!(FmtIdx::Table, line_start, line_end);
             continue;
         }
 
-        if memchr::memchr(b'`', line.as_bytes()).is_none() {
+        if memchr::memchr(b'`', line.as_bytes()).is_none()
+            && (wiki_link.is_none() || memchr::memmem::find(line.as_bytes(), b"[[").is_none())
+        {
             extend_run!(FmtIdx::Base, line_start, line_end);
             continue;
         }
 
-        // Line contains inline code - emit individual sections for each fragment.
+        // Line contains inline code or a wiki link - emit individual
+        // sections for each fragment.
         flush(&mut job, &pending_fmt, pending_start, pending_end);
         pending_fmt = None;
         emit_inline_code_sections(
@@ -183,6 +276,7 @@ pub fn markdown_layout_job(
             &base,
             &weak,
             &inline_code,
+            wiki_link.as_ref(),
         );
     }
 
@@ -190,8 +284,75 @@ pub fn markdown_layout_job(
     job
 }
 
-/// Emit layout sections for a line that contains inline backtick code spans.
-/// Uses `FmtIdx` to defer format resolution, matching the batched-run path.
+/// Returns `true` if `trimmed` (a line without its indentation) can be the
+/// text of a setext heading: a non-blank line that doesn't open another
+/// block (quote, list item, table row, ATX heading).
+fn is_setext_text(trimmed: &str) -> bool {
+    let bytes = trimmed.as_bytes();
+    let Some(&first) = bytes.first() else {
+        return false;
+    };
+    if trimmed.trim_end().is_empty() || matches!(first, b'#' | b'>' | b'|') {
+        return false;
+    }
+    let bullet =
+        matches!(first, b'-' | b'*' | b'+') && bytes.get(1).is_none_or(u8::is_ascii_whitespace);
+    let digits = bytes.iter().take_while(|b| b.is_ascii_digit()).count();
+    let ordered = (1..=9).contains(&digits)
+        && matches!(bytes.get(digits), Some(b'.' | b')'))
+        && bytes.get(digits + 1).is_none_or(u8::is_ascii_whitespace);
+    !bullet && !ordered
+}
+
+/// The heading level a setext underline gives the line above it: `1` for a
+/// run of `=`, `2` for a run of `-`, indented at most three spaces.
+fn setext_underline_level(line: &str) -> Option<usize> {
+    let content = line
+        .trim_end_matches(['\n', '\r'])
+        .trim_end_matches([' ', '\t']);
+    let marks = content.trim_start_matches(' ');
+    if content.len() - marks.len() > 3 {
+        return None;
+    }
+    let mark = *marks.as_bytes().first()?;
+    let level = match mark {
+        b'=' => 1,
+        b'-' => 2,
+        _ => return None,
+    };
+    marks.bytes().all(|b| b == mark).then_some(level)
+}
+
+/// Returns `true` for a thematic break: three or more `-`, `*` or `_`
+/// (all the same), optionally separated by spaces or tabs, indented at most
+/// three spaces.
+fn is_thematic_break(line: &str) -> bool {
+    let content = line.trim_end_matches(['\n', '\r']);
+    let marks = content.trim_start_matches(' ');
+    if content.len() - marks.len() > 3 {
+        return false;
+    }
+    let Some(&mark) = marks.as_bytes().first() else {
+        return false;
+    };
+    if !matches!(mark, b'-' | b'*' | b'_') {
+        return false;
+    }
+    let mut count = 0;
+    for b in marks.bytes() {
+        match b {
+            b' ' | b'\t' => {}
+            b if b == mark => count += 1,
+            _ => return false,
+        }
+    }
+    count >= 3
+}
+
+/// Emit layout sections for a line that contains inline backtick code spans
+/// or `[[wiki links]]`, the links in `wiki_link` colour (if given) outside
+/// the code.
+#[allow(clippy::too_many_arguments)]
 fn emit_inline_code_sections(
     job: &mut egui::text::LayoutJob,
     line_start: usize,
@@ -200,6 +361,7 @@ fn emit_inline_code_sections(
     base: &egui::TextFormat,
     weak: &egui::TextFormat,
     inline_code: &egui::TextFormat,
+    wiki_link: Option<&egui::TextFormat>,
 ) {
     let mut pos = line_start;
     let line_bytes = line.as_bytes();
@@ -216,10 +378,26 @@ fn emit_inline_code_sections(
                 });
             }
         };
+    // Text outside code: wiki links in link colour, their brackets weak.
+    let text = |job: &mut egui::text::LayoutJob, range: std::ops::Range<usize>| {
+        let mut pos = range.start;
+        while let Some(wiki_link) = wiki_link
+            && let Some((open, close)) =
+                find_wiki_link(&line[pos - line_start..range.end - line_start])
+        {
+            let (open, close) = (pos + open, pos + close);
+            push(job, pos..open, base);
+            push(job, open..open + 2, weak);
+            push(job, open + 2..close, wiki_link);
+            push(job, close..close + 2, weak);
+            pos = close + 2;
+        }
+        push(job, pos..range.end, base);
+    };
 
     while let Some(tick_rel) = memchr::memchr(b'`', &line_bytes[i..]) {
         let tick_i = i + tick_rel;
-        push(job, pos..line_start + tick_i, base);
+        text(job, pos..line_start + tick_i);
         if let Some(close) = memchr::memchr(b'`', &line_bytes[tick_i + 1..]) {
             let tick_start = line_start + tick_i;
             let code_start = tick_start + 1;
@@ -232,12 +410,28 @@ fn emit_inline_code_sections(
             i = tick_i + 1 + close + 1;
         } else {
             push(job, line_start + tick_i..line_start + tick_i + 1, weak);
-            push(job, line_start + tick_i + 1..line_end, base);
+            text(job, line_start + tick_i + 1..line_end);
             pos = line_end;
             i = line_bytes.len();
         }
     }
-    push(job, pos..line_end, base);
+    text(job, pos..line_end);
+}
+
+/// Offsets of the `[[` and `]]` of the first wiki link in `text`: a
+/// non-empty target without brackets, on one line.
+fn find_wiki_link(text: &str) -> Option<(usize, usize)> {
+    let mut from = 0;
+    loop {
+        let open = from + memchr::memmem::find(&text.as_bytes()[from..], b"[[")?;
+        let inner = &text[open + 2..];
+        match inner.find(['[', ']', '\n']) {
+            Some(end) if end > 0 && inner[end..].starts_with("]]") => {
+                return Some((open, open + 2 + end));
+            }
+            _ => from = open + 1,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -266,12 +460,86 @@ mod tests {
         section.unwrap_or_else(|| unreachable!())
     }
 
+    #[test]
+    fn markdown_layout_job_colours_wiki_links_outside_code() {
+        let style = egui::Style::default();
+        let visuals = egui::Visuals::dark();
+        let source = "See [[Other Note]] and `[[not a link]]`, [[]] or [[x\n";
+        let plain =
+            markdown_layout_job(&style, &visuals, source, false, &crate::HEADING_FONT_SCALES);
+        assert_ne!(
+            section_for_snippet(&plain, "Other Note").format.color,
+            visuals.hyperlink_color,
+            "wiki links are plain text unless enabled"
+        );
+        let job = markdown_layout_job_with_code_colors(
+            &style,
+            &visuals,
+            source,
+            false,
+            &crate::HEADING_FONT_SCALES,
+            CodeColors::default(),
+            true,
+        );
+        let link = section_for_snippet(&job, "Other Note");
+        assert_eq!(link.format.color, visuals.hyperlink_color);
+        let brackets = job.sections.iter().find(|s| s.byte_range == (4..6));
+        assert!(
+            brackets.is_some_and(|s| s.format.color == visuals.weak_text_color()),
+            "brackets are their own weak section"
+        );
+        let code = section_for_snippet(&job, "not a link");
+        assert_eq!(code.format.background, visuals.faint_bg_color);
+        assert_ne!(
+            section_for_snippet(&job, "[[]]").format.color,
+            visuals.hyperlink_color
+        );
+        assert_eq!(find_wiki_link("[[[x]] y"), Some((1, 4)));
+        assert_eq!(find_wiki_link("[[a]b]]"), None);
+    }
+
+    #[test]
+    fn markdown_layout_job_uses_configured_code_colors() {
+        let style = egui::Style::default();
+        let visuals = egui::Visuals::light();
+        let colors = CodeColors {
+            inline_bg: Some(egui::Color32::YELLOW),
+            inline_text: Some(egui::Color32::RED),
+            block_bg: Some(egui::Color32::BLACK),
+            block_text: None,
+        };
+        let source = "Run `make` first
+```
+make all
+```
+";
+        let job = markdown_layout_job_with_code_colors(
+            &style,
+            &visuals,
+            source,
+            false,
+            &crate::HEADING_FONT_SCALES,
+            colors,
+            false,
+        );
+        let inline = section_for_snippet(&job, "make");
+        assert_eq!(
+            (inline.format.background, inline.format.color),
+            (egui::Color32::YELLOW, egui::Color32::RED)
+        );
+        let block = section_for_snippet(&job, "make all");
+        assert_eq!(
+            (block.format.background, block.format.color),
+            (egui::Color32::BLACK, visuals.text_color())
+        );
+    }
+
     #[test]
     fn markdown_layout_job_marks_fence_content_and_delimiters() {
         let style = egui::Style::default();
         let visuals = egui::Visuals::dark();
         let source = "~~~azurecli\naz aks list\n~~~\n~~~bash\necho hi\n~~~\n";
-        let job = markdown_layout_job(&style, &visuals, source, false);
+        let job = markdown_layout_job(&style, &visuals, source, false, &crate::HEADING_FONT_SCALES);
         let code_section = section_for_snippet(&job, "az aks list");
         assert_eq!(code_section.format.background, visuals.faint_bg_color);
         assert_eq!(
@@ -287,8 +555,10 @@ mod tests {
         let style = egui::Style::default();
         let visuals = egui::Visuals::dark();
         let source = "# Top\n## Next\n";
-        let default_job = markdown_layout_job(&style, &visuals, source, false);
-        let color_job = markdown_layout_job(&style, &visuals, source, true);
+        let default_job =
+            markdown_layout_job(&style, &visuals, source, false, &crate::HEADING_FONT_SCALES);
+        let color_job =
+            markdown_layout_job(&style, &visuals, source, true, &crate::HEADING_FONT_SCALES);
 
         let default_h1 = section_for_snippet(&default_job, "Top");
         let default_h2 = section_for_snippet(&default_job, "Next");
@@ -308,7 +578,13 @@ mod tests {
         let visuals = egui::Visuals::dark();
 
         // Normal inline code with delimiters.
-        let job = markdown_layout_job(&style, &visuals, "Use `foo` here\n", false);
+        let job = markdown_layout_job(
+            &style,
+            &visuals,
+            "Use `foo` here\n",
+            false,
+            &crate::HEADING_FONT_SCALES,
+        );
         let code = section_for_snippet(&job, "foo");
         assert_eq!(code.format.background, visuals.faint_bg_color);
         assert_eq!(
@@ -326,7 +602,13 @@ mod tests {
         );
 
         // Unmatched backtick emits weak section.
-        let job = markdown_layout_job(&style, &visuals, "text `orphan\n", false);
+        let job = markdown_layout_job(
+            &style,
+            &visuals,
+            "text `orphan\n",
+            false,
+            &crate::HEADING_FONT_SCALES,
+        );
         let tick = job
             .sections
             .iter()
@@ -338,7 +620,13 @@ mod tests {
         );
 
         // Multiple inline code spans.
-        let job = markdown_layout_job(&style, &visuals, "`a` and `b`\n", false);
+        let job = markdown_layout_job(
+            &style,
+            &visuals,
+            "`a` and `b`\n",
+            false,
+            &crate::HEADING_FONT_SCALES,
+        );
         assert_eq!(
             section_for_snippet(&job, "a").format.background,
             visuals.faint_bg_color
@@ -350,7 +638,7 @@ mod tests {
 
         // Double backtick doesn't panic and covers all bytes.
         let source = "Use ``double`` backticks\n";
-        let job = markdown_layout_job(&style, &visuals, source, false);
+        let job = markdown_layout_job(&style, &visuals, source, false, &crate::HEADING_FONT_SCALES);
         let covered: usize = job
             .sections
             .iter()
@@ -394,12 +682,18 @@ mod tests {
         let style = egui::Style::default();
         let visuals = egui::Visuals::dark();
 
-        let job = markdown_layout_job(&style, &visuals, "just plain text\n", false);
+        let job = markdown_layout_job(
+            &style,
+            &visuals,
+            "just plain text\n",
+            false,
+            &crate::HEADING_FONT_SCALES,
+        );
         assert_eq!(job.sections.len(), 1);
         assert_eq!(job.sections[0].byte_range, 0..16);
         assert_eq!(job.sections[0].format.color, visuals.text_color());
 
-        let job = markdown_layout_job(&style, &visuals, "", false);
+        let job = markdown_layout_job(&style, &visuals, "", false, &crate::HEADING_FONT_SCALES);
         assert!(job.sections.is_empty());
     }
 
@@ -408,7 +702,7 @@ mod tests {
         let style = egui::Style::default();
         let visuals = egui::Visuals::dark();
         let source = "| A | B |\n|---|---|\n| 1 | 2 |\n";
-        let job = markdown_layout_job(&style, &visuals, source, false);
+        let job = markdown_layout_job(&style, &visuals, source, false, &crate::HEADING_FONT_SCALES);
         let header_sec = section_for_snippet(&job, "| A | B |");
         assert_eq!(header_sec.format.color, visuals.weak_text_color());
         assert_eq!(
@@ -418,7 +712,7 @@ mod tests {
 
         // All pipe-lines batched into one section.
         let source = "| A |\n| B |\n| C |\n";
-        let job = markdown_layout_job(&style, &visuals, source, false);
+        let job = markdown_layout_job(&style, &visuals, source, false, &crate::HEADING_FONT_SCALES);
         assert_eq!(job.sections.len(), 1);
         assert_eq!(job.sections[0].byte_range, 0..source.len());
     }
@@ -431,14 +725,26 @@ mod tests {
         let visuals = egui::Visuals::dark();
 
         // Invalid backtick fence (backtick in info) — content is base-styled.
-        let job = markdown_layout_job(&style, &visuals, "```foo`bar\nsome text\n```\n", false);
+        let job = markdown_layout_job(
+            &style,
+            &visuals,
+            "```foo`bar\nsome text\n```\n",
+            false,
+            &crate::HEADING_FONT_SCALES,
+        );
         assert_eq!(
             section_for_snippet(&job, "some text").format.color,
             visuals.text_color()
         );
 
         // Heading with inline code — styled entirely as heading.
-        let job = markdown_layout_job(&style, &visuals, "# Title with `code`\n", false);
+        let job = markdown_layout_job(
+            &style,
+            &visuals,
+            "# Title with `code`\n",
+            false,
+            &crate::HEADING_FONT_SCALES,
+        );
         let sec = section_for_snippet(&job, "Title with `code`");
         assert_ne!(sec.format.color, visuals.text_color());
         assert!(sec.format.font_id.size > egui::TextStyle::Body.resolve(&style).size);
@@ -449,6 +755,7 @@ mod tests {
             &visuals,
             "```really-long-language-name\ncontent\n```\n",
             false,
+            &crate::HEADING_FONT_SCALES,
         );
         assert_eq!(
             section_for_snippet(&job, "```really-long-language-name")
@@ -464,13 +771,85 @@ mod tests {
 
     // ── Indentation-limit and edge-case tests ──────────────────────
 
+    #[test]
+    fn setext_headings_style_text_and_underline() {
+        let style = egui::Style::default();
+        let visuals = egui::Visuals::dark();
+        let job = |source: &str| {
+            markdown_layout_job(&style, &visuals, source, true, &crate::HEADING_FONT_SCALES)
+        };
+        let h1 = heading_color(&visuals, 1, true);
+        let h2 = heading_color(&visuals, 2, true);
+        let text = visuals.text_color();
+
+        let doc = job("Title\n=====\n\nSub `code`\n  ---\nbody\n");
+        assert_eq!(section_for_snippet(&doc, "Title").format.color, h1);
+        assert_eq!(section_for_snippet(&doc, "=====").format.color, h1);
+        assert_eq!(section_for_snippet(&doc, "Sub `code`").format.color, h2);
+        assert_eq!(section_for_snippet(&doc, "  ---").format.color, h2);
+        assert_eq!(section_for_snippet(&doc, "body").format.color, text);
+
+        let rule = |source: &str, snippet: &str| {
+            let doc = job(source);
+            section_for_snippet(&doc, snippet).format.color
+        };
+        assert_eq!(rule("para\n\n---\n", "---"), visuals.weak_text_color());
+        assert_eq!(rule("- item\n---\n", "---"), visuals.weak_text_color());
+        assert_eq!(rule("text\n* * *\n", "* * *"), visuals.weak_text_color());
+        assert_eq!(rule("___\n", "___"), visuals.weak_text_color());
+        assert_eq!(rule("text\n---\n", "---"), h2);
+        assert_eq!(rule("a --- b\n", "a --- b"), text);
+        assert_eq!(rule("--\n", "--"), text);
+
+        // Front matter fields are plain and its delimiters dimmed; a setext
+        // heading can follow it.
+        let doc = job("---\ntitle: x\n---\nbody\n---\n");
+        let color_at = |byte: usize| {
+            doc.sections
+                .iter()
+                .find(|section| section.byte_range.contains(&byte))
+                .map(|section| section.format.color)
+        };
+        let weak = Some(visuals.weak_text_color());
+        assert_eq!(color_at(0), weak);
+        assert_eq!(color_at(4), Some(text));
+        assert_eq!(color_at(13), weak);
+        assert_eq!(color_at(17), Some(h2));
+        assert_eq!(color_at(22), Some(h2));
+
+        // After a blank line, in lists and quotes, `---` is a thematic break
+        // and `===` plain text.
+        for source in [
+            "para\n\n---\n",
+            "- item\n---\n",
+            "> quote\n---\n",
+            "1. item\n===\n",
+            "text\n- - -\n",
+            "text\n    ===\n",
+        ] {
+            let doc = job(source);
+            for section in &doc.sections {
+                assert!(
+                    section.format.color != h1 && section.format.color != h2,
+                    "{source:?}"
+                );
+            }
+        }
+    }
+
     #[test]
     fn indentation_affects_heading_and_fence_detection() {
         let style = egui::Style::default();
         let visuals = egui::Visuals::dark();
 
         // 4-space indented heading is NOT styled as heading.
-        let job = markdown_layout_job(&style, &visuals, "    # Not a heading\n", false);
+        let job = markdown_layout_job(
+            &style,
+            &visuals,
+            "    # Not a heading\n",
+            false,
+            &crate::HEADING_FONT_SCALES,
+        );
         assert_eq!(job.sections.len(), 1);
         assert_eq!(
             job.sections[0].format.color,
@@ -479,12 +858,24 @@ mod tests {
         );
 
         // 3-space indented heading IS styled as heading.
-        let job = markdown_layout_job(&style, &visuals, "   # Heading\n", false);
+        let job = markdown_layout_job(
+            &style,
+            &visuals,
+            "   # Heading\n",
+            false,
+            &crate::HEADING_FONT_SCALES,
+        );
         let sec = section_for_snippet(&job, "Heading");
         assert_ne!(sec.format.color, visuals.text_color(), "3-space heading");
 
         // 4-space indented fence content is NOT fenced-code-styled.
-        let job = markdown_layout_job(&style, &visuals, "    ```rust\n    code\n    ```\n", false);
+        let job = markdown_layout_job(
+            &style,
+            &visuals,
+            "    ```rust\n    code\n    ```\n",
+            false,
+            &crate::HEADING_FONT_SCALES,
+        );
         let sec = section_for_snippet(&job, "code");
         assert_ne!(
             sec.format.background, visuals.faint_bg_color,
@@ -510,7 +901,7 @@ mod tests {
         // The editor scanner treats each backtick independently, so "double"
         // gets base format instead of inline-code format.
         let source = "Use ``double`` backticks\n";
-        let job = markdown_layout_job(&style, &visuals, source, false);
+        let job = markdown_layout_job(&style, &visuals, source, false, &crate::HEADING_FONT_SCALES);
 
         // Find the section covering the word "double".
         let sec = section_for_snippet(&job, "double");
@@ -531,7 +922,13 @@ mod tests {
         let style = egui::Style::default();
         let visuals = egui::Visuals::dark();
 
-        let job = markdown_layout_job(&style, &visuals, "Use `code` here\n", false);
+        let job = markdown_layout_job(
+            &style,
+            &visuals,
+            "Use `code` here\n",
+            false,
+            &crate::HEADING_FONT_SCALES,
+        );
         let sec = section_for_snippet(&job, "code");
 
         let mono_size = egui::TextStyle::Monospace.resolve(&style).size;
@@ -557,7 +954,7 @@ mod tests {
         let style = egui::Style::default();
         let visuals = egui::Visuals::dark();
         let source = "> A block quote.\n> Second line.\n";
-        let job = markdown_layout_job(&style, &visuals, source, false);
+        let job = markdown_layout_job(&style, &visuals, source, false, &crate::HEADING_FONT_SCALES);
         let sec = section_for_snippet(&job, "> A block quote.");
         // DOCUMENTS GAP: blockquotes get base text color, not weak/indented
         assert_eq!(
@@ -573,7 +970,7 @@ mod tests {
         let style = egui::Style::default();
         let visuals = egui::Visuals::dark();
         let source = "- Item one\n- Item two\n";
-        let job = markdown_layout_job(&style, &visuals, source, false);
+        let job = markdown_layout_job(&style, &visuals, source, false, &crate::HEADING_FONT_SCALES);
         let sec = section_for_snippet(&job, "- Item one");
         assert_eq!(
             sec.format.color,
@@ -588,7 +985,7 @@ mod tests {
         let style = egui::Style::default();
         let visuals = egui::Visuals::dark();
         let source = "Some **bold** and *italic* text.\n";
-        let job = markdown_layout_job(&style, &visuals, source, false);
+        let job = markdown_layout_job(&style, &visuals, source, false, &crate::HEADING_FONT_SCALES);
         let sec = section_for_snippet(&job, "bold");
         // DOCUMENTS GAP: bold text has same format as regular text
         assert_eq!(
@@ -606,7 +1003,7 @@ mod tests {
         let style = egui::Style::default();
         let visuals = egui::Visuals::dark();
         let source = "## Heading with `code` inside\n";
-        let job = markdown_layout_job(&style, &visuals, source, false);
+        let job = markdown_layout_job(&style, &visuals, source, false, &crate::HEADING_FONT_SCALES);
         let sec = section_for_snippet(&job, "code");
         // In editor: the `code` part is styled as heading (scaled font, heading color)
         // In preview: `code` would get monospace + background
@@ -627,8 +1024,8 @@ mod tests {
     fn diag_bundled_demo_produces_sections_without_gaps() {
         let style = egui::Style::default();
         let visuals = egui::Visuals::dark();
-        let demo = include_str!("bundled/demo.md");
-        let job = markdown_layout_job(&style, &visuals, demo, true);
+        let demo = include_str!("../../rustdown-gui/src/bundled/demo.md");
+        let job = markdown_layout_job(&style, &visuals, demo, true, &crate::HEADING_FONT_SCALES);
         // All bytes should be covered by sections (no rendering gaps)
         let mut covered = vec![false; demo.len()];
         for sec in &job.sections {
@@ -659,8 +1056,8 @@ mod tests {
     fn diag_bundled_verification_produces_sections_without_gaps() {
         let style = egui::Style::default();
         let visuals = egui::Visuals::dark();
-        let verif = include_str!("bundled/verification.md");
-        let job = markdown_layout_job(&style, &visuals, verif, true);
+        let verif = include_str!("../../rustdown-gui/src/bundled/verification.md");
+        let job = markdown_layout_job(&style, &visuals, verif, true, &crate::HEADING_FONT_SCALES);
         let mut covered = vec![false; verif.len()];
         for sec in &job.sections {
             for i in sec.byte_range.clone() {
@@ -0,0 +1,486 @@
+#![forbid(unsafe_code)]
+//! HTML rendering of Markdown, for export and rich clipboard copies.
+
+use std::fmt::Write as _;
+
+use pulldown_cmark::{CowStr, Event, LinkType, Parser, Tag, TagEnd, TextMergeStream};
+
+use crate::parse::{FrontMatter, ParseOptions, cmark_options, find_bare_url, sanitize_html};
+use crate::render::percent_encode_path;
+use crate::slug::{HeadingSlugger, heading_slug};
+use crate::title::{front_matter_fields, split_front_matter};
+
+/// Layout shared by every theme: a readable column, bordered tables and
+/// padded code blocks, close to the preview's look.
+const BASE_STYLE: &str = "body { max-width: 48em; margin: 2em auto; padding: 0 1em; \
+line-height: 1.5; }
+pre, code { font-family: ui-monospace, SFMono-Regular, Menlo, Consolas, monospace; font-size: 0.9em; }
+code { padding: 0.1em 0.3em; border-radius: 4px; }
+pre { padding: 0.75em 1em; overflow-x: auto; border-radius: 6px; }
+pre code { padding: 0; font-size: 1em; background: none; }
+table { border-collapse: collapse; }
+th, td { padding: 0.25em 0.6em; }
+blockquote { margin-left: 0; padding-left: 1em; }
+img { max-width: 100%; }
+h1, h2, h3, h4, h5, h6 { line-height: 1.25; margin: 1.2em 0 0.5em; }
+";
+
+/// Built-in stylesheets for standalone HTML documents.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HtmlTheme {
+    /// Dark text on white, with a system font.
+    #[default]
+    Light,
+    /// Light text on a dark background.
+    Dark,
+    /// GitHub's README look: its font stack, colours and underlined H1/H2.
+    Github,
+}
+
+impl HtmlTheme {
+    /// The theme's stylesheet, with `h1`–`h6` sized by `heading_scales`
+    /// (multiples of the body size, as in the preview).
+    #[must_use]
+    pub fn stylesheet(self, heading_scales: [f32; 6]) -> String {
+        let palette = match self {
+            Self::Light => {
+                "body { font-family: system-ui, sans-serif; color: #1f1f1f; background: #fff; }
+a { color: #0b62c4; }
+code, pre { background: #f4f4f4; }
+th, td { border: 1px solid #ccc; }
+blockquote { border-left: 3px solid #ccc; color: #555; }
+hr { border: 0; border-top: 1px solid #ddd; }
+"
+            }
+            Self::Dark => {
+                "body { font-family: system-ui, sans-serif; color: #dcdcdc; background: #1e1e1e; }
+a { color: #6cb6ff; }
+code, pre { background: #2b2b2b; }
+th, td { border: 1px solid #444; }
+blockquote { border-left: 3px solid #555; color: #aaa; }
+hr { border: 0; border-top: 1px solid #444; }
+"
+            }
+            Self::Github => {
+                "body { font-family: -apple-system, BlinkMacSystemFont, \"Segoe UI\", \"Noto Sans\", \
+Helvetica, Arial, sans-serif; color: #1f2328; background: #fff; }
+a { color: #0969da; text-decoration: none; }
+a:hover { text-decoration: underline; }
+code { background: rgba(175, 184, 193, 0.2); }
+pre { background: #f6f8fa; }
+th, td { border: 1px solid #d1d9e0; }
+tr:nth-child(2n) { background: #f6f8fa; }
+blockquote { border-left: 0.25em solid #d1d9e0; color: #59636e; }
+hr { border: 0; height: 0.25em; background: #d1d9e0; }
+h1, h2 { padding-bottom: 0.3em; border-bottom: 1px solid #d1d9e0; }
+"
+            }
+        };
+        let mut css = String::with_capacity(BASE_STYLE.len() + palette.len() + 160);
+        css.push_str(BASE_STYLE);
+        css.push_str(palette);
+        for (level, scale) in (1..).zip(heading_scales) {
+            let _ = writeln!(css, "h{level} {{ font-size: {scale}em; }}");
+        }
+        css
+    }
+}
+
+/// Render Markdown `source` as an HTML fragment, with the same extensions
+/// and `options` as the preview.
+///
+/// Headings get the preview's anchor slugs as ids, so `#section` links
+/// keep working in the exported page, and bare URLs become links as they
+/// do in the preview.  Raw HTML is escaped as text, or with
+/// [`ParseOptions::render_html`] cut down to the preview's safe subset.
+#[must_use]
+pub fn markdown_to_html(source: &str, options: ParseOptions) -> String {
+    let source = options.front_matter.body(source);
+    let mut html = String::with_capacity(source.len() + source.len() / 2);
+    let parser = TextMergeStream::new(Parser::new_ext(source, cmark_options(options)));
+    let mut events = autolink_bare_urls(parser.map(|event| match event {
+        Event::SoftBreak if options.preserve_line_breaks => Event::HardBreak,
+        Event::Start(Tag::Link {
+            link_type: link_type @ LinkType::WikiLink { .. },
+            dest_url,
+            title,
+            id,
+        }) => Event::Start(Tag::Link {
+            link_type,
+            dest_url: wiki_link_href(&dest_url).into(),
+            title,
+            id,
+        }),
+        event => event,
+    }));
+    events = sanitize_raw_html(events, options.render_html);
+    add_heading_ids(&mut events);
+    pulldown_cmark::html::push_html(&mut html, events.into_iter());
+    html
+}
+
+/// The exported link to the note `[[target]]` names: `Name.md` beside the
+/// page, percent-encoded, with a `#Heading` as its anchor slug.
+fn wiki_link_href(target: &str) -> String {
+    let (name, heading) = target.split_once('#').unwrap_or((target, ""));
+    let name = name.trim();
+    let markdown = std::path::Path::new(name)
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("md") || ext.eq_ignore_ascii_case("markdown"));
+    let mut href = percent_encode_path(name);
+    if !markdown {
+        href.push_str(".md");
+    }
+    if !heading.is_empty() {
+        href.push('#');
+        href.push_str(&heading_slug(heading));
+    }
+    href
+}
+
+/// Turn bare `http(s)://` URLs in text outside links and code blocks into
+/// links, as the preview does.
+fn autolink_bare_urls<'a>(events: impl Iterator<Item = Event<'a>>) -> Vec<Event<'a>> {
+    let mut out = Vec::new();
+    let mut skip_depth = 0_usize;
+    for event in events {
+        match &event {
+            Event::Start(Tag::Link { .. } | Tag::Image { .. } | Tag::CodeBlock(_)) => {
+                skip_depth += 1;
+            }
+            Event::End(TagEnd::Link | TagEnd::Image | TagEnd::CodeBlock) => {
+                skip_depth = skip_depth.saturating_sub(1);
+            }
+            Event::Text(text) if skip_depth == 0 && find_bare_url(text).is_some() => {
+                let mut rest: &str = text;
+                while let Some((start, end)) = find_bare_url(rest) {
+                    if start > 0 {
+                        out.push(Event::Text(CowStr::from(rest[..start].to_owned())));
+                    }
+                    let url = CowStr::from(rest[start..end].to_owned());
+                    out.push(Event::Start(Tag::Link {
+                        link_type: LinkType::Autolink,
+                        dest_url: url.clone(),
+                        title: CowStr::Borrowed(""),
+                        id: CowStr::Borrowed(""),
+                    }));
+                    out.push(Event::Text(url));
+                    out.push(Event::End(TagEnd::Link));
+                    rest = &rest[end..];
+                }
+                if !rest.is_empty() {
+                    out.push(Event::Text(CowStr::from(rest.to_owned())));
+                }
+                continue;
+            }
+            _ => {}
+        }
+        out.push(event);
+    }
+    out
+}
+
+/// Escape raw HTML as text, or with `render_html` keep only the safe tag
+/// subset and drop what hidden tags (`<script>`…) enclose.
+fn sanitize_raw_html(events: Vec<Event<'_>>, render_html: bool) -> Vec<Event<'_>> {
+    let mut hidden_depth = 0;
+    let mut out = Vec::with_capacity(events.len());
+    for event in events {
+        match event {
+            Event::Html(html) | Event::InlineHtml(html) if !render_html => {
+                out.push(Event::Text(html));
+            }
+            Event::Html(html) => {
+                out.push(Event::Html(sanitize_html(&html, &mut hidden_depth).into()));
+            }
+            Event::InlineHtml(html) => {
+                out.push(Event::InlineHtml(
+                    sanitize_html(&html, &mut hidden_depth).into(),
+                ));
+            }
+            Event::Text(_)
+            | Event::Code(_)
+            | Event::InlineMath(_)
+            | Event::DisplayMath(_)
+            | Event::SoftBreak
+            | Event::HardBreak
+                if hidden_depth > 0 => {}
+            Event::End(
+                TagEnd::Paragraph
+                | TagEnd::Heading(_)
+                | TagEnd::HtmlBlock
+                | TagEnd::Item
+                | TagEnd::TableCell,
+            ) => {
+                hidden_depth = 0;
+                out.push(event);
+            }
+            event => out.push(event),
+        }
+    }
+    out
+}
+
+/// Give each heading without an explicit `{#id}` the slug of its text.
+fn add_heading_ids(events: &mut [Event<'_>]) {
+    let mut slugger = HeadingSlugger::new();
+    let mut i = 0;
+    while i < events.len() {
+        if let Event::Start(Tag::Heading { id: None, .. }) = &events[i] {
+            let mut text = String::new();
+            let mut end = i + 1;
+            while end < events.len() && !matches!(events[end], Event::End(TagEnd::Heading(_))) {
+                if let Event::Text(t) | Event::Code(t) | Event::InlineMath(t) = &events[end] {
+                    text.push_str(t);
+                }
+                end += 1;
+            }
+            let slug = slugger.slug(&text);
+            if let Event::Start(Tag::Heading { id, .. }) = &mut events[i]
+                && !slug.is_empty()
+            {
+                *id = Some(slug.into());
+            }
+            i = end;
+        }
+        i += 1;
+    }
+}
+
+/// Render Markdown `source` as a standalone UTF-8 HTML document titled
+/// `title`, with `stylesheet` embedded (see [`HtmlTheme::stylesheet`]).
+///
+/// With [`FrontMatter::Metadata`], front matter fields become `<meta>` tags
+/// and its `title` replaces `title`.
+#[must_use]
+pub fn markdown_to_html_document(
+    source: &str,
+    title: &str,
+    stylesheet: &str,
+    options: ParseOptions,
+) -> String {
+    let body = markdown_to_html(source, options);
+    let mut meta = String::new();
+    let mut title = escape_html(title);
+    if options.front_matter == FrontMatter::Metadata {
+        let (block, _) = split_front_matter(source);
+        for (key, value) in front_matter_fields(block) {
+            if key == "title" {
+                title = escape_html(value);
+            } else {
+                let _ = writeln!(
+                    meta,
+                    "<meta name=\"{}\" content=\"{}\">",
+                    escape_html(key),
+                    escape_html(value)
+                );
+            }
+        }
+    }
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n{meta}\
+         <title>{title}</title>\n<style>\n{stylesheet}</style>\n</head>\n\
+         <body>\n{body}</body>\n</html>\n"
+    )
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            _ => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fragment_uses_preview_extensions() {
+        let html = markdown_to_html(
+            "# Hi\n\n**bold** ~~gone~~\n\n- [x] done\n- [ ] todo\n\n| a |\n|---|\n| 1 |\n",
+            ParseOptions::default(),
+        );
+        assert!(html.contains("<h1 id=\"hi\">Hi</h1>"));
+        assert!(html.contains("<strong>bold</strong> <del>gone</del>"));
+        assert!(
+            html.contains("<li><input disabled=\"\" type=\"checkbox\" checked=\"\"/>\ndone</li>"),
+            "{html}"
+        );
+        assert!(
+            html.contains("<li><input disabled=\"\" type=\"checkbox\"/>\ntodo</li>"),
+            "{html}"
+        );
+        assert!(html.contains("<table>"));
+    }
+
+    #[test]
+    fn headings_get_anchor_ids_that_links_resolve() {
+        let html = markdown_to_html(
+            "# Intro\n\nSee [intro](#intro).\n\n## Intro\n\n## Own {#custom}\n",
+            ParseOptions::default(),
+        );
+        assert!(html.contains("<h1 id=\"intro\">Intro</h1>"), "{html}");
+        assert!(html.contains("href=\"#intro\""));
+        assert!(html.contains("<h2 id=\"intro-1\">Intro</h2>"));
+        assert!(html.contains("<h2 id=\"custom\">Own</h2>"));
+    }
+
+    #[test]
+    fn bare_urls_become_links_outside_code_and_links() {
+        let html = markdown_to_html(
+            "See https://example.com/a_(b). and [x](https://x.io) \
+             `https://code.io`\n\n```\nhttps://fenced.io\n```\n",
+            ParseOptions::default(),
+        );
+        assert!(
+            html.contains(
+                "See <a href=\"https://example.com/a_(b)\">https://example.com/a_(b)</a>. and"
+            ),
+            "{html}"
+        );
+        assert!(html.contains("<a href=\"https://x.io\">x</a>"));
+        assert!(html.contains("<code>https://code.io</code>"));
+        assert!(html.contains("<code>https://fenced.io\n</code>"));
+    }
+
+    #[test]
+    fn raw_html_is_escaped_or_cut_to_the_safe_subset() {
+        let source = "<script>alert(1)</script>\n\nH<sub onclick=\"x()\">2</sub>O \
+                      <iframe src=\"e\">in</iframe> <div>kept</div>\n";
+        let escaped = markdown_to_html(source, ParseOptions::default());
+        assert!(!escaped.contains("<script"), "{escaped}");
+        assert!(escaped.contains("&lt;script&gt;alert(1)&lt;/script&gt;"));
+        assert!(escaped.contains("H&lt;sub onclick="));
+
+        let rendered = markdown_to_html(
+            source,
+            ParseOptions {
+                render_html: true,
+                ..ParseOptions::default()
+            },
+        );
+        assert!(!rendered.contains("script"), "{rendered}");
+        assert!(!rendered.contains("alert"));
+        assert!(!rendered.contains("iframe") && !rendered.contains(">in<"));
+        assert!(rendered.contains("H<sub>2</sub>O"));
+        assert!(rendered.contains("kept") && !rendered.contains("<div"));
+    }
+
+    #[test]
+    fn wiki_links_point_at_markdown_files() {
+        let html = markdown_to_html(
+            "[[Other Note]], [[a.md|A]] and [[Deep Dive#The Intro]]\n",
+            ParseOptions {
+                wiki_links: true,
+                ..ParseOptions::default()
+            },
+        );
+        assert!(
+            html.contains("<a href=\"Other%20Note.md\">Other Note</a>"),
+            "{html}"
+        );
+        assert!(html.contains("<a href=\"a.md\">A</a>"));
+        assert!(html.contains("href=\"Deep%20Dive.md#the-intro\""));
+    }
+
+    #[test]
+    fn preserve_line_breaks_emits_br() {
+        let source = "one\ntwo\n";
+        assert!(!markdown_to_html(source, ParseOptions::default()).contains("<br"));
+        let preserved = markdown_to_html(
+            source,
+            ParseOptions {
+                preserve_line_breaks: true,
+                ..ParseOptions::default()
+            },
+        );
+        assert!(preserved.contains("one<br />\ntwo"));
+    }
+
+    #[test]
+    fn smart_punctuation_follows_options() {
+        let source = "a -- \"b\"\n";
+        assert!(markdown_to_html(source, ParseOptions::default()).contains("a -- \"b\""));
+        let smart = ParseOptions {
+            smart_punctuation: true,
+            ..ParseOptions::default()
+        };
+        assert!(markdown_to_html(source, smart).contains("a \u{2013} \u{201c}b\u{201d}"));
+    }
+
+    #[test]
+    fn front_matter_is_stripped_or_becomes_metadata() {
+        let source = "---\ntitle: \"Q3 <plan>\"\nauthor: Sam\ntags:\n  - a\n---\n# Body\n";
+        let kept = markdown_to_html(source, ParseOptions::default());
+        assert!(kept.contains("author: Sam"), "{kept}");
+
+        let strip = ParseOptions {
+            front_matter: FrontMatter::Strip,
+            ..ParseOptions::default()
+        };
+        assert_eq!(
+            markdown_to_html(source, strip),
+            "<h1 id=\"body\">Body</h1>\n"
+        );
+        let doc = markdown_to_html_document(source, "file.md", "", strip);
+        assert!(doc.contains("<title>file.md</title>"));
+        assert!(!doc.contains("<meta name="));
+
+        let metadata = ParseOptions {
+            front_matter: FrontMatter::Metadata,
+            ..ParseOptions::default()
+        };
+        let doc = markdown_to_html_document(source, "file.md", "", metadata);
+        assert!(doc.contains("<title>Q3 &lt;plan&gt;</title>"), "{doc}");
+        assert!(doc.contains("<meta name=\"author\" content=\"Sam\">\n"));
+        assert!(!doc.contains("tags"), "nested values are skipped");
+        assert!(doc.contains("<body>\n<h1 id=\"body\">Body</h1>\n</body>"));
+
+        // A document without front matter renders the same either way.
+        let plain = "# Body\n\ntext\n";
+        assert_eq!(
+            markdown_to_html_document(plain, "t", "", metadata),
+            markdown_to_html_document(plain, "t", "", ParseOptions::default())
+        );
+    }
+
+    #[test]
+    fn document_wraps_body_and_escapes_title() {
+        let doc = markdown_to_html_document(
+            "text\n",
+            "a <b> & \"c\"",
+            "p { margin: 0; }\n",
+            ParseOptions::default(),
+        );
+        assert!(doc.starts_with("<!DOCTYPE html>\n"));
+        assert!(doc.contains("<meta charset=\"utf-8\">"));
+        assert!(doc.contains("<title>a &lt;b&gt; &amp; &quot;c&quot;</title>"));
+        assert!(doc.contains("<style>\np { margin: 0; }\n</style>"));
+        assert!(doc.contains("<body>\n<p>text</p>\n</body>"));
+        assert!(doc.ends_with("</html>\n"));
+    }
+
+    #[test]
+    fn themes_share_layout_and_size_headings() {
+        let scales = [2.0, 1.5, 1.25, 1.1, 1.0, 0.9];
+        let mut palettes = Vec::new();
+        for theme in [HtmlTheme::Light, HtmlTheme::Dark, HtmlTheme::Github] {
+            let css = theme.stylesheet(scales);
+            assert!(css.starts_with(BASE_STYLE));
+            assert!(css.contains("h1 { font-size: 2em; }"));
+            assert!(css.contains("h6 { font-size: 0.9em; }"));
+            palettes.push(css);
+        }
+        assert!(palettes[1].contains("background: #1e1e1e"));
+        assert!(palettes[2].contains("border-bottom"));
+        assert_ne!(palettes[0], palettes[2]);
+    }
+}
@@ -3,20 +3,39 @@
 //!
 //! Renders parsed Markdown (via `pulldown-cmark`) directly into egui widgets,
 //! supporting configurable heading colours/sizes and viewport-culled scrolling.
+//! [`markdown_layout_job`] highlights Markdown source into an egui
+//! `LayoutJob` for use as a `TextEdit` layouter.
 
+mod fence;
+mod highlight;
+mod html;
 mod parse;
+mod plain;
 pub(crate) mod render;
+mod slug;
 #[cfg(test)]
 mod stress;
 mod style;
+mod title;
 
 #[cfg(test)]
 mod bench;
 
+pub use fence::{FenceState, consume_fence_delimiter, unclosed_fence_line};
+pub use highlight::{heading_color, markdown_layout_job, markdown_layout_job_with_code_colors};
+pub use html::{HtmlTheme, markdown_to_html, markdown_to_html_document};
 pub use parse::{
-    Alignment, Block, ListItem, Span, SpanStyle, StyledText, TableData, heading_level_to_u8,
+    Alignment, Block, DefinitionItem, FrontMatter, ListItem, ParseOptions, Span, SpanStyle,
+    StyledText, TableData, WIKI_LINK_SCHEME, heading_level_to_u8, wiki_link_target,
 };
-pub use render::{MarkdownCache, MarkdownViewer, bytecount_newlines};
+pub use plain::plain_text;
+pub use render::{
+    MarkdownCache, MarkdownViewer, bytecount_newlines, percent_decode, percent_encode_path,
+    show_image_zoom, take_shown_images, zoomed_image,
+};
+pub use slug::{HeadingSlugger, heading_slug};
 pub use style::{
-    DARK_HEADING_COLORS, HEADING_FONT_SCALES, HeadingStyle, LIGHT_HEADING_COLORS, MarkdownStyle,
+    CodeColors, DARK_HEADING_COLORS, HEADING_FONT_SCALES, HeadingStyle, ImageMaxWidth,
+    LIGHT_HEADING_COLORS, MarkdownStyle,
 };
+pub use title::{document_title, front_matter_len};
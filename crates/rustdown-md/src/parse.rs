@@ -1,9 +1,12 @@
 #![forbid(unsafe_code)]
 //! Markdown parsing: converts source text into a flat list of render blocks.
 
+use std::fmt::Write as _;
 use std::rc::Rc;
 
-use pulldown_cmark::{Event, HeadingLevel, Options, Parser, Tag, TagEnd};
+use pulldown_cmark::{
+    Event, HeadingLevel, LinkType, Options, Parser, Tag, TagEnd, TextMergeStream,
+};
 
 /// A single renderable block produced by parsing.
 ///
@@ -17,6 +20,9 @@ pub enum Block {
     Heading {
         level: u8,
         text: StyledText,
+        /// Anchor set with a `{#id}` attribute, used instead of the slug.
+        /// Boxed again so the rare attribute costs headings one pointer.
+        id: Option<Box<Box<str>>>,
     },
     Paragraph(StyledText),
     Code {
@@ -36,6 +42,11 @@ pub enum Block {
         url: Box<str>,
         alt: Box<str>,
     },
+    DefinitionList(Vec<DefinitionItem>),
+    /// Display math (`$$…$$`) standing alone in its paragraph.
+    Math {
+        tex: Box<str>,
+    },
 }
 
 /// Table block data, boxed inside `Block::Table` to keep enum size down.
@@ -62,6 +73,17 @@ pub struct ListItem {
     pub children: Vec<Block>,
     /// Task-list checkbox state: `Some(true)` = checked, `Some(false)` = unchecked, `None` = normal item.
     pub checked: Option<bool>,
+    /// The item's text was a paragraph: its list is loose, with blank lines
+    /// between items.
+    pub loose: bool,
+}
+
+/// A definition-list term with its definitions (`Term` / `: definition`).
+#[derive(Clone, Debug)]
+pub struct DefinitionItem {
+    pub term: StyledText,
+    /// One entry per `:` definition, each holding its block content.
+    pub definitions: Vec<Vec<Block>>,
 }
 
 /// Styled text: a string with inline formatting spans.
@@ -82,7 +104,8 @@ pub struct StyledText {
 /// Inline formatting flags that can be combined (e.g., bold + italic).
 #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
 pub struct SpanStyle {
-    /// Bitfield: bit 0 = strong, 1 = emphasis, 2 = strikethrough, 3 = code.
+    /// Bitfield: bit 0 = strong, 1 = emphasis, 2 = strikethrough, 3 = code,
+    /// 4 = math, 5 = raw HTML, 6 = subscript, 7 = superscript.
     flags: u8,
     /// Index into `StyledText::links`, or `NO_LINK` if no link.
     pub(crate) link_idx: u8,
@@ -92,6 +115,10 @@ const FLAG_STRONG: u8 = 1;
 const FLAG_EMPHASIS: u8 = 2;
 const FLAG_STRIKETHROUGH: u8 = 4;
 const FLAG_CODE: u8 = 8;
+const FLAG_MATH: u8 = 16;
+const FLAG_HTML: u8 = 32;
+const FLAG_SUBSCRIPT: u8 = 64;
+const FLAG_SUPERSCRIPT: u8 = 128;
 const NO_LINK: u8 = u8::MAX;
 
 impl SpanStyle {
@@ -148,6 +175,31 @@ impl SpanStyle {
     pub const fn set_code(&mut self) {
         self.flags |= FLAG_CODE;
     }
+
+    #[inline]
+    #[must_use]
+    pub const fn math(self) -> bool {
+        self.flags & FLAG_MATH != 0
+    }
+
+    /// Raw HTML shown as typed, because HTML rendering is off.
+    #[inline]
+    #[must_use]
+    pub const fn html(self) -> bool {
+        self.flags & FLAG_HTML != 0
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn subscript(self) -> bool {
+        self.flags & FLAG_SUBSCRIPT != 0
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn superscript(self) -> bool {
+        self.flags & FLAG_SUPERSCRIPT != 0
+    }
 }
 
 /// An inline formatting span within a `StyledText`.
@@ -257,7 +309,7 @@ impl Default for StyledText {
 #[cfg(test)]
 pub fn parse_markdown(source: &str) -> Vec<Block> {
     let mut blocks = Vec::new();
-    parse_markdown_into(source, &mut blocks);
+    parse_markdown_into(source, &mut blocks, ParseOptions::default());
     blocks
 }
 
@@ -268,12 +320,98 @@ pub fn parse_markdown(source: &str) -> Vec<Block> {
 /// inputs. 64 MiB is generous for any realistic markdown document.
 const MAX_PARSE_BYTES: usize = 64 * 1024 * 1024;
 
+/// What the renderers do with a front matter block opening the source.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FrontMatter {
+    /// Treat it as Markdown, as `CommonMark` does.
+    #[default]
+    Keep,
+    /// Leave it out.
+    Strip,
+    /// Leave it out of the body; standalone HTML documents carry its fields
+    /// as `<meta>` tags and its `title` as the document title.
+    Metadata,
+}
+
+impl FrontMatter {
+    /// The part of `source` to render as Markdown.
+    #[must_use]
+    pub fn body(self, source: &str) -> &str {
+        match self {
+            Self::Keep => source,
+            Self::Strip | Self::Metadata => crate::title::split_front_matter(source).1,
+        }
+    }
+}
+
+/// Parser behaviour that deviates from plain `CommonMark`.
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ParseOptions {
+    /// Keep single newlines inside paragraphs as line breaks instead of
+    /// joining the lines with a space, for one-sentence-per-line writing.
+    pub preserve_line_breaks: bool,
+    /// Turn straight quotes into curly ones, `--`/`---` into en/em dashes
+    /// and `...` into an ellipsis.  Off by default so code-heavy prose keeps
+    /// the characters as typed.
+    pub smart_punctuation: bool,
+    /// Render a safe subset of HTML tags (`<br>`, `<sub>`, `<sup>`, `<kbd>`,
+    /// `<b>`, `<i>`, `<s>`, `<span>`…) in the preview, dropping scripts and
+    /// embeds.  Off shows HTML as dimmed raw text.  The HTML renderers keep
+    /// the same subset when on, and escape HTML as text when off.
+    pub render_html: bool,
+    /// Handling of a leading YAML (`---`) or TOML (`+++`) front matter block.
+    pub front_matter: FrontMatter,
+    /// Parse `[[Note]]` and `[[Note|label]]` as links to other notes.  In
+    /// the preview their URL is the target behind [`WIKI_LINK_SCHEME`], for
+    /// the host to resolve; HTML links to `Other%20Note.md` beside the page,
+    /// and plain text keeps only the label.
+    pub wiki_links: bool,
+}
+
+/// Scheme of the preview URL of a wiki link, `wiki:Other Note`.
+pub const WIKI_LINK_SCHEME: &str = "wiki:";
+
+/// The note a preview link URL made from a wiki link names, if it is one.
+#[must_use]
+pub fn wiki_link_target(url: &str) -> Option<&str> {
+    url.strip_prefix(WIKI_LINK_SCHEME)
+}
+
+/// Supported extensions: tables, strikethrough, task lists, heading
+/// attributes, GFM blockquote tags, definition lists and `$`/`$$` math.
+/// Footnotes, metadata blocks and super/subscript are not enabled;
+/// footnote references render as plain text, and HTML as dimmed raw text
+/// unless [`ParseOptions::render_html`] is set.
+const CMARK_OPTIONS: Options = Options::ENABLE_STRIKETHROUGH
+    .union(Options::ENABLE_TABLES)
+    .union(Options::ENABLE_HEADING_ATTRIBUTES)
+    .union(Options::ENABLE_TASKLISTS)
+    .union(Options::ENABLE_GFM)
+    .union(Options::ENABLE_DEFINITION_LIST)
+    .union(Options::ENABLE_MATH);
+
+/// The parser extensions for `options`: [`CMARK_OPTIONS`], plus smart
+/// punctuation and wiki links when enabled.  Every renderer parses with
+/// these so the preview, HTML and plain text agree.
+pub(crate) const fn cmark_options(options: ParseOptions) -> Options {
+    let mut cmark = CMARK_OPTIONS;
+    if options.smart_punctuation {
+        cmark = cmark.union(Options::ENABLE_SMART_PUNCTUATION);
+    }
+    if options.wiki_links {
+        cmark = cmark.union(Options::ENABLE_WIKILINKS);
+    }
+    cmark
+}
+
 /// Parse markdown source, appending blocks to an existing `Vec`.
 /// Reuses the existing allocation when possible.
 ///
 /// Sources larger than [`MAX_PARSE_BYTES`] are truncated at the last
 /// newline within the limit to prevent unbounded memory allocation.
-pub fn parse_markdown_into(source: &str, blocks: &mut Vec<Block>) {
+pub fn parse_markdown_into(source: &str, blocks: &mut Vec<Block>, options: ParseOptions) {
+    let source = options.front_matter.body(source);
     let source = if source.len() > MAX_PARSE_BYTES {
         // Truncate at the last newline within the limit for clean output.
         match source[..MAX_PARSE_BYTES].rfind('\n') {
@@ -283,23 +421,26 @@ pub fn parse_markdown_into(source: &str, blocks: &mut Vec<Block>) {
     } else {
         source
     };
-    let opts = Options::ENABLE_STRIKETHROUGH
-        | Options::ENABLE_TABLES
-        | Options::ENABLE_HEADING_ATTRIBUTES
-        | Options::ENABLE_TASKLISTS
-        | Options::ENABLE_SMART_PUNCTUATION
-        | Options::ENABLE_GFM;
-    let parser = Parser::new_ext(source, opts);
+    let parser = Parser::new_ext(source, cmark_options(options));
     // Collect into Vec — required for our indexed recursive descent.
-    // Pre-allocate based on source size heuristic.
+    // Pre-allocate based on source size heuristic.  Adjacent text events
+    // are merged so bare-URL autolink detection sees whole runs.
     let events: Vec<Event<'_>> = {
         let capacity = source.len() / 20 + 16;
         let mut v = Vec::with_capacity(capacity);
-        v.extend(parser);
+        v.extend(TextMergeStream::new(parser));
+        if options.preserve_line_breaks {
+            for event in &mut v {
+                if matches!(event, Event::SoftBreak) {
+                    *event = Event::HardBreak;
+                }
+            }
+        }
         v
     };
     blocks.reserve(events.len() / 4 + 4);
     let mut fmt = InlineState::new();
+    fmt.render_html = options.render_html;
     let mut pos = 0;
     while pos < events.len() {
         parse_block(&events, &mut pos, blocks, &mut fmt);
@@ -356,9 +497,9 @@ fn parse_block(
     fmt: &mut InlineState,
 ) {
     match &events[*pos] {
-        Event::Start(Tag::Heading { level, .. }) => {
-            let level = *level;
-            parse_heading(events, pos, level, blocks, fmt);
+        Event::Start(Tag::Heading { level, id, .. }) => {
+            let id = id.as_deref().map(|id| Box::new(Box::from(id)));
+            parse_heading(events, pos, *level, id, blocks, fmt);
         }
         Event::Start(Tag::Paragraph) => parse_paragraph(events, pos, blocks, fmt),
         Event::Start(Tag::CodeBlock(kind)) => {
@@ -386,6 +527,8 @@ fn parse_block(
                 alt: alt.into_boxed_str(),
             });
         }
+        Event::Start(Tag::DefinitionList) => parse_definition_list(events, pos, blocks, fmt),
+        Event::Start(Tag::HtmlBlock) => parse_html_block(events, pos, blocks, fmt),
         Event::Rule => {
             blocks.push(Block::ThematicBreak);
             *pos += 1;
@@ -397,10 +540,53 @@ fn parse_block(
     }
 }
 
+/// An HTML block becomes a paragraph: its raw source, or the text of the
+/// safe tag subset when HTML rendering is on.  Nothing visible is dropped.
+fn parse_html_block(
+    events: &[Event<'_>],
+    pos: &mut usize,
+    blocks: &mut Vec<Block>,
+    fmt: &mut InlineState,
+) {
+    *pos += 1;
+    let mut raw = String::new();
+    while *pos < events.len() {
+        let ev = &events[*pos];
+        *pos += 1;
+        match ev {
+            Event::End(TagEnd::HtmlBlock) => break,
+            Event::Html(html) | Event::Text(html) => raw.push_str(html),
+            _ => {}
+        }
+    }
+    fmt.clear();
+    let mut styled = StyledText::with_capacity(raw.len(), 4);
+    consume_inline(&Event::Html(raw.trim_end().into()), &mut styled, fmt);
+    // Rendered HTML can leave a collapsed space before the closing tag.
+    let trimmed = styled.text.trim_end().len();
+    if trimmed == 0 {
+        return;
+    }
+    if trimmed < styled.text.len() {
+        let removed = styled.text.len() - trimmed;
+        styled.text.truncate(trimmed);
+        styled.char_count = styled
+            .char_count
+            .saturating_sub(u32::try_from(removed).unwrap_or(u32::MAX));
+        let end = u32::try_from(trimmed).unwrap_or(u32::MAX);
+        for span in &mut styled.spans {
+            span.end = span.end.min(end);
+        }
+        styled.spans.retain(|span| span.start < span.end);
+    }
+    blocks.push(Block::Paragraph(styled));
+}
+
 fn parse_heading(
     events: &[Event<'_>],
     pos: &mut usize,
     level: HeadingLevel,
+    id: Option<Box<Box<str>>>,
     blocks: &mut Vec<Block>,
     fmt: &mut InlineState,
 ) {
@@ -423,6 +609,7 @@ fn parse_heading(
     blocks.push(Block::Heading {
         level: lvl,
         text: styled,
+        id,
     });
 }
 
@@ -435,7 +622,9 @@ fn parse_paragraph(
     // Check if this paragraph is a standalone image (the only inline content
     // inside the paragraph is a single Image tag). If so, emit Block::Image
     // instead of a paragraph containing alt text.
-    if try_parse_standalone_image(events, pos, blocks) {
+    if try_parse_standalone_image(events, pos, blocks)
+        || try_parse_display_math(events, pos, blocks)
+    {
         return;
     }
 
@@ -498,6 +687,22 @@ fn try_parse_standalone_image(
     true
 }
 
+/// If the paragraph holds nothing but one display-math expression, emit
+/// `Block::Math` and advance `pos` past the closing `End(Paragraph)`.
+fn try_parse_display_math(events: &[Event<'_>], pos: &mut usize, blocks: &mut Vec<Block>) -> bool {
+    let start = *pos;
+    match events.get(start + 1..start + 3) {
+        Some([Event::DisplayMath(tex), Event::End(TagEnd::Paragraph)]) => {
+            blocks.push(Block::Math {
+                tex: tex.trim().into(),
+            });
+            *pos = start + 3;
+            true
+        }
+        _ => false,
+    }
+}
+
 fn parse_code_block(
     events: &[Event<'_>],
     pos: &mut usize,
@@ -569,6 +774,7 @@ fn parse_list(
                 // Paragraph start/end events; subsequent paragraphs become
                 // child blocks.
                 let mut first_para_done = false;
+                let mut loose = false;
                 // Collect inline text for a secondary paragraph inside the
                 // item, to be flushed as `Block::Paragraph` into `children`.
                 let mut extra_para: Option<StyledText> = None;
@@ -586,6 +792,7 @@ fn parse_list(
                         }
                         Event::Start(Tag::Paragraph) => {
                             *pos += 1;
+                            loose = true;
                             if first_para_done {
                                 // Start collecting a new paragraph into
                                 // `extra_para`; it will be flushed on
@@ -649,6 +856,7 @@ fn parse_list(
                     content: item_text,
                     children,
                     checked,
+                    loose,
                 });
             }
             _ => *pos += 1,
@@ -661,6 +869,95 @@ fn parse_list(
     }
 }
 
+fn parse_definition_list(
+    events: &[Event<'_>],
+    pos: &mut usize,
+    blocks: &mut Vec<Block>,
+    fmt: &mut InlineState,
+) {
+    let mut items: Vec<DefinitionItem> = Vec::with_capacity(4);
+    *pos += 1;
+    while *pos < events.len() {
+        match &events[*pos] {
+            Event::End(TagEnd::DefinitionList) => {
+                *pos += 1;
+                break;
+            }
+            Event::Start(Tag::DefinitionListTitle) => {
+                let mut term = StyledText::with_capacity(32, 2);
+                *pos += 1;
+                fmt.clear();
+                while *pos < events.len() {
+                    if matches!(&events[*pos], Event::End(TagEnd::DefinitionListTitle)) {
+                        *pos += 1;
+                        break;
+                    }
+                    consume_inline(&events[*pos], &mut term, fmt);
+                    *pos += 1;
+                }
+                items.push(DefinitionItem {
+                    term,
+                    definitions: Vec::with_capacity(1),
+                });
+            }
+            Event::Start(Tag::DefinitionListDefinition) => {
+                *pos += 1;
+                let definition = parse_definition(events, pos, fmt);
+                match items.last_mut() {
+                    Some(item) => item.definitions.push(definition),
+                    None => items.push(DefinitionItem {
+                        term: StyledText::default(),
+                        definitions: vec![definition],
+                    }),
+                }
+            }
+            _ => *pos += 1,
+        }
+    }
+    blocks.push(Block::DefinitionList(items));
+}
+
+/// Parse one definition body.  Tight definitions carry bare inline
+/// events, which are gathered into a paragraph; loose ones contain blocks.
+fn parse_definition(events: &[Event<'_>], pos: &mut usize, fmt: &mut InlineState) -> Vec<Block> {
+    let mut content = Vec::with_capacity(1);
+    let mut inline = StyledText::default();
+    fmt.clear();
+    while *pos < events.len() {
+        match &events[*pos] {
+            Event::End(TagEnd::DefinitionListDefinition) => {
+                *pos += 1;
+                break;
+            }
+            Event::Start(
+                Tag::Paragraph
+                | Tag::List(_)
+                | Tag::CodeBlock(_)
+                | Tag::BlockQuote(_)
+                | Tag::Heading { .. }
+                | Tag::Table(_)
+                | Tag::HtmlBlock
+                | Tag::DefinitionList,
+            )
+            | Event::Rule => {
+                if !inline.text.is_empty() {
+                    content.push(Block::Paragraph(std::mem::take(&mut inline)));
+                }
+                parse_block(events, pos, &mut content, fmt);
+                fmt.clear();
+            }
+            ev => {
+                consume_inline(ev, &mut inline, fmt);
+                *pos += 1;
+            }
+        }
+    }
+    if !inline.text.is_empty() {
+        content.push(Block::Paragraph(inline));
+    }
+    content
+}
+
 fn parse_table(
     events: &[Event<'_>],
     pos: &mut usize,
@@ -747,6 +1044,10 @@ enum InlineFlag {
     Emphasis,
     Strikethrough,
     Link(Rc<str>),
+    /// `<sub>`, `<sup>` and `<kbd>`/`<code>` from rendered HTML.
+    Subscript,
+    Superscript,
+    Code,
 }
 
 #[derive(Clone, Debug)]
@@ -766,6 +1067,13 @@ struct InlineState {
     strong_count: u8,
     emphasis_count: u8,
     strikethrough_count: u8,
+    subscript_count: u8,
+    superscript_count: u8,
+    code_count: u8,
+    /// Open `<script>`-like tags whose content is dropped.
+    hidden_depth: u8,
+    /// Map HTML tags to formatting instead of showing them raw.
+    render_html: bool,
     /// Active links in nesting order. The cached `idx` avoids repeatedly
     /// interning the same URL for every text fragment inside one link span.
     link_stack: Vec<ActiveLink>,
@@ -778,6 +1086,11 @@ impl InlineState {
             strong_count: 0,
             emphasis_count: 0,
             strikethrough_count: 0,
+            subscript_count: 0,
+            superscript_count: 0,
+            code_count: 0,
+            hidden_depth: 0,
+            render_html: false,
             link_stack: Vec::new(),
         }
     }
@@ -788,6 +1101,10 @@ impl InlineState {
         self.strong_count = 0;
         self.emphasis_count = 0;
         self.strikethrough_count = 0;
+        self.subscript_count = 0;
+        self.superscript_count = 0;
+        self.code_count = 0;
+        self.hidden_depth = 0;
     }
 
     /// Compute the flags bitfield from counters — O(1).
@@ -803,6 +1120,15 @@ impl InlineState {
         if self.strikethrough_count > 0 {
             f |= FLAG_STRIKETHROUGH;
         }
+        if self.subscript_count > 0 {
+            f |= FLAG_SUBSCRIPT;
+        }
+        if self.superscript_count > 0 {
+            f |= FLAG_SUPERSCRIPT;
+        }
+        if self.code_count > 0 {
+            f |= FLAG_CODE;
+        }
         f
     }
 
@@ -812,6 +1138,11 @@ impl InlineState {
             InlineFlag::Strong => self.strong_count += 1,
             InlineFlag::Emphasis => self.emphasis_count += 1,
             InlineFlag::Strikethrough => self.strikethrough_count += 1,
+            InlineFlag::Subscript => self.subscript_count = self.subscript_count.saturating_add(1),
+            InlineFlag::Superscript => {
+                self.superscript_count = self.superscript_count.saturating_add(1);
+            }
+            InlineFlag::Code => self.code_count = self.code_count.saturating_add(1),
             InlineFlag::Link(url) => {
                 self.link_stack.push(ActiveLink {
                     url: Rc::clone(url),
@@ -870,6 +1201,11 @@ impl InlineState {
             InlineFlag::Strikethrough => {
                 self.strikethrough_count = self.strikethrough_count.saturating_sub(1);
             }
+            InlineFlag::Subscript => self.subscript_count = self.subscript_count.saturating_sub(1),
+            InlineFlag::Superscript => {
+                self.superscript_count = self.superscript_count.saturating_sub(1);
+            }
+            InlineFlag::Code => self.code_count = self.code_count.saturating_sub(1),
             InlineFlag::Link(_) => {
                 self.link_stack.pop();
             }
@@ -888,10 +1224,17 @@ fn consume_inline(event: &Event<'_>, styled: &mut StyledText, state: &mut Inline
         }
     }
 
+    if state.hidden_depth > 0 && !matches!(event, Event::InlineHtml(_) | Event::Html(_)) {
+        return;
+    }
     match event {
         Event::Text(t) => {
             let s = current_style(state, styled, 0);
-            styled.push_text(t, s);
+            if s.has_link() {
+                styled.push_text(t, s);
+            } else {
+                push_text_with_autolinks(styled, t, s);
+            }
         }
         Event::Code(c) => {
             let s = current_style(state, styled, FLAG_CODE);
@@ -911,6 +1254,14 @@ fn consume_inline(event: &Event<'_>, styled: &mut StyledText, state: &mut Inline
         Event::End(TagEnd::Emphasis) => state.pop(&InlineFlag::Emphasis),
         Event::Start(Tag::Strikethrough) => state.push(InlineFlag::Strikethrough),
         Event::End(TagEnd::Strikethrough) => state.pop(&InlineFlag::Strikethrough),
+        Event::Start(Tag::Link {
+            link_type: LinkType::WikiLink { .. },
+            dest_url,
+            ..
+        }) => {
+            let url = format!("{WIKI_LINK_SCHEME}{dest_url}");
+            state.push(InlineFlag::Link(Rc::from(url)));
+        }
         Event::Start(Tag::Link { dest_url, .. }) => {
             state.push(InlineFlag::Link(Rc::from(dest_url.as_ref())));
         }
@@ -921,18 +1272,242 @@ fn consume_inline(event: &Event<'_>, styled: &mut StyledText, state: &mut Inline
             styled.push_text(label, s);
             styled.push_text("]", s);
         }
+        Event::InlineHtml(html) | Event::Html(html) if state.render_html => {
+            push_rendered_html(styled, state, html);
+        }
         Event::InlineHtml(html) | Event::Html(html) => {
-            let s = current_style(state, styled, FLAG_CODE);
+            let s = current_style(state, styled, FLAG_CODE | FLAG_HTML);
             styled.push_text(html, s);
         }
         Event::InlineMath(math) | Event::DisplayMath(math) => {
-            let s = current_style(state, styled, FLAG_CODE);
+            let s = current_style(state, styled, FLAG_MATH);
             styled.push_text(math, s);
         }
         _ => {}
     }
 }
 
+/// What a tag in the rendered HTML subset does.
+#[derive(Debug, PartialEq, Eq)]
+enum HtmlTag {
+    Break,
+    Open(InlineFlag),
+    Close(InlineFlag),
+    /// A tag whose content is never shown (`<script>`, `<iframe>`…).
+    OpenHidden,
+    CloseHidden,
+    /// Any other tag or a comment: dropped, keeping the content.
+    Ignore,
+}
+
+/// Classify one tag (`<…>`) of the rendered HTML subset.
+fn classify_html_tag(tag: &str) -> HtmlTag {
+    let Some(inner) = tag.strip_prefix('<') else {
+        return HtmlTag::Ignore;
+    };
+    let (closing, inner) = match inner.strip_prefix('/') {
+        Some(rest) => (true, rest),
+        None => (false, inner),
+    };
+    let name_len = inner
+        .find(|c: char| !c.is_ascii_alphanumeric())
+        .unwrap_or(inner.len());
+    let name = inner[..name_len].to_ascii_lowercase();
+    let flag = match name.as_str() {
+        "br" => return HtmlTag::Break,
+        "b" | "strong" => InlineFlag::Strong,
+        "i" | "em" => InlineFlag::Emphasis,
+        "s" | "del" | "strike" => InlineFlag::Strikethrough,
+        "sub" => InlineFlag::Subscript,
+        "sup" => InlineFlag::Superscript,
+        "kbd" | "code" | "samp" | "tt" => InlineFlag::Code,
+        "script" | "style" | "iframe" | "object" | "embed" | "noscript" | "template"
+        | "textarea" | "title" => {
+            // A self-closing `<iframe … />` hides nothing.
+            return if closing {
+                HtmlTag::CloseHidden
+            } else if tag.ends_with("/>") {
+                HtmlTag::Ignore
+            } else {
+                HtmlTag::OpenHidden
+            };
+        }
+        _ => return HtmlTag::Ignore,
+    };
+    if closing {
+        HtmlTag::Close(flag)
+    } else {
+        HtmlTag::Open(flag)
+    }
+}
+
+/// `html` with the rendered HTML subset applied, for the HTML renderers:
+/// tags in the subset are kept without their attributes, the content of
+/// hidden tags (tracked across calls in `hidden_depth`) is dropped, and
+/// other tags are removed.  Stray `<` and `>` are escaped.
+pub(crate) fn sanitize_html(html: &str, hidden_depth: &mut u8) -> String {
+    let mut out = String::with_capacity(html.len());
+    let mut rest = html;
+    while !rest.is_empty() {
+        let (text, tag) = match rest.find('<') {
+            Some(0) => match rest.find('>') {
+                Some(end) => ("", &rest[..=end]),
+                None => (rest, ""),
+            },
+            Some(start) => (&rest[..start], ""),
+            None => (rest, ""),
+        };
+        rest = &rest[text.len() + tag.len()..];
+        if *hidden_depth == 0 {
+            out.push_str(&text.replace('<', "&lt;").replace('>', "&gt;"));
+        }
+        let name = || {
+            let name = tag.trim_start_matches(['<', '/']);
+            let len = name
+                .find(|c: char| !c.is_ascii_alphanumeric())
+                .unwrap_or(name.len());
+            name[..len].to_ascii_lowercase()
+        };
+        match classify_html_tag(tag) {
+            _ if tag.is_empty() => {}
+            HtmlTag::OpenHidden => *hidden_depth = hidden_depth.saturating_add(1),
+            HtmlTag::CloseHidden => *hidden_depth = hidden_depth.saturating_sub(1),
+            _ if *hidden_depth > 0 => {}
+            HtmlTag::Break | HtmlTag::Open(_) => {
+                let _ = write!(out, "<{}>", name());
+            }
+            HtmlTag::Close(_) => {
+                let _ = write!(out, "</{}>", name());
+            }
+            HtmlTag::Ignore => {}
+        }
+    }
+    out
+}
+
+/// Push HTML source with the safe tag subset applied: tags become
+/// formatting or line breaks and are never shown; text between them is
+/// pushed with runs of whitespace collapsed, as a browser would.
+fn push_rendered_html(styled: &mut StyledText, state: &mut InlineState, html: &str) {
+    let mut rest = html;
+    while !rest.is_empty() {
+        let (text, tag) = match rest.find('<') {
+            Some(0) => match rest.find('>') {
+                Some(end) => ("", &rest[..=end]),
+                None => (rest, ""),
+            },
+            Some(start) => (&rest[..start], ""),
+            None => (rest, ""),
+        };
+        rest = &rest[text.len() + tag.len()..];
+        if !text.is_empty() && state.hidden_depth == 0 {
+            let mut collapsed = String::with_capacity(text.len());
+            for (i, word) in text.split_whitespace().enumerate() {
+                if i > 0 {
+                    collapsed.push(' ');
+                }
+                collapsed.push_str(word);
+            }
+            let at_line_start = styled.text.is_empty() || styled.text.ends_with('\n');
+            if text.starts_with(char::is_whitespace) && !at_line_start {
+                collapsed.insert(0, ' ');
+            }
+            if text.ends_with(char::is_whitespace) && !collapsed.trim().is_empty() {
+                collapsed.push(' ');
+            }
+            if !collapsed.is_empty() {
+                let s = SpanStyle {
+                    flags: state.flags(),
+                    link_idx: state.current_link_idx(styled),
+                };
+                styled.push_text(&collapsed, s);
+            }
+        }
+        match classify_html_tag(tag) {
+            _ if tag.is_empty() => {}
+            HtmlTag::OpenHidden => state.hidden_depth = state.hidden_depth.saturating_add(1),
+            HtmlTag::CloseHidden => state.hidden_depth = state.hidden_depth.saturating_sub(1),
+            _ if state.hidden_depth > 0 => {}
+            HtmlTag::Break => {
+                let s = SpanStyle {
+                    flags: state.flags(),
+                    link_idx: state.current_link_idx(styled),
+                };
+                styled.push_text("\n", s);
+            }
+            HtmlTag::Open(flag) => state.push(flag),
+            HtmlTag::Close(flag) => state.pop(&flag),
+            HtmlTag::Ignore => {}
+        }
+    }
+}
+
+/// Push plain text, turning bare `http://`/`https://` URLs into link spans
+/// (GFM extended autolinks, which pulldown-cmark does not detect).
+fn push_text_with_autolinks(styled: &mut StyledText, text: &str, style: SpanStyle) {
+    let mut rest = text;
+    while let Some((start, end)) = find_bare_url(rest) {
+        styled.push_text(&rest[..start], style);
+        let url = &rest[start..end];
+        let link_idx = styled.intern_link(Rc::from(url));
+        styled.push_text(url, SpanStyle { link_idx, ..style });
+        rest = &rest[end..];
+    }
+    styled.push_text(rest, style);
+}
+
+/// Find the byte range of the first bare `http(s)://` URL in `text`.
+///
+/// The URL runs until whitespace or `<`; trailing punctuation and
+/// unbalanced closing parentheses are left out, as in GFM.
+pub(crate) fn find_bare_url(text: &str) -> Option<(usize, usize)> {
+    let bytes = text.as_bytes();
+    let mut from = 0;
+    while let Some(rel) = memchr::memmem::find(&bytes[from..], b"://") {
+        let sep = from + rel;
+        let host_start = sep + 3;
+        from = host_start;
+        let start = if bytes[..sep].ends_with(b"https") {
+            sep - 5
+        } else if bytes[..sep].ends_with(b"http") {
+            sep - 4
+        } else {
+            continue;
+        };
+        if start > 0 && bytes[start - 1].is_ascii_alphanumeric() {
+            continue;
+        }
+        let mut end = bytes[host_start..]
+            .iter()
+            .position(|&b| b.is_ascii_whitespace() || b == b'<')
+            .map_or(bytes.len(), |len| host_start + len);
+        loop {
+            match bytes[end - 1] {
+                b'.' | b',' | b':' | b';' | b'!' | b'?' | b'\'' | b'"' | b'*' | b'_' | b'~' => {
+                    end -= 1;
+                }
+                b')' if {
+                    let url = &bytes[start..end];
+                    let opens = memchr::memchr_iter(b'(', url).count();
+                    let closes = memchr::memchr_iter(b')', url).count();
+                    closes > opens
+                } =>
+                {
+                    end -= 1;
+                }
+                _ => break,
+            }
+            if end <= host_start {
+                break;
+            }
+        }
+        if end > host_start {
+            return Some((start, end));
+        }
+    }
+    None
+}
+
 #[inline]
 #[must_use]
 pub const fn heading_level_to_u8(level: HeadingLevel) -> u8 {
@@ -1035,7 +1610,7 @@ mod tests {
             let headings: Vec<_> = blocks
                 .iter()
                 .filter_map(|b| match b {
-                    Block::Heading { level, text } => Some((*level, text.text.as_str())),
+                    Block::Heading { level, text, .. } => Some((*level, text.text.as_str())),
                     _ => None,
                 })
                 .collect();
@@ -1332,6 +1907,32 @@ mod tests {
 
     // ── Link parsing ─────────────────────────────────────────────
 
+    #[test]
+    fn wiki_links_parse_only_when_enabled() {
+        let md = "See [[Other Note]] and [[Plans#Q3|the plan]].";
+        let links = |options: ParseOptions| {
+            let mut blocks = Vec::new();
+            parse_markdown_into(md, &mut blocks, options);
+            match &blocks[0] {
+                Block::Paragraph(st) => (st.text.clone(), st.links.clone()),
+                other => panic!("expected paragraph, got {other:?}"),
+            }
+        };
+        let (text, urls) = links(ParseOptions {
+            wiki_links: true,
+            ..ParseOptions::default()
+        });
+        assert_eq!(text, "See Other Note and the plan.");
+        let urls: Vec<&str> = urls.iter().map(Rc::as_ref).collect();
+        assert_eq!(urls, ["wiki:Other Note", "wiki:Plans#Q3"]);
+        assert_eq!(wiki_link_target(urls[0]), Some("Other Note"));
+        assert_eq!(wiki_link_target("https://x"), None);
+
+        let (text, urls) = links(ParseOptions::default());
+        assert_eq!(text, md);
+        assert!(urls.is_empty());
+    }
+
     #[test]
     fn link_parsing() {
         for (label, md, url) in [
@@ -1516,6 +2117,216 @@ mod tests {
         assert!(max_depth(&parse_markdown(md)) >= 5);
     }
 
+    #[test]
+    fn blockquote_fixtures_capture_inner_blocks() {
+        fn code(block: &Block) -> Option<&str> {
+            match block {
+                Block::Code { code, .. } => Some(code),
+                _ => None,
+            }
+        }
+
+        // `> > nested`: a quote holding only a quote holding the paragraph.
+        match parse_markdown("> > nested\n").as_slice() {
+            [Block::Quote(outer)] => match outer.as_slice() {
+                [Block::Quote(inner)] => {
+                    assert!(
+                        matches!(inner.as_slice(), [Block::Paragraph(t)] if t.text == "nested")
+                    );
+                }
+                other => panic!("expected nested Quote, got {other:?}"),
+            },
+            other => panic!("expected Quote, got {other:?}"),
+        }
+
+        // A fence opened inside a quote ends with the quote: unprefixed
+        // lines are not lazy continuations of fenced code.
+        let blocks = parse_markdown("> \n> ```\ncode\n```\n");
+        match blocks.as_slice() {
+            [
+                Block::Quote(inner),
+                Block::Paragraph(text),
+                Block::Code { .. },
+            ] => {
+                assert_eq!(inner.iter().filter_map(code).collect::<Vec<_>>(), [""]);
+                assert_eq!(text.text, "code");
+            }
+            other => panic!("unexpected blocks {other:?}"),
+        }
+
+        // Fully quoted fence keeps its body inside the quote.
+        match parse_markdown("> ```\n> code\n> ```\n").as_slice() {
+            [Block::Quote(inner)] => {
+                assert_eq!(
+                    inner.iter().filter_map(code).collect::<Vec<_>>(),
+                    ["code\n"]
+                );
+            }
+            other => panic!("expected Quote, got {other:?}"),
+        }
+
+        // Code in a nested quote, then text back at the outer level.
+        match parse_markdown("> > ```\n> > deep\n> > ```\n> back\n").as_slice() {
+            [Block::Quote(outer)] => match outer.as_slice() {
+                [Block::Quote(inner), Block::Paragraph(back)] => {
+                    assert_eq!(
+                        inner.iter().filter_map(code).collect::<Vec<_>>(),
+                        ["deep\n"]
+                    );
+                    assert_eq!(back.text, "back");
+                }
+                other => panic!("unexpected quote contents {other:?}"),
+            },
+            other => panic!("expected Quote, got {other:?}"),
+        }
+
+        // A list with a fenced child inside a quote.
+        match parse_markdown("> - a\n>   ```\n>   x\n>   ```\n> - b\n").as_slice() {
+            [Block::Quote(inner)] => match inner.as_slice() {
+                [Block::UnorderedList(items)] => {
+                    assert_eq!(items.len(), 2);
+                    assert_eq!(
+                        items[0]
+                            .children
+                            .iter()
+                            .filter_map(code)
+                            .collect::<Vec<_>>(),
+                        ["x\n"]
+                    );
+                }
+                other => panic!("expected list in quote, got {other:?}"),
+            },
+            other => panic!("expected Quote, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn smart_punctuation_is_opt_in() {
+        let source = "\"Quoted\" -- it's...\n";
+        let paragraph = |options| {
+            let mut blocks = Vec::new();
+            parse_markdown_into(source, &mut blocks, options);
+            match blocks.as_slice() {
+                [Block::Paragraph(t)] => t.text.clone(),
+                other => panic!("unexpected blocks {other:?}"),
+            }
+        };
+        assert_eq!(paragraph(ParseOptions::default()), "\"Quoted\" -- it's...");
+        let smart = ParseOptions {
+            smart_punctuation: true,
+            ..ParseOptions::default()
+        };
+        assert_eq!(
+            paragraph(smart),
+            "\u{201c}Quoted\u{201d} \u{2013} it\u{2019}s\u{2026}"
+        );
+    }
+
+    fn parse_with_html(md: &str) -> Vec<Block> {
+        let options = ParseOptions {
+            render_html: true,
+            ..ParseOptions::default()
+        };
+        let mut blocks = Vec::new();
+        parse_markdown_into(md, &mut blocks, options);
+        blocks
+    }
+
+    fn span_texts(st: &StyledText, keep: impl Fn(SpanStyle) -> bool) -> Vec<&str> {
+        st.spans
+            .iter()
+            .filter(|s| keep(s.style))
+            .map(|s| &st.text[s.start as usize..s.end as usize])
+            .collect()
+    }
+
+    #[test]
+    fn raw_html_is_dimmed_when_not_rendered() {
+        let st = parse_paragraph("H<sub>2</sub>O");
+        assert_eq!(st.text, "H<sub>2</sub>O");
+        assert_eq!(span_texts(&st, SpanStyle::html), ["<sub>", "</sub>"]);
+
+        // HTML blocks are kept as raw text rather than dropped.
+        match parse_markdown("<div>\n<b>block</b>\n</div>\n").as_slice() {
+            [Block::Paragraph(t)] => {
+                assert_eq!(t.text, "<div>\n<b>block</b>\n</div>");
+                assert!(t.spans.iter().all(|s| s.style.html()));
+            }
+            other => panic!("unexpected blocks {other:?}"),
+        }
+    }
+
+    #[test]
+    fn rendered_html_maps_the_safe_subset() {
+        let blocks = parse_with_html(
+            "H<sub>2</sub>O, x<sup>2</sup>, <kbd>Ctrl</kbd>+<kbd>S</kbd>, \
+             <b>bold</b> <span class=\"a\">span</span><br>next",
+        );
+        let Some(Block::Paragraph(st)) = blocks.first() else {
+            panic!("expected a paragraph, got {blocks:?}");
+        };
+        assert_eq!(st.text, "H2O, x2, Ctrl+S, bold span\nnext");
+        assert_eq!(span_texts(st, SpanStyle::subscript), ["2"]);
+        assert_eq!(span_texts(st, SpanStyle::superscript), ["2"]);
+        assert_eq!(span_texts(st, SpanStyle::code), ["Ctrl", "S"]);
+        assert_eq!(span_texts(st, SpanStyle::strong), ["bold"]);
+        assert!(span_texts(st, SpanStyle::html).is_empty());
+    }
+
+    #[test]
+    fn rendered_html_drops_scripts_and_embeds() {
+        let blocks = parse_with_html(
+            "a <script>alert(1)</script>b <iframe src=\"x\"></iframe>c\n\n\
+             <script>\nsteal()\n</script>\n\n<div>\n  kept   text\n</div>\n",
+        );
+        let texts: Vec<&str> = blocks
+            .iter()
+            .map(|block| match block {
+                Block::Paragraph(t) => t.text.as_str(),
+                other => panic!("unexpected block {other:?}"),
+            })
+            .collect();
+        assert_eq!(texts, ["a b c", "kept text"]);
+    }
+
+    #[test]
+    fn preserve_line_breaks_keeps_soft_breaks() {
+        let preserve = ParseOptions {
+            preserve_line_breaks: true,
+            ..ParseOptions::default()
+        };
+        let mut blocks = Vec::new();
+        parse_markdown_into("One.\nTwo.\n\n- a\n  b\n", &mut blocks, preserve);
+        match blocks.as_slice() {
+            [Block::Paragraph(t), Block::UnorderedList(items)] => {
+                assert_eq!(t.text, "One.\nTwo.");
+                assert_eq!(items[0].content.text, "a\nb");
+            }
+            other => panic!("unexpected blocks {other:?}"),
+        }
+
+        // Image alt text still joins lines.
+        blocks.clear();
+        parse_markdown_into("![a\nb](x.png)\n", &mut blocks, preserve);
+        assert!(matches!(blocks.as_slice(), [Block::Image { alt, .. }] if &**alt == "a b"));
+    }
+
+    #[test]
+    fn hard_breaks_split_lines_and_soft_breaks_become_spaces() {
+        for (label, md, expected) in [
+            ("soft", "Line one\nLine two\n", "Line one Line two"),
+            ("two spaces", "Line one  \nLine two\n", "Line one\nLine two"),
+            ("backslash", "Line one\\\nLine two\n", "Line one\nLine two"),
+            ("mixed", "a\nb  \nc\n", "a b\nc"),
+            ("emphasis", "*a\nb*\n", "a b"),
+        ] {
+            match parse_markdown(md).as_slice() {
+                [Block::Paragraph(t)] => assert_eq!(t.text, expected, "{label}"),
+                other => panic!("{label}: expected Paragraph, got {other:?}"),
+            }
+        }
+    }
+
     // ── Span coverage ────────────────────────────────────────────
 
     #[test]
@@ -1618,6 +2429,49 @@ mod tests {
         }
     }
 
+    #[test]
+    fn parse_definition_lists() {
+        let para_text = |blocks: &[Block]| match blocks {
+            [Block::Paragraph(st)] => st.text.clone(),
+            other => panic!("expected one paragraph, got {other:?}"),
+        };
+
+        // Tight: inline definition content becomes a paragraph.
+        let md = "Term\n: First meaning\n: Second *meaning*\n\nOther\n: Its meaning\n";
+        match &parse_markdown(md)[0] {
+            Block::DefinitionList(items) => {
+                assert_eq!(items.len(), 2);
+                assert_eq!(items[0].term.text, "Term");
+                assert_eq!(items[0].definitions.len(), 2);
+                assert_eq!(para_text(&items[0].definitions[0]), "First meaning");
+                assert_eq!(para_text(&items[0].definitions[1]), "Second meaning");
+                assert_eq!(items[1].term.text, "Other");
+                assert_eq!(para_text(&items[1].definitions[0]), "Its meaning");
+            }
+            other => panic!("expected DefinitionList, got {other:?}"),
+        }
+
+        // Loose definitions keep nested blocks.
+        let md = "Term\n\n: Para one\n\n    - nested item\n";
+        match &parse_markdown(md)[0] {
+            Block::DefinitionList(items) => {
+                let def = &items[0].definitions[0];
+                assert!(matches!(def[0], Block::Paragraph(_)), "got {def:?}");
+                assert!(
+                    def.iter().any(|b| matches!(b, Block::UnorderedList(_))),
+                    "got {def:?}"
+                );
+            }
+            other => panic!("expected DefinitionList, got {other:?}"),
+        }
+
+        // A lone colon line without a term stays a paragraph.
+        assert!(matches!(
+            parse_markdown(": not a definition\n")[0],
+            Block::Paragraph(_)
+        ));
+    }
+
     #[test]
     fn parse_task_lists() {
         // Unordered
@@ -2309,25 +3163,47 @@ mod tests {
 
     // ── Rendering parity diagnostic tests ────────────────────────
 
-    /// GFM bare-URL autolinks (no angle brackets) are NOT parsed as
-    /// clickable links.  Despite `ENABLE_GFM` being set, pulldown-cmark
-    /// 0.13 does not include autolink detection in that flag — it only
-    /// enables blockquote admonition tags.
-    ///
-    /// This means bare URLs like `https://example.com` in paragraphs
-    /// (including verification.md §2.3) render as plain text, not links.
+    /// GFM bare-URL autolinks (no angle brackets) become clickable links.
+    /// pulldown-cmark 0.13 does not detect them, so the parser scans text
+    /// runs for `http://`/`https://` itself.
     #[test]
-    fn gfm_bare_url_autolinks_not_parsed() {
-        // Bare https:// URL should become a link per GFM spec,
-        // but pulldown-cmark 0.13 does NOT parse them.
+    fn gfm_bare_url_autolinks_parsed() {
+        let link_urls = |st: &StyledText| -> Vec<String> {
+            st.spans
+                .iter()
+                .filter_map(|s| st.link_url(s.style.link_idx).map(ToString::to_string))
+                .collect()
+        };
+
         let st = parse_paragraph("Visit https://example.com for details.");
         validate_styled_text(&st);
-        let link_span = st.spans.iter().find(|s| s.style.has_link());
-        // BUG / LIMITATION: bare URLs are plain text, not links.
-        assert!(
-            link_span.is_none(),
-            "pulldown-cmark 0.13 does NOT parse bare URLs as links (known limitation)"
+        assert_eq!(link_urls(&st), ["https://example.com"]);
+        assert_eq!(st.text, "Visit https://example.com for details.");
+
+        // Trailing punctuation is not part of the URL.
+        let st = parse_paragraph("See http://example.com/path.");
+        assert_eq!(link_urls(&st), ["http://example.com/path"]);
+
+        // Balanced parentheses stay, an unbalanced closer is dropped.
+        let st = parse_paragraph("(https://en.wikipedia.org/wiki/Rust_(language))");
+        assert_eq!(
+            link_urls(&st),
+            ["https://en.wikipedia.org/wiki/Rust_(language)"]
         );
+
+        // Several URLs in one run, and URLs inside emphasis.
+        let st = parse_paragraph("https://a.com and **https://b.com**");
+        assert_eq!(link_urls(&st), ["https://a.com", "https://b.com"]);
+
+        // Not a URL: missing host, embedded in a word, or inside code.
+        for md in ["https:// nothing", "xhttps://a.com", "`https://a.com`"] {
+            let st = parse_paragraph(md);
+            assert!(link_urls(&st).is_empty(), "md={md:?}");
+        }
+
+        // Explicit links keep their own destination.
+        let st = parse_paragraph("[https://a.com](https://b.com)");
+        assert_eq!(link_urls(&st), ["https://b.com"]);
     }
 
     /// Bold text inside a link paragraph produces spans with both
@@ -2367,6 +3243,36 @@ mod tests {
         );
     }
 
+    #[test]
+    fn parse_math() {
+        // Inline math gets the math flag (not code) and keeps its source.
+        let st = parse_paragraph("Euler: $e^{i\\pi} + 1 = 0$ holds.");
+        validate_styled_text(&st);
+        let math: Vec<_> = st
+            .spans
+            .iter()
+            .filter(|s| s.style.math())
+            .map(|s| &st.text[s.start as usize..s.end as usize])
+            .collect();
+        assert_eq!(math, ["e^{i\\pi} + 1 = 0"]);
+        assert!(st.spans.iter().all(|s| !s.style.code()));
+
+        // Display math alone in a paragraph becomes a block.
+        let blocks = parse_markdown("$$\n\\int_0^1 x\\,dx\n$$\n");
+        match &blocks[0] {
+            Block::Math { tex } => assert_eq!(&**tex, "\\int_0^1 x\\,dx"),
+            other => panic!("expected Math, got {other:?}"),
+        }
+
+        // Display math mixed with text stays inline.
+        let st = parse_paragraph("Area $$\\pi r^2$$ here");
+        assert!(st.spans.iter().any(|s| s.style.math()));
+
+        // A lone dollar sign is plain text.
+        let st = parse_paragraph("Costs $5 today");
+        assert!(st.spans.iter().all(|s| !s.style.math()));
+    }
+
     /// Input size limit: documents above `MAX_PARSE_BYTES` are truncated.
     #[test]
     fn parse_truncates_oversized_input() {
@@ -0,0 +1,248 @@
+#![forbid(unsafe_code)]
+//! Plain-text rendering of parsed Markdown, for pasting into contexts that
+//! don't understand Markdown (email, chat) and for screen readers.
+
+use std::fmt::Write as _;
+
+use crate::parse::{
+    Block, ListItem, ParseOptions, StyledText, parse_markdown_into, wiki_link_target,
+};
+
+/// Render Markdown `source` as plain text.
+///
+/// Inline formatting is dropped, links other than wiki links keep their
+/// URL in parentheses, blocks are separated by a blank line, lists keep
+/// their markers and quotes their `>` prefix.  `options` should match the
+/// preview's so the text reads the same as what is shown.
+#[must_use]
+pub fn plain_text(source: &str, options: ParseOptions) -> String {
+    let mut blocks = Vec::new();
+    parse_markdown_into(source, &mut blocks, options);
+    let mut out = blocks_text(&blocks);
+    if !out.is_empty() {
+        out.push('\n');
+    }
+    out
+}
+
+fn blocks_text(blocks: &[Block]) -> String {
+    let mut out = String::new();
+    for block in blocks {
+        if !out.is_empty() {
+            out.push_str("\n\n");
+        }
+        push_block(&mut out, block);
+    }
+    out
+}
+
+fn push_block(out: &mut String, block: &Block) {
+    match block {
+        Block::Heading { text, .. } | Block::Paragraph(text) => push_styled(out, text),
+        Block::Code { code, .. } => out.push_str(code.trim_end_matches('\n')),
+        Block::Quote(children) => push_prefixed(out, &blocks_text(children), "> ", "> "),
+        Block::UnorderedList(items) => push_items(out, items, |_| "- ".to_owned()),
+        Block::OrderedList { start, items } => push_items(out, items, |i| {
+            format!(
+                "{}. ",
+                start.saturating_add(u64::try_from(i).unwrap_or(u64::MAX))
+            )
+        }),
+        Block::ThematicBreak => out.push_str("---"),
+        Block::Table(table) => {
+            let rows = std::iter::once(&table.header).chain(&table.rows);
+            for (i, row) in rows.enumerate() {
+                if i > 0 {
+                    out.push('\n');
+                }
+                for (j, cell) in row.iter().enumerate() {
+                    if j > 0 {
+                        out.push('\t');
+                    }
+                    push_styled(out, cell);
+                }
+            }
+        }
+        Block::Image { alt, .. } => out.push_str(alt),
+        Block::DefinitionList(items) => {
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push_str("\n\n");
+                }
+                push_styled(out, &item.term);
+                for definition in &item.definitions {
+                    out.push('\n');
+                    push_prefixed(out, &blocks_text(definition), "    ", "    ");
+                }
+            }
+        }
+        Block::Math { tex } => out.push_str(tex.trim()),
+    }
+}
+
+/// Append list `items`, keeping a loose list's blank lines between items
+/// and before their child blocks.
+fn push_items(out: &mut String, items: &[ListItem], marker: impl Fn(usize) -> String) {
+    for (i, item) in items.iter().enumerate() {
+        let separator = if item.loose { "\n\n" } else { "\n" };
+        if i > 0 {
+            out.push_str(separator);
+        }
+        let mut body = String::new();
+        match item.checked {
+            Some(true) => body.push_str("[x] "),
+            Some(false) => body.push_str("[ ] "),
+            None => {}
+        }
+        push_styled(&mut body, &item.content);
+        if !item.children.is_empty() {
+            body.push_str(separator);
+            body.push_str(&blocks_text(&item.children));
+        }
+        let marker = marker(i);
+        let indent = " ".repeat(marker.chars().count());
+        push_prefixed(out, &body, &marker, &indent);
+    }
+}
+
+/// Append `text` with `first` before its first line and `rest` before the
+/// others; blank lines only get the prefix's non-space part.
+fn push_prefixed(out: &mut String, text: &str, first: &str, rest: &str) {
+    for (i, line) in text.split('\n').enumerate() {
+        let prefix = if i == 0 { first } else { rest };
+        if i > 0 {
+            out.push('\n');
+        }
+        if line.is_empty() {
+            out.push_str(prefix.trim_end());
+        } else {
+            out.push_str(prefix);
+            out.push_str(line);
+        }
+    }
+}
+
+/// Append the text of `styled`, following each link with its URL unless
+/// the link text already is the URL, it points inside the document or it
+/// is a wiki link.
+fn push_styled(out: &mut String, styled: &StyledText) {
+    if !styled.has_links {
+        out.push_str(&styled.text);
+        return;
+    }
+    let spans = &styled.spans;
+    let mut written = 0;
+    let mut i = 0;
+    while i < spans.len() {
+        let link_idx = spans[i].style.link_idx;
+        let start = spans[i].start as usize;
+        let mut end = spans[i].end as usize;
+        i += 1;
+        while i < spans.len()
+            && spans[i].style.link_idx == link_idx
+            && spans[i].start as usize == end
+        {
+            end = spans[i].end as usize;
+            i += 1;
+        }
+        let Some(url) = styled.link_url(link_idx) else {
+            continue;
+        };
+        out.push_str(&styled.text[written..end]);
+        written = end;
+        if styled.text[start..end] != **url
+            && !url.starts_with('#')
+            && wiki_link_target(url).is_none()
+        {
+            let _ = write!(out, " ({url})");
+        }
+    }
+    out.push_str(&styled.text[written..]);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse::FrontMatter;
+
+    fn plain(source: &str) -> String {
+        plain_text(source, ParseOptions::default())
+    }
+
+    #[test]
+    fn inline_formatting_is_dropped_and_links_keep_urls() {
+        assert_eq!(
+            plain(
+                "# Title\n\nSome **bold**, _em_ and `code`. See [docs](https://x.io), <https://y.io> or [below](#end).\n"
+            ),
+            "Title\n\nSome bold, em and code. See docs (https://x.io), https://y.io or below.\n"
+        );
+        assert_eq!(plain(""), "");
+        let wiki = ParseOptions {
+            wiki_links: true,
+            ..ParseOptions::default()
+        };
+        assert_eq!(
+            plain_text("See [[Other Note|notes]].\n", wiki),
+            "See notes.\n"
+        );
+    }
+
+    #[test]
+    fn lists_quotes_and_code_keep_their_shape() {
+        let source = "- [x] done\n- [ ] todo\n  1. first\n  2. second\n\n> quoted\n>\n> more\n\n```rust\nfn main() {}\n```\n\n---\n";
+        assert_eq!(
+            plain(source),
+            "- [x] done\n- [ ] todo\n  1. first\n  2. second\n\n> quoted\n>\n> more\n\nfn main() {}\n\n---\n"
+        );
+    }
+
+    #[test]
+    fn loose_lists_keep_blank_lines_between_items() {
+        assert_eq!(
+            plain("- one\n\n- two\n\n  more\n- three\n"),
+            "- one\n\n- two\n\n  more\n\n- three\n"
+        );
+        assert_eq!(plain("1. one\n2. two\n"), "1. one\n2. two\n");
+    }
+
+    #[test]
+    fn tables_are_tab_separated_and_images_use_alt_text() {
+        assert_eq!(
+            plain("| a | b |\n|---|---|\n| 1 | 2 |\n\n![a cat](cat.png)\n"),
+            "a\tb\n1\t2\n\na cat\n"
+        );
+    }
+
+    #[test]
+    fn parse_options_are_honoured() {
+        let source = "one\ntwo\n";
+        assert_eq!(plain(source), "one two\n");
+        assert_eq!(
+            plain_text(
+                source,
+                ParseOptions {
+                    preserve_line_breaks: true,
+                    ..ParseOptions::default()
+                }
+            ),
+            "one\ntwo\n"
+        );
+    }
+
+    #[test]
+    fn front_matter_is_kept_or_stripped() {
+        let source = "---\ntitle: Notes\n---\nBody\n";
+        let kept = plain(source);
+        assert!(kept.contains("title: Notes"), "{kept:?}");
+        for front_matter in [FrontMatter::Strip, FrontMatter::Metadata] {
+            let options = ParseOptions {
+                front_matter,
+                ..ParseOptions::default()
+            };
+            assert_eq!(plain_text(source, options), "Body\n");
+            // Without front matter nothing changes.
+            assert_eq!(plain_text("---\n\nBody\n", options), plain("---\n\nBody\n"));
+        }
+    }
+}
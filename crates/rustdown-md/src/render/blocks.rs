@@ -1,10 +1,12 @@
 #![forbid(unsafe_code)]
 
+use std::fmt::Write as _;
+
 use super::layout::{RenderContext, RenderMetrics};
 use super::lists::{render_ordered_list, render_unordered_list};
 use super::table::render_table;
-use super::text::{render_styled_text, render_styled_text_ex};
-use crate::parse::{Block, StyledText};
+use super::text::{math_background, render_styled_text, render_styled_text_ex, strengthen_color};
+use crate::parse::{Block, DefinitionItem, StyledText};
 use crate::style::MarkdownStyle;
 
 // ── Block rendering ────────────────────────────────────────────────
@@ -37,7 +39,7 @@ pub(super) fn render_block(
 ) {
     let metrics = ctx.metrics();
     match block {
-        Block::Heading { level, text } => {
+        Block::Heading { level, text, .. } => {
             render_heading(ui, *level, text, style, metrics);
         }
 
@@ -83,6 +85,15 @@ pub(super) fn render_block(
         Block::Image { url, alt } => {
             render_image(ui, url, alt, style, metrics);
         }
+
+        Block::DefinitionList(items) => {
+            render_definition_list(ui, items, style, ctx);
+            ui.add_space(metrics.paragraph_spacing());
+        }
+
+        Block::Math { tex } => {
+            render_math_block(ui, tex, style, metrics);
+        }
     }
 }
 
@@ -94,16 +105,29 @@ pub(super) fn render_block(
 /// Otherwise the URL is appended to `base_uri` with exactly one `/`
 /// separator.
 ///
-/// **Security:** relative URLs containing `..` path segments are rejected
-/// to prevent directory-traversal attacks via malicious markdown images.
+/// `file://` results are rewritten with [`file_url_to_loader_uri`] so that
+/// percent-encoded paths load.
+///
+/// **Security:** relative URLs containing `..` path segments (also when
+/// percent-encoded) are rejected to prevent directory-traversal attacks via
+/// malicious markdown images.
 pub(super) fn resolve_image_url<'a>(url: &'a str, base_uri: &str) -> std::borrow::Cow<'a, str> {
+    file_url_to_loader_uri(join_image_url(url, base_uri))
+}
+
+/// Join a markdown image `url` onto `base_uri`, percent-encoding the path
+/// (existing `%XX` escapes are kept) so the result is a valid URI.
+pub(super) fn join_image_url<'a>(url: &'a str, base_uri: &str) -> std::borrow::Cow<'a, str> {
     if url.starts_with("//") || url.contains("://") || base_uri.is_empty() {
         return std::borrow::Cow::Borrowed(url);
     }
 
     // Reject path-traversal attempts: any `..` that appears as a full
     // path component (e.g. `../`, `foo/../../bar`, or trailing `..`).
-    if contains_dot_dot_segment(url) {
+    // Escapes are decoded before loading, so check the decoded form too.
+    if contains_dot_dot_segment(url)
+        || percent_decode(url).is_some_and(|decoded| contains_dot_dot_segment(&decoded))
+    {
         return std::borrow::Cow::Borrowed("");
     }
 
@@ -119,7 +143,7 @@ pub(super) fn resolve_image_url<'a>(url: &'a str, base_uri: &str) -> std::borrow
                 .map_or(base_uri.len(), |i| after_scheme + i);
             let mut s = String::with_capacity(authority_end + url.len());
             s.push_str(&base_uri[..authority_end]);
-            s.push_str(url);
+            push_uri_path(&mut s, url);
             return std::borrow::Cow::Owned(s);
         }
         // No scheme — just use url as-is.
@@ -133,10 +157,108 @@ pub(super) fn resolve_image_url<'a>(url: &'a str, base_uri: &str) -> std::borrow
     if !base_slash {
         s.push('/');
     }
-    s.push_str(url);
+    push_uri_path(&mut s, url);
     std::borrow::Cow::Owned(s)
 }
 
+/// `true` for bytes allowed unescaped in a URI path: RFC 3986 `pchar`
+/// and `/`.
+fn is_path_byte(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || b"/-._~!$&'()*+,;=:@".contains(&byte)
+}
+
+/// Append `path` to `out`, percent-encoding bytes outside RFC 3986 `pchar`
+/// and `/`.  Valid `%XX` escapes pass through so pre-encoded links still work.
+fn push_uri_path(out: &mut String, path: &str) {
+    let bytes = path.as_bytes();
+    for (i, &byte) in bytes.iter().enumerate() {
+        let escape = byte == b'%'
+            && bytes
+                .get(i + 1..i + 3)
+                .is_some_and(|pair| pair.iter().all(u8::is_ascii_hexdigit));
+        if escape || is_path_byte(byte) {
+            out.push(char::from(byte));
+        } else {
+            let _ = write!(out, "%{byte:02X}");
+        }
+    }
+}
+
+/// `path` percent-encoded for use as a URL path or Markdown link: every
+/// byte outside RFC 3986 `pchar` and `/`, `%` included, is escaped.
+#[must_use]
+pub fn percent_encode_path(path: &str) -> String {
+    let mut out = String::with_capacity(path.len());
+    for &byte in path.as_bytes() {
+        if is_path_byte(byte) {
+            out.push(char::from(byte));
+        } else {
+            let _ = write!(out, "%{byte:02X}");
+        }
+    }
+    out
+}
+
+/// Decode `%XX` escapes in `s`.  Returns `None` when there is nothing to
+/// decode or the decoded bytes are not UTF-8.
+#[must_use]
+pub fn percent_decode(s: &str) -> Option<String> {
+    if !s.contains('%') {
+        return None;
+    }
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|pair| std::str::from_utf8(pair).ok())
+            .and_then(|pair| u8::from_str_radix(pair, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                out.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                out.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8(out).ok()
+}
+
+/// Rewrite a `file://` URL into the form egui's file loader reads: the
+/// percent-decoded path right after `file://`.  A UNC host becomes a
+/// `\\host` prefix (Windows accepts `/` separators after it), and
+/// `localhost` is dropped.  Other URIs pass through unchanged.
+pub(super) fn file_url_to_loader_uri(uri: std::borrow::Cow<'_, str>) -> std::borrow::Cow<'_, str> {
+    let rewritten = {
+        let Some(rest) = uri.strip_prefix("file://") else {
+            return uri;
+        };
+        let (host, path) = rest.split_at(rest.find('/').unwrap_or(rest.len()));
+        let decoded = percent_decode(path);
+        if host.is_empty() && decoded.is_none() {
+            return uri;
+        }
+        let host = if host.eq_ignore_ascii_case("localhost") {
+            ""
+        } else {
+            host
+        };
+        let mut out = String::with_capacity(uri.len() + 2);
+        out.push_str("file://");
+        if !host.is_empty() {
+            out.push_str("\\\\");
+            out.push_str(host);
+        }
+        out.push_str(decoded.as_deref().unwrap_or(path));
+        out
+    };
+    std::borrow::Cow::Owned(rewritten)
+}
+
 /// Returns `true` if `path` contains a `..` path component.
 ///
 /// Matches `..` when it appears as the entire path, at the start
@@ -160,33 +282,27 @@ fn render_image(
 ) {
     let resolved = resolve_image_url(url, &style.image_base_uri);
 
-    let max_width = ui.available_width();
+    let max_width = style.image_max_width.resolve(ui.available_width());
     let image = egui::Image::new(resolved.as_ref())
         .max_width(max_width)
-        .corner_radius(4.0);
+        .corner_radius(4.0)
+        .sense(egui::Sense::click());
 
     let response = ui.add(image);
+    super::zoom::note_shown_image(ui.ctx(), &resolved);
 
-    // Show alt text (or URL) on hover.
+    // Show alt text (or URL) on hover; a click opens it at full size.
     let hover_text = if alt.is_empty() { url } else { alt };
-    response.on_hover_text(hover_text);
+    let response = response
+        .on_hover_text(hover_text)
+        .on_hover_cursor(egui::CursorIcon::ZoomIn);
+    if response.clicked() && !resolved.is_empty() {
+        super::zoom::request_zoom(ui.ctx(), &resolved);
+    }
 
     ui.add_space(metrics.paragraph_spacing());
 }
 
-/// Draw a full-width horizontal rule at the current cursor position.
-fn draw_horizontal_rule(ui: &egui::Ui, style: &MarkdownStyle) {
-    let rect = ui.available_rect_before_wrap();
-    let y = rect.min.y;
-    let color = style
-        .hr_color
-        .unwrap_or_else(|| ui.visuals().weak_text_color());
-    ui.painter().line_segment(
-        [egui::pos2(rect.min.x, y), egui::pos2(rect.max.x, y)],
-        egui::Stroke::new(1.5, color),
-    );
-}
-
 fn render_heading(
     ui: &mut egui::Ui,
     level: u8,
@@ -216,31 +332,90 @@ fn render_code_block(
     style: &MarkdownStyle,
     metrics: RenderMetrics,
 ) {
-    let bg = style.code_bg.unwrap_or_else(|| ui.visuals().faint_bg_color);
+    let bg = style
+        .code_block_bg
+        .or(style.code_bg)
+        .unwrap_or_else(|| ui.visuals().faint_bg_color);
+    let color = style
+        .code_block_color
+        .unwrap_or_else(|| ui.visuals().text_color());
     let available = ui.available_width();
-    if !language.is_empty() {
-        ui.label(egui::RichText::new(language).small().weak());
-    }
+    render_code_header(ui, language, code);
     egui::Frame::NONE
         .fill(bg)
         .corner_radius(4.0)
         .inner_margin(egui::Margin::same(RenderMetrics::code_block_inner_margin()))
         .show(ui, |ui| {
             ui.set_min_width(available - RenderMetrics::code_block_horizontal_padding());
-            egui::ScrollArea::horizontal().show(ui, |ui| {
-                let mono = egui::FontId::new(metrics.code_font_size(), egui::FontFamily::Monospace);
-                // Only strip trailing newlines, not whitespace — intentional
-                // trailing spaces in code should be preserved.
-                let trimmed = code.trim_end_matches('\n');
-                // Show a non-breaking space for empty blocks so the frame
-                // maintains a visible minimum height.
-                let display = if trimmed.is_empty() {
-                    "\u{00A0}"
-                } else {
-                    trimmed
-                };
+            let mono = egui::FontId::new(metrics.code_font_size(), egui::FontFamily::Monospace);
+            // Only strip trailing newlines, not whitespace — intentional
+            // trailing spaces in code should be preserved.
+            let trimmed = code.trim_end_matches('\n');
+            // Show a non-breaking space for empty blocks so the frame
+            // maintains a visible minimum height.
+            let display = if trimmed.is_empty() {
+                "\u{00A0}"
+            } else {
+                trimmed
+            };
+            let label = egui::Label::new(egui::RichText::new(display).font(mono).color(color));
+            if style.code_wrap {
+                ui.add(label.wrap());
+            } else {
+                egui::ScrollArea::horizontal().show(ui, |ui| {
+                    ui.add(label.extend());
+                });
+            }
+        });
+    ui.add_space(metrics.paragraph_spacing());
+}
+
+/// How long the "Copied" confirmation stays beside a code block's copy button.
+const COPIED_FEEDBACK_SECS: f64 = 1.5;
+
+/// Header row above a code block: language label and a copy button.
+fn render_code_header(ui: &mut egui::Ui, language: &str, code: &str) {
+    ui.horizontal(|ui| {
+        if !language.is_empty() {
+            ui.label(egui::RichText::new(language).small().weak());
+        }
+        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+            let response = ui
+                .small_button(egui::RichText::new("Copy").small())
+                .on_hover_text("Copy code to clipboard");
+            let now = ui.input(|i| i.time);
+            if response.clicked() {
+                ui.ctx().copy_text(code.to_owned());
+                ui.data_mut(|d| d.insert_temp(response.id, now));
+            }
+            let copied_at = ui.data(|d| d.get_temp::<f64>(response.id));
+            let remaining = copied_at.map_or(0.0, |at| COPIED_FEEDBACK_SECS - (now - at));
+            if remaining > 0.0 {
+                ui.ctx()
+                    .request_repaint_after(std::time::Duration::from_secs_f64(remaining));
+                ui.label(egui::RichText::new("\u{2714} Copied").small().weak());
+            }
+        });
+    });
+}
+
+/// Render display math.
+///
+/// There is no TeX typesetter yet, so the source is shown as centred
+/// monospace on a tinted frame.  This is the single entry point for block
+/// math, so a real typesetting backend only needs to replace this body.
+fn render_math_block(ui: &mut egui::Ui, tex: &str, style: &MarkdownStyle, metrics: RenderMetrics) {
+    let available = ui.available_width();
+    egui::Frame::NONE
+        .fill(math_background(style, ui))
+        .corner_radius(4.0)
+        .inner_margin(egui::Margin::same(RenderMetrics::code_block_inner_margin()))
+        .show(ui, |ui| {
+            ui.set_min_width(available - RenderMetrics::code_block_horizontal_padding());
+            ui.vertical_centered(|ui| {
+                let mono = egui::FontId::new(metrics.body_size(), egui::FontFamily::Monospace);
                 ui.label(
-                    egui::RichText::new(display)
+                    egui::RichText::new(tex)
                         .font(mono)
                         .color(ui.visuals().text_color()),
                 );
@@ -313,8 +488,46 @@ fn render_blockquote(
     ui.add_space(metrics.paragraph_spacing());
 }
 
+fn render_definition_list(
+    ui: &mut egui::Ui,
+    items: &[DefinitionItem],
+    style: &MarkdownStyle,
+    ctx: RenderContext,
+) {
+    let metrics = ctx.metrics();
+    let term_color = strengthen_color(
+        style
+            .body_color
+            .unwrap_or_else(|| ui.visuals().text_color()),
+    );
+    for item in items {
+        render_styled_text_ex(ui, &item.term, style, None, Some(term_color));
+        for definition in &item.definitions {
+            ui.horizontal(|ui| {
+                ui.add_space(metrics.definition_indent());
+                ui.vertical(|ui| {
+                    render_blocks(ui, definition, style, ctx.quote_inner());
+                });
+            });
+        }
+    }
+}
+
+/// A thematic break: a full-width rule, as thick as the body font scales
+/// it, laid out between block spacing so it stands apart from headings.
 fn render_hr(ui: &mut egui::Ui, style: &MarkdownStyle, metrics: RenderMetrics) {
     ui.add_space(metrics.paragraph_spacing());
-    draw_horizontal_rule(ui, style);
+    let stroke = metrics.thematic_break_stroke();
+    let (rect, _) = ui.allocate_exact_size(
+        egui::vec2(ui.available_width(), stroke),
+        egui::Sense::hover(),
+    );
+    let color = style
+        .hr_color
+        .unwrap_or_else(|| ui.visuals().weak_text_color());
+    ui.painter().line_segment(
+        [rect.left_center(), rect.right_center()],
+        egui::Stroke::new(stroke, color),
+    );
     ui.add_space(metrics.paragraph_spacing());
 }
@@ -2,7 +2,7 @@
 
 #![allow(clippy::cast_precision_loss)] // UI math — counts/dimensions are small
 
-use crate::parse::{Block, ListItem, StyledText, TableData};
+use crate::parse::{Block, DefinitionItem, ListItem, StyledText, TableData};
 use crate::style::MarkdownStyle;
 
 use super::layout::RenderMetrics;
@@ -25,7 +25,7 @@ fn estimate_block_height_with_metrics(
     style: &MarkdownStyle,
 ) -> f32 {
     match block {
-        Block::Heading { level, text } => {
+        Block::Heading { level, text, .. } => {
             if text.text.is_empty() {
                 return 0.0;
             }
@@ -38,19 +38,18 @@ fn estimate_block_height_with_metrics(
             metrics.paragraph_spacing()
                 + estimate_styled_height(text, metrics.body_size(), wrap_width)
         }
-        Block::Code { language, code, .. } => {
+        Block::Code { code, .. } => {
             let mono_size = metrics.code_font_size();
             // Match render_code_block: trailing newlines are stripped before display.
             let trimmed = code.trim_end_matches('\n');
-            let lines = (bytecount_newlines(trimmed.as_bytes()) + 1).max(1) as f32;
-            let lang_h = if language.is_empty() {
-                0.0
+            let lines = if style.code_wrap {
+                estimate_wrapped_code_lines(trimmed, mono_size, wrap_width)
             } else {
-                metrics.body_size()
+                (bytecount_newlines(trimmed.as_bytes()) + 1).max(1) as f32
             };
             metrics.paragraph_spacing()
                 + (lines * mono_size).mul_add(1.4, RenderMetrics::code_block_horizontal_padding())
-                + lang_h
+                + metrics.code_header_height()
         }
         Block::Quote(inner) => estimate_quote_height(inner, metrics, wrap_width, style),
         Block::UnorderedList(items) => {
@@ -63,11 +62,51 @@ fn estimate_block_height_with_metrics(
         Block::Table(table) => estimate_table_height(table, metrics.body_size(), wrap_width),
         Block::Image { .. } => {
             metrics.paragraph_spacing()
-                + RenderMetrics::image_max_height(wrap_width).max(metrics.image_fallback_height())
+                + RenderMetrics::image_max_height(style.image_max_width.resolve(wrap_width))
+                    .max(metrics.image_fallback_height())
+        }
+        Block::DefinitionList(items) => {
+            estimate_definition_list_height(items, metrics, wrap_width, style)
+        }
+        Block::Math { tex } => {
+            let lines = (bytecount_newlines(tex.as_bytes()) + 1) as f32;
+            metrics.paragraph_spacing()
+                + (lines * metrics.body_size())
+                    .mul_add(1.4, RenderMetrics::code_block_horizontal_padding())
         }
     }
 }
 
+fn estimate_definition_list_height(
+    items: &[DefinitionItem],
+    metrics: RenderMetrics,
+    wrap_width: f32,
+    style: &MarkdownStyle,
+) -> f32 {
+    let content_w = metrics.definition_content_width(wrap_width);
+    let items_h: f32 = items
+        .iter()
+        .map(|item| {
+            let term_h = estimate_styled_height(&item.term, metrics.body_size(), wrap_width);
+            let defs_h: f32 = item
+                .definitions
+                .iter()
+                .flatten()
+                .map(|b| {
+                    estimate_block_height_with_metrics(
+                        b,
+                        metrics.with_list_depth(0),
+                        content_w,
+                        style,
+                    )
+                })
+                .sum();
+            term_h + defs_h
+        })
+        .sum();
+    metrics.paragraph_spacing() + items_h
+}
+
 fn estimate_quote_height(
     inner: &[Block],
     metrics: RenderMetrics,
@@ -114,6 +153,26 @@ fn estimate_list_height_with_metrics(
     metrics.list_spacing() + item_h
 }
 
+/// Visual line count of a code block whose long lines wrap.
+fn estimate_wrapped_code_lines(code: &str, mono_size: f32, wrap_width: f32) -> f32 {
+    let content_w = RenderMetrics::code_block_horizontal_padding()
+        .mul_add(-2.0, wrap_width)
+        .max(40.0);
+    let chars_per_line = (content_w / (mono_size * MONO_CHAR_WIDTH_EM))
+        .floor()
+        .max(1.0);
+    code.split('\n')
+        .map(|line| {
+            (line.chars().count() as f32 / chars_per_line)
+                .ceil()
+                .max(1.0)
+        })
+        .sum()
+}
+
+/// Approximate monospace glyph advance, in ems.
+const MONO_CHAR_WIDTH_EM: f32 = 0.6;
+
 pub(super) fn estimate_table_height(table: &TableData, body_size: f32, wrap_width: f32) -> f32 {
     let metrics = RenderMetrics::new(body_size);
     let num_cols = table.header.len().max(1);
@@ -12,6 +12,7 @@ const HEADING_MIN_BOTTOM_BODY_EM: f32 = 0.3;
 const CODE_FONT_SCALE: f32 = 0.9;
 const CODE_BLOCK_INNER_MARGIN_PX: i8 = 6;
 const CODE_BLOCK_HORIZONTAL_PADDING_PX: f32 = 12.0;
+const CODE_HEADER_HEIGHT_EM: f32 = 1.4;
 const LIST_INDENT_PX: f32 = 16.0;
 const UNORDERED_BULLET_COLUMN_EM: f32 = 1.5;
 const UNORDERED_GAP_PX: f32 = 2.0;
@@ -27,9 +28,11 @@ const TABLE_SINGLE_COLUMN_MAX_FRACTION: f32 = 0.6;
 const TABLE_ROW_HEIGHT_EM: f32 = 1.4;
 const TABLE_ROW_SPACING_PX: f32 = 3.0;
 const TABLE_SCROLLBAR_HEIGHT_PX: f32 = 14.0;
-const THEMATIC_BREAK_HEIGHT_EM: f32 = 0.8;
+const THEMATIC_BREAK_STROKE_EM: f32 = 0.1;
+const THEMATIC_BREAK_MIN_STROKE_PX: f32 = 1.0;
 const IMAGE_FALLBACK_HEIGHT_EM: f32 = 8.0;
 const IMAGE_MAX_HEIGHT_FRACTION: f32 = 0.75;
+const DEFINITION_INDENT_EM: f32 = 1.5;
 
 /// Shared render/estimation measurements derived from the body font size.
 #[derive(Clone, Copy, Debug)]
@@ -90,6 +93,11 @@ impl RenderMetrics {
         CODE_BLOCK_HORIZONTAL_PADDING_PX
     }
 
+    /// Height of the language label / copy button row above a code block.
+    pub(super) fn code_header_height(self) -> f32 {
+        self.body_size * CODE_HEADER_HEIGHT_EM
+    }
+
     pub(super) const fn bullet_text(self) -> &'static str {
         match self.list_depth {
             0 => "\u{2022}",
@@ -164,8 +172,15 @@ impl RenderMetrics {
         TABLE_SCROLLBAR_HEIGHT_PX
     }
 
+    /// Thickness of a thematic break's rule.
+    pub(super) fn thematic_break_stroke(self) -> f32 {
+        (self.body_size * THEMATIC_BREAK_STROKE_EM).max(THEMATIC_BREAK_MIN_STROKE_PX)
+    }
+
+    /// A thematic break: its rule with block spacing above and below.
     pub(super) fn thematic_break_height(self) -> f32 {
-        self.body_size * THEMATIC_BREAK_HEIGHT_EM
+        self.paragraph_spacing()
+            .mul_add(2.0, self.thematic_break_stroke())
     }
 
     pub(super) fn image_fallback_height(self) -> f32 {
@@ -175,6 +190,14 @@ impl RenderMetrics {
     pub(super) fn image_max_height(wrap_width: f32) -> f32 {
         wrap_width * IMAGE_MAX_HEIGHT_FRACTION
     }
+
+    pub(super) fn definition_indent(self) -> f32 {
+        self.body_size * DEFINITION_INDENT_EM
+    }
+
+    pub(super) fn definition_content_width(self, wrap_width: f32) -> f32 {
+        (wrap_width - self.definition_indent()).max(MIN_CONTENT_WIDTH)
+    }
 }
 
 /// Shared render-time state for nested block traversal.
@@ -10,17 +10,21 @@ mod layout;
 mod lists;
 mod table;
 mod text;
+mod zoom;
 
 #[cfg(test)]
 mod tests;
 
-use crate::parse::{Block, parse_markdown_into};
+use crate::parse::{Block, ParseOptions, parse_markdown_into};
+use crate::slug::HeadingSlugger;
 use crate::style::MarkdownStyle;
 
+pub use blocks::{percent_decode, percent_encode_path};
 use blocks::{render_block, render_blocks};
 pub use height::bytecount_newlines;
 use height::estimate_block_height;
 use layout::RenderContext;
+pub use zoom::{show_image_zoom, take_shown_images, zoomed_image};
 
 const PREVIEW_WHEEL_SCROLL_MULTIPLIER: f32 = 1.15;
 
@@ -47,9 +51,23 @@ pub struct MarkdownCache {
     pub last_scroll_y: f32,
     /// Block indices of non-empty headings, cached for O(1) `heading_y` lookup.
     heading_block_indices: Vec<usize>,
+    /// Unique anchor slug of each non-empty heading, in the same order as
+    /// `heading_block_indices`.
+    heading_slugs: Vec<String>,
+    /// Options the cached blocks were parsed with.
+    parse_options: ParseOptions,
 }
 
 impl MarkdownCache {
+    /// Change how the source is parsed; re-parses on the next render when
+    /// the options differ from the cached ones.
+    pub fn set_parse_options(&mut self, options: ParseOptions) {
+        if self.parse_options != options {
+            self.parse_options = options;
+            self.clear();
+        }
+    }
+
     /// Invalidate the cache so the next render re-parses.
     pub fn clear(&mut self) {
         self.text_hash = 0;
@@ -63,6 +81,7 @@ impl MarkdownCache {
         self.height_wrap_width = 0.0;
         self.last_scroll_y = 0.0;
         self.heading_block_indices.clear();
+        self.heading_slugs.clear();
     }
 
     pub fn ensure_parsed(&mut self, source: &str) {
@@ -86,18 +105,26 @@ impl MarkdownCache {
         // Content actually changed — re-parse, reusing the blocks allocation.
         self.text_hash = hash;
         self.blocks.clear();
-        parse_markdown_into(source, &mut self.blocks);
+        parse_markdown_into(source, &mut self.blocks, self.parse_options);
         self.heights.clear();
         self.cum_y.clear();
         self.total_height = 0.0;
 
-        // Rebuild heading index for fast heading_y lookup.
+        // Rebuild heading index and anchor slugs for fast heading_y lookup.
         self.heading_block_indices.clear();
+        self.heading_slugs.clear();
+        let mut slugger = HeadingSlugger::new();
         for (idx, block) in self.blocks.iter().enumerate() {
-            if let Block::Heading { text, .. } = block
+            if let Block::Heading { text, id, .. } = block
                 && !text.text.is_empty()
             {
                 self.heading_block_indices.push(idx);
+                // As in HTML export, an explicit `{#id}` leaves the slugs of
+                // the other headings alone.
+                self.heading_slugs.push(
+                    id.as_ref()
+                        .map_or_else(|| slugger.slug(&text.text), ToString::to_string),
+                );
             }
         }
     }
@@ -147,6 +174,20 @@ impl MarkdownCache {
         let block_idx = *self.heading_block_indices.get(ordinal)?;
         self.cum_y.get(block_idx).copied()
     }
+
+    /// Return the ordinal (as used by [`Self::heading_y`]) of the heading
+    /// whose anchor slug is `slug`, e.g. `"intro-1"` for the second "Intro".
+    #[must_use]
+    pub fn heading_ordinal(&self, slug: &str) -> Option<usize> {
+        self.heading_slugs.iter().position(|s| s == slug)
+    }
+
+    /// Return the Y offset of the heading an in-document `#slug` link
+    /// points at.
+    #[must_use]
+    pub fn anchor_y(&self, slug: &str) -> Option<f32> {
+        self.heading_y(self.heading_ordinal(slug)?)
+    }
 }
 
 // ── Viewer widget ──────────────────────────────────────────────────
@@ -162,6 +203,11 @@ impl MarkdownViewer {
         Self { id_salt }
     }
 
+    /// Scopes link focus and anchor requests to this viewer.
+    fn id(&self) -> egui::Id {
+        egui::Id::new(self.id_salt)
+    }
+
     /// Render markdown in a scrollable area with **viewport culling**.
     ///
     /// Only blocks overlapping the visible viewport are actually rendered;
@@ -177,9 +223,24 @@ impl MarkdownViewer {
         scroll_to_y: Option<f32>,
     ) {
         cache.ensure_parsed(source);
+        self.show_scrollable_cached(ui, cache, style, scroll_to_y);
+    }
 
+    /// [`Self::show_scrollable`] for the source `cache` last parsed, without
+    /// needing that source: keeps a render up while its text is edited.
+    pub fn show_scrollable_cached(
+        &self,
+        ui: &mut egui::Ui,
+        cache: &mut MarkdownCache,
+        style: &MarkdownStyle,
+        scroll_to_y: Option<f32>,
+    ) {
         let body_size = ui.text_style_height(&egui::TextStyle::Body);
-        let wrap_width = ui.available_width();
+        let full_width = ui.available_width();
+        let wrap_width = style
+            .max_content_width
+            .filter(|w| w.is_finite() && *w > 0.0)
+            .map_or(full_width, |w| w.min(full_width));
         cache.ensure_heights(body_size, wrap_width, style);
 
         if cache.blocks.is_empty() {
@@ -191,6 +252,12 @@ impl MarkdownViewer {
             .auto_shrink([false, false])
             .wheel_scroll_multiplier(egui::vec2(1.0, PREVIEW_WHEEL_SCROLL_MULTIPLIER));
 
+        // An explicit scroll target wins; otherwise follow an in-document
+        // link clicked last frame (the request stays pending until then).
+        let scroll_to_y = scroll_to_y.or_else(|| {
+            let slug = text::take_anchor_request(ui.ctx(), self.id())?;
+            cache.anchor_y(&slug)
+        });
         if let Some(y) = scroll_to_y
             && y.is_finite()
         {
@@ -199,64 +266,37 @@ impl MarkdownViewer {
 
         // Track whether any estimated heights were corrected by measurement.
         let mut heights_changed = false;
+        let link_focused = text::take_link_focus(ui.ctx(), self.id());
+        let stack_info = text::viewer_stack_info(self.id());
 
         scroll_area.show_viewport(ui, |ui, viewport| {
             // Record current scroll offset for external sync.
             cache.last_scroll_y = viewport.min.y;
 
-            // Allocate total height so scroll thumb is correct.
-            ui.set_min_height(cache.total_height);
-
-            let vis_top = viewport.min.y;
-            let vis_bottom = viewport.max.y;
-
-            // Binary search for first visible block.
-            let first = match cache
-                .cum_y
-                .binary_search_by(|y| y.partial_cmp(&vis_top).unwrap_or(std::cmp::Ordering::Equal))
-            {
-                Ok(i) => i,
-                Err(i) => i.saturating_sub(1),
+            // While a link has keyboard focus, also lay out a screen above
+            // and below so Tab and Shift+Tab can reach the next link.
+            let viewport = if link_focused {
+                viewport.expand2(egui::vec2(0.0, viewport.height()))
+            } else {
+                viewport
             };
 
-            // Allocate space for all blocks above viewport.
-            if first > 0 {
-                let skip_h = cache.cum_y[first];
-                ui.add_space(skip_h);
-            }
-
-            // Render visible blocks, measuring actual heights.
-            let mut idx = first;
-            while idx < cache.blocks.len() {
-                let block_y = cache.cum_y[idx];
-                if block_y > vis_bottom {
-                    break;
-                }
-
-                // ── Progressive height refinement ──────────────────
-                // Measure the full child-ui rect instead of cursor deltas;
-                // labels/galleys can extend without advancing the parent
-                // cursor in the same way spaces do.
-                let rendered = ui.scope(|ui| {
-                    render_block(ui, &cache.blocks[idx], style, RenderContext::root(ui));
-                });
-                let actual_h = rendered.response.rect.height();
-
-                if actual_h > 0.0 && (cache.heights[idx] - actual_h).abs() > 2.0 {
-                    cache.heights[idx] = actual_h;
-                    heights_changed = true;
-                }
-
-                idx += 1;
-            }
+            // Allocate total height so scroll thumb is correct.
+            ui.set_min_height(cache.total_height);
 
-            // Allocate space for blocks below viewport.
-            if idx < cache.blocks.len() {
-                let remaining = cache.total_height - cache.cum_y[idx];
-                if remaining > 0.0 {
-                    ui.add_space(remaining);
-                }
+            // With a capped reading width, lay blocks out in a centered
+            // column; the scroll area itself keeps the full width.
+            let mut builder = egui::UiBuilder::new().ui_stack_info(stack_info);
+            let spare = ui.available_width() - wrap_width;
+            if spare >= 1.0 {
+                let mut column = ui.max_rect();
+                column.min.x += spare / 2.0;
+                column.max.x = column.min.x + wrap_width;
+                builder = builder.max_rect(column);
             }
+            ui.scope_builder(builder, |ui| {
+                heights_changed = Self::show_visible_blocks(ui, cache, style, viewport);
+            });
         });
 
         // Recompute cumulative offsets outside the viewport pass so the
@@ -267,6 +307,69 @@ impl MarkdownViewer {
         }
     }
 
+    /// Render the blocks overlapping `viewport`, with spacers standing in for
+    /// the blocks above and below it.  Returns `true` if any block's measured
+    /// height corrected its estimate.
+    fn show_visible_blocks(
+        ui: &mut egui::Ui,
+        cache: &mut MarkdownCache,
+        style: &MarkdownStyle,
+        viewport: egui::Rect,
+    ) -> bool {
+        let mut heights_changed = false;
+        let vis_top = viewport.min.y;
+        let vis_bottom = viewport.max.y;
+
+        // Binary search for first visible block.
+        let first = match cache
+            .cum_y
+            .binary_search_by(|y| y.partial_cmp(&vis_top).unwrap_or(std::cmp::Ordering::Equal))
+        {
+            Ok(i) => i,
+            Err(i) => i.saturating_sub(1),
+        };
+
+        // Allocate space for all blocks above viewport.
+        if first > 0 {
+            let skip_h = cache.cum_y[first];
+            ui.add_space(skip_h);
+        }
+
+        // Render visible blocks, measuring actual heights.
+        let mut idx = first;
+        while idx < cache.blocks.len() {
+            let block_y = cache.cum_y[idx];
+            if block_y > vis_bottom {
+                break;
+            }
+
+            // ── Progressive height refinement ──────────────────
+            // Measure the full child-ui rect instead of cursor deltas;
+            // labels/galleys can extend without advancing the parent
+            // cursor in the same way spaces do.
+            let rendered = ui.scope(|ui| {
+                render_block(ui, &cache.blocks[idx], style, RenderContext::root(ui));
+            });
+            let actual_h = rendered.response.rect.height();
+
+            if actual_h > 0.0 && (cache.heights[idx] - actual_h).abs() > 2.0 {
+                cache.heights[idx] = actual_h;
+                heights_changed = true;
+            }
+
+            idx += 1;
+        }
+
+        // Allocate space for blocks below viewport.
+        if idx < cache.blocks.len() {
+            let remaining = cache.total_height - cache.cum_y[idx];
+            if remaining > 0.0 {
+                ui.add_space(remaining);
+            }
+        }
+        heights_changed
+    }
+
     /// Render markdown inline (no scroll area, no culling).
     pub fn show(
         &self,
@@ -276,7 +379,10 @@ impl MarkdownViewer {
         source: &str,
     ) {
         cache.ensure_parsed(source);
-        render_blocks(ui, &cache.blocks, style, RenderContext::root(ui));
+        let builder = egui::UiBuilder::new().ui_stack_info(text::viewer_stack_info(self.id()));
+        ui.scope_builder(builder, |ui| {
+            render_blocks(ui, &cache.blocks, style, RenderContext::root(ui));
+        });
     }
 }
 
@@ -541,6 +541,27 @@ fn diag_hr_syntaxes_produce_identical_blocks() {
     assert!((h1 - h2).abs() < f32::EPSILON, "HR heights should match");
 }
 
+#[test]
+fn diag_hr_scales_with_body_size_and_differs_from_setext() {
+    // A setext underline makes a heading; only a detached `---` is a rule.
+    let blocks = crate::parse::parse_markdown("Title\n---\n\n---\n");
+    assert!(matches!(&blocks[0], Block::Heading { level: 2, .. }));
+    assert!(matches!(&blocks[1], Block::ThematicBreak));
+    assert_eq!(blocks.len(), 2);
+
+    let style = dark_style();
+    let hr = Block::ThematicBreak;
+    let small = height::estimate_block_height(&hr, 14.0, 600.0, &style);
+    let large = height::estimate_block_height(&hr, 28.0, 600.0, &style);
+    assert!(
+        (large - 2.0 * small).abs() < 0.01,
+        "rule and spacing scale with the body size: {small} vs {large}"
+    );
+    // Thin bodies still get a visible one-point rule.
+    let tiny = height::estimate_block_height(&hr, 4.0, 600.0, &style);
+    assert!(tiny > 4.0 * 0.4 * 2.0);
+}
+
 #[test]
 fn diag_code_block_whitespace_only_preserved() {
     let blocks = crate::parse::parse_markdown("```\n   \n```\n");
@@ -780,10 +801,10 @@ fn diag_hr_between_blocks_spacing() {
     assert!(matches!(&blocks[2], Block::Paragraph(_)));
     assert!(height > 0.0);
 
-    // HR height estimate: body_size * 0.8.
+    // HR height estimate: 0.4em spacing either side of a 0.1em rule.
     let style = dark_style();
     let hr_h = height::estimate_block_height(&Block::ThematicBreak, 14.0, 600.0, &style);
-    let expected = 14.0 * 0.8;
+    let expected = 14.0 * 0.9;
     assert!(
         (hr_h - expected).abs() < 0.01,
         "HR height ({hr_h}) should be ~{expected}"
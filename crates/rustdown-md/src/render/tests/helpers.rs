@@ -1,6 +1,6 @@
 pub(super) use crate::parse::{Alignment, Block, ListItem, Span, SpanStyle, StyledText, TableData};
 pub(super) use crate::render::blocks::{
-    contains_dot_dot_segment, render_blocks, resolve_image_url,
+    contains_dot_dot_segment, join_image_url, render_blocks, resolve_image_url,
 };
 pub(super) use crate::render::height::{
     self, estimate_block_height, estimate_table_height, estimate_text_height,
@@ -219,5 +219,6 @@ pub(super) fn plain_item(text: &str) -> ListItem {
         content: plain(text),
         children: vec![],
         checked: None,
+        loose: false,
     }
 }
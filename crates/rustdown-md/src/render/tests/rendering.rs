@@ -126,6 +126,68 @@ fn heading_y_behavior() {
     assert!(cache.heading_y(0).is_none(), "cleared → None");
 }
 
+#[test]
+fn parse_options_change_reparses_cached_source() {
+    let source = "One.\nTwo.\n";
+    let mut cache = build_cache(source);
+    assert!(matches!(&cache.blocks[..], [Block::Paragraph(t)] if t.text == "One. Two."));
+
+    cache.set_parse_options(crate::ParseOptions {
+        preserve_line_breaks: true,
+        ..crate::ParseOptions::default()
+    });
+    cache.ensure_parsed(source);
+    assert!(matches!(&cache.blocks[..], [Block::Paragraph(t)] if t.text == "One.\nTwo."));
+}
+
+#[test]
+fn anchor_slugs_map_to_heading_positions() {
+    let style = dark_style();
+    let mut cache = MarkdownCache::default();
+    cache.ensure_parsed("# Intro\n\ntext\n\n## What's new?\n\n## Intro\n\n## \n");
+    cache.ensure_heights(14.0, 400.0, &style);
+    assert_eq!(cache.heading_ordinal("intro"), Some(0));
+    assert_eq!(cache.heading_ordinal("whats-new"), Some(1));
+    assert_eq!(cache.heading_ordinal("intro-1"), Some(2));
+    assert_eq!(cache.heading_ordinal("missing"), None);
+    assert_eq!(cache.anchor_y("intro-1"), cache.heading_y(2));
+    assert!(cache.anchor_y("missing").is_none());
+
+    // An explicit `{#id}` replaces the slug, and the next "Intro" still
+    // gets the plain one.
+    cache.ensure_parsed("# Intro {#start}\n\n## Intro\n");
+    assert_eq!(cache.heading_ordinal("start"), Some(0));
+    assert_eq!(cache.heading_ordinal("intro"), Some(1));
+
+    cache.clear();
+    assert_eq!(cache.heading_ordinal("intro"), None);
+}
+
+#[test]
+fn max_content_width_narrows_wrap_width() {
+    let md = "Lorem ipsum dolor sit amet, consectetur adipiscing elit. ".repeat(40);
+    let render = |max_width: Option<f32>| {
+        let ctx = headless_ctx();
+        let mut cache = MarkdownCache::default();
+        let mut style = dark_style();
+        style.max_content_width = max_width;
+        let viewer = MarkdownViewer::new("reading_width");
+        let _ = ctx.run(raw_input_1024x768(), |ctx| {
+            egui::CentralPanel::default().show(ctx, |ui| {
+                viewer.show_scrollable(ui, &mut cache, &style, &md, None);
+            });
+        });
+        cache.height_wrap_width
+    };
+    let full_wrap = render(None);
+    assert!((render(Some(300.0)) - 300.0).abs() < f32::EPSILON);
+    assert!(full_wrap > 900.0);
+
+    // A cap wider than the viewport, or a nonsensical one, is ignored.
+    assert!((render(Some(5_000.0)) - full_wrap).abs() < f32::EPSILON);
+    assert!((render(Some(f32::NAN)) - full_wrap).abs() < f32::EPSILON);
+}
+
 #[test]
 fn estimate_height_all_block_types_positive() {
     // Text height basics
@@ -151,6 +213,7 @@ fn estimate_height_all_block_types_positive() {
             Block::Heading {
                 level: 1,
                 text: plain("h"),
+                id: None,
             },
         ),
         ("paragraph", Block::Paragraph(plain("Hello world"))),
@@ -200,14 +263,29 @@ fn estimate_height_all_block_types_positive() {
                     content: plain("checked"),
                     children: vec![],
                     checked: Some(true),
+                    loose: false,
                 },
                 ListItem {
                     content: plain("unchecked"),
                     children: vec![],
                     checked: Some(false),
+                    loose: false,
                 },
             ]),
         ),
+        (
+            "definition_list",
+            Block::DefinitionList(vec![crate::parse::DefinitionItem {
+                term: plain("Term"),
+                definitions: vec![vec![Block::Paragraph(plain("meaning"))]],
+            }]),
+        ),
+        (
+            "math",
+            Block::Math {
+                tex: Box::from("e^{i\\pi} + 1 = 0"),
+            },
+        ),
     ];
     for (label, block) in &blocks {
         let h = estimate_block_height(block, 14.0, 400.0, &style);
@@ -305,6 +383,114 @@ fn render_image_variations() {
     }
 }
 
+#[test]
+fn image_max_width_resolves_fractions_and_points() {
+    use crate::ImageMaxWidth;
+    assert!((ImageMaxWidth::default().resolve(800.0) - 800.0).abs() < f32::EPSILON);
+    assert!((ImageMaxWidth::Fraction(0.5).resolve(800.0) - 400.0).abs() < f32::EPSILON);
+    assert!((ImageMaxWidth::Fraction(0.0).resolve(800.0) - 800.0).abs() < f32::EPSILON);
+    assert!((ImageMaxWidth::Fraction(0.01).resolve(800.0) - 80.0).abs() < f32::EPSILON);
+    assert!((ImageMaxWidth::Points(300.0).resolve(800.0) - 300.0).abs() < f32::EPSILON);
+    assert!((ImageMaxWidth::Points(3_000.0).resolve(800.0) - 800.0).abs() < f32::EPSILON);
+    assert!((ImageMaxWidth::Points(f32::NAN).resolve(800.0) - 800.0).abs() < f32::EPSILON);
+
+    // A narrower cap shrinks the estimated image height.
+    let mut style = dark_style();
+    let estimate = |style: &MarkdownStyle| {
+        let mut cache = MarkdownCache::default();
+        cache.ensure_parsed("![alt](pic.png)");
+        cache.ensure_heights(14.0, 1_000.0, style);
+        cache.total_height
+    };
+    let full = estimate(&style);
+    style.image_max_width = ImageMaxWidth::Points(200.0);
+    assert!(estimate(&style) < full);
+}
+
+#[test]
+fn shown_local_images_are_recorded_and_zoom_opens() {
+    let ctx = headless_ctx();
+    let mut cache = MarkdownCache::default();
+    let mut style = dark_style();
+    style.image_base_uri = "file:///base/dir/".to_owned();
+    let viewer = MarkdownViewer::new("img_shown");
+    let source = "![a](pic.png)\n\n![b](https://example.com/remote.png)\n";
+    let _ = ctx.run(raw_input_1024x768(), |ctx| {
+        egui::CentralPanel::default().show(ctx, |ui| {
+            viewer.show(ui, &mut cache, &style, source);
+        });
+    });
+    let shown = crate::take_shown_images(&ctx);
+    assert_eq!(
+        shown.into_iter().collect::<Vec<_>>(),
+        ["file:///base/dir/pic.png"]
+    );
+    assert!(crate::take_shown_images(&ctx).is_empty(), "taking drains");
+
+    super::super::zoom::request_zoom(&ctx, "file:///base/dir/pic.png");
+    let _ = ctx.run(raw_input_1024x768(), crate::show_image_zoom);
+    assert_eq!(
+        crate::zoomed_image(&ctx).as_deref(),
+        Some("file:///base/dir/pic.png")
+    );
+}
+
+#[test]
+fn tab_focuses_links_in_order_and_enter_follows_them() {
+    let ctx = headless_ctx();
+    let mut cache = MarkdownCache::default();
+    let style = dark_style();
+    let viewer = MarkdownViewer::new("link_focus");
+    let source = "[ext](https://x.io) then [**split** link](#intro)\n\n# Intro\n";
+    let mut frame = |key: Option<egui::Key>| {
+        let mut input = raw_input_1024x768();
+        if let Some(key) = key {
+            for pressed in [true, false] {
+                input.events.push(egui::Event::Key {
+                    key,
+                    physical_key: None,
+                    pressed,
+                    repeat: false,
+                    modifiers: egui::Modifiers::NONE,
+                });
+            }
+        }
+        ctx.run(input, |ctx| {
+            egui::CentralPanel::default().show(ctx, |ui| {
+                viewer.show_scrollable(ui, &mut cache, &style, source, None);
+            });
+        })
+    };
+    let _ = frame(None);
+    assert!(ctx.memory(egui::Memory::focused).is_none());
+
+    let _ = frame(Some(egui::Key::Tab));
+    let first = ctx.memory(egui::Memory::focused);
+    assert!(first.is_some());
+    let output = frame(Some(egui::Key::Enter));
+    let opened = output
+        .platform_output
+        .commands
+        .into_iter()
+        .find_map(|command| match command {
+            egui::OutputCommand::OpenUrl(open) => Some(open.url),
+            _ => None,
+        });
+    assert_eq!(opened.as_deref(), Some("https://x.io"));
+
+    // The two-span link is a single Tab stop, then focus wraps around.
+    let _ = frame(Some(egui::Key::Tab));
+    let second = ctx.memory(egui::Memory::focused);
+    assert!(second.is_some() && second != first);
+    let _ = frame(Some(egui::Key::Enter));
+    let take = |salt: &str| super::super::text::take_anchor_request(&ctx, egui::Id::new(salt));
+    assert_eq!(take("other_viewer"), None);
+    assert_eq!(take("link_focus").as_deref(), Some("intro"));
+    let _ = frame(Some(egui::Key::Tab));
+    let _ = frame(None);
+    assert_eq!(ctx.memory(egui::Memory::focused), first);
+}
+
 #[test]
 fn render_various_inputs_no_panic() {
     let cases: Vec<(&str, &str)> = vec![
@@ -402,8 +588,22 @@ fn render_various_inputs_no_panic() {
         other => panic!("expected UnorderedList, got {other:?}"),
     }
 
-    // Smart quotes
-    let blocks = crate::parse::parse_markdown(r#"He said "hello" and she said 'world'."#);
+    // Smart quotes, only when enabled
+    let source = r#"He said "hello" and she said 'world'."#;
+    let blocks = crate::parse::parse_markdown(source);
+    match &blocks[0] {
+        Block::Paragraph(text) => assert_eq!(text.text, source),
+        other => panic!("expected Paragraph, got {other:?}"),
+    }
+    let mut blocks = Vec::new();
+    crate::parse::parse_markdown_into(
+        source,
+        &mut blocks,
+        crate::ParseOptions {
+            smart_punctuation: true,
+            ..crate::ParseOptions::default()
+        },
+    );
     match &blocks[0] {
         Block::Paragraph(text) => {
             assert!(text.text.contains('\u{201c}') || text.text.contains('\u{201d}'));
@@ -471,6 +671,7 @@ fn heading_style_and_rendering() {
             &Block::Heading {
                 level,
                 text: plain("Title"),
+                id: None,
             },
             14.0,
             400.0,
@@ -654,7 +855,7 @@ fn render_code_block_variations() {
     // Code in heading
     let (blocks, _) = headless_render("## The `render()` Function");
     match &blocks[0] {
-        Block::Heading { level, text } => {
+        Block::Heading { level, text, .. } => {
             assert_eq!(*level, 2);
             assert!(text.text.contains("render()"));
             assert!(
@@ -1097,6 +1298,30 @@ fn image_url_resolution_cases() {
             "/images/pic.png",
             "no scheme passthrough",
         ),
+        (
+            "my%20pic.png",
+            "file:///home/me/My%20Notes/",
+            "file:///home/me/My Notes/my pic.png",
+            "file URLs are percent-decoded for the loader",
+        ),
+        (
+            "pic.png",
+            "file:///C:/Users/me/",
+            "file:///C:/Users/me/pic.png",
+            "drive-letter base unchanged",
+        ),
+        (
+            "a%20b.png",
+            "file://server/share/dir/",
+            "file://\\\\server/share/dir/a b.png",
+            "UNC host becomes a backslash prefix",
+        ),
+        (
+            "pic.png",
+            "file://localhost/srv/",
+            "file:///srv/pic.png",
+            "localhost host dropped",
+        ),
     ];
     for (url, base, expected, desc) in cases {
         assert_eq!(
@@ -1107,6 +1332,40 @@ fn image_url_resolution_cases() {
     }
 }
 
+#[test]
+fn image_paths_with_spaces_and_unicode_are_encoded_then_loaded() {
+    let base = "file:///home/me/My%20Notes/";
+    for (url, joined, loaded) in [
+        (
+            "My File.png",
+            "file:///home/me/My%20Notes/My%20File.png",
+            "file:///home/me/My Notes/My File.png",
+        ),
+        (
+            "shots/café ☕.png",
+            "file:///home/me/My%20Notes/shots/caf%C3%A9%20%E2%98%95.png",
+            "file:///home/me/My Notes/shots/café ☕.png",
+        ),
+        (
+            "pre%20encoded.png",
+            "file:///home/me/My%20Notes/pre%20encoded.png",
+            "file:///home/me/My Notes/pre encoded.png",
+        ),
+        (
+            "/abs dir/x.png",
+            "file:///abs%20dir/x.png",
+            "file:///abs dir/x.png",
+        ),
+    ] {
+        assert_eq!(join_image_url(url, base), joined, "{url}");
+        assert_eq!(resolve_image_url(url, base), loaded, "{url}");
+    }
+    assert_eq!(
+        join_image_url("a b.png", "https://example.com/docs/"),
+        "https://example.com/docs/a%20b.png"
+    );
+}
+
 #[test]
 fn estimate_height_larger_inputs_taller() {
     let style = dark_style();
@@ -1119,6 +1378,7 @@ fn estimate_height_larger_inputs_taller() {
             &Block::Heading {
                 level,
                 text: text.clone(),
+                id: None,
             },
             14.0,
             400.0,
@@ -1447,6 +1707,7 @@ fn height_estimation_edge_cases_comprehensive() {
             content: plain(&format!("level {depth}")),
             children: vec![list],
             checked: None,
+            loose: false,
         }]);
     }
     assert_sane_height(
@@ -1970,10 +2231,28 @@ fn code_block_height_estimation_cases() {
         "only-newlines ({h_nl}) ≈ empty ({h_empty})"
     );
 
-    // Language label adds height
+    // The header row (language label + copy button) is always present.
     let h_wl = estimate_block_height(&code_block("python", "pass"), 14.0, 600.0, &style);
     let h_nl2 = estimate_block_height(&code_block("", "pass"), 14.0, 600.0, &style);
-    assert!(h_wl > h_nl2, "with language ({h_wl}) > without ({h_nl2})");
+    assert!(
+        (h_wl - h_nl2).abs() < f32::EPSILON,
+        "with language ({h_wl}) == without ({h_nl2})"
+    );
+
+    // Wrapping code blocks grow with long lines; scrolling ones don't.
+    let long = code_block("", &"x".repeat(2000));
+    let h_scroll = estimate_block_height(&long, 14.0, 600.0, &style);
+    let mut wrap_style = dark_style();
+    wrap_style.code_wrap = true;
+    let h_wrap = estimate_block_height(&long, 14.0, 600.0, &wrap_style);
+    assert!(
+        h_wrap > h_scroll * 3.0,
+        "wrapped ({h_wrap}) >> scrolled ({h_scroll})"
+    );
+    let short = code_block("", "a\nb");
+    let h_short_scroll = estimate_block_height(&short, 14.0, 600.0, &style);
+    let h_short_wrap = estimate_block_height(&short, 14.0, 600.0, &wrap_style);
+    assert!((h_short_scroll - h_short_wrap).abs() < f32::EPSILON);
 }
 
 #[test]
@@ -1985,6 +2264,7 @@ fn block_height_robustness() {
         Block::Heading {
             level: 1,
             text: plain("Title"),
+            id: None,
         },
         Block::Paragraph(plain("text")),
         Block::Code {
@@ -2021,6 +2301,7 @@ fn block_height_robustness() {
         Block::Heading {
             level: 2,
             text: plain("Section"),
+            id: None,
         },
         Block::Paragraph(plain("Some body text here.")),
         Block::Code {
@@ -2810,7 +3091,7 @@ fn render_edge_case_structures_no_panic() {
     // Link in heading
     let (blocks, _) = headless_render("## [Documentation](https://docs.rs)\n");
     match &blocks[0] {
-        Block::Heading { level, text } => {
+        Block::Heading { level, text, .. } => {
             assert_eq!(*level, 2);
             assert!(
                 text.spans.iter().any(|s| s.style.has_link()),
@@ -3056,12 +3337,17 @@ fn fuzz_resolve_image_url() {
             "traversal blocked: {url}"
         );
     }
-    // URL-encoded dots are NOT path traversal — they pass through.
-    let encoded = "img/..%2f..%2f..%2fetc/passwd";
-    assert_ne!(
-        resolve_image_url(encoded, base).as_ref(),
-        "",
-        "URL-encoded dots are safe"
+    // Escapes are decoded before loading, so encoded traversal is blocked too.
+    for encoded in ["img/..%2f..%2f..%2fetc/passwd", "%2E%2E/secret.txt"] {
+        assert_eq!(
+            resolve_image_url(encoded, base).as_ref(),
+            "",
+            "encoded traversal blocked: {encoded}"
+        );
+    }
+    assert_eq!(
+        resolve_image_url("100%25%20done.png", base).as_ref(),
+        "file:///home/user/docs/100% done.png"
     );
 
     // Safe paths resolve correctly
@@ -3176,6 +3462,21 @@ fn blockquote_layout_and_cursor_advance() {
     assert!(reserved > 0.0, "reserved space must be positive");
 }
 
+#[test]
+fn quoted_code_and_nested_quotes_render_with_height() {
+    let (_, one_line) = headless_render("> quoted\n");
+    for md in [
+        "> ```\n> line 1\n> line 2\n> ```\n",
+        "> > ```\n> > deep\n> > ```\n> back\n",
+        "> - a\n>   ```\n>   x\n>   ```\n> - b\n",
+        "> \n> ```\ncode\n```\n",
+    ] {
+        let (blocks, height) = headless_render(md);
+        assert!(matches!(blocks.first(), Some(Block::Quote(_))), "{md:?}");
+        assert!(height > one_line, "{md:?}: {height} <= {one_line}");
+    }
+}
+
 #[test]
 fn issue2_list_child_blocks_ignore_indent() {
     // Parse a list item with child blocks (paragraph + code).
@@ -3341,7 +3642,7 @@ fn issue8_empty_heading_renders_spacing() {
     // Parse an empty H2 heading.
     let blocks = crate::parse::parse_markdown("##\n");
     match &blocks[0] {
-        Block::Heading { level, text } => {
+        Block::Heading { level, text, .. } => {
             assert_eq!(*level, 2);
             assert!(text.text.is_empty(), "empty heading should have empty text");
         }
@@ -3361,6 +3662,7 @@ fn issue8_empty_heading_renders_spacing() {
         &Block::Heading {
             level: 2,
             text: StyledText::default(),
+            id: None,
         },
         14.0,
         400.0,
@@ -3384,12 +3686,14 @@ fn bug11_nested_list_height_underestimates_due_to_missing_indent_px() {
         content: plain(&long_text),
         children: vec![],
         checked: None,
+        loose: false,
     }]);
     for depth in 0..4 {
         list = Block::UnorderedList(vec![ListItem {
             content: plain(&format!("Level {depth} item")),
             children: vec![list],
             checked: None,
+            loose: false,
         }]);
     }
 
@@ -3502,10 +3806,11 @@ fn bug13_code_block_height_missing_scrollbar() {
     };
     let estimated = estimate_block_height(&block, body_size, narrow_width, &style);
 
-    // The estimate should cover: 1 line * mono_size * 1.4 + 12 (margins) + 0.4*body.
+    // The estimate should cover: 1 line * mono_size * 1.4 + 12 (margins)
+    // + 0.4*body + 1.4*body (header row).
     // It does NOT include the horizontal scrollbar (~14-16px).
     let mono_size = body_size * 0.9;
-    let expected_without_scroll = body_size.mul_add(0.4, mono_size.mul_add(1.4, 12.0));
+    let expected_without_scroll = body_size.mul_add(1.8, mono_size.mul_add(1.4, 12.0));
     // The estimate should match the formula without scrollbar:
     assert!(
         (estimated - expected_without_scroll).abs() < 1.0,
@@ -13,6 +13,21 @@ const fn snap_to_char_boundary(text: &str, pos: usize) -> usize {
     text.floor_char_boundary(pos)
 }
 
+/// Size of subscript and superscript text relative to the surrounding text.
+const SCRIPT_FONT_SCALE: f32 = 0.75;
+
+/// Font size for a span in text of `size` points: code is slightly smaller,
+/// sub- and superscripts more so.
+#[inline]
+fn span_font_size(ss: SpanStyle, size: f32) -> f32 {
+    let size = if ss.code() { size * 0.9 } else { size };
+    if ss.subscript() || ss.superscript() {
+        size * SCRIPT_FONT_SCALE
+    } else {
+        size
+    }
+}
+
 /// Inline formatting properties resolved from a composite `SpanStyle`.
 #[allow(clippy::struct_excessive_bools)]
 pub(super) struct SpanFormat {
@@ -23,6 +38,7 @@ pub(super) struct SpanFormat {
     strikethrough: bool,
     italics: bool,
     strong: bool,
+    valign: egui::Align,
 }
 
 impl SpanFormat {
@@ -34,7 +50,7 @@ impl SpanFormat {
         ui: &egui::Ui,
     ) -> Self {
         Self {
-            font_family: if ss.code() {
+            font_family: if ss.code() || ss.math() {
                 egui::FontFamily::Monospace
             } else {
                 egui::FontFamily::Proportional
@@ -43,10 +59,16 @@ impl SpanFormat {
                 md_style
                     .link_color
                     .unwrap_or_else(|| ui.visuals().hyperlink_color)
+            } else if ss.html() {
+                ui.visuals().weak_text_color()
+            } else if ss.code() {
+                md_style.code_color.unwrap_or(base_color)
             } else {
                 base_color
             },
-            background: if ss.code() {
+            background: if ss.math() {
+                math_background(md_style, ui)
+            } else if ss.code() {
                 md_style
                     .code_bg
                     .unwrap_or_else(|| ui.visuals().faint_bg_color)
@@ -57,6 +79,13 @@ impl SpanFormat {
             strikethrough: ss.strikethrough(),
             italics: ss.emphasis(),
             strong: ss.strong(),
+            // Smaller subscript text sits on the row's bottom, like the
+            // default alignment; superscripts ride at the top.
+            valign: if ss.superscript() {
+                egui::Align::TOP
+            } else {
+                egui::Align::BOTTOM
+            },
         }
     }
 }
@@ -122,6 +151,7 @@ pub(super) fn render_text_with_links(
 
     ui.horizontal_wrapped(|ui| {
         ui.spacing_mut().item_spacing.x = 0.0;
+        let mut previous_link = None;
         for span in &st.spans {
             let start = snap_to_char_boundary(&st.text, (span.start as usize).min(st.text.len()));
             let end = snap_to_char_boundary(&st.text, (span.end as usize).min(st.text.len()));
@@ -130,8 +160,9 @@ pub(super) fn render_text_with_links(
             }
             let text = &st.text[start..end];
             let is_code = span.style.code();
-            let span_size = if is_code { size * 0.9 } else { size };
-            let font_family = if is_code {
+            let is_math = span.style.math();
+            let span_size = span_font_size(span.style, size);
+            let font_family = if is_code || is_math {
                 egui::FontFamily::Monospace
             } else {
                 egui::FontFamily::Proportional
@@ -144,7 +175,12 @@ pub(super) fn render_text_with_links(
             if span.style.strikethrough() {
                 rt = rt.strikethrough();
             }
-            if is_code {
+            if span.style.superscript() {
+                rt = rt.raised();
+            }
+            if is_math {
+                rt = rt.background_color(math_background(style, ui));
+            } else if is_code {
                 rt = rt
                     .background_color(style.code_bg.unwrap_or_else(|| ui.visuals().faint_bg_color));
             }
@@ -153,9 +189,18 @@ pub(super) fn render_text_with_links(
                 if span.style.strong() {
                     rt = rt.strong();
                 }
-                ui.hyperlink_to(rt, url.as_ref());
+                // A link split across styled spans is one Tab stop: only its
+                // first span takes keyboard focus.
+                let focusable = previous_link != Some(span.style.link_idx);
+                link_span(ui, rt, url, focusable);
+                previous_link = Some(span.style.link_idx);
             } else {
-                rt = rt.color(if span.style.strong() {
+                previous_link = None;
+                rt = rt.color(if span.style.html() {
+                    ui.visuals().weak_text_color()
+                } else if let Some(color) = style.code_color.filter(|_| is_code) {
+                    color
+                } else if span.style.strong() {
                     strengthen_color(base_color)
                 } else {
                     base_color
@@ -166,6 +211,109 @@ pub(super) fn render_text_with_links(
     });
 }
 
+/// Draw one span of a link and follow the link when it is clicked, or
+/// activated with Enter or Space while focused.
+///
+/// A focused link gets a focus ring and is scrolled into view.  Spans that
+/// continue a link sense clicks but stay out of the Tab order.
+fn link_span(ui: &mut egui::Ui, text: egui::RichText, url: &str, focusable: bool) {
+    let response = if focusable {
+        ui.link(text)
+    } else {
+        let color = ui.visuals().hyperlink_color;
+        let response = ui.add(egui::Label::new(text.color(color)).sense(egui::Sense::CLICK));
+        if response.hovered() {
+            ui.ctx().set_cursor_icon(egui::CursorIcon::PointingHand);
+        }
+        response
+    };
+    if response.has_focus() {
+        note_link_focus(ui);
+        if response.gained_focus() {
+            response.scroll_to_me(None);
+        }
+        ui.painter().rect_stroke(
+            response.rect.expand(1.0),
+            2.0,
+            ui.visuals().selection.stroke,
+            egui::StrokeKind::Outside,
+        );
+    }
+    let response = response.on_hover_text(url);
+    if let Some(slug) = url.strip_prefix('#') {
+        // In-document anchor: scroll the preview, don't open a URL.
+        if response.clicked() {
+            request_anchor(ui, slug);
+        }
+    } else if response.clicked() || response.middle_clicked() {
+        ui.ctx().open_url(egui::OpenUrl {
+            url: url.to_owned(),
+            new_tab: response.clicked_with_open_in_background(),
+        });
+    }
+}
+
+/// Tag on the `Ui` a viewer draws its blocks in, holding the viewer's id.
+const VIEWER_TAG: &str = "rustdown_md_viewer";
+
+/// Stack info that marks a `Ui` as drawing the blocks of `viewer`.
+pub(super) fn viewer_stack_info(viewer: egui::Id) -> egui::UiStackInfo {
+    egui::UiStackInfo::default().with_tag_value(VIEWER_TAG, viewer)
+}
+
+/// The viewer `ui` draws blocks for, so that link focus and anchor requests
+/// aren't picked up by another viewer on screen.
+fn viewer_id(ui: &egui::Ui) -> egui::Id {
+    ui.stack()
+        .iter()
+        .find_map(|frame| frame.tags().get_downcast::<egui::Id>(VIEWER_TAG).copied())
+        .unwrap_or(egui::Id::NULL)
+}
+
+fn link_focus_id(viewer: egui::Id) -> egui::Id {
+    viewer.with("rustdown_md_link_focus")
+}
+
+/// Record that a preview link holds keyboard focus this frame.
+fn note_link_focus(ui: &egui::Ui) {
+    let id = link_focus_id(viewer_id(ui));
+    ui.ctx().data_mut(|d| d.insert_temp(id, true));
+}
+
+/// Take whether a link of `viewer` held keyboard focus when last drawn, so
+/// the viewer can lay out the links just off screen that Tab moves to next.
+pub(super) fn take_link_focus(ctx: &egui::Context, viewer: egui::Id) -> bool {
+    ctx.data_mut(|d| d.remove_temp::<bool>(link_focus_id(viewer)))
+        .unwrap_or(false)
+}
+
+fn anchor_request_id(viewer: egui::Id) -> egui::Id {
+    viewer.with("rustdown_md_anchor_request")
+}
+
+/// Ask the scrollable viewer `ui` belongs to to jump to the heading with
+/// anchor `slug`.
+fn request_anchor(ui: &egui::Ui, slug: &str) {
+    let id = anchor_request_id(viewer_id(ui));
+    ui.ctx().data_mut(|d| d.insert_temp(id, slug.to_owned()));
+    ui.ctx().request_repaint();
+}
+
+/// Take `viewer`'s pending anchor request, if one of its in-document links
+/// was clicked.
+pub(super) fn take_anchor_request(ctx: &egui::Context, viewer: egui::Id) -> Option<String> {
+    ctx.data_mut(|d| d.remove_temp::<String>(anchor_request_id(viewer)))
+}
+
+/// Background for math spans and blocks: `math_bg`, then `code_bg`, then
+/// the theme's code background.
+pub(super) fn math_background(style: &MarkdownStyle, ui: &egui::Ui) -> egui::Color32 {
+    style
+        .math_bg
+        .or(style.code_bg)
+        .unwrap_or_else(|| ui.visuals().code_bg_color)
+}
+
 /// Build a `LayoutJob` for non-link text spans.
 pub(super) fn build_layout_job(
     st: &StyledText,
@@ -193,7 +341,7 @@ pub(super) fn build_layout_job(
             continue;
         }
         let sf = SpanFormat::resolve(span.style, style, base_color, ui);
-        let span_size = if span.style.code() { size * 0.9 } else { size };
+        let span_size = span_font_size(span.style, size);
         let color = if sf.strong {
             strengthen_color(sf.color)
         } else {
@@ -216,6 +364,7 @@ pub(super) fn build_layout_job(
                 italics: sf.italics,
                 underline: stroke(sf.underline),
                 strikethrough: stroke(sf.strikethrough),
+                valign: sf.valign,
                 ..Default::default()
             },
         });
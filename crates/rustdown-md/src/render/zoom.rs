@@ -0,0 +1,74 @@
+#![forbid(unsafe_code)]
+//! Click-to-zoom for preview images, and the record of which local images
+//! the preview has drawn so a host can reload them when they change.
+
+use std::collections::BTreeSet;
+
+/// Share of the screen the zoom modal may cover.
+const ZOOM_MAX_SCREEN_FRACTION: f32 = 0.9;
+
+fn zoom_id() -> egui::Id {
+    egui::Id::new("rustdown_md_image_zoom")
+}
+
+fn shown_images_id() -> egui::Id {
+    egui::Id::new("rustdown_md_shown_images")
+}
+
+/// Open the zoom modal for the image at `uri` on the next frame.
+pub(super) fn request_zoom(ctx: &egui::Context, uri: &str) {
+    ctx.data_mut(|d| d.insert_temp(zoom_id(), uri.to_owned()));
+    ctx.request_repaint();
+}
+
+/// The image currently shown in the zoom modal, if any.
+#[must_use]
+pub fn zoomed_image(ctx: &egui::Context) -> Option<String> {
+    ctx.data(|d| d.get_temp::<String>(zoom_id()))
+}
+
+/// Remember that the local image at `uri` was drawn.
+pub(super) fn note_shown_image(ctx: &egui::Context, uri: &str) {
+    if !uri.starts_with("file://") {
+        return;
+    }
+    ctx.data_mut(|d| {
+        let shown = d.get_temp_mut_or_default::<BTreeSet<String>>(shown_images_id());
+        if !shown.contains(uri) {
+            shown.insert(uri.to_owned());
+        }
+    });
+}
+
+/// Take the loader URIs (`file://…`) of the local images drawn since the
+/// last call.
+#[must_use]
+pub fn take_shown_images(ctx: &egui::Context) -> BTreeSet<String> {
+    ctx.data_mut(|d| d.remove_temp::<BTreeSet<String>>(shown_images_id()))
+        .unwrap_or_default()
+}
+
+/// Draw the zoom modal for a clicked preview image.
+///
+/// The image is shown at its native size in a scroll area panned by
+/// dragging or scrolling, and closed by Escape or a click outside.  Call
+/// once per frame after the preview.
+pub fn show_image_zoom(ctx: &egui::Context) {
+    let Some(uri) = zoomed_image(ctx) else {
+        return;
+    };
+    let max_size = ctx.content_rect().size() * ZOOM_MAX_SCREEN_FRACTION;
+    let modal = egui::Modal::new(zoom_id().with("modal")).show(ctx, |ui| {
+        egui::ScrollArea::both()
+            .id_salt("rustdown_md_image_zoom_scroll")
+            .max_width(max_size.x)
+            .max_height(max_size.y)
+            .show(ui, |ui| {
+                ui.add(egui::Image::new(uri.as_str()).fit_to_original_size(1.0));
+            });
+        ui.weak("Drag to pan · Esc to close");
+    });
+    if modal.should_close() {
+        ctx.data_mut(|d| d.remove_temp::<String>(zoom_id()));
+    }
+}
@@ -0,0 +1,86 @@
+#![forbid(unsafe_code)]
+//! GitHub-style heading slugs used for in-document anchors.
+
+use std::collections::HashMap;
+
+/// Convert heading text into a GitHub-style anchor slug.
+///
+/// Letters are lower-cased, whitespace becomes `-`, alphanumerics, `-` and
+/// `_` are kept, and all other punctuation is dropped.
+#[must_use]
+pub fn heading_slug(text: &str) -> String {
+    let mut slug = String::with_capacity(text.len());
+    for ch in text.trim().chars() {
+        if ch.is_alphanumeric() || ch == '_' || ch == '-' {
+            slug.extend(ch.to_lowercase());
+        } else if ch.is_whitespace() {
+            slug.push('-');
+        }
+    }
+    slug
+}
+
+/// Generates unique slugs for the headings of one document.
+///
+/// Repeated headings get `-1`, `-2`, … suffixes in document order, so the
+/// same source always yields the same anchors.
+#[derive(Debug, Default)]
+pub struct HeadingSlugger {
+    seen: HashMap<String, usize>,
+}
+
+impl HeadingSlugger {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Return the unique slug for the next heading with the given text.
+    pub fn slug(&mut self, text: &str) -> String {
+        let base = heading_slug(text);
+        let mut candidate = base.clone();
+        while self.seen.contains_key(&candidate) {
+            let count = self.seen.entry(base.clone()).or_insert(0);
+            *count += 1;
+            candidate = format!("{base}-{count}");
+        }
+        self.seen.insert(candidate.clone(), 0);
+        self.seen.entry(base).or_insert(0);
+        candidate
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn heading_slug_matches_github_rules() {
+        assert_eq!(heading_slug("Hello World"), "hello-world");
+        assert_eq!(heading_slug("  Trim me  "), "trim-me");
+        assert_eq!(heading_slug("What's new?"), "whats-new");
+        assert_eq!(heading_slug("snake_case and-dash"), "snake_case-and-dash");
+        assert_eq!(heading_slug("Café Über"), "café-über");
+        assert_eq!(heading_slug("🦀 Rust"), "-rust");
+        assert_eq!(heading_slug("A  B"), "a--b");
+        assert_eq!(heading_slug(""), "");
+    }
+
+    #[test]
+    fn slugger_dedups_in_document_order() {
+        let mut slugger = HeadingSlugger::new();
+        assert_eq!(slugger.slug("Intro"), "intro");
+        assert_eq!(slugger.slug("Intro"), "intro-1");
+        assert_eq!(slugger.slug("Intro"), "intro-2");
+        assert_eq!(slugger.slug("Other"), "other");
+    }
+
+    #[test]
+    fn slugger_avoids_collisions_with_literal_suffixes() {
+        let mut slugger = HeadingSlugger::new();
+        assert_eq!(slugger.slug("Intro 1"), "intro-1");
+        assert_eq!(slugger.slug("Intro"), "intro");
+        assert_eq!(slugger.slug("Intro"), "intro-2");
+        assert_eq!(slugger.slug("Intro 1"), "intro-1-1");
+    }
+}
@@ -34,6 +34,45 @@ pub struct HeadingStyle {
     pub color: egui::Color32,
 }
 
+/// Widest an image is drawn in the preview; narrower images keep their size.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ImageMaxWidth {
+    /// Fraction of the available width, clamped to `0.1..=1.0`.
+    Fraction(f32),
+    /// Absolute width in points, never wider than the available width.
+    Points(f32),
+}
+
+impl Default for ImageMaxWidth {
+    fn default() -> Self {
+        Self::Fraction(1.0)
+    }
+}
+
+impl ImageMaxWidth {
+    /// The cap in points for an `available` width.  Non-finite or
+    /// non-positive settings fall back to the full width.
+    #[must_use]
+    pub fn resolve(self, available: f32) -> f32 {
+        match self {
+            Self::Fraction(f) if f.is_finite() && f > 0.0 => available * f.clamp(0.1, 1.0),
+            Self::Points(p) if p.is_finite() && p > 0.0 => p.min(available),
+            _ => available,
+        }
+    }
+}
+
+/// Colours of inline code and code blocks, shared by the preview and the
+/// editor highlighter.  Unset colours follow the theme: the faint
+/// background and the body text colour.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CodeColors {
+    pub inline_bg: Option<egui::Color32>,
+    pub inline_text: Option<egui::Color32>,
+    pub block_bg: Option<egui::Color32>,
+    pub block_text: Option<egui::Color32>,
+}
+
 /// Full style configuration for the Markdown renderer.
 #[derive(Clone, Debug)]
 pub struct MarkdownStyle {
@@ -43,6 +82,14 @@ pub struct MarkdownStyle {
     pub body_color: Option<egui::Color32>,
     /// Code background tint.
     pub code_bg: Option<egui::Color32>,
+    /// Inline code text colour (falls back to the surrounding text's).
+    pub code_color: Option<egui::Color32>,
+    /// Code block background (falls back to `code_bg` if `None`).
+    pub code_block_bg: Option<egui::Color32>,
+    /// Code block text colour (falls back to `visuals.text_color()`).
+    pub code_block_color: Option<egui::Color32>,
+    /// Math background tint (falls back to `code_bg` if `None`).
+    pub math_bg: Option<egui::Color32>,
     /// Blockquote left-border colour.
     pub blockquote_bar: Option<egui::Color32>,
     /// Link colour.
@@ -51,6 +98,13 @@ pub struct MarkdownStyle {
     pub hr_color: Option<egui::Color32>,
     /// Base URI for resolving relative image paths (e.g. `"file:///path/to/dir/"`).
     pub image_base_uri: String,
+    /// Wrap long code-block lines instead of scrolling them horizontally.
+    pub code_wrap: bool,
+    /// Cap the text column of the scrollable viewer to this width (points),
+    /// centering it in wider viewports.
+    pub max_content_width: Option<f32>,
+    /// Widest an image is drawn.
+    pub image_max_width: ImageMaxWidth,
 }
 
 impl MarkdownStyle {
@@ -66,10 +120,17 @@ impl MarkdownStyle {
             headings,
             body_color: None,
             code_bg: Some(visuals.faint_bg_color),
+            code_color: None,
+            code_block_bg: None,
+            code_block_color: None,
+            math_bg: Some(visuals.code_bg_color),
             blockquote_bar: Some(visuals.weak_text_color()),
             link_color: Some(link),
             hr_color: Some(visuals.weak_text_color()),
             image_base_uri: String::new(),
+            code_wrap: false,
+            max_content_width: None,
+            image_max_width: ImageMaxWidth::default(),
         }
     }
 
@@ -93,6 +154,15 @@ impl MarkdownStyle {
         }
     }
 
+    /// Use the colours set in `colors` for inline code and code blocks,
+    /// keeping the theme's for the others.
+    pub fn set_code_colors(&mut self, colors: CodeColors) {
+        self.code_bg = colors.inline_bg.or(self.code_bg);
+        self.code_color = colors.inline_text;
+        self.code_block_bg = colors.block_bg;
+        self.code_block_color = colors.block_text;
+    }
+
     /// Set heading font scales.
     pub fn set_heading_scales(&mut self, scales: [f32; 6]) {
         for (h, s) in self.headings.iter_mut().zip(scales) {
@@ -244,6 +314,19 @@ mod tests {
         assert!(light.code_bg.is_some());
     }
 
+    #[test]
+    fn set_code_colors_keeps_theme_defaults_for_unset_ones() {
+        let visuals = egui::Visuals::dark();
+        let mut style = MarkdownStyle::from_visuals(&visuals);
+        style.set_code_colors(CodeColors {
+            block_bg: Some(egui::Color32::BLACK),
+            ..CodeColors::default()
+        });
+        assert_eq!(style.code_bg, Some(visuals.faint_bg_color));
+        assert_eq!(style.code_block_bg, Some(egui::Color32::BLACK));
+        assert_eq!((style.code_color, style.code_block_color), (None, None));
+    }
+
     #[test]
     fn all_heading_scales_at_least_body_size() {
         for (i, &scale) in HEADING_FONT_SCALES.iter().enumerate() {
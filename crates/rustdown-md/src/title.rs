@@ -0,0 +1,162 @@
+#![forbid(unsafe_code)]
+//! Front matter blocks, and a document's own title: its front matter
+//! `title`, else its first H1.
+
+use pulldown_cmark::{Event, Parser};
+
+use crate::fence::consume_fence_delimiter;
+use crate::parse::{ParseOptions, cmark_options};
+
+/// Only this much of the source is searched for a first heading, so a
+/// document without one isn't scanned end to end.
+const TITLE_SCAN_BYTES: usize = 64 * 1024;
+
+/// Byte length of the front matter block opening `source` — a YAML (`---`)
+/// or TOML (`+++`) block on the first line, through its closing delimiter
+/// line — or `None` when there isn't a closed one.
+#[must_use]
+pub fn front_matter_len(source: &str) -> Option<usize> {
+    let mut lines = source.split_inclusive('\n');
+    let first = lines.next()?;
+    let open = first.trim_end();
+    if open != "---" && open != "+++" {
+        return None;
+    }
+    let mut len = first.len();
+    for line in lines {
+        len += line.len();
+        let line = line.trim_end();
+        if line == open || (open == "---" && line == "...") {
+            return Some(len);
+        }
+    }
+    None
+}
+
+/// Split `source` into its front matter block (empty when there is none)
+/// and the Markdown after it.
+#[must_use]
+pub(crate) fn split_front_matter(source: &str) -> (&str, &str) {
+    source.split_at(front_matter_len(source).unwrap_or(0))
+}
+
+/// The top-level `key: value` (YAML) or `key = value` (TOML) fields of a
+/// front matter `block`, with quotes around values removed.  Nested or
+/// empty values are skipped.
+pub(crate) fn front_matter_fields(block: &str) -> impl Iterator<Item = (&str, &str)> {
+    let separator = if block.starts_with("+++") { '=' } else { ':' };
+    block.lines().skip(1).filter_map(move |line| {
+        let (key, value) = line.split_once(separator)?;
+        let key = key.trim_end();
+        let is_key = !key.is_empty()
+            && key
+                .bytes()
+                .all(|b| b.is_ascii_alphanumeric() || b == b'_' || b == b'-');
+        let value = value.trim();
+        let value = ['"', '\'']
+            .iter()
+            .find_map(|&quote| value.strip_prefix(quote)?.strip_suffix(quote))
+            .unwrap_or(value)
+            .trim();
+        (is_key && !value.is_empty()).then_some((key, value))
+    })
+}
+
+/// The document's title: a non-empty `title` in its front matter, else the
+/// text of its first level-1 heading (ATX or setext) outside code fences.
+#[must_use]
+pub fn document_title(source: &str) -> Option<String> {
+    let body_start = match front_matter_len(source) {
+        Some(len) => {
+            if let Some(title) = front_matter_title(&source[..len]) {
+                return Some(title);
+            }
+            len
+        }
+        None => 0,
+    };
+    first_h1(&source[body_start..])
+}
+
+/// The `title` field of a front matter block.
+pub(crate) fn front_matter_title(block: &str) -> Option<String> {
+    front_matter_fields(block)
+        .find(|(key, _)| *key == "title")
+        .map(|(_, value)| value.to_owned())
+}
+
+fn first_h1(body: &str) -> Option<String> {
+    let scan = &body[..body.floor_char_boundary(TITLE_SCAN_BYTES)];
+    let mut fence = None;
+    let mut previous: Option<&str> = None;
+    for line in scan.lines() {
+        if consume_fence_delimiter(line, &mut fence) || fence.is_some() {
+            previous = None;
+            continue;
+        }
+        let indent = line.len() - line.trim_start_matches(' ').len();
+        let trimmed = line.trim();
+        if indent <= 3 {
+            let atx = trimmed
+                .strip_prefix('#')
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with([' ', '\t']));
+            if atx {
+                let text = heading_text(trimmed);
+                return (!text.is_empty()).then_some(text);
+            }
+            let setext = !trimmed.is_empty() && trimmed.bytes().all(|b| b == b'=');
+            if setext && let Some(text) = previous {
+                let text = heading_text(&format!("{text}\n{trimmed}"));
+                return (!text.is_empty()).then_some(text);
+            }
+        }
+        previous = (!trimmed.is_empty()).then_some(line);
+    }
+    None
+}
+
+/// The plain text of a heading's Markdown source, with inline formatting
+/// and link targets dropped.
+fn heading_text(source: &str) -> String {
+    let mut text = String::new();
+    for event in Parser::new_ext(source, cmark_options(ParseOptions::default())) {
+        match event {
+            Event::Text(t) | Event::Code(t) | Event::InlineMath(t) => text.push_str(&t),
+            Event::SoftBreak | Event::HardBreak => text.push(' '),
+            _ => {}
+        }
+    }
+    text.trim().to_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn front_matter_title_wins_over_the_first_heading() {
+        let source = "---\nauthor: me\ntitle: \"From YAML\"\n---\n# Heading\n";
+        assert_eq!(front_matter_len(source), Some(38));
+        assert_eq!(document_title(source).as_deref(), Some("From YAML"));
+
+        let toml = "+++\ntitle = 'From TOML'\n+++\nbody\n";
+        assert_eq!(document_title(toml).as_deref(), Some("From TOML"));
+
+        // No title key, or an unclosed block: fall through to the heading.
+        let untitled = "---\ndraft: true\n...\n# **Bold** [link](x) `code`\n";
+        assert_eq!(document_title(untitled).as_deref(), Some("Bold link code"));
+        assert_eq!(front_matter_len("---\ntitle: x\n# H\n"), None);
+    }
+
+    #[test]
+    fn first_h1_skips_fences_and_lower_levels() {
+        let source = "Intro\n\n## Section\n\n```\n# not a heading\n```\n\nReal Title ##\n===\n";
+        assert_eq!(document_title(source).as_deref(), Some("Real Title ##"));
+        assert_eq!(
+            document_title("#hashtag\n\n# Closed #\n").as_deref(),
+            Some("Closed")
+        );
+        assert_eq!(document_title("Just text\n\n---\n"), None);
+        assert_eq!(document_title(""), None);
+    }
+}
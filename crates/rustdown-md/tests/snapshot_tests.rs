@@ -31,7 +31,7 @@
     clippy::float_cmp
 )]
 
-use rustdown_md::{MarkdownCache, MarkdownStyle, MarkdownViewer};
+use rustdown_md::{MarkdownCache, MarkdownStyle, MarkdownViewer, ParseOptions};
 
 // ── Test infrastructure ────────────────────────────────────────────
 
@@ -64,8 +64,13 @@ fn render(source: &str) -> RenderResult {
 }
 
 fn render_at(source: &str, width: f32, height: f32) -> RenderResult {
+    render_with(source, width, height, ParseOptions::default())
+}
+
+fn render_with(source: &str, width: f32, height: f32, options: ParseOptions) -> RenderResult {
     let ctx = headless_ctx();
     let mut cache = MarkdownCache::default();
+    cache.set_parse_options(options);
     let style = MarkdownStyle::colored(&egui::Visuals::dark());
     let viewer = MarkdownViewer::new("snap");
 
@@ -98,6 +103,8 @@ const fn block_name(block: &rustdown_md::Block) -> &'static str {
         rustdown_md::Block::ThematicBreak => "ThematicBreak",
         rustdown_md::Block::Table(_) => "Table",
         rustdown_md::Block::Image { .. } => "Image",
+        rustdown_md::Block::DefinitionList(_) => "DefinitionList",
+        rustdown_md::Block::Math { .. } => "Math",
     }
 }
 
@@ -121,7 +128,7 @@ fn headings_all_levels() {
     let mut prev_height = f32::MAX;
     for (i, block) in r.blocks.iter().enumerate() {
         match block {
-            rustdown_md::Block::Heading { level, text } => {
+            rustdown_md::Block::Heading { level, text, .. } => {
                 assert_eq!(*level as usize, i + 1, "heading {i} level mismatch");
                 assert!(
                     !text.text.is_empty(),
@@ -551,7 +558,15 @@ fn mixed_heading_then_table() {
 
 #[test]
 fn smart_punctuation_converted() {
-    let r = render("\"quotes\" and 'single' and em---dash and en--dash and dots...\n");
+    let source = "\"quotes\" and 'single' and em---dash and en--dash and dots...\n";
+    if let rustdown_md::Block::Paragraph(st) = &render(source).blocks[0] {
+        assert_eq!(st.text, source.trim_end(), "off by default");
+    }
+    let smart = ParseOptions {
+        smart_punctuation: true,
+        ..ParseOptions::default()
+    };
+    let r = render_with(source, 800.0, 600.0, smart);
     if let rustdown_md::Block::Paragraph(st) = &r.blocks[0] {
         assert!(
             st.text.contains('\u{201c}') || st.text.contains('\u{201d}'),